@@ -1,32 +1,108 @@
 use std::collections::HashMap;
+use std::fmt::Display;
 
 use anyhow::{Error, Result};
 use lazy_static::lazy_static;
 
-use crate::catalog::schema::{Schema, TypeId};
+use crate::catalog::functions::UserDefinedFunction;
+use crate::catalog::schema::{ColumnDefinition, Schema, TextLength, TypeId};
 use crate::common::TableId;
-use crate::parser::ast::{self, JoinType};
+use crate::parser::ast::{self, JoinType, SetOperator, WindowFrame};
+use crate::tuple::datetime::{DateTimeField, Interval};
+use crate::tuple::json::Json;
 
 lazy_static! {
     pub static ref EMPTY_SCHEMA: Schema = Schema::new(vec![]);
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum AggregationFunc {
+    /// count(*), counts all tuples including NULL values
+    CountAll,
     Count,
+    Sum,
+    Avg,
+    Min,
     Max,
+    BoolAnd,
+    BoolOr,
+    StringAgg,
+    ArrayAgg,
 }
 
 impl AggregationFunc {
-    /// Validates whether this aggregation can be applied to the child type
-    pub fn validate_aggregation_type(&self, child_type: TypeId) -> Result<()> {
+    /// Validates whether this aggregation can be applied to arguments of these types
+    pub fn validate_argument_types(&self, arg_types: &[TypeId]) -> Result<()> {
+        let expected_args = match self {
+            Self::CountAll => 0,
+            Self::StringAgg => 2,
+            _ => 1,
+        };
+        if arg_types.len() != expected_args {
+            return Err(Error::msg(format!(
+                "`{}` expects {} argument(s), found {}",
+                self,
+                expected_args,
+                arg_types.len()
+            )));
+        }
+
         match self {
-            Self::Count => Ok(()),
-            Self::Max => {
-                if !&[TypeId::Text, TypeId::Integer].contains(&child_type) {
+            Self::CountAll | Self::Count => Ok(()),
+            Self::Sum | Self::Avg => {
+                if arg_types[0] != TypeId::Integer {
                     Err(Error::msg(format!(
-                        "`max` accepts text and integer, found {}",
-                        child_type
+                        "`{}` accepts integer, found {}",
+                        self, arg_types[0]
+                    )))
+                } else {
+                    Ok(())
+                }
+            }
+            Self::Min | Self::Max => {
+                if !&[
+                    TypeId::Text,
+                    TypeId::Integer,
+                    TypeId::Date,
+                    TypeId::Time,
+                    TypeId::Timestamp,
+                    TypeId::Interval,
+                ]
+                .contains(&arg_types[0])
+                {
+                    Err(Error::msg(format!(
+                        "`{}` accepts text, integer and temporal types, found {}",
+                        self, arg_types[0]
+                    )))
+                } else {
+                    Ok(())
+                }
+            }
+            Self::BoolAnd | Self::BoolOr => {
+                if arg_types[0] != TypeId::Boolean {
+                    Err(Error::msg(format!(
+                        "`{}` accepts boolean, found {}",
+                        self, arg_types[0]
+                    )))
+                } else {
+                    Ok(())
+                }
+            }
+            Self::StringAgg => {
+                if arg_types.iter().any(|arg_type| arg_type != &TypeId::Text) {
+                    Err(Error::msg(format!(
+                        "`{}` accepts a text value and a text delimiter, found {} and {}",
+                        self, arg_types[0], arg_types[1]
+                    )))
+                } else {
+                    Ok(())
+                }
+            }
+            Self::ArrayAgg => {
+                if arg_types[0].array_type().is_none() {
+                    Err(Error::msg(format!(
+                        "`{}` accepts integer and text, found {}",
+                        self, arg_types[0]
                     )))
                 } else {
                     Ok(())
@@ -36,10 +112,13 @@ impl AggregationFunc {
     }
 
     /// Returns the type of the aggregation result
-    pub fn aggregation_result_type(&self, child_type: TypeId) -> TypeId {
+    pub fn result_type(&self, arg_types: &[TypeId]) -> TypeId {
         match self {
-            Self::Count => TypeId::Integer,
-            Self::Max => child_type,
+            Self::CountAll | Self::Count | Self::Sum | Self::Avg => TypeId::Integer,
+            Self::Min | Self::Max => arg_types[0],
+            Self::BoolAnd | Self::BoolOr => TypeId::Boolean,
+            Self::StringAgg => TypeId::Text,
+            Self::ArrayAgg => arg_types[0].array_type().unwrap(),
         }
     }
 
@@ -47,20 +126,365 @@ impl AggregationFunc {
     pub fn is_aggregation_func(s: &str) -> Option<Self> {
         let res = match s {
             "count" => Self::Count,
+            "sum" => Self::Sum,
+            "avg" => Self::Avg,
+            "min" => Self::Min,
             "max" => Self::Max,
+            "bool_and" | "every" => Self::BoolAnd,
+            "bool_or" => Self::BoolOr,
+            "string_agg" => Self::StringAgg,
+            "array_agg" => Self::ArrayAgg,
             _ => return None,
         };
         Some(res)
     }
 }
 
-#[derive(Debug, PartialEq)]
+impl Display for AggregationFunc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CountAll | Self::Count => write!(f, "count"),
+            Self::Sum => write!(f, "sum"),
+            Self::Avg => write!(f, "avg"),
+            Self::Min => write!(f, "min"),
+            Self::Max => write!(f, "max"),
+            Self::BoolAnd => write!(f, "bool_and"),
+            Self::BoolOr => write!(f, "bool_or"),
+            Self::StringAgg => write!(f, "string_agg"),
+            Self::ArrayAgg => write!(f, "array_agg"),
+        }
+    }
+}
+
+/// A scalar function. Most functions are called by name and resolved by their signature in the
+/// function registry, see `FunctionRegistry`
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScalarFunc {
+    Length,
+    OctetLength,
+    Lower,
+    Upper,
+    /// the characters starting at a 1-based position, optionally limited to a count
+    Substring,
+    /// removes the characters (spaces by default) from both ends of a string, see TRIM
+    BTrim,
+    /// removes the characters (spaces by default) from the start of a string
+    LTrim,
+    /// removes the characters (spaces by default) from the end of a string
+    RTrim,
+    Replace,
+    /// the 1-based position of the second argument within the first one, 0 if not found
+    Position,
+    Abs,
+    /// rounds to the given number of decimal places, only negative ones affect integers
+    Round,
+    Mod,
+    Power,
+    /// a random integer between 0 and 2^31 - 1, or one in the given inclusive bounds. There
+    /// are no floating point types, so it cannot return a value between 0 and 1 yet
+    Random,
+    GenRandomUuid,
+    JsonExtractPath,
+    JsonExtractPathText,
+    /// returns the first argument which is not NULL, it also merges the columns of a FULL JOIN
+    /// with USING. The remaining arguments are not evaluated
+    Coalesce,
+    /// NULL if both arguments are equal, else the first argument
+    NullIf,
+    /// the largest argument, NULL arguments are ignored
+    Greatest,
+    /// the smallest argument, NULL arguments are ignored
+    Least,
+    /// converts a LIKE pattern with a custom escape character to one escaped with backslashes,
+    /// see LIKE ... ESCAPE
+    LikeEscape,
+    /// a function implemented by the application embedding the database
+    UserDefined(UserDefinedFunction),
+}
+
+impl ScalarFunc {
+    /// Returns whether the arguments are unified to a common type, which is also the type of
+    /// the result. These functions accept arguments of any type, so they are not part of the
+    /// function registry
+    pub fn has_common_argument_type(&self) -> bool {
+        matches!(
+            self,
+            Self::Coalesce | Self::NullIf | Self::Greatest | Self::Least
+        )
+    }
+
+    /// Returns whether the result is NULL if any argument is NULL, without calling the function
+    pub fn is_strict(&self) -> bool {
+        !self.has_common_argument_type()
+    }
+
+    /// Returns whether the result can be NULL, although no argument is
+    pub fn can_return_null(&self) -> bool {
+        matches!(
+            self,
+            Self::NullIf | Self::JsonExtractPath | Self::JsonExtractPathText | Self::UserDefined(_)
+        )
+    }
+
+    /// Validates whether a function with a common argument type can be applied to arguments of
+    /// these types. The arguments have already been unified, so only the number of arguments
+    /// and whether the type supports the function are left to check
+    pub fn validate_argument_types(&self, arg_types: &[TypeId]) -> Result<()> {
+        let (min_args, max_args) = match self {
+            Self::NullIf => (2, Some(2)),
+            _ => (1, None),
+        };
+        if arg_types.len() < min_args || max_args.is_some_and(|max| arg_types.len() > max) {
+            let expected = match max_args {
+                Some(max) => max.to_string(),
+                None => format!("at least {}", min_args),
+            };
+            return Err(Error::msg(format!(
+                "`{}` expects {} argument(s), found {}",
+                self,
+                expected,
+                arg_types.len()
+            )));
+        }
+
+        let mut known_types = arg_types.iter().filter(|&&t| t != TypeId::Unknown);
+        if let Some(first) = known_types.next() {
+            if let Some(other) = known_types.find(|&t| t != first) {
+                return Err(Error::msg(format!(
+                    "`{}` types {} and {} cannot be matched",
+                    self, first, other
+                )));
+            }
+            let ordered = *first != TypeId::Json && first.element_type().is_none();
+            if matches!(self, Self::Greatest | Self::Least) && !ordered {
+                return Err(Error::msg(format!(
+                    "`{}` is not defined for type {}",
+                    self, first
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the type of the result of a function with a common argument type
+    pub fn result_type(&self, arg_types: &[TypeId]) -> TypeId {
+        arg_types
+            .iter()
+            .copied()
+            .find(|&t| t != TypeId::Unknown)
+            .unwrap_or(TypeId::Unknown)
+    }
+
+    /// Returns a function with a common argument type if there exists one with this name, the
+    /// other functions are looked up in the function registry
+    pub fn is_scalar_func(s: &str) -> Option<Self> {
+        let res = match s {
+            "coalesce" => Self::Coalesce,
+            "nullif" => Self::NullIf,
+            "greatest" => Self::Greatest,
+            "least" => Self::Least,
+            _ => return None,
+        };
+        Some(res)
+    }
+}
+
+impl Display for ScalarFunc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Length => write!(f, "length"),
+            Self::OctetLength => write!(f, "octet_length"),
+            Self::Lower => write!(f, "lower"),
+            Self::Upper => write!(f, "upper"),
+            Self::Substring => write!(f, "substring"),
+            Self::BTrim => write!(f, "btrim"),
+            Self::LTrim => write!(f, "ltrim"),
+            Self::RTrim => write!(f, "rtrim"),
+            Self::Replace => write!(f, "replace"),
+            Self::Position => write!(f, "position"),
+            Self::Abs => write!(f, "abs"),
+            Self::Round => write!(f, "round"),
+            Self::Mod => write!(f, "mod"),
+            Self::Power => write!(f, "power"),
+            Self::Random => write!(f, "random"),
+            Self::GenRandomUuid => write!(f, "gen_random_uuid"),
+            Self::JsonExtractPath => write!(f, "json_extract_path"),
+            Self::JsonExtractPathText => write!(f, "json_extract_path_text"),
+            Self::Coalesce => write!(f, "coalesce"),
+            Self::NullIf => write!(f, "nullif"),
+            Self::Greatest => write!(f, "greatest"),
+            Self::Least => write!(f, "least"),
+            Self::LikeEscape => write!(f, "like_escape"),
+            Self::UserDefined(func) => write!(f, "{}", func.name),
+        }
+    }
+}
+
+/// A function which is evaluated over the rows of a window, see OVER
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WindowFunc {
+    RowNumber,
+    Rank,
+    DenseRank,
+    Lag,
+    Lead,
+    FirstValue,
+    LastValue,
+    /// an aggregation over the rows of the window frame
+    Aggregation(AggregationFunc),
+}
+
+impl WindowFunc {
+    /// Validates whether this function can be applied to arguments of these types
+    pub fn validate_argument_types(&self, arg_types: &[TypeId]) -> Result<()> {
+        let (min_args, max_args) = match self {
+            Self::Aggregation(agg) => return agg.validate_argument_types(arg_types),
+            Self::RowNumber | Self::Rank | Self::DenseRank => (0, 0),
+            Self::FirstValue | Self::LastValue => (1, 1),
+            Self::Lag | Self::Lead => (1, 3),
+        };
+        if arg_types.len() < min_args || arg_types.len() > max_args {
+            let expected = if min_args == max_args {
+                min_args.to_string()
+            } else {
+                format!("{} to {}", min_args, max_args)
+            };
+            return Err(Error::msg(format!(
+                "`{}` expects {} argument(s), found {}",
+                self,
+                expected,
+                arg_types.len()
+            )));
+        }
+
+        if let (Self::Lag | Self::Lead, [value_type, rest @ ..]) = (self, arg_types) {
+            if let Some(offset_type) = rest.first() {
+                if ![TypeId::Integer, TypeId::Unknown].contains(offset_type) {
+                    return Err(Error::msg(format!(
+                        "`{}` expects an integer offset, found {}",
+                        self, offset_type
+                    )));
+                }
+            }
+            if let Some(default_type) = rest.get(1) {
+                if value_type != default_type
+                    && *value_type != TypeId::Unknown
+                    && *default_type != TypeId::Unknown
+                {
+                    return Err(Error::msg(format!(
+                        "`{}` expects a default of the same type as the value, found {} and {}",
+                        self, value_type, default_type
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the type of the function result
+    pub fn result_type(&self, arg_types: &[TypeId]) -> TypeId {
+        match self {
+            Self::Aggregation(agg) => agg.result_type(arg_types),
+            Self::RowNumber | Self::Rank | Self::DenseRank => TypeId::Integer,
+            // the default of lag and lead determines the type of a NULL value
+            Self::Lag | Self::Lead if arg_types[0] == TypeId::Unknown && arg_types.len() == 3 => {
+                arg_types[2]
+            }
+            Self::Lag | Self::Lead | Self::FirstValue | Self::LastValue => arg_types[0],
+        }
+    }
+
+    /// Returns a window function if there exists one with this name, else None. Aggregations
+    /// are window functions as well, but are not returned
+    pub fn is_window_func(s: &str) -> Option<Self> {
+        let res = match s {
+            "row_number" => Self::RowNumber,
+            "rank" => Self::Rank,
+            "dense_rank" => Self::DenseRank,
+            "lag" => Self::Lag,
+            "lead" => Self::Lead,
+            "first_value" => Self::FirstValue,
+            "last_value" => Self::LastValue,
+            _ => return None,
+        };
+        Some(res)
+    }
+}
+
+impl Display for WindowFunc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RowNumber => write!(f, "row_number"),
+            Self::Rank => write!(f, "rank"),
+            Self::DenseRank => write!(f, "dense_rank"),
+            Self::Lag => write!(f, "lag"),
+            Self::Lead => write!(f, "lead"),
+            Self::FirstValue => write!(f, "first_value"),
+            Self::LastValue => write!(f, "last_value"),
+            Self::Aggregation(agg) => write!(f, "{}", agg),
+        }
+    }
+}
+
+/// A function which returns a set of rows and can be used in the FROM clause
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TableFunc {
+    Unnest,
+}
+
+impl TableFunc {
+    /// Validates the argument types and returns the type of the returned column
+    pub fn output_column_type(&self, arg_types: &[TypeId]) -> Result<TypeId> {
+        match self {
+            Self::Unnest => match arg_types {
+                [arg_type] => arg_type.element_type().ok_or_else(|| {
+                    Error::msg(format!("`{}` expects an array, found {}", self, arg_type))
+                }),
+                _ => Err(Error::msg(format!(
+                    "`{}` expects 1 argument, found {}",
+                    self,
+                    arg_types.len()
+                ))),
+            },
+        }
+    }
+
+    /// Returns a table function if there exists one with this name, else None
+    pub fn is_table_func(s: &str) -> Option<Self> {
+        let res = match s {
+            "unnest" => Self::Unnest,
+            _ => return None,
+        };
+        Some(res)
+    }
+}
+
+impl Display for TableFunc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unnest => write!(f, "unnest"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum LogicalExpr {
     /// A fully specified column
     Column(Vec<String>),
+    /// A column of an enclosing query, referenced by a correlated subquery
+    OuterColumn(Vec<String>),
     Integer(i32),
     String(String),
     Boolean(bool),
+    Date(i32),
+    Time(i64),
+    Timestamp(i64),
+    Interval(Interval),
+    Bytea(Vec<u8>),
+    Uuid(u128),
+    Json(Json),
+    CurrentDate,
+    CurrentTimestamp,
     Null,
     Unary {
         op: ast::UnaryOperator,
@@ -73,17 +497,68 @@ pub enum LogicalExpr {
     },
     IsNull(Box<LogicalExpr>),
     IsNotNull(Box<LogicalExpr>),
-    Aggregation(AggregationFunc, Box<LogicalExpr>),
+    Array(Vec<LogicalExpr>),
+    Subscript {
+        expr: Box<LogicalExpr>,
+        index: Box<LogicalExpr>,
+    },
+    AnyComparison {
+        left: Box<LogicalExpr>,
+        op: ast::BinaryOperator,
+        array: Box<LogicalExpr>,
+    },
+    Cast {
+        expr: Box<LogicalExpr>,
+        type_id: TypeId,
+        /// only set for explicit casts to VARCHAR(n) or CHAR(n)
+        text_length: Option<TextLength>,
+    },
+    /// CASE, only the result of the first true condition is evaluated
+    Case {
+        operand: Option<Box<LogicalExpr>>,
+        conditions: Vec<(LogicalExpr, LogicalExpr)>,
+        else_result: Option<Box<LogicalExpr>>,
+    },
+    Aggregation {
+        func: AggregationFunc,
+        args: Vec<LogicalExpr>,
+        /// only aggregate distinct argument values
+        distinct: bool,
+    },
+    ScalarFunction(ScalarFunc, Vec<LogicalExpr>),
+    /// a window function, e.g. rank() OVER (ORDER BY col_a)
+    WindowFunction {
+        func: WindowFunc,
+        args: Vec<LogicalExpr>,
+        window: Box<Window>,
+    },
+    Extract {
+        field: DateTimeField,
+        expr: Box<LogicalExpr>,
+    },
+    /// a subquery returning a single value, or NULL if it returns no rows
+    Subquery(Box<Query>),
+    /// EXISTS (SELECT ...)
+    Exists(Box<Query>),
+    /// expr [NOT] IN (SELECT ...), the subquery returns a single column
+    InSubquery {
+        expr: Box<LogicalExpr>,
+        query: Box<Query>,
+        negated: bool,
+    },
 }
 
 impl LogicalExpr {
     /// Returns whether an expression contains any aggregation calls
     pub fn has_aggregation(&self) -> bool {
         match self {
-            Self::Column(_) => false,
+            Self::Column(_) | Self::OuterColumn(_) => false,
             Self::Integer(_) => false,
             Self::String(_) => false,
             Self::Boolean(_) => false,
+            Self::Date(_) | Self::Time(_) | Self::Timestamp(_) | Self::Interval(_) => false,
+            Self::Bytea(_) | Self::Uuid(_) | Self::Json(_) => false,
+            Self::CurrentDate | Self::CurrentTimestamp => false,
             Self::Null => false,
             Self::Unary { op: _, expr } => expr.has_aggregation(),
             Self::Binary { left, op: _, right } => {
@@ -91,35 +566,251 @@ impl LogicalExpr {
             }
             Self::IsNull(expr) => expr.has_aggregation(),
             Self::IsNotNull(expr) => expr.has_aggregation(),
-            Self::Aggregation(_, _) => true,
+            Self::Array(elements) => elements.iter().any(|element| element.has_aggregation()),
+            Self::Subscript { expr, index } => expr.has_aggregation() || index.has_aggregation(),
+            Self::AnyComparison { left, op: _, array } => {
+                left.has_aggregation() || array.has_aggregation()
+            }
+            Self::Cast {
+                expr,
+                type_id: _,
+                text_length: _,
+            } => expr.has_aggregation(),
+            Self::Case {
+                operand: _,
+                conditions: _,
+                else_result: _,
+            } => self.case_children().any(|expr| expr.has_aggregation()),
+            Self::Aggregation {
+                func: _,
+                args: _,
+                distinct: _,
+            } => true,
+            Self::ScalarFunction(_, args) => args.iter().any(|arg| arg.has_aggregation()),
+            // an aggregation over a window does not group the query, but its arguments may
+            Self::WindowFunction {
+                func: _,
+                args,
+                window,
+            } => args
+                .iter()
+                .chain(window.partition_by.iter())
+                .chain(window.order_by.iter().map(|order| &order.expr))
+                .any(|expr| expr.has_aggregation()),
+            Self::Extract { field: _, expr } => expr.has_aggregation(),
+            // aggregations within a subquery belong to the subquery
+            Self::Subquery(_) | Self::Exists(_) => false,
+            Self::InSubquery {
+                expr,
+                query: _,
+                negated: _,
+            } => expr.has_aggregation(),
+        }
+    }
+
+    /// Returns the operand, the conditions, the results and the ELSE result of a CASE
+    /// expression, or nothing for any other expression
+    pub fn case_children(&self) -> impl Iterator<Item = &LogicalExpr> {
+        let (operand, conditions, else_result) = match self {
+            Self::Case {
+                operand,
+                conditions,
+                else_result,
+            } => (
+                operand.as_deref(),
+                conditions.as_slice(),
+                else_result.as_deref(),
+            ),
+            _ => (None, &[][..], None),
+        };
+        operand
+            .into_iter()
+            .chain(
+                conditions
+                    .iter()
+                    .flat_map(|(condition, result)| [condition, result]),
+            )
+            .chain(else_result)
+    }
+
+    /// Returns whether an expression contains any window functions
+    pub fn has_window_function(&self) -> bool {
+        !self.window_functions().is_empty()
+    }
+
+    /// Returns the window functions within the expression
+    pub fn window_functions(&self) -> Vec<&LogicalExpr> {
+        let mut found = vec![];
+        self.collect(
+            &|expr| {
+                matches!(
+                    expr,
+                    Self::WindowFunction {
+                        func: _,
+                        args: _,
+                        window: _,
+                    }
+                )
+            },
+            &mut found,
+        );
+        found
+    }
+
+    /// Returns the aggregations within the expression, including those within the arguments
+    /// and the window of a window function
+    pub fn aggregations(&self) -> Vec<&LogicalExpr> {
+        let mut found = vec![];
+        self.collect(
+            &|expr| {
+                matches!(
+                    expr,
+                    Self::Aggregation {
+                        func: _,
+                        args: _,
+                        distinct: _,
+                    }
+                )
+            },
+            &mut found,
+        );
+        found
+    }
+
+    /// Collects the matching expressions, which are not nested within another match.
+    /// Subqueries are not visited, as their expressions belong to the subquery
+    fn collect<'a>(&'a self, matches: &impl Fn(&LogicalExpr) -> bool, found: &mut Vec<&'a Self>) {
+        if matches(self) {
+            found.push(self);
+            return;
+        }
+        let children: Vec<&LogicalExpr> = match self {
+            Self::Column(_) | Self::OuterColumn(_) => vec![],
+            Self::Integer(_) | Self::String(_) | Self::Boolean(_) => vec![],
+            Self::Date(_) | Self::Time(_) | Self::Timestamp(_) | Self::Interval(_) => vec![],
+            Self::Bytea(_) | Self::Uuid(_) | Self::Json(_) => vec![],
+            Self::CurrentDate | Self::CurrentTimestamp | Self::Null => vec![],
+            Self::Unary { op: _, expr } => vec![expr],
+            Self::Binary { left, op: _, right } => vec![left, right],
+            Self::IsNull(expr) | Self::IsNotNull(expr) => vec![expr],
+            Self::Array(elements) => elements.iter().collect(),
+            Self::Subscript { expr, index } => vec![expr, index],
+            Self::AnyComparison { left, op: _, array } => vec![left, array],
+            Self::Cast {
+                expr,
+                type_id: _,
+                text_length: _,
+            } => vec![expr],
+            Self::Case {
+                operand: _,
+                conditions: _,
+                else_result: _,
+            } => self.case_children().collect(),
+            Self::Aggregation {
+                func: _,
+                args,
+                distinct: _,
+            } => args.iter().collect(),
+            Self::ScalarFunction(_, args) => args.iter().collect(),
+            Self::WindowFunction {
+                func: _,
+                args,
+                window,
+            } => args
+                .iter()
+                .chain(window.partition_by.iter())
+                .chain(window.order_by.iter().map(|order| &order.expr))
+                .collect(),
+            Self::Extract { field: _, expr } => vec![expr],
+            Self::Subquery(_) | Self::Exists(_) => vec![],
+            Self::InSubquery {
+                expr,
+                query: _,
+                negated: _,
+            } => vec![expr],
+        };
+        for child in children {
+            child.collect(matches, found);
         }
     }
 
-    /// If this expression references any column, returns the first it finds, else None.
-    /// Ignores any column references in aggregations
-    pub fn find_any_referenced_column(&self) -> Option<String> {
+    /// Returns the first column reference, which is neither part of an aggregation nor of one
+    /// of the grouping expressions, or None if there is no such column reference
+    pub fn find_ungrouped_column(&self, group_by: &[LogicalExpr]) -> Option<String> {
+        if group_by.contains(self) {
+            return None;
+        }
         match self {
             Self::Column(col) => Some(col.join(".")),
+            // outer columns are constant within the grouped subquery
+            Self::OuterColumn(_) => None,
             Self::Integer(_) => None,
             Self::String(_) => None,
             Self::Boolean(_) => None,
+            Self::Date(_) | Self::Time(_) | Self::Timestamp(_) | Self::Interval(_) => None,
+            Self::Bytea(_) | Self::Uuid(_) | Self::Json(_) => None,
+            Self::CurrentDate | Self::CurrentTimestamp => None,
             Self::Null => None,
-            Self::Unary { op: _, expr } => expr.find_any_referenced_column(),
+            Self::Unary { op: _, expr } => expr.find_ungrouped_column(group_by),
             Self::Binary { left, op: _, right } => {
-                if let Some(col_ref) = left.find_any_referenced_column() {
+                if let Some(col_ref) = left.find_ungrouped_column(group_by) {
                     Some(col_ref)
                 } else {
-                    right.find_any_referenced_column()
+                    right.find_ungrouped_column(group_by)
                 }
             }
-            Self::IsNull(expr) => expr.find_any_referenced_column(),
-            Self::IsNotNull(expr) => expr.find_any_referenced_column(),
-            Self::Aggregation(_, _) => None,
+            Self::IsNull(expr) => expr.find_ungrouped_column(group_by),
+            Self::IsNotNull(expr) => expr.find_ungrouped_column(group_by),
+            Self::Array(elements) => elements
+                .iter()
+                .find_map(|element| element.find_ungrouped_column(group_by)),
+            Self::Subscript { expr, index } => expr
+                .find_ungrouped_column(group_by)
+                .or_else(|| index.find_ungrouped_column(group_by)),
+            Self::AnyComparison { left, op: _, array } => left
+                .find_ungrouped_column(group_by)
+                .or_else(|| array.find_ungrouped_column(group_by)),
+            Self::Cast {
+                expr,
+                type_id: _,
+                text_length: _,
+            } => expr.find_ungrouped_column(group_by),
+            Self::Case {
+                operand: _,
+                conditions: _,
+                else_result: _,
+            } => self
+                .case_children()
+                .find_map(|expr| expr.find_ungrouped_column(group_by)),
+            Self::Aggregation {
+                func: _,
+                args: _,
+                distinct: _,
+            } => None,
+            Self::ScalarFunction(_, args) => args
+                .iter()
+                .find_map(|arg| arg.find_ungrouped_column(group_by)),
+            Self::WindowFunction {
+                func: _,
+                args,
+                window,
+            } => args
+                .iter()
+                .chain(window.partition_by.iter())
+                .chain(window.order_by.iter().map(|order| &order.expr))
+                .find_map(|expr| expr.find_ungrouped_column(group_by)),
+            Self::Extract { field: _, expr } => expr.find_ungrouped_column(group_by),
+            Self::Subquery(_) | Self::Exists(_) => None,
+            Self::InSubquery {
+                expr,
+                query: _,
+                negated: _,
+            } => expr.find_ungrouped_column(group_by),
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum TableReference {
     BaseTable {
         table_id: TableId,
@@ -132,6 +823,22 @@ pub enum TableReference {
         right: Box<TableReference>,
         join_type: JoinType,
         on: Vec<LogicalExpr>,
+        /// the columns merged by USING or NATURAL, they are visible without a table name once
+        using: Vec<String>,
+    },
+    TableFunction {
+        func: TableFunc,
+        args: Vec<LogicalExpr>,
+        name: String,
+        schema: Schema,
+        filter: Vec<LogicalExpr>,
+    },
+    /// a reference to a common table expression of the WITH clause
+    Cte {
+        cte_id: usize,
+        name: String,
+        schema: Schema,
+        filter: Vec<LogicalExpr>,
     },
     EmptyTable,
 }
@@ -156,7 +863,7 @@ pub enum LogicalPlan {
     Select(Query),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Query {
     /// VALUES
     pub values: Vec<Vec<LogicalExpr>>,
@@ -166,6 +873,87 @@ pub struct Query {
     pub projections: Vec<LogicalExpr>,
     /// WHERE clause
     pub filter: Vec<LogicalExpr>,
+    /// GROUP BY clause
+    pub group_by: Vec<LogicalExpr>,
+    /// HAVING clause
+    pub having: Vec<LogicalExpr>,
+    /// ORDER BY clause
+    pub order_by: Vec<OrderByExpr>,
+    /// the expressions by which duplicate rows are removed, see DISTINCT and DISTINCT ON
+    pub distinct: Vec<LogicalExpr>,
+    /// LIMIT clause, evaluates to the maximum number of rows or NULL for no limit
+    pub limit: Option<LogicalExpr>,
+    /// OFFSET clause, evaluates to the number of rows to skip
+    pub offset: Option<LogicalExpr>,
+    /// WITH clause, the common table expressions which can be referenced by the query
+    pub ctes: Vec<CommonTableExpression>,
+    /// UNION, INTERSECT or EXCEPT of two queries. If set, the query only consists of the set
+    /// operation with its ORDER BY, LIMIT and OFFSET
+    pub set_operation: Option<Box<SetOperation>>,
     /// the output schema of the query
     pub output_schema: Schema,
 }
+
+impl Query {
+    /// Returns a query, which only consists of a set operation
+    pub fn from_set_operation(
+        set_operation: SetOperation,
+        output_columns: Vec<ColumnDefinition>,
+    ) -> Self {
+        Self {
+            values: vec![],
+            from: TableReference::EmptyTable,
+            projections: vec![],
+            filter: vec![],
+            group_by: vec![],
+            having: vec![],
+            order_by: vec![],
+            distinct: vec![],
+            limit: None,
+            offset: None,
+            ctes: vec![],
+            set_operation: Some(Box::new(set_operation)),
+            output_schema: Schema::new(output_columns),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommonTableExpression {
+    /// identifies the CTE within the statement, as names can be shadowed by nested queries
+    pub id: usize,
+    pub name: String,
+    pub query: Query,
+    /// the number of references to the CTE, it is materialized if referenced more than once
+    pub references: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetOperation {
+    pub op: SetOperator,
+    /// whether duplicates are kept, e.g. UNION ALL
+    pub all: bool,
+    pub left: Query,
+    pub right: Query,
+    /// for the UNION of a recursive CTE, the id of the CTE. The right input references the
+    /// CTE to get the rows added by the previous iteration, i.e. the working table
+    pub working_table: Option<usize>,
+}
+
+/// The window of a window function, see OVER
+#[derive(Clone, Debug, PartialEq)]
+pub struct Window {
+    pub partition_by: Vec<LogicalExpr>,
+    pub order_by: Vec<OrderByExpr>,
+    /// the rows of the partition, which the function is evaluated on. Without a frame, they
+    /// range from the first row of the partition to the last row, which is ordered equally to
+    /// the current row
+    pub frame: Option<WindowFrame<LogicalExpr>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderByExpr {
+    pub expr: LogicalExpr,
+    pub ascending: bool,
+    pub nulls_first: bool,
+}
@@ -1,19 +1,29 @@
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, VecDeque};
 
 use anyhow::{Error, Result};
 
 use crate::analyzer::logical_plan::Query;
-use crate::catalog::schema::{ColumnDefinition, Schema, TypeId};
+use crate::catalog::schema::{ColumnDefinition, Schema, TextLength, TypeId};
 use crate::catalog::Catalog;
 use crate::parser::ast::{
-    self, BinaryOperator, ExprNode, JoinType, Projection, SelectStatement, Statement, TableNode,
+    self, BinaryOperator, DataType, ExprNode, JoinConstraint, JoinType, Projection,
+    SelectStatement, SetOperator, Statement, TableNode, UnaryOperator, WindowSpec,
 };
+use crate::tuple::cast::{self, CastContext};
+use crate::tuple::datetime::{self, DateTimeField};
+use crate::tuple::json::Json;
+use crate::tuple::value::Value;
+use crate::tuple::{bytea, uuid};
 
 pub mod logical_plan;
 
 use logical_plan::LogicalPlan;
 
-use self::logical_plan::{AggregationFunc, LogicalExpr, TableReference};
+use self::logical_plan::{
+    AggregationFunc, CommonTableExpression, LogicalExpr, OrderByExpr, ScalarFunc, SetOperation,
+    TableFunc, TableReference, Window, WindowFunc,
+};
 
 /// Splits an expression into a conjunctive normal form
 /// i.e. a AND b AND c will be split into vec![a, b, c]
@@ -22,7 +32,11 @@ fn split_expression(expr: LogicalExpr) -> Vec<LogicalExpr> {
     let mut to_visit = vec![expr];
     while let Some(expr) = to_visit.pop() {
         match expr {
-            LogicalExpr::Binary { left, op, right } if op == BinaryOperator::And => {
+            LogicalExpr::Binary {
+                left,
+                op: BinaryOperator::And,
+                right,
+            } => {
                 to_visit.push(*left);
                 to_visit.push(*right);
             }
@@ -33,13 +47,325 @@ fn split_expression(expr: LogicalExpr) -> Vec<LogicalExpr> {
     expressions
 }
 
+/// Returns the result type of an arithmetic operation, or None if the operation
+/// is not defined for these types
+fn arithmetic_result_type(left: TypeId, op: BinaryOperator, right: TypeId) -> Option<TypeId> {
+    use BinaryOperator::{Divide, Minus, Modulo, Multiply, Plus};
+    use TypeId::{Date, Integer, Interval, Time, Timestamp};
+
+    let result = match (left, op, right) {
+        (Integer, Plus | Minus | Multiply | Divide | Modulo, Integer) => Integer,
+        (Date, Plus | Minus, Integer) | (Integer, Plus, Date) => Date,
+        (Date, Minus, Date) => Integer,
+        (Date, Plus, Time) | (Time, Plus, Date) => Timestamp,
+        (Date | Timestamp, Plus | Minus, Interval) | (Interval, Plus, Date | Timestamp) => {
+            Timestamp
+        }
+        (Timestamp, Minus, Timestamp) => Interval,
+        (Time, Plus | Minus, Interval) | (Interval, Plus, Time) => Time,
+        (Time, Minus, Time) => Interval,
+        (Interval, Plus | Minus, Interval) => Interval,
+        (Interval, Multiply, Integer) | (Integer, Multiply, Interval) => Interval,
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// String literals have no type on their own in SQL. E.g. `'\x0a'` is a valid BYTEA value if it
+/// is compared to or stored in a BYTEA column. Converts a string literal to the target type
+/// where such a conversion exists, else leaves the expression untouched
+fn coerce_string_literal(
+    expr: &mut LogicalExpr,
+    col_def: &mut ColumnDefinition,
+    target: TypeId,
+) -> Result<()> {
+    let s = match (&*expr, target) {
+        (_, TypeId::Text | TypeId::Unknown) => return Ok(()),
+        (LogicalExpr::String(s), _) => s.clone(),
+        _ => return Ok(()),
+    };
+    *expr = literal_expr(cast::cast_value(Value::String(s), target)?);
+    col_def.type_id = target;
+    Ok(())
+}
+
+/// Wraps the expression in a cast to the target type, if the coercion table allows this
+/// cast to be applied implicitly. Returns whether the expression was coerced
+fn coerce_implicitly(
+    expr: &mut LogicalExpr,
+    col_def: &mut ColumnDefinition,
+    target: TypeId,
+) -> bool {
+    if cast::cast_context(col_def.type_id, target) != Some(CastContext::Implicit) {
+        return false;
+    }
+    let inner = std::mem::replace(expr, LogicalExpr::Null);
+    *expr = LogicalExpr::Cast {
+        expr: Box::new(inner),
+        type_id: target,
+        text_length: None,
+    };
+    col_def.type_id = target;
+    true
+}
+
+/// Like Postgres' bpchar, CHAR(n) values are compared without the trailing spaces, which pad
+/// them to their declared length. String literals compared to them are trimmed as well
+fn strip_char_padding(operands: Vec<(&mut LogicalExpr, &ColumnDefinition)>) {
+    let is_char =
+        |col_def: &ColumnDefinition| matches!(col_def.text_length, Some(TextLength::Fixed(_)));
+    if !operands.iter().any(|(_, col_def)| is_char(col_def)) {
+        return;
+    }
+    for (expr, col_def) in operands {
+        if let LogicalExpr::String(s) = expr {
+            s.truncate(s.trim_end_matches(' ').len());
+        } else if is_char(col_def) {
+            let inner = std::mem::replace(expr, LogicalExpr::Null);
+            *expr = LogicalExpr::ScalarFunction(ScalarFunc::RTrim, vec![inner]);
+        }
+    }
+}
+
+/// Unifies the types of expressions whose values end up in the same column, e.g. the results
+/// of CASE. Like for set operations, string literals are converted directly and other
+/// expressions only if the coercion table allows an implicit cast. Returns the common type
+fn unify_types(context: &str, exprs: &mut [(LogicalExpr, ColumnDefinition)]) -> Result<TypeId> {
+    let is_literal = |expr: &LogicalExpr| matches!(expr, LogicalExpr::String(_));
+    let mut target = TypeId::Unknown;
+    for (_, col_def) in exprs.iter().filter(|(expr, _)| !is_literal(expr)) {
+        let type_id = col_def.type_id;
+        let is_preferred = cast::cast_context(target, type_id) == Some(CastContext::Implicit)
+            && cast::cast_context(type_id, target) != Some(CastContext::Implicit);
+        if type_id != TypeId::Unknown && (target == TypeId::Unknown || is_preferred) {
+            target = type_id;
+        }
+    }
+    if target == TypeId::Unknown && exprs.iter().any(|(expr, _)| is_literal(expr)) {
+        target = TypeId::Text;
+    }
+
+    for (expr, col_def) in exprs.iter_mut() {
+        if col_def.type_id == target || col_def.type_id == TypeId::Unknown {
+            continue;
+        }
+        if is_literal(expr) {
+            coerce_string_literal(expr, col_def, target)?;
+        } else {
+            coerce_implicitly(expr, col_def, target);
+        }
+        if col_def.type_id != target {
+            return Err(Error::msg(format!(
+                "{} types {} and {} cannot be matched",
+                context, target, col_def.type_id
+            )));
+        }
+    }
+    Ok(target)
+}
+
+/// Returns the expressions, which produce a column of a query. For a set operation, these are
+/// the expressions of both inputs
+fn query_column_expressions(query: &mut Query, col: usize) -> Vec<&mut LogicalExpr> {
+    if let Some(set_operation) = query.set_operation.as_mut() {
+        let SetOperation {
+            op: _,
+            all: _,
+            left,
+            right,
+            working_table: _,
+        } = &mut **set_operation;
+        let mut expressions = query_column_expressions(left, col);
+        expressions.extend(query_column_expressions(right, col));
+        expressions
+    } else if !query.values.is_empty() {
+        query.values.iter_mut().map(|row| &mut row[col]).collect()
+    } else {
+        vec![&mut query.projections[col]]
+    }
+}
+
+/// Names the output columns of a CTE, optionally by the given column names
+fn name_cte_columns(query: &mut Query, name: &str, columns: Vec<String>) -> Result<()> {
+    let mut output_columns = query.output_schema.columns().to_vec();
+    if columns.len() > output_columns.len() {
+        return Err(Error::msg(format!(
+            "WITH query {} has {} columns available but {} columns specified",
+            name,
+            output_columns.len(),
+            columns.len()
+        )));
+    }
+    // a referenced column is named without its table, e.g. `t.col` can be referenced as
+    // `cte.col` instead of `cte."t.col"`
+    for (col, col_def) in output_columns.iter_mut().enumerate() {
+        if let LogicalExpr::Column(path) = &*query_column_expressions(query, col)[0] {
+            if col_def.column_name == path.join(".") {
+                col_def.column_name = path.last().unwrap().clone();
+            }
+        }
+    }
+    for (col_def, column_name) in output_columns.iter_mut().zip(columns) {
+        col_def.column_name = column_name;
+    }
+    query.output_schema = Schema::new(output_columns);
+    Ok(())
+}
+
+/// Sets the type of a column in the output schema of a query and of its set operation inputs
+fn set_query_column_type(query: &mut Query, col: usize, type_id: TypeId) {
+    let mut output_columns = query.output_schema.columns().to_vec();
+    output_columns[col].type_id = type_id;
+    query.output_schema = Schema::new(output_columns);
+    if let Some(set_operation) = query.set_operation.as_mut() {
+        set_query_column_type(&mut set_operation.left, col, type_id);
+        set_query_column_type(&mut set_operation.right, col, type_id);
+    }
+}
+
+/// Unifies the column types of both inputs of a set operation by coercing one side to the
+/// type of the other. Returns the output columns of the set operation
+fn unify_set_operation_columns(
+    op: SetOperator,
+    left: &mut Query,
+    right: &mut Query,
+) -> Result<Vec<ColumnDefinition>> {
+    let mut output_columns = vec![];
+    for col in 0..left.output_schema.columns().len() {
+        let left_type = left.output_schema.columns()[col].type_id;
+        let right_type = right.output_schema.columns()[col].type_id;
+        if left_type != right_type
+            && !coerce_query_column(right, col, left_type)?
+            && !coerce_query_column(left, col, right_type)?
+        {
+            return Err(Error::msg(format!(
+                "{} types {} and {} cannot be matched",
+                op, left_type, right_type
+            )));
+        }
+
+        // like in Postgres, the column names are taken from the left input
+        let mut col_def = left.output_schema.columns()[col].clone();
+        let right_col = &right.output_schema.columns()[col];
+        col_def.not_null = col_def.not_null && right_col.not_null;
+        if col_def.text_length != right_col.text_length {
+            col_def.text_length = None;
+        }
+        output_columns.push(col_def);
+    }
+    Ok(output_columns)
+}
+
+/// Converts a column of a set operation input to the target type. Like for INSERT, a column
+/// consisting only of string literals is converted directly, other columns only if the
+/// coercion table allows an implicit cast. Returns whether the column was converted
+fn coerce_query_column(query: &mut Query, col: usize, target: TypeId) -> Result<bool> {
+    let mut col_def = query.output_schema.columns()[col].clone();
+    let mut expressions = query_column_expressions(query, col);
+    let only_literals = expressions
+        .iter()
+        .all(|expr| matches!(expr, LogicalExpr::String(_) | LogicalExpr::Null));
+    if col_def.type_id == TypeId::Text && only_literals {
+        for expr in expressions.iter_mut() {
+            coerce_string_literal(expr, &mut col_def, target)?;
+        }
+    } else if cast::cast_context(col_def.type_id, target) == Some(CastContext::Implicit) {
+        for expr in expressions.iter_mut() {
+            coerce_implicitly(expr, &mut col_def.clone(), target);
+        }
+    } else {
+        return Ok(false);
+    }
+    set_query_column_type(query, col, target);
+    Ok(true)
+}
+
+/// Returns the projection at a 1-based position of the select list, which can be referenced
+/// like this in some clauses, e.g. GROUP BY 1
+fn projection_at_position(
+    projections: &[LogicalExpr],
+    position: &str,
+    clause: &str,
+) -> Result<LogicalExpr> {
+    position
+        .parse::<usize>()
+        .ok()
+        .and_then(|position| position.checked_sub(1))
+        .and_then(|idx| projections.get(idx))
+        .cloned()
+        .ok_or_else(|| {
+            Error::msg(format!(
+                "{} position {} is not in select list",
+                clause, position
+            ))
+        })
+}
+
+/// Returns the projection of an output column, which must exist, by its name, e.g. for
+/// SELECT a % 10 AS k ... GROUP BY k
+fn projection_with_name(
+    projections: &[LogicalExpr],
+    output_columns: &[ColumnDefinition],
+    name: &str,
+    clause: &str,
+) -> Result<LogicalExpr> {
+    let mut matching = output_columns
+        .iter()
+        .zip(projections)
+        .filter(|(col, _)| col.column_name == name)
+        .map(|(_, expr)| expr);
+    let expr = matching.next().unwrap();
+    if matching.any(|other| other != expr) {
+        return Err(Error::msg(format!(
+            "{} column {} is ambiguous",
+            clause, name
+        )));
+    }
+    Ok(expr.clone())
+}
+
+/// Converts a value, which is known at analysis time, to a literal expression
+fn literal_expr(value: Value) -> LogicalExpr {
+    match value {
+        Value::Boolean(val) => LogicalExpr::Boolean(val),
+        Value::Integer(val) => LogicalExpr::Integer(val),
+        Value::String(val) => LogicalExpr::String(val),
+        Value::Date(val) => LogicalExpr::Date(val),
+        Value::Time(val) => LogicalExpr::Time(val),
+        Value::Timestamp(val) => LogicalExpr::Timestamp(val),
+        Value::Interval(val) => LogicalExpr::Interval(val),
+        Value::Bytea(val) => LogicalExpr::Bytea(val),
+        Value::Uuid(val) => LogicalExpr::Uuid(val),
+        Value::Json(val) => LogicalExpr::Json(val),
+        Value::Array(elements) => {
+            LogicalExpr::Array(elements.into_iter().map(literal_expr).collect())
+        }
+        Value::Null => LogicalExpr::Null,
+    }
+}
+
 pub struct Analyzer<'a> {
     catalog: &'a Catalog,
+    /// the scopes of the queries enclosing the subquery being analyzed, innermost last.
+    /// Columns of these scopes can be referenced by correlated subqueries
+    outer_scopes: RefCell<Vec<TableReference>>,
+    /// the common table expressions which can be referenced, innermost last
+    ctes: RefCell<Vec<CommonTableExpression>>,
+    next_cte_id: Cell<usize>,
+    /// the ids of the recursive CTEs, whose recursive term is being analyzed, together with
+    /// the number of outer scopes at their definition
+    working_tables: RefCell<Vec<(usize, usize)>>,
 }
 
 impl<'a> Analyzer<'a> {
     pub fn new(catalog: &'a Catalog) -> Self {
-        Self { catalog }
+        Self {
+            catalog,
+            outer_scopes: RefCell::new(vec![]),
+            ctes: RefCell::new(vec![]),
+            next_cte_id: Cell::new(0),
+            working_tables: RefCell::new(vec![]),
+        }
     }
 
     pub fn analyze(&self, query: ast::Statement) -> Result<LogicalPlan> {
@@ -64,14 +390,19 @@ impl<'a> Analyzer<'a> {
             .into_iter()
             .map(|(column, expression)| {
                 let (col_expr, col_def) =
-                    Self::analyze_expression(ExprNode::Identifier(column), &table)?;
+                    self.analyze_expression(ExprNode::Identifier(column), &table)?;
                 let column: Vec<String> = match col_expr {
                     LogicalExpr::Column(col) => col,
                     _ => unreachable!(),
                 };
-                let (value_expr, value_def) = Self::analyze_expression(expression, &table)?;
-
-                if value_def.type_id != TypeId::Unknown && value_def.type_id != col_def.type_id {
+                let (mut value_expr, mut value_def) =
+                    self.analyze_expression(expression, &table)?;
+                coerce_string_literal(&mut value_expr, &mut value_def, col_def.type_id)?;
+
+                if value_def.type_id != TypeId::Unknown
+                    && value_def.type_id != col_def.type_id
+                    && !coerce_implicitly(&mut value_expr, &mut value_def, col_def.type_id)
+                {
                     return Err(Error::msg(format!(
                         "Cannot set value for column '{}'. Left type '{}', right type '{}'",
                         column.join("."),
@@ -91,7 +422,7 @@ impl<'a> Analyzer<'a> {
             .collect::<Result<HashMap<_, _>>>()?;
 
         let filter = if let Some(filter_expr) = filter {
-            let (expr, col_def) = Self::analyze_expression(filter_expr, &table)?;
+            let (expr, col_def) = self.analyze_expression(filter_expr, &table)?;
             if col_def.type_id != TypeId::Unknown && col_def.type_id != TypeId::Boolean {
                 return Err(Error::msg(format!(
                     "WHERE condition must evaluate to boolean, but evaluates to {}",
@@ -114,7 +445,7 @@ impl<'a> Analyzer<'a> {
         let table = self.analyze_table(from)?;
 
         let filter = if let Some(filter_expr) = filter {
-            let (expr, col_def) = Self::analyze_expression(filter_expr, &table)?;
+            let (expr, col_def) = self.analyze_expression(filter_expr, &table)?;
             if col_def.type_id != TypeId::Unknown && col_def.type_id != TypeId::Boolean {
                 return Err(Error::msg(format!(
                     "WHERE condition must evaluate to boolean, but evaluates to {}",
@@ -143,7 +474,7 @@ impl<'a> Analyzer<'a> {
             _ => unreachable!(),
         };
 
-        let query = self.analyze_select(select)?;
+        let mut query = self.analyze_select(select)?;
 
         if schema.columns().len() != query.output_schema.columns().len() {
             return Err(Error::msg(format!(
@@ -152,6 +483,9 @@ impl<'a> Analyzer<'a> {
                 query.output_schema.columns().len()
             )));
         }
+        if !query.values.is_empty() {
+            Self::coerce_values_literals(&mut query, &schema)?;
+        }
         for (col_offset, (target_col, value_col)) in schema
             .columns()
             .iter()
@@ -182,15 +516,245 @@ impl<'a> Analyzer<'a> {
         })
     }
 
-    fn analyze_select(&self, select: SelectStatement) -> Result<Query> {
+    /// Converts the string literals of a VALUES list to the column types of the insert target,
+    /// as long as a column consists only of string literals (and NULLs)
+    fn coerce_values_literals(query: &mut Query, target_schema: &Schema) -> Result<()> {
+        let mut output_columns = query.output_schema.columns().to_vec();
+        for (col, (target_col, value_col)) in target_schema
+            .columns()
+            .iter()
+            .zip(output_columns.iter_mut())
+            .enumerate()
+        {
+            let only_literals = query
+                .values
+                .iter()
+                .all(|row| matches!(row[col], LogicalExpr::String(_) | LogicalExpr::Null));
+            if value_col.type_id() != TypeId::Text || !only_literals {
+                continue;
+            }
+
+            let mut coerced_def = value_col.clone();
+            for row in query.values.iter_mut() {
+                coerce_string_literal(&mut row[col], &mut coerced_def, target_col.type_id())?;
+            }
+            value_col.type_id = coerced_def.type_id;
+        }
+        query.output_schema = Schema::new(output_columns);
+        Ok(())
+    }
+
+    fn analyze_select(&self, mut select: SelectStatement) -> Result<Query> {
+        let with = std::mem::take(&mut select.with);
+        if with.is_empty() {
+            return self.analyze_select_without_ctes(select);
+        }
+
+        // the CTEs are only visible within this query, including its subqueries
+        let scope_start = self.ctes.borrow().len();
+        let query = self
+            .analyze_ctes(with)
+            .and_then(|()| self.analyze_select_without_ctes(select));
+        let ctes = self.ctes.borrow_mut().split_off(scope_start);
+        let mut query = query?;
+        query.ctes = ctes;
+        Ok(query)
+    }
+
+    /// Analyzes the common table expressions of a WITH clause and makes them visible to the
+    /// query. Each of them can reference the ones before it
+    fn analyze_ctes(&self, with: Vec<ast::CommonTableExpression>) -> Result<()> {
+        let scope_start = self.ctes.borrow().len();
+        for cte in with {
+            let ast::CommonTableExpression {
+                name,
+                columns,
+                query,
+                recursive,
+            } = cte;
+            if self.ctes.borrow()[scope_start..]
+                .iter()
+                .any(|other| other.name == name)
+            {
+                return Err(Error::msg(format!(
+                    "WITH query name {} specified more than once",
+                    name
+                )));
+            }
+
+            let id = self.next_cte_id.get();
+            self.next_cte_id.set(id + 1);
+            let query = if recursive {
+                self.analyze_recursive_cte(id, &name, columns, query)?
+            } else {
+                let mut query = self.analyze_select(query)?;
+                name_cte_columns(&mut query, &name, columns)?;
+                query
+            };
+
+            self.ctes.borrow_mut().push(CommonTableExpression {
+                id,
+                name,
+                query,
+                references: 0,
+            });
+        }
+        Ok(())
+    }
+
+    /// Analyzes the query of a CTE of WITH RECURSIVE. If it is a UNION, whose right input
+    /// references the CTE, the UNION is evaluated iteratively: the right input is evaluated
+    /// on the rows added by the previous iteration, starting with the rows of the left input,
+    /// until no more rows are added
+    fn analyze_recursive_cte(
+        &self,
+        id: usize,
+        name: &str,
+        columns: Vec<String>,
+        select: SelectStatement,
+    ) -> Result<Query> {
+        let is_union = matches!(
+            &select.set_operation,
+            Some(set_operation) if set_operation.op == SetOperator::Union
+        );
+        if !is_union || !select.with.is_empty() {
+            let mut query = self.analyze_select(select)?;
+            name_cte_columns(&mut query, name, columns)?;
+            return Ok(query);
+        }
+        if !select.order_by.is_empty() || select.limit.is_some() || select.offset.is_some() {
+            return Err(Error::msg(format!(
+                "ORDER BY, LIMIT and OFFSET are not supported for the recursive query {}",
+                name
+            )));
+        }
+        let ast::SetOperation {
+            op,
+            all,
+            left,
+            right,
+        } = select.set_operation.unwrap();
+
+        // the left input determines the columns of the CTE
+        let mut left = self.analyze_select(*left)?;
+        name_cte_columns(&mut left, name, columns)?;
+        let mut working_table = left.clone();
+        working_table.output_schema = Schema::new(
+            left.output_schema
+                .columns()
+                .iter()
+                .map(|col_def| {
+                    let mut col_def = col_def.clone();
+                    col_def.not_null = false;
+                    col_def
+                })
+                .collect(),
+        );
+
+        self.ctes.borrow_mut().push(CommonTableExpression {
+            id,
+            name: name.to_owned(),
+            query: working_table,
+            references: 0,
+        });
+        self.working_tables
+            .borrow_mut()
+            .push((id, self.outer_scopes.borrow().len()));
+        let right = self.analyze_select(*right);
+        self.working_tables.borrow_mut().pop();
+        let self_references = self.ctes.borrow_mut().pop().unwrap().references;
+        let mut right = right?;
+
+        let column_count = left.output_schema.columns().len();
+        if column_count != right.output_schema.columns().len() {
+            return Err(Error::msg(format!(
+                "Each {} query must have the same number of columns",
+                op
+            )));
+        }
+        if self_references == 0 {
+            let output_columns = unify_set_operation_columns(op, &mut left, &mut right)?;
+            return Ok(Query::from_set_operation(
+                SetOperation {
+                    op,
+                    all,
+                    left,
+                    right,
+                    working_table: None,
+                },
+                output_columns,
+            ));
+        }
+        if self_references > 1 {
+            return Err(Error::msg(format!(
+                "Recursive reference to query {} must not appear more than once",
+                name
+            )));
+        }
+
+        // the rows of the right input are added to the working table, so its column types are
+        // already fixed by the left input
+        let mut output_columns = vec![];
+        for col in 0..column_count {
+            let left_col = &left.output_schema.columns()[col];
+            let right_type = right.output_schema.columns()[col].type_id;
+            if left_col.type_id != right_type
+                && !coerce_query_column(&mut right, col, left_col.type_id)?
+            {
+                return Err(Error::msg(format!(
+                    "Recursive query {} column {} has type {} in non-recursive term but type {} \
+                     overall",
+                    name,
+                    col + 1,
+                    left_col.type_id,
+                    right_type
+                )));
+            }
+            let mut col_def = left_col.clone();
+            let right_col = &right.output_schema.columns()[col];
+            col_def.not_null = col_def.not_null && right_col.not_null;
+            if col_def.text_length != right_col.text_length {
+                col_def.text_length = None;
+            }
+            output_columns.push(col_def);
+        }
+        Ok(Query::from_set_operation(
+            SetOperation {
+                op,
+                all,
+                left,
+                right,
+                working_table: Some(id),
+            },
+            output_columns,
+        ))
+    }
+
+    fn analyze_select_without_ctes(&self, select: SelectStatement) -> Result<Query> {
         let SelectStatement {
+            with: _,
             values,
+            distinct,
+            set_operation,
             projections,
             from,
             filter,
+            group_by,
+            having,
+            order_by,
+            limit,
+            offset,
         } = select;
+        if let Some(set_operation) = set_operation {
+            return self.analyze_set_operation(set_operation, order_by, limit, offset);
+        }
         if let Some(values) = values {
-            return Self::analyze_values(values);
+            if !order_by.is_empty() || limit.is_some() || offset.is_some() {
+                return Err(Error::msg(
+                    "ORDER BY, LIMIT and OFFSET are not supported for VALUES",
+                ));
+            }
+            return self.analyze_values(values);
         }
 
         let table = self.analyze_tables(from)?;
@@ -198,55 +762,359 @@ impl<'a> Analyzer<'a> {
 
         let mut projections = vec![];
         let mut output_columns = vec![];
-
-        let mut has_aggregations = false;
-        let mut referenced_column = None;
         for (col, (expr, mut col_def)) in projections_with_specification.into_iter().enumerate() {
-            has_aggregations |= expr.has_aggregation();
-            if referenced_column.is_none() {
-                referenced_column = expr.find_any_referenced_column();
-            }
-
-            match &referenced_column {
-                Some(column) if has_aggregations => {
-                    // don't allow column references if projections contains any aggregations
-                    // disallow e.g. SELECT count(col_a), col_a from table
-                    return Err(Error::msg(format!(
-                        "column '{}' must be used in an aggregation",
-                        column
-                    )));
-                }
-                _ => (),
-            }
-
             projections.push(expr);
             col_def.column_offset = col as u8;
             output_columns.push(col_def);
         }
 
         let filter = if let Some(filter_expr) = filter {
-            let (expr, col_def) = Self::analyze_expression(filter_expr, &table)?;
+            let (expr, col_def) = self.analyze_expression(filter_expr, &table)?;
             if col_def.type_id != TypeId::Unknown && col_def.type_id != TypeId::Boolean {
                 return Err(Error::msg(format!(
                     "WHERE condition must evaluate to boolean, but evaluates to {}",
                     col_def.type_id
                 )));
             }
+            if expr.has_aggregation() {
+                return Err(Error::msg("Aggregations are not allowed in WHERE"));
+            }
+            if expr.has_window_function() {
+                return Err(Error::msg("Window functions are not allowed in WHERE"));
+            }
             split_expression(expr)
         } else {
             vec![]
         };
 
+        let group_by = group_by
+            .into_iter()
+            .map(|expr| {
+                let expr = match expr {
+                    // like in Postgres, GROUP BY 1 refers to the first projection
+                    ExprNode::Number(position) => {
+                        projection_at_position(&projections, &position, "GROUP BY")?
+                    }
+                    // an output column, unless the name is also an input column, which takes
+                    // precedence in GROUP BY
+                    ExprNode::Identifier(name)
+                        if Self::identify_column(&table, None, &name)?.is_none()
+                            && output_columns.iter().any(|col| col.column_name == name) =>
+                    {
+                        projection_with_name(&projections, &output_columns, &name, "GROUP BY")?
+                    }
+                    expr => self.analyze_expression(expr, &table)?.0,
+                };
+                if expr.has_aggregation() {
+                    return Err(Error::msg("Aggregations are not allowed in GROUP BY"));
+                }
+                if expr.has_window_function() {
+                    return Err(Error::msg("Window functions are not allowed in GROUP BY"));
+                }
+                Ok(expr)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let having = if let Some(having_expr) = having {
+            let (expr, col_def) = self.analyze_expression(having_expr, &table)?;
+            if col_def.type_id != TypeId::Unknown && col_def.type_id != TypeId::Boolean {
+                return Err(Error::msg(format!(
+                    "HAVING condition must evaluate to boolean, but evaluates to {}",
+                    col_def.type_id
+                )));
+            }
+            if expr.has_window_function() {
+                return Err(Error::msg("Window functions are not allowed in HAVING"));
+            }
+            split_expression(expr)
+        } else {
+            vec![]
+        };
+
+        let order_by = order_by
+            .into_iter()
+            .map(|order_by_expr| {
+                let ast::OrderByExpr {
+                    expr,
+                    asc,
+                    nulls_first,
+                } = order_by_expr;
+                let (expr, type_id) = self.analyze_output_reference(
+                    expr,
+                    &projections,
+                    &output_columns,
+                    &table,
+                    "ORDER BY",
+                )?;
+                if matches!(
+                    type_id,
+                    TypeId::Json | TypeId::IntegerArray | TypeId::TextArray
+                ) {
+                    return Err(Error::msg(format!(
+                        "Cannot order by values of type {}",
+                        type_id
+                    )));
+                }
+
+                // like in Postgres, NULL values are considered larger than any other value
+                let ascending = asc.unwrap_or(true);
+                Ok(OrderByExpr {
+                    expr,
+                    ascending,
+                    nulls_first: nulls_first.unwrap_or(!ascending),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let distinct = match distinct {
+            Some(ast::Distinct::AllColumns) => {
+                // like in Postgres, the sorted values have to be part of the distinct rows
+                if order_by
+                    .iter()
+                    .any(|order| !projections.contains(&order.expr))
+                {
+                    return Err(Error::msg(
+                        "For SELECT DISTINCT, ORDER BY expressions must appear in select list",
+                    ));
+                }
+                projections.clone()
+            }
+            Some(ast::Distinct::On(expressions)) => {
+                let distinct = expressions
+                    .into_iter()
+                    .map(|expr| {
+                        self.analyze_output_reference(
+                            expr,
+                            &projections,
+                            &output_columns,
+                            &table,
+                            "DISTINCT ON",
+                        )
+                        .map(|(expr, _)| expr)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                // the first row of each group is determined by the ORDER BY expressions, which
+                // have to start with the DISTINCT ON expressions (in any order)
+                let leading_order_by = order_by
+                    .iter()
+                    .take(distinct.len())
+                    .map(|order| &order.expr)
+                    .collect::<Vec<_>>();
+                let matches_order_by = order_by.is_empty()
+                    || (leading_order_by.len() == distinct.len()
+                        && leading_order_by.iter().all(|expr| distinct.contains(expr))
+                        && distinct.iter().all(|expr| leading_order_by.contains(&expr)));
+                if !matches_order_by {
+                    return Err(Error::msg(
+                        "SELECT DISTINCT ON expressions must match initial ORDER BY expressions",
+                    ));
+                }
+                distinct
+            }
+            None => vec![],
+        };
+
+        // in a grouped query, columns can only be referenced through the grouping expressions
+        // or within aggregations, disallow e.g. SELECT count(col_a), col_a FROM table
+        let is_grouped = !group_by.is_empty()
+            || !having.is_empty()
+            || projections.iter().any(|expr| expr.has_aggregation())
+            || order_by.iter().any(|order| order.expr.has_aggregation())
+            || distinct.iter().any(|expr| expr.has_aggregation());
+        if is_grouped {
+            let ungrouped_column = projections
+                .iter()
+                .chain(having.iter())
+                .chain(order_by.iter().map(|order| &order.expr))
+                .chain(distinct.iter())
+                .find_map(|expr| expr.find_ungrouped_column(&group_by));
+            if let Some(column) = ungrouped_column {
+                return Err(Error::msg(format!(
+                    "column '{}' must appear in the GROUP BY clause or be used in an aggregation",
+                    column
+                )));
+            }
+        }
+
+        let limit = limit
+            .map(|expr| self.analyze_row_count(expr, "LIMIT"))
+            .transpose()?;
+        let offset = offset
+            .map(|expr| self.analyze_row_count(expr, "OFFSET"))
+            .transpose()?;
+
         Ok(Query {
             values: vec![],
             from: table,
             projections,
             filter,
+            group_by,
+            having,
+            order_by,
+            distinct,
+            limit,
+            offset,
+            ctes: vec![],
+            set_operation: None,
             output_schema: Schema::new(output_columns),
         })
     }
 
-    fn analyze_values(values: Vec<Vec<ast::ExprNode>>) -> Result<Query> {
+    /// Analyzes UNION, INTERSECT or EXCEPT. Both inputs must have the same number of columns,
+    /// whose types are unified by coercing one side to the type of the other
+    fn analyze_set_operation(
+        &self,
+        set_operation: ast::SetOperation,
+        order_by: Vec<ast::OrderByExpr>,
+        limit: Option<ExprNode>,
+        offset: Option<ExprNode>,
+    ) -> Result<Query> {
+        let ast::SetOperation {
+            op,
+            all,
+            left,
+            right,
+        } = set_operation;
+        let mut left = self.analyze_select(*left)?;
+        let mut right = self.analyze_select(*right)?;
+
+        if left.output_schema.columns().len() != right.output_schema.columns().len() {
+            return Err(Error::msg(format!(
+                "Each {} query must have the same number of columns",
+                op
+            )));
+        }
+        let output_columns = unify_set_operation_columns(op, &mut left, &mut right)?;
+
+        // the result of a set operation can only be ordered by its output columns, which are
+        // referenced by their position in the physical plan
+        let order_by = order_by
+            .into_iter()
+            .map(|order_by_expr| {
+                let ast::OrderByExpr {
+                    expr,
+                    asc,
+                    nulls_first,
+                } = order_by_expr;
+                let idx = match &expr {
+                    ExprNode::Number(position) => position
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|position| position.checked_sub(1))
+                        .filter(|idx| *idx < output_columns.len()),
+                    ExprNode::Identifier(name) => output_columns
+                        .iter()
+                        .position(|col| &col.column_name == name),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    Error::msg(format!(
+                        "ORDER BY on a {} result must reference an output column by name or \
+                         position",
+                        op
+                    ))
+                })?;
+                let type_id = output_columns[idx].type_id;
+                if matches!(
+                    type_id,
+                    TypeId::Json | TypeId::IntegerArray | TypeId::TextArray
+                ) {
+                    return Err(Error::msg(format!(
+                        "Cannot order by values of type {}",
+                        type_id
+                    )));
+                }
+
+                let ascending = asc.unwrap_or(true);
+                Ok(OrderByExpr {
+                    expr: LogicalExpr::Column(vec![(idx + 1).to_string()]),
+                    ascending,
+                    nulls_first: nulls_first.unwrap_or(!ascending),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let limit = limit
+            .map(|expr| self.analyze_row_count(expr, "LIMIT"))
+            .transpose()?;
+        let offset = offset
+            .map(|expr| self.analyze_row_count(expr, "OFFSET"))
+            .transpose()?;
+
+        let mut query = Query::from_set_operation(
+            SetOperation {
+                op,
+                all,
+                left,
+                right,
+                working_table: None,
+            },
+            output_columns,
+        );
+        query.order_by = order_by;
+        query.limit = limit;
+        query.offset = offset;
+        Ok(query)
+    }
+
+    /// Analyzes an expression of ORDER BY or DISTINCT ON. Besides input columns, these can
+    /// reference the output columns by position or by name,
+    /// e.g. SELECT a + b AS c FROM table ORDER BY c
+    fn analyze_output_reference(
+        &self,
+        expr: ExprNode,
+        projections: &[LogicalExpr],
+        output_columns: &[ColumnDefinition],
+        table: &TableReference,
+        clause: &str,
+    ) -> Result<(LogicalExpr, TypeId)> {
+        match expr {
+            ExprNode::Number(position) => {
+                let expr = projection_at_position(projections, &position, clause)?;
+                let position = position.parse::<usize>().unwrap();
+                Ok((expr, output_columns[position - 1].type_id))
+            }
+            ExprNode::Identifier(name)
+                if output_columns.iter().any(|col| col.column_name == name) =>
+            {
+                let idx = output_columns
+                    .iter()
+                    .position(|col| col.column_name == name)
+                    .unwrap();
+                Ok((projections[idx].clone(), output_columns[idx].type_id))
+            }
+            expr => {
+                let (expr, col_def) = self.analyze_expression(expr, table)?;
+                Ok((expr, col_def.type_id))
+            }
+        }
+    }
+
+    /// Analyzes the argument of LIMIT or OFFSET, which can't reference any columns
+    fn analyze_row_count(&self, expr: ExprNode, clause: &str) -> Result<LogicalExpr> {
+        let (expr, col_def) = self.analyze_expression(expr, &TableReference::EmptyTable)?;
+        if col_def.type_id != TypeId::Unknown && col_def.type_id != TypeId::Integer {
+            return Err(Error::msg(format!(
+                "Argument of {} must be of type integer, not {}",
+                clause, col_def.type_id
+            )));
+        }
+        if expr.has_aggregation() {
+            return Err(Error::msg(format!(
+                "Aggregations are not allowed in {}",
+                clause
+            )));
+        }
+        if expr.has_window_function() {
+            return Err(Error::msg(format!(
+                "Window functions are not allowed in {}",
+                clause
+            )));
+        }
+        Ok(expr)
+    }
+
+    fn analyze_values(&self, values: Vec<Vec<ast::ExprNode>>) -> Result<Query> {
         let mut expressions = vec![];
         let mut output_columns = vec![];
 
@@ -255,7 +1123,7 @@ impl<'a> Analyzer<'a> {
             let mut current_expressions = vec![];
             for (col, value) in current_values.into_iter().enumerate() {
                 let (expr, mut col_def) =
-                    Self::analyze_expression(value, &TableReference::EmptyTable)?;
+                    self.analyze_expression(value, &TableReference::EmptyTable)?;
 
                 if !first_row_added {
                     col_def.column_name = format!("col_{}", col);
@@ -300,7 +1168,15 @@ impl<'a> Analyzer<'a> {
             projections: vec![],
             filter: vec![],
             values: expressions,
+            ctes: vec![],
+            set_operation: None,
             output_schema: Schema::new(output_columns),
+            group_by: vec![],
+            having: vec![],
+            order_by: vec![],
+            distinct: vec![],
+            limit: None,
+            offset: None,
         })
     }
 
@@ -316,6 +1192,7 @@ impl<'a> Analyzer<'a> {
                 right: Box::new(right),
                 join_type: JoinType::Inner,
                 on: vec![],
+                using: vec![],
             };
             while let Some(table) = tables.pop_front() {
                 let table = self.analyze_table(table)?;
@@ -325,6 +1202,7 @@ impl<'a> Analyzer<'a> {
                     right: Box::new(table),
                     join_type: JoinType::Inner,
                     on: vec![],
+                    using: vec![],
                 }
             }
 
@@ -337,6 +1215,33 @@ impl<'a> Analyzer<'a> {
     fn analyze_table(&self, table: TableNode) -> Result<TableReference> {
         match table {
             TableNode::TableReference { name, alias } => {
+                // CTEs shadow the tables of the catalog
+                if let Some(cte) = self
+                    .ctes
+                    .borrow_mut()
+                    .iter_mut()
+                    .rev()
+                    .find(|cte| cte.name == name)
+                {
+                    // the working table is different in every iteration, but subqueries are
+                    // only evaluated once
+                    let in_subquery = self.working_tables.borrow().iter().any(|(id, depth)| {
+                        *id == cte.id && *depth != self.outer_scopes.borrow().len()
+                    });
+                    if in_subquery {
+                        return Err(Error::msg(format!(
+                            "Recursive reference to query {} must not appear within a subquery",
+                            name
+                        )));
+                    }
+                    cte.references += 1;
+                    return Ok(TableReference::Cte {
+                        cte_id: cte.id,
+                        name: alias.unwrap_or(name),
+                        schema: cte.query.output_schema.clone(),
+                        filter: vec![],
+                    });
+                }
                 let table_id = self
                     .catalog
                     .get_table_id(&name)
@@ -353,17 +1258,32 @@ impl<'a> Analyzer<'a> {
                 left,
                 right,
                 join_type,
-                on,
+                constraint,
             } => {
                 let left = self.analyze_table(*left)?;
                 let right = self.analyze_table(*right)?;
+                let on = match constraint {
+                    JoinConstraint::On(on) => on,
+                    JoinConstraint::Using(using) => {
+                        return Self::analyze_using_join(left, right, join_type, using)
+                    }
+                    JoinConstraint::Natural => {
+                        let right_columns = Self::get_column_names(&right);
+                        let using = Self::get_column_names(&left)
+                            .into_iter()
+                            .filter(|column| right_columns.contains(column))
+                            .collect();
+                        return Self::analyze_using_join(left, right, join_type, using);
+                    }
+                };
                 let mut result_table = TableReference::Join {
                     left: Box::new(left),
                     right: Box::new(right),
                     join_type,
                     on: vec![],
+                    using: vec![],
                 };
-                let (on_expr, on_def) = Self::analyze_expression(on, &result_table)?;
+                let (on_expr, on_def) = self.analyze_expression(on, &result_table)?;
 
                 if on_def.type_id != TypeId::Boolean && on_def.type_id != TypeId::Unknown {
                     return Err(Error::msg(format!(
@@ -378,12 +1298,44 @@ impl<'a> Analyzer<'a> {
                         right: _,
                         join_type: _,
                         on,
+                        using: _,
                     } => on.push(on_expr),
                     _ => unreachable!(),
                 };
 
                 Ok(result_table)
             }
+            TableNode::TableFunction { name, args, alias } => {
+                let func = TableFunc::is_table_func(&name)
+                    .ok_or_else(|| Error::msg(format!("Cannot find table function {}.", name)))?;
+                // the arguments cannot reference other tables of the FROM clause
+                let (args, arg_types): (Vec<_>, Vec<_>) = args
+                    .into_iter()
+                    .map(|arg| {
+                        self.analyze_expression(arg, &TableReference::EmptyTable)
+                            .map(|(expr, col_def)| (expr, col_def.type_id))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+                    .into_iter()
+                    .unzip();
+                let column_type = func.output_column_type(&arg_types)?;
+
+                // like in Postgres, the column is named after the alias if there is one
+                let name = alias.unwrap_or(name);
+                let schema = Schema::new(vec![ColumnDefinition::new(
+                    column_type,
+                    name.clone(),
+                    0,
+                    false,
+                )]);
+                Ok(TableReference::TableFunction {
+                    func,
+                    args,
+                    name,
+                    schema,
+                    filter: vec![],
+                })
+            }
             TableNode::CrossJoin { left, right } => {
                 let left = self.analyze_table(*left)?;
                 let right = self.analyze_table(*right)?;
@@ -393,6 +1345,7 @@ impl<'a> Analyzer<'a> {
                     right: Box::new(right),
                     join_type: JoinType::Inner,
                     on: vec![],
+                    using: vec![],
                 })
             }
         }
@@ -430,12 +1383,12 @@ impl<'a> Analyzer<'a> {
         match projection {
             Projection::UnnamedExpr(expr) => {
                 let alias = expr.to_string();
-                let (expr, mut col_def) = Self::analyze_expression(expr, scope)?;
+                let (expr, mut col_def) = self.analyze_expression(expr, scope)?;
                 col_def.column_name = alias;
                 Ok((expr, col_def))
             }
             Projection::NamedExpr { expr, alias } => {
-                let (expr, mut col_def) = Self::analyze_expression(expr, scope)?;
+                let (expr, mut col_def) = self.analyze_expression(expr, scope)?;
                 col_def.column_name = alias;
                 Ok((expr, col_def))
             }
@@ -446,12 +1399,16 @@ impl<'a> Analyzer<'a> {
     }
 
     fn analyze_expression(
+        &self,
         expr: ast::ExprNode,
         scope: &TableReference,
     ) -> Result<(LogicalExpr, ColumnDefinition)> {
         match expr {
             ExprNode::Identifier(column_name) => {
-                let column = Self::identify_column(scope, None, &column_name)?;
+                let column = match Self::identify_column(scope, None, &column_name)? {
+                    Some(column) => Some(column),
+                    None => self.identify_outer_column(None, &column_name)?,
+                };
                 if let Some(res) = column {
                     Ok(res)
                 } else {
@@ -459,7 +1416,10 @@ impl<'a> Analyzer<'a> {
                 }
             }
             ExprNode::QualifiedIdentifier(table, column_name) => {
-                let column = Self::identify_column(scope, Some(&table), &column_name)?;
+                let column = match Self::identify_column(scope, Some(&table), &column_name)? {
+                    Some(column) => Some(column),
+                    None => self.identify_outer_column(Some(&table), &column_name)?,
+                };
                 column.ok_or_else(|| {
                     Error::msg(format!("Coudl not find column {}.{}", table, column_name))
                 })
@@ -468,124 +1428,748 @@ impl<'a> Analyzer<'a> {
             ExprNode::Number(number) => {
                 let num = number.parse::<i32>()?;
                 Ok((
-                    LogicalExpr::Integer(num),
-                    ColumnDefinition::with_type_id(TypeId::Integer),
+                    LogicalExpr::Integer(num),
+                    ColumnDefinition::with_type_id(TypeId::Integer),
+                ))
+            }
+            ExprNode::String(s) => Ok((
+                LogicalExpr::String(s),
+                ColumnDefinition::with_type_id(TypeId::Text),
+            )),
+            ExprNode::Boolean(val) => Ok((
+                LogicalExpr::Boolean(val),
+                ColumnDefinition::with_type_id(TypeId::Boolean),
+            )),
+            ExprNode::TypedString { data_type, value } => {
+                let (expr, type_id) = match data_type {
+                    DataType::Date => (
+                        LogicalExpr::Date(datetime::parse_date(&value)?),
+                        TypeId::Date,
+                    ),
+                    DataType::Time => (
+                        LogicalExpr::Time(datetime::parse_time(&value)?),
+                        TypeId::Time,
+                    ),
+                    DataType::Timestamp => (
+                        LogicalExpr::Timestamp(datetime::parse_timestamp(&value)?),
+                        TypeId::Timestamp,
+                    ),
+                    DataType::Interval => (
+                        LogicalExpr::Interval(datetime::parse_interval(&value)?),
+                        TypeId::Interval,
+                    ),
+                    DataType::Bytea => {
+                        (LogicalExpr::Bytea(bytea::parse_hex(&value)?), TypeId::Bytea)
+                    }
+                    DataType::Uuid => (LogicalExpr::Uuid(uuid::parse_uuid(&value)?), TypeId::Uuid),
+                    DataType::Json => (LogicalExpr::Json(Json::parse(&value)?), TypeId::Json),
+                    data_type => {
+                        return Err(Error::msg(format!(
+                            "Typed literals are not supported for type {}",
+                            data_type
+                        )))
+                    }
+                };
+                Ok((expr, ColumnDefinition::with_type_id(type_id)))
+            }
+            ExprNode::CurrentDate => Ok((
+                LogicalExpr::CurrentDate,
+                ColumnDefinition::with_type_id(TypeId::Date),
+            )),
+            ExprNode::CurrentTimestamp => Ok((
+                LogicalExpr::CurrentTimestamp,
+                ColumnDefinition::with_type_id(TypeId::Timestamp),
+            )),
+            ExprNode::Extract { field, expr } => {
+                let (expr, col_def) = self.analyze_expression(*expr, scope)?;
+                let valid = match col_def.type_id {
+                    TypeId::Date | TypeId::Timestamp | TypeId::Unknown => true,
+                    TypeId::Time => matches!(
+                        field,
+                        DateTimeField::Hour
+                            | DateTimeField::Minute
+                            | DateTimeField::Second
+                            | DateTimeField::Epoch
+                    ),
+                    TypeId::Interval => !matches!(
+                        field,
+                        DateTimeField::Quarter
+                            | DateTimeField::DayOfWeek
+                            | DateTimeField::DayOfYear
+                    ),
+                    _ => false,
+                };
+                if !valid {
+                    return Err(Error::msg(format!(
+                        "Cannot extract '{}' from type {}",
+                        field, col_def.type_id
+                    )));
+                }
+                Ok((
+                    LogicalExpr::Extract {
+                        field,
+                        expr: Box::new(expr),
+                    },
+                    ColumnDefinition::with_type_id(TypeId::Integer),
+                ))
+            }
+            ExprNode::Grouping(expr) => self.analyze_expression(*expr, scope),
+            ExprNode::Binary { left, op, right } => {
+                let (mut left, mut left_def) = self.analyze_expression(*left, scope)?;
+                let (mut right, mut right_def) = self.analyze_expression(*right, scope)?;
+                // the right side of `->` is a key or an index, not a json value, and `||` converts
+                // other types to text
+                if !matches!(
+                    op,
+                    BinaryOperator::JsonGet | BinaryOperator::JsonGetText | BinaryOperator::Concat
+                ) {
+                    coerce_string_literal(&mut left, &mut left_def, right_def.type_id)?;
+                    coerce_string_literal(&mut right, &mut right_def, left_def.type_id)?;
+                }
+                let result_type = match op {
+                    BinaryOperator::Plus
+                    | BinaryOperator::Minus
+                    | BinaryOperator::Multiply
+                    | BinaryOperator::Divide
+                    | BinaryOperator::Modulo => {
+                        match arithmetic_result_type(left_def.type_id, op, right_def.type_id) {
+                            Some(type_id) => ColumnDefinition::with_type_id(type_id),
+                            None => {
+                                return Err(Error::msg(format!(
+                                    "Operator '{}' is not defined for types. Left: {}, Right: {}",
+                                    op, left_def.type_id, right_def.type_id
+                                )))
+                            }
+                        }
+                    }
+                    BinaryOperator::Eq
+                    | BinaryOperator::NotEq
+                    | BinaryOperator::Less
+                    | BinaryOperator::LessEq
+                    | BinaryOperator::Greater
+                    | BinaryOperator::GreaterEq
+                    | BinaryOperator::IsDistinctFrom
+                    | BinaryOperator::IsNotDistinctFrom => {
+                        if left_def.type_id != right_def.type_id
+                            && left_def.type_id != TypeId::Unknown
+                            && right_def.type_id != TypeId::Unknown
+                            && !coerce_implicitly(&mut left, &mut left_def, right_def.type_id)
+                            && !coerce_implicitly(&mut right, &mut right_def, left_def.type_id)
+                        {
+                            return Err(Error::msg(format!(
+                                "Arguments for '{}' must be of same type. Left: {}, Right: {}",
+                                op, left_def.type_id, right_def.type_id
+                            )));
+                        }
+                        let equality_only = left_def.type_id == TypeId::Json
+                            || left_def.type_id.element_type().is_some();
+                        if equality_only
+                            && !matches!(
+                                op,
+                                BinaryOperator::Eq
+                                    | BinaryOperator::NotEq
+                                    | BinaryOperator::IsDistinctFrom
+                                    | BinaryOperator::IsNotDistinctFrom
+                            )
+                        {
+                            return Err(Error::msg(format!(
+                                "Operator '{}' is not defined for type {}",
+                                op, left_def.type_id
+                            )));
+                        }
+                        strip_char_padding(vec![(&mut left, &left_def), (&mut right, &right_def)]);
+                        ColumnDefinition::with_type_id(TypeId::Boolean)
+                    }
+                    BinaryOperator::JsonGet | BinaryOperator::JsonGetText => {
+                        if ![TypeId::Json, TypeId::Unknown].contains(&left_def.type_id)
+                            || ![TypeId::Text, TypeId::Integer, TypeId::Unknown]
+                                .contains(&right_def.type_id)
+                        {
+                            return Err(Error::msg(format!(
+                                "Operator '{}' expects json on the left and a text key or an \
+                                 integer index on the right. Left: {}, Right: {}",
+                                op, left_def.type_id, right_def.type_id
+                            )));
+                        }
+                        let result_type = if op == BinaryOperator::JsonGet {
+                            TypeId::Json
+                        } else {
+                            TypeId::Text
+                        };
+                        ColumnDefinition::with_type_id(result_type)
+                    }
+                    BinaryOperator::JsonContains => {
+                        let valid_types = [TypeId::Json, TypeId::Unknown];
+                        if !valid_types.contains(&left_def.type_id)
+                            || !valid_types.contains(&right_def.type_id)
+                        {
+                            return Err(Error::msg(format!(
+                                "Arguments for '{}' must be of type json. Left: {}, Right: {}",
+                                op, left_def.type_id, right_def.type_id
+                            )));
+                        }
+                        ColumnDefinition::with_type_id(TypeId::Boolean)
+                    }
+                    BinaryOperator::Concat => {
+                        // like in Postgres, the other side is converted to text
+                        let is_text = |type_id| [TypeId::Text, TypeId::Unknown].contains(&type_id);
+                        let convertible =
+                            |type_id: TypeId| is_text(type_id) || type_id.element_type().is_none();
+                        if !(is_text(left_def.type_id) || is_text(right_def.type_id))
+                            || !convertible(left_def.type_id)
+                            || !convertible(right_def.type_id)
+                        {
+                            return Err(Error::msg(format!(
+                                "Operator '{}' is not defined for types. Left: {}, Right: {}",
+                                op, left_def.type_id, right_def.type_id
+                            )));
+                        }
+                        for (expr, col_def) in [(&mut left, &left_def), (&mut right, &right_def)] {
+                            if !is_text(col_def.type_id) {
+                                let inner = std::mem::replace(expr, LogicalExpr::Null);
+                                *expr = LogicalExpr::Cast {
+                                    expr: Box::new(inner),
+                                    type_id: TypeId::Text,
+                                    text_length: None,
+                                };
+                            }
+                        }
+                        ColumnDefinition::with_type_id(TypeId::Text)
+                    }
+                    BinaryOperator::Like | BinaryOperator::ILike => {
+                        unreachable!("LIKE is analyzed on its own")
+                    }
+                    BinaryOperator::And | BinaryOperator::Or => {
+                        let valid_types = [TypeId::Boolean, TypeId::Unknown];
+                        if !valid_types.contains(&left_def.type_id)
+                            || !valid_types.contains(&right_def.type_id)
+                        {
+                            return Err(Error::msg(format!(
+                                "Arguments for '{}' must be of type boolean. Left: {}, Right: {}",
+                                op, left_def.type_id, right_def.type_id
+                            )));
+                        }
+                        ColumnDefinition::with_type_id(TypeId::Boolean)
+                    }
+                };
+                Ok((
+                    LogicalExpr::Binary {
+                        left: Box::new(left),
+                        op,
+                        right: Box::new(right),
+                    },
+                    result_type,
+                ))
+            }
+            ExprNode::Unary { op, expr } => {
+                let (expr, col_def) = self.analyze_expression(*expr, scope)?;
+                let (valid, result_type) = match (&op, col_def.type_id) {
+                    (UnaryOperator::Not, TypeId::Boolean | TypeId::Unknown) => {
+                        (true, TypeId::Boolean)
+                    }
+                    (UnaryOperator::Not, type_id) => (false, type_id),
+                    (_, TypeId::Integer) => (true, TypeId::Integer),
+                    (op, TypeId::Interval) => (op == &UnaryOperator::Minus, TypeId::Interval),
+                    (_, type_id) => (false, type_id),
+                };
+                if !valid {
+                    Err(Error::msg(format!(
+                        "Cannot apply '{}' to type {}",
+                        op, col_def.type_id
+                    )))
+                } else {
+                    Ok((
+                        LogicalExpr::Unary {
+                            op,
+                            expr: Box::new(expr),
+                        },
+                        ColumnDefinition::with_type_id(result_type),
+                    ))
+                }
+            }
+            ExprNode::IsNull(expr) => {
+                let (expr, _) = self.analyze_expression(*expr, scope)?;
+                Ok((
+                    LogicalExpr::IsNull(Box::new(expr)),
+                    ColumnDefinition::with_type_id(TypeId::Boolean),
+                ))
+            }
+            ExprNode::IsNotNull(expr) => {
+                let (expr, _) = self.analyze_expression(*expr, scope)?;
+                Ok((
+                    LogicalExpr::IsNotNull(Box::new(expr)),
+                    ColumnDefinition::with_type_id(TypeId::Boolean),
+                ))
+            }
+            ExprNode::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let mut values = vec![self.analyze_expression(*expr, scope)?];
+                for element in list {
+                    values.push(self.analyze_expression(element, scope)?);
+                }
+                unify_types("IN", &mut values)?;
+                strip_char_padding(
+                    values
+                        .iter_mut()
+                        .map(|(expr, col_def)| (expr, &*col_def))
+                        .collect(),
+                );
+
+                // x IN (a, b) is the same as x = a OR x = b, also if any of them is NULL
+                let mut values = values.into_iter().map(|(expr, _)| expr);
+                let expr = values.next().unwrap();
+                let comparison = values
+                    .map(|value| LogicalExpr::Binary {
+                        left: Box::new(expr.clone()),
+                        op: BinaryOperator::Eq,
+                        right: Box::new(value),
+                    })
+                    .reduce(|left, right| LogicalExpr::Binary {
+                        left: Box::new(left),
+                        op: BinaryOperator::Or,
+                        right: Box::new(right),
+                    })
+                    .unwrap();
+                Ok((
+                    Self::negate_if(comparison, negated),
+                    ColumnDefinition::with_type_id(TypeId::Boolean),
+                ))
+            }
+            ExprNode::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let mut values = vec![
+                    self.analyze_expression(*expr, scope)?,
+                    self.analyze_expression(*low, scope)?,
+                    self.analyze_expression(*high, scope)?,
+                ];
+                let type_id = unify_types("BETWEEN", &mut values)?;
+                if type_id == TypeId::Json || type_id.element_type().is_some() {
+                    return Err(Error::msg(format!(
+                        "BETWEEN is not defined for type {}",
+                        type_id
+                    )));
+                }
+                strip_char_padding(
+                    values
+                        .iter_mut()
+                        .map(|(expr, col_def)| (expr, &*col_def))
+                        .collect(),
+                );
+
+                // x BETWEEN a AND b is the same as x >= a AND x <= b
+                let mut values = values.into_iter().map(|(expr, _)| Box::new(expr));
+                let (expr, low, high) = (
+                    values.next().unwrap(),
+                    values.next().unwrap(),
+                    values.next().unwrap(),
+                );
+                let (low_op, high_op, op) = if negated {
+                    (
+                        BinaryOperator::Less,
+                        BinaryOperator::Greater,
+                        BinaryOperator::Or,
+                    )
+                } else {
+                    (
+                        BinaryOperator::GreaterEq,
+                        BinaryOperator::LessEq,
+                        BinaryOperator::And,
+                    )
+                };
+                let comparison = LogicalExpr::Binary {
+                    left: Box::new(LogicalExpr::Binary {
+                        left: expr.clone(),
+                        op: low_op,
+                        right: low,
+                    }),
+                    op,
+                    right: Box::new(LogicalExpr::Binary {
+                        left: expr,
+                        op: high_op,
+                        right: high,
+                    }),
+                };
+                Ok((comparison, ColumnDefinition::with_type_id(TypeId::Boolean)))
+            }
+            ExprNode::Like {
+                expr,
+                pattern,
+                escape,
+                case_insensitive,
+                negated,
+            } => {
+                let op = if case_insensitive {
+                    BinaryOperator::ILike
+                } else {
+                    BinaryOperator::Like
+                };
+                let (expr, expr_def) = self.analyze_expression(*expr, scope)?;
+                let (mut pattern, pattern_def) = self.analyze_expression(*pattern, scope)?;
+                let valid_types = [TypeId::Text, TypeId::Unknown];
+                if !valid_types.contains(&expr_def.type_id)
+                    || !valid_types.contains(&pattern_def.type_id)
+                {
+                    return Err(Error::msg(format!(
+                        "Arguments for '{}' must be of type text. Left: {}, Right: {}",
+                        op, expr_def.type_id, pattern_def.type_id
+                    )));
+                }
+                if let Some(escape) = escape {
+                    let escape = self.analyze_expression(*escape, scope)?;
+                    (pattern, _) = self.analyze_function_call(
+                        "like_escape",
+                        vec![(pattern, pattern_def), escape],
+                    )?;
+                }
+
+                let like = LogicalExpr::Binary {
+                    left: Box::new(expr),
+                    op,
+                    right: Box::new(pattern),
+                };
+                Ok((
+                    Self::negate_if(like, negated),
+                    ColumnDefinition::with_type_id(TypeId::Boolean),
+                ))
+            }
+            ExprNode::IsTruthValue {
+                expr,
+                value,
+                negated,
+            } => {
+                let (expr, col_def) = self.analyze_expression(*expr, scope)?;
+                if ![TypeId::Boolean, TypeId::Unknown].contains(&col_def.type_id) {
+                    return Err(Error::msg(format!(
+                        "Argument of IS TRUE, FALSE or UNKNOWN must be of type boolean, but is {}",
+                        col_def.type_id
+                    )));
+                }
+                // IS TRUE is the same as IS NOT DISTINCT FROM TRUE, IS UNKNOWN the same as IS NULL
+                let expr = match (value, negated) {
+                    (Some(value), negated) => LogicalExpr::Binary {
+                        left: Box::new(expr),
+                        op: if negated {
+                            BinaryOperator::IsDistinctFrom
+                        } else {
+                            BinaryOperator::IsNotDistinctFrom
+                        },
+                        right: Box::new(LogicalExpr::Boolean(value)),
+                    },
+                    (None, false) => LogicalExpr::IsNull(Box::new(expr)),
+                    (None, true) => LogicalExpr::IsNotNull(Box::new(expr)),
+                };
+                Ok((expr, ColumnDefinition::with_type_id(TypeId::Boolean)))
+            }
+            ExprNode::Array(elements) => {
+                let mut element_type = TypeId::Unknown;
+                let mut exprs = vec![];
+                for element in elements {
+                    let (expr, col_def) = self.analyze_expression(element, scope)?;
+                    if element_type == TypeId::Unknown {
+                        element_type = col_def.type_id;
+                    } else if col_def.type_id != TypeId::Unknown && col_def.type_id != element_type
+                    {
+                        return Err(Error::msg(format!(
+                            "Array elements must be of same type, found {} and {}",
+                            element_type, col_def.type_id
+                        )));
+                    }
+                    exprs.push(expr);
+                }
+                if element_type == TypeId::Unknown {
+                    return Err(Error::msg("Cannot determine the type of an array of NULLs"));
+                }
+                let array_type = element_type.array_type().ok_or_else(|| {
+                    Error::msg(format!("Arrays of type {} are not supported", element_type))
+                })?;
+                Ok((
+                    LogicalExpr::Array(exprs),
+                    ColumnDefinition::with_type_id(array_type),
+                ))
+            }
+            ExprNode::Subscript { expr, index } => {
+                let (expr, col_def) = self.analyze_expression(*expr, scope)?;
+                let (index, index_def) = self.analyze_expression(*index, scope)?;
+                let element_type = match col_def.type_id.element_type() {
+                    Some(element_type) => element_type,
+                    None => {
+                        return Err(Error::msg(format!(
+                            "Cannot subscript type {}, it is not an array",
+                            col_def.type_id
+                        )))
+                    }
+                };
+                if ![TypeId::Integer, TypeId::Unknown].contains(&index_def.type_id) {
+                    return Err(Error::msg(format!(
+                        "Array subscripts must be of type integer, found {}",
+                        index_def.type_id
+                    )));
+                }
+                // out of bounds subscripts evaluate to NULL
+                let mut result_def = ColumnDefinition::with_type_id(element_type);
+                result_def.not_null = false;
+                Ok((
+                    LogicalExpr::Subscript {
+                        expr: Box::new(expr),
+                        index: Box::new(index),
+                    },
+                    result_def,
+                ))
+            }
+            ExprNode::AnyComparison { left, op, array } => {
+                let (mut left, mut left_def) = self.analyze_expression(*left, scope)?;
+                let (array, array_def) = self.analyze_expression(*array, scope)?;
+                let element_type = match array_def.type_id {
+                    TypeId::Unknown => TypeId::Unknown,
+                    array_type => array_type.element_type().ok_or_else(|| {
+                        Error::msg(format!(
+                            "The right side of '{} ANY' must be an array, found {}",
+                            op, array_type
+                        ))
+                    })?,
+                };
+                coerce_string_literal(&mut left, &mut left_def, element_type)?;
+                if left_def.type_id != element_type
+                    && left_def.type_id != TypeId::Unknown
+                    && element_type != TypeId::Unknown
+                {
+                    return Err(Error::msg(format!(
+                        "Cannot compare {} with the elements of {}",
+                        left_def.type_id, array_def.type_id
+                    )));
+                }
+                Ok((
+                    LogicalExpr::AnyComparison {
+                        left: Box::new(left),
+                        op,
+                        array: Box::new(array),
+                    },
+                    ColumnDefinition::with_type_id(TypeId::Boolean),
                 ))
             }
-            ExprNode::String(s) => Ok((
-                LogicalExpr::String(s),
-                ColumnDefinition::with_type_id(TypeId::Text),
-            )),
-            ExprNode::Boolean(val) => Ok((
-                LogicalExpr::Boolean(val),
-                ColumnDefinition::with_type_id(TypeId::Boolean),
-            )),
-            ExprNode::Grouping(expr) => Self::analyze_expression(*expr, scope),
-            ExprNode::Binary { left, op, right } => {
-                let (left, left_def) = Self::analyze_expression(*left, scope)?;
-                let (right, right_def) = Self::analyze_expression(*right, scope)?;
-                let result_type = match op {
-                    BinaryOperator::Plus
-                    | BinaryOperator::Minus
-                    | BinaryOperator::Multiply
-                    | BinaryOperator::Divide
-                    | BinaryOperator::Modulo => {
-                        if left_def.type_id != TypeId::Integer
-                            || right_def.type_id != TypeId::Integer
-                        {
-                            return Err(Error::msg(format!(
-                                "Arguments for '{}' must be of type integer. Left: {}, Right: {}",
-                                op, left_def.type_id, right_def.type_id
-                            )));
-                        }
-                        ColumnDefinition::with_type_id(TypeId::Integer)
+            ExprNode::Cast { expr, data_type } => {
+                let text_length = TextLength::from_data_type(&data_type);
+                let target = TypeId::from(data_type);
+                let (expr, col_def) = self.analyze_expression(*expr, scope)?;
+                let expr = match expr {
+                    // literals are cast right away, so that invalid ones are reported early
+                    LogicalExpr::String(s) => match text_length {
+                        Some(text_length) => LogicalExpr::String(text_length.cast(&s)),
+                        None => literal_expr(cast::cast_value(Value::String(s), target)?),
+                    },
+                    // like in Postgres, casting CHAR(n) to text removes the padding
+                    expr if target == TypeId::Text
+                        && text_length.is_none()
+                        && matches!(col_def.text_length, Some(TextLength::Fixed(_))) =>
+                    {
+                        LogicalExpr::ScalarFunction(ScalarFunc::RTrim, vec![expr])
                     }
-                    BinaryOperator::Eq
-                    | BinaryOperator::NotEq
-                    | BinaryOperator::Less
-                    | BinaryOperator::LessEq
-                    | BinaryOperator::Greater
-                    | BinaryOperator::GreaterEq => {
-                        if left_def.type_id != right_def.type_id
-                            && left_def.type_id != TypeId::Unknown
-                            && right_def.type_id != TypeId::Unknown
-                        {
-                            return Err(Error::msg(format!(
-                                "Arguments for '{}' must be of same type. Left: {}, Right: {}",
-                                op, left_def.type_id, right_def.type_id
-                            )));
-                        }
-                        ColumnDefinition::with_type_id(TypeId::Boolean)
+                    expr if col_def.type_id == target
+                        && (text_length.is_none() || text_length == col_def.text_length) =>
+                    {
+                        expr
                     }
-                    BinaryOperator::And | BinaryOperator::Or => {
-                        let valid_types = [TypeId::Boolean, TypeId::Unknown];
-                        if !valid_types.contains(&left_def.type_id)
-                            || !valid_types.contains(&right_def.type_id)
-                        {
+                    expr => {
+                        if cast::cast_context(col_def.type_id, target).is_none() {
                             return Err(Error::msg(format!(
-                                "Arguments for '{}' must be of type boolean. Left: {}, Right: {}",
-                                op, left_def.type_id, right_def.type_id
+                                "Cannot cast type {} to {}",
+                                col_def.type_id, target
                             )));
                         }
-                        ColumnDefinition::with_type_id(TypeId::Boolean)
+                        LogicalExpr::Cast {
+                            expr: Box::new(expr),
+                            type_id: target,
+                            text_length,
+                        }
                     }
                 };
-                Ok((
-                    LogicalExpr::Binary {
-                        left: Box::new(left),
-                        op,
-                        right: Box::new(right),
-                    },
-                    result_type,
-                ))
+                let mut result_def = ColumnDefinition::with_type_id(target);
+                result_def.not_null = col_def.not_null;
+                result_def.text_length = text_length;
+                Ok((expr, result_def))
             }
-            ExprNode::Unary { op, expr } => {
-                let (expr, col_def) = Self::analyze_expression(*expr, scope)?;
-                if col_def.type_id != TypeId::Integer {
+            ExprNode::FunctionCall {
+                name,
+                args,
+                distinct,
+                wildcard,
+                over: Some(over),
+            } => self.analyze_window_function(name, args, distinct, wildcard, over, scope),
+            ExprNode::FunctionCall {
+                name,
+                args,
+                distinct,
+                wildcard,
+                over: None,
+            } => {
+                if let Some(agg) = AggregationFunc::is_aggregation_func(&name) {
+                    let agg = match agg {
+                        AggregationFunc::Count if wildcard => AggregationFunc::CountAll,
+                        _ if wildcard => {
+                            return Err(Error::msg(format!("`{}` cannot be called with *", name)))
+                        }
+                        agg => agg,
+                    };
+                    let (args, arg_types): (Vec<_>, Vec<_>) = args
+                        .into_iter()
+                        .map(|arg| {
+                            self.analyze_expression(arg, scope)
+                                .map(|(expr, col_def)| (expr, col_def.type_id))
+                        })
+                        .collect::<Result<Vec<_>>>()?
+                        .into_iter()
+                        .unzip();
+                    if args.iter().any(|arg| arg.has_aggregation()) {
+                        return Err(Error::msg("Aggregations cannot be nested"));
+                    }
+                    if args.iter().any(|arg| arg.has_window_function()) {
+                        return Err(Error::msg("Aggregations cannot contain window functions"));
+                    }
+
+                    agg.validate_argument_types(&arg_types)?;
+                    let result_type = agg.result_type(&arg_types);
+
+                    let agg_expr = LogicalExpr::Aggregation {
+                        func: agg,
+                        args,
+                        distinct,
+                    };
+                    Ok((agg_expr, ColumnDefinition::with_type_id(result_type)))
+                } else if distinct || wildcard {
                     Err(Error::msg(format!(
-                        "Cannot apply '{}' to type {}",
-                        op, col_def.type_id
+                        "`{}` is not an aggregation, so it accepts neither DISTINCT nor *",
+                        name
                     )))
+                } else if let Some(func) = ScalarFunc::is_scalar_func(&name) {
+                    let mut args = args
+                        .into_iter()
+                        .map(|arg| self.analyze_expression(arg, scope))
+                        .collect::<Result<Vec<_>>>()?;
+                    unify_types(&func.to_string().to_uppercase(), &mut args)?;
+                    // coalesce, greatest and least are only NULL if all arguments are
+                    let any_not_null = args.iter().any(|(_, col_def)| col_def.not_null);
+                    let (args, arg_types): (Vec<_>, Vec<_>) = args
+                        .into_iter()
+                        .map(|(expr, col_def)| (expr, col_def.type_id))
+                        .unzip();
+                    func.validate_argument_types(&arg_types)?;
+                    let result_type = func.result_type(&arg_types);
+
+                    let mut col_def = ColumnDefinition::with_type_id(result_type);
+                    col_def.not_null = !func.can_return_null() && any_not_null;
+                    Ok((LogicalExpr::ScalarFunction(func, args), col_def))
                 } else {
-                    Ok((
-                        LogicalExpr::Unary {
-                            op,
-                            expr: Box::new(expr),
-                        },
-                        ColumnDefinition::with_type_id(TypeId::Integer),
-                    ))
+                    let args = args
+                        .into_iter()
+                        .map(|arg| self.analyze_expression(arg, scope))
+                        .collect::<Result<Vec<_>>>()?;
+                    self.analyze_function_call(&name, args)
                 }
             }
-            ExprNode::IsNull(expr) => {
-                let (expr, _) = Self::analyze_expression(*expr, scope)?;
+            ExprNode::Case {
+                operand,
+                conditions,
+                else_result,
+            } => {
+                let mut values = vec![];
+                let mut results = vec![];
+                if let Some(operand) = operand {
+                    values.push(self.analyze_expression(*operand, scope)?);
+                }
+                let has_operand = !values.is_empty();
+                for (condition, result) in conditions {
+                    values.push(self.analyze_expression(condition, scope)?);
+                    results.push(self.analyze_expression(result, scope)?);
+                }
+                let has_else = else_result.is_some();
+                if let Some(else_result) = else_result {
+                    results.push(self.analyze_expression(*else_result, scope)?);
+                }
+
+                // the WHEN values are compared to the operand, so they share its type
+                if has_operand {
+                    unify_types("CASE/WHEN", &mut values)?;
+                    strip_char_padding(
+                        values
+                            .iter_mut()
+                            .map(|(expr, col_def)| (expr, &*col_def))
+                            .collect(),
+                    );
+                } else if let Some((_, col_def)) = values.iter().find(|(_, col_def)| {
+                    ![TypeId::Boolean, TypeId::Unknown].contains(&col_def.type_id)
+                }) {
+                    return Err(Error::msg(format!(
+                        "CASE/WHEN conditions must evaluate to boolean but evaluates to: {}",
+                        col_def.type_id
+                    )));
+                }
+                let type_id = unify_types("CASE", &mut results)?;
+                let mut col_def = ColumnDefinition::with_type_id(type_id);
+                col_def.not_null = has_else && results.iter().all(|(_, col_def)| col_def.not_null);
+
+                let mut values = values.into_iter().map(|(expr, _)| expr);
+                let mut results = results.into_iter().map(|(expr, _)| expr);
+                let operand = if has_operand { values.next() } else { None };
+                let conditions = values.zip(results.by_ref()).collect();
+                let else_result = results.next();
+                let expr = LogicalExpr::Case {
+                    operand: operand.map(Box::new),
+                    conditions,
+                    else_result: else_result.map(Box::new),
+                };
+                Ok((expr, col_def))
+            }
+            ExprNode::Subquery(subquery) => {
+                let query = self.analyze_subquery(*subquery, scope)?;
+                // without any rows, a subquery evaluates to NULL
+                let mut col_def =
+                    ColumnDefinition::with_type_id(query.output_schema.columns()[0].type_id);
+                col_def.not_null = false;
+                Ok((LogicalExpr::Subquery(Box::new(query)), col_def))
+            }
+            ExprNode::Exists(subquery) => {
+                let query = self.analyze_correlated_select(*subquery, scope)?;
                 Ok((
-                    LogicalExpr::IsNull(Box::new(expr)),
+                    LogicalExpr::Exists(Box::new(query)),
                     ColumnDefinition::with_type_id(TypeId::Boolean),
                 ))
             }
-            ExprNode::IsNotNull(expr) => {
-                let (expr, _) = Self::analyze_expression(*expr, scope)?;
+            ExprNode::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                let (mut expr, mut col_def) = self.analyze_expression(*expr, scope)?;
+                let mut query = self.analyze_subquery(*subquery, scope)?;
+                let query_type = query.output_schema.columns()[0].type_id;
+                coerce_string_literal(&mut expr, &mut col_def, query_type)?;
+                if col_def.type_id != query_type
+                    && col_def.type_id != TypeId::Unknown
+                    && query_type != TypeId::Unknown
+                    && !coerce_implicitly(&mut expr, &mut col_def, query_type)
+                    && !coerce_query_column(&mut query, 0, col_def.type_id)?
+                {
+                    return Err(Error::msg(format!(
+                        "Cannot compare {} with the results of a subquery of type {}",
+                        col_def.type_id, query_type
+                    )));
+                }
                 Ok((
-                    LogicalExpr::IsNotNull(Box::new(expr)),
+                    LogicalExpr::InSubquery {
+                        expr: Box::new(expr),
+                        query: Box::new(query),
+                        negated,
+                    },
                     ColumnDefinition::with_type_id(TypeId::Boolean),
                 ))
             }
-            ExprNode::FunctionCall { name, expr } => {
-                if let Some(agg) = AggregationFunc::is_aggregation_func(&name) {
-                    let (expr, col_def) = Self::analyze_expression(*expr, scope)?;
-                    if expr.has_aggregation() {
-                        return Err(Error::msg("Aggregations cannot be nested"));
-                    }
-
-                    agg.validate_aggregation_type(col_def.type_id)?;
-                    let result_type = agg.aggregation_result_type(col_def.type_id);
-
-                    let agg_expr = LogicalExpr::Aggregation(agg, Box::new(expr));
-                    Ok((agg_expr, ColumnDefinition::with_type_id(result_type)))
-                } else {
-                    Err(Error::msg(format!("Cannot find function {}.", name)))
-                }
-            }
             ExprNode::Null => Ok((
                 LogicalExpr::Null,
                 ColumnDefinition::with_type_id(TypeId::Unknown),
@@ -593,6 +2177,190 @@ impl<'a> Analyzer<'a> {
         }
     }
 
+    /// Analyzes a window function, i.e. a function call with an OVER clause. Besides the
+    /// dedicated window functions, every aggregation can be evaluated over a window
+    fn analyze_window_function(
+        &self,
+        name: String,
+        args: Vec<ExprNode>,
+        distinct: bool,
+        wildcard: bool,
+        over: WindowSpec,
+        scope: &TableReference,
+    ) -> Result<(LogicalExpr, ColumnDefinition)> {
+        let func = match (WindowFunc::is_window_func(&name), wildcard) {
+            (Some(func), false) => func,
+            (None, _) => match AggregationFunc::is_aggregation_func(&name) {
+                Some(AggregationFunc::Count) if wildcard => {
+                    WindowFunc::Aggregation(AggregationFunc::CountAll)
+                }
+                Some(agg) if !wildcard => WindowFunc::Aggregation(agg),
+                Some(_) => return Err(Error::msg(format!("`{}` cannot be called with *", name))),
+                None => return Err(Error::msg(format!("`{}` is not a window function", name))),
+            },
+            (Some(_), true) => {
+                return Err(Error::msg(format!("`{}` cannot be called with *", name)))
+            }
+        };
+        if distinct {
+            return Err(Error::msg("DISTINCT is not supported for window functions"));
+        }
+
+        let (args, arg_types): (Vec<_>, Vec<_>) = args
+            .into_iter()
+            .map(|arg| {
+                self.analyze_expression(arg, scope)
+                    .map(|(expr, col_def)| (expr, col_def.type_id))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+        func.validate_argument_types(&arg_types)?;
+        let result_type = func.result_type(&arg_types);
+
+        let WindowSpec {
+            partition_by,
+            order_by,
+            frame,
+        } = over;
+        let partition_by = partition_by
+            .into_iter()
+            .map(|expr| Ok(self.analyze_expression(expr, scope)?.0))
+            .collect::<Result<Vec<_>>>()?;
+        let order_by = order_by
+            .into_iter()
+            .map(|order_by_expr| {
+                let ast::OrderByExpr {
+                    expr,
+                    asc,
+                    nulls_first,
+                } = order_by_expr;
+                let (expr, col_def) = self.analyze_expression(expr, scope)?;
+                if matches!(
+                    col_def.type_id,
+                    TypeId::Json | TypeId::IntegerArray | TypeId::TextArray
+                ) {
+                    return Err(Error::msg(format!(
+                        "Cannot order by values of type {}",
+                        col_def.type_id
+                    )));
+                }
+                let ascending = asc.unwrap_or(true);
+                Ok(OrderByExpr {
+                    expr,
+                    ascending,
+                    nulls_first: nulls_first.unwrap_or(!ascending),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let frame = frame
+            .map(|frame| frame.try_map(|offset| self.analyze_row_count(*offset, "ROWS")))
+            .transpose()?;
+
+        let is_nested = args
+            .iter()
+            .chain(partition_by.iter())
+            .chain(order_by.iter().map(|order| &order.expr))
+            .any(|expr| expr.has_window_function());
+        if is_nested {
+            return Err(Error::msg("Window functions cannot be nested"));
+        }
+
+        let mut col_def = ColumnDefinition::with_type_id(result_type);
+        col_def.not_null = matches!(
+            func,
+            WindowFunc::RowNumber
+                | WindowFunc::Rank
+                | WindowFunc::DenseRank
+                | WindowFunc::Aggregation(AggregationFunc::Count | AggregationFunc::CountAll)
+        );
+        let window = Window {
+            partition_by,
+            order_by,
+            frame,
+        };
+        let expr = LogicalExpr::WindowFunction {
+            func,
+            args,
+            window: Box::new(window),
+        };
+        Ok((expr, col_def))
+    }
+
+    /// Resolves a call of a function from the function registry. String literals have no type
+    /// of their own, so they match any parameter type. Arguments which do not match the
+    /// parameter types exactly are converted to them
+    fn analyze_function_call(
+        &self,
+        name: &str,
+        mut args: Vec<(LogicalExpr, ColumnDefinition)>,
+    ) -> Result<(LogicalExpr, ColumnDefinition)> {
+        let arg_types = args
+            .iter()
+            .map(|(expr, col_def)| match expr {
+                LogicalExpr::String(_) => TypeId::Unknown,
+                _ => col_def.type_id,
+            })
+            .collect::<Vec<_>>();
+        let overload = self.catalog.functions().resolve(name, &arg_types)?;
+        let param_types = overload.parameter_types(args.len()).unwrap();
+
+        for ((expr, col_def), param_type) in args.iter_mut().zip(param_types) {
+            if col_def.type_id == param_type || col_def.type_id == TypeId::Unknown {
+                continue;
+            }
+            if matches!(expr, LogicalExpr::String(_)) {
+                coerce_string_literal(expr, col_def, param_type)?;
+            } else {
+                coerce_implicitly(expr, col_def, param_type);
+            }
+        }
+
+        let func = overload.func;
+        let mut col_def = ColumnDefinition::with_type_id(overload.return_type);
+        col_def.not_null =
+            !func.can_return_null() && args.iter().all(|(_, col_def)| col_def.not_null);
+        let args = args.into_iter().map(|(expr, _)| expr).collect();
+        Ok((LogicalExpr::ScalarFunction(func, args), col_def))
+    }
+
+    /// Wraps the expression in NOT if it is negated, e.g. for NOT IN
+    fn negate_if(expr: LogicalExpr, negated: bool) -> LogicalExpr {
+        if negated {
+            LogicalExpr::Unary {
+                op: UnaryOperator::Not,
+                expr: Box::new(expr),
+            }
+        } else {
+            expr
+        }
+    }
+
+    /// Analyzes a subquery, which can reference the columns of the enclosing queries
+    fn analyze_correlated_select(
+        &self,
+        subquery: SelectStatement,
+        scope: &TableReference,
+    ) -> Result<Query> {
+        self.outer_scopes.borrow_mut().push(scope.clone());
+        let query = self.analyze_select(subquery);
+        self.outer_scopes.borrow_mut().pop();
+        query
+    }
+
+    /// Analyzes a subquery, which is used as a value and must return a single column
+    fn analyze_subquery(&self, subquery: SelectStatement, scope: &TableReference) -> Result<Query> {
+        let query = self.analyze_correlated_select(subquery, scope)?;
+        let column_count = query.output_schema.columns().len();
+        if column_count != 1 {
+            return Err(Error::msg(format!(
+                "Subquery must return only one column, but returns {}",
+                column_count
+            )));
+        }
+        Ok(query)
+    }
+
     fn identify_column(
         scope: &TableReference,
         table: Option<&str>,
@@ -604,6 +2372,19 @@ impl<'a> Analyzer<'a> {
                 name,
                 schema,
                 filter: _,
+            }
+            | TableReference::TableFunction {
+                func: _,
+                args: _,
+                name,
+                schema,
+                filter: _,
+            }
+            | TableReference::Cte {
+                cte_id: _,
+                name,
+                schema,
+                filter: _,
             } => {
                 if let Some(table) = table {
                     if name != table {
@@ -611,14 +2392,16 @@ impl<'a> Analyzer<'a> {
                     }
                 }
                 let column = schema.find_column(column).map(|col_def| {
+                    let mut new_col_def = ColumnDefinition::new(
+                        col_def.type_id(),
+                        String::new(),
+                        col_def.column_offset(),
+                        col_def.not_null(),
+                    );
+                    new_col_def.text_length = col_def.text_length();
                     (
                         LogicalExpr::Column(vec![name.clone(), col_def.column_name().to_owned()]),
-                        ColumnDefinition::new(
-                            col_def.type_id(),
-                            String::new(),
-                            col_def.column_offset(),
-                            col_def.not_null(),
-                        ),
+                        new_col_def,
                     )
                 });
                 Ok(column)
@@ -626,9 +2409,15 @@ impl<'a> Analyzer<'a> {
             TableReference::Join {
                 left,
                 right,
-                join_type: _,
+                join_type,
                 on: _,
+                using,
             } => {
+                if table.is_none() && using.iter().any(|using| using == column) {
+                    return Ok(Some(Self::merge_using_column(
+                        left, right, join_type, column,
+                    )));
+                }
                 let left = Self::identify_column(left, table, column)?;
                 let right = Self::identify_column(right, table, column)?;
 
@@ -646,6 +2435,162 @@ impl<'a> Analyzer<'a> {
         }
     }
 
+    /// Looks up a column in the scopes of the enclosing queries, starting with the innermost
+    fn identify_outer_column(
+        &self,
+        table: Option<&str>,
+        column: &str,
+    ) -> Result<Option<(LogicalExpr, ColumnDefinition)>> {
+        for scope in self.outer_scopes.borrow().iter().rev() {
+            if let Some((expr, col_def)) = Self::identify_column(scope, table, column)? {
+                return Ok(Some((Self::to_outer_column(expr), col_def)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Converts the column references of an identified column to outer column references
+    fn to_outer_column(expr: LogicalExpr) -> LogicalExpr {
+        match expr {
+            LogicalExpr::Column(path) => LogicalExpr::OuterColumn(path),
+            // the merged column of a FULL JOIN with USING
+            LogicalExpr::ScalarFunction(func, args) => LogicalExpr::ScalarFunction(
+                func,
+                args.into_iter().map(Self::to_outer_column).collect(),
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Analyzes a join with USING, the columns have to exist in both tables and are compared for
+    /// equality
+    fn analyze_using_join(
+        left: TableReference,
+        right: TableReference,
+        join_type: JoinType,
+        using: Vec<String>,
+    ) -> Result<TableReference> {
+        let mut on = vec![];
+        for (i, column) in using.iter().enumerate() {
+            if using[..i].contains(column) {
+                return Err(Error::msg(format!(
+                    "Column {} appears more than once in USING clause",
+                    column
+                )));
+            }
+            let (left_expr, left_def) =
+                Self::identify_column(&left, None, column)?.ok_or_else(|| {
+                    Error::msg(format!(
+                        "Column {} specified in USING clause does not exist in left table",
+                        column
+                    ))
+                })?;
+            let (right_expr, right_def) =
+                Self::identify_column(&right, None, column)?.ok_or_else(|| {
+                    Error::msg(format!(
+                        "Column {} specified in USING clause does not exist in right table",
+                        column
+                    ))
+                })?;
+            let types = [left_def.type_id, right_def.type_id];
+            if types[0] != types[1] && !types.contains(&TypeId::Unknown) {
+                return Err(Error::msg(format!(
+                    "JOIN/USING types {} and {} of column {} cannot be matched",
+                    types[0], types[1], column
+                )));
+            }
+            on.push(LogicalExpr::Binary {
+                left: Box::new(left_expr),
+                op: BinaryOperator::Eq,
+                right: Box::new(right_expr),
+            });
+        }
+
+        Ok(TableReference::Join {
+            left: Box::new(left),
+            right: Box::new(right),
+            join_type,
+            on,
+            using,
+        })
+    }
+
+    /// Returns the merged column of a join with USING. Outer joins take the value of the
+    /// preserved table, full joins the first value which is not NULL
+    fn merge_using_column(
+        left: &TableReference,
+        right: &TableReference,
+        join_type: &JoinType,
+        column: &str,
+    ) -> (LogicalExpr, ColumnDefinition) {
+        // the columns have been validated when the join was analyzed
+        let (left_expr, left_def) = Self::identify_column(left, None, column).unwrap().unwrap();
+        let (right_expr, right_def) = Self::identify_column(right, None, column).unwrap().unwrap();
+        let (expr, not_null) = match join_type {
+            JoinType::Inner => (left_expr, left_def.not_null || right_def.not_null),
+            JoinType::Left | JoinType::Semi | JoinType::Anti => (left_expr, left_def.not_null),
+            JoinType::Right => (right_expr, right_def.not_null),
+            JoinType::Full => (
+                LogicalExpr::ScalarFunction(ScalarFunc::Coalesce, vec![left_expr, right_expr]),
+                left_def.not_null && right_def.not_null,
+            ),
+        };
+        let type_id = match left_def.type_id {
+            TypeId::Unknown => right_def.type_id,
+            type_id => type_id,
+        };
+        let mut col_def = ColumnDefinition::new(type_id, String::new(), 0, not_null);
+        if left_def.text_length == right_def.text_length {
+            col_def.text_length = left_def.text_length;
+        }
+        (expr, col_def)
+    }
+
+    /// Returns the names of the columns of a table reference, as returned by SELECT *
+    fn get_column_names(scope: &TableReference) -> Vec<String> {
+        match scope {
+            TableReference::BaseTable {
+                table_id: _,
+                name: _,
+                schema,
+                filter: _,
+            }
+            | TableReference::TableFunction {
+                func: _,
+                args: _,
+                name: _,
+                schema,
+                filter: _,
+            }
+            | TableReference::Cte {
+                cte_id: _,
+                name: _,
+                schema,
+                filter: _,
+            } => schema
+                .columns()
+                .iter()
+                .map(|col_def| col_def.column_name().to_owned())
+                .collect(),
+            TableReference::Join {
+                left,
+                right,
+                join_type,
+                on: _,
+                using,
+            } => {
+                let mut names = using.clone();
+                let mut other_names = Self::get_column_names(left);
+                if !join_type.returns_left_only() {
+                    other_names.append(&mut Self::get_column_names(right));
+                }
+                names.extend(other_names.into_iter().filter(|name| !using.contains(name)));
+                names
+            }
+            TableReference::EmptyTable => vec![],
+        }
+    }
+
     fn get_all_columns(
         scope: &TableReference,
         table: Option<String>,
@@ -656,6 +2601,19 @@ impl<'a> Analyzer<'a> {
                 name: table_name,
                 schema,
                 filter: _,
+            }
+            | TableReference::TableFunction {
+                func: _,
+                args: _,
+                name: table_name,
+                schema,
+                filter: _,
+            }
+            | TableReference::Cte {
+                cte_id: _,
+                name: table_name,
+                schema,
+                filter: _,
             } => {
                 if let Some(table) = table {
                     if table_name != &table {
@@ -671,12 +2629,13 @@ impl<'a> Analyzer<'a> {
                             col_def.column_name().to_owned(),
                         ]);
                         let name = format!("{}.{}", table_name, col_def.column_name());
-                        let new_col_def = ColumnDefinition::new(
+                        let mut new_col_def = ColumnDefinition::new(
                             col_def.type_id(),
                             name,
                             col_def.column_offset(),
                             col_def.not_null(),
                         );
+                        new_col_def.text_length = col_def.text_length();
                         (expr, new_col_def)
                     })
                     .collect()
@@ -684,13 +2643,40 @@ impl<'a> Analyzer<'a> {
             TableReference::Join {
                 left,
                 right,
-                join_type: _,
+                join_type,
                 on: _,
+                using,
             } => {
                 let mut left_columns = Self::get_all_columns(left, table.clone());
                 let mut right_columns = Self::get_all_columns(right, table.clone());
-                left_columns.append(&mut right_columns);
-                left_columns
+                if table.is_some() || using.is_empty() {
+                    left_columns.append(&mut right_columns);
+                    return left_columns;
+                }
+                // the merged columns come first and replace the columns of both tables
+                let mut columns = vec![];
+                let mut replaced = vec![];
+                for column in using {
+                    let (expr, mut col_def) =
+                        Self::merge_using_column(left, right, join_type, column);
+                    col_def.column_name = column.clone();
+                    columns.push((expr, col_def));
+                    for scope in [left, right] {
+                        replaced.push(
+                            Self::identify_column(scope, None, column)
+                                .unwrap()
+                                .unwrap()
+                                .0,
+                        );
+                    }
+                }
+                columns.extend(
+                    left_columns
+                        .into_iter()
+                        .chain(right_columns)
+                        .filter(|(expr, _)| !replaced.contains(expr)),
+                );
+                columns
             }
             TableReference::EmptyTable => vec![],
         }
@@ -790,11 +2776,19 @@ mod tests {
                 LogicalExpr::Column(vec!["accounts".to_owned(), "name".to_owned()]),
             ],
             filter: vec![],
+            ctes: vec![],
+            set_operation: None,
             output_schema: Schema::new(vec![
                 ColumnDefinition::new(TypeId::Integer, "accounts.id".to_owned(), 0, true),
                 ColumnDefinition::new(TypeId::Text, "accounts.name".to_owned(), 1, true),
             ]),
             values: vec![],
+            group_by: vec![],
+            having: vec![],
+            order_by: vec![],
+            distinct: vec![],
+            limit: None,
+            offset: None,
         });
 
         assert_eq!(query, expected_query);
@@ -833,11 +2827,19 @@ mod tests {
                 LogicalExpr::Column(vec!["acc".to_owned(), "name".to_owned()]),
             ],
             filter: vec![],
+            ctes: vec![],
+            set_operation: None,
             output_schema: Schema::new(vec![
                 ColumnDefinition::new(TypeId::Integer, "acc.id".to_owned(), 0, true),
                 ColumnDefinition::new(TypeId::Text, "acc.name".to_owned(), 1, true),
             ]),
             values: vec![],
+            group_by: vec![],
+            having: vec![],
+            order_by: vec![],
+            distinct: vec![],
+            limit: None,
+            offset: None,
         });
 
         assert_eq!(query, expected_query);
@@ -898,12 +2900,20 @@ mod tests {
                 },
             ],
             filter: vec![],
+            ctes: vec![],
+            set_operation: None,
             output_schema: Schema::new(vec![
                 ColumnDefinition::new(TypeId::Integer, "negative_id".to_owned(), 0, true),
                 ColumnDefinition::new(TypeId::Integer, "id + 1".to_owned(), 1, true),
                 ColumnDefinition::new(TypeId::Integer, "2 * (3 + 5)".to_owned(), 2, true),
             ]),
             values: vec![],
+            group_by: vec![],
+            having: vec![],
+            order_by: vec![],
+            distinct: vec![],
+            limit: None,
+            offset: None,
         });
 
         assert_eq!(query, expected_query);
@@ -942,7 +2952,15 @@ mod tests {
             ],
             filter: vec![],
             projections: vec![],
+            ctes: vec![],
+            set_operation: None,
             output_schema: expected_output_schema,
+            group_by: vec![],
+            having: vec![],
+            order_by: vec![],
+            distinct: vec![],
+            limit: None,
+            offset: None,
         });
 
         assert_eq!(query, expected_query);
@@ -976,31 +2994,41 @@ mod tests {
                 filter: vec![],
             },
             projections: vec![
-                LogicalExpr::Aggregation(
-                    AggregationFunc::Count,
-                    Box::new(LogicalExpr::Column(vec![
+                LogicalExpr::Aggregation {
+                    func: AggregationFunc::Count,
+                    args: vec![LogicalExpr::Column(vec![
                         "accounts".to_owned(),
                         "name".to_owned(),
-                    ])),
-                ),
+                    ])],
+                    distinct: false,
+                },
                 LogicalExpr::Binary {
                     left: Box::new(LogicalExpr::Integer(2)),
                     op: BinaryOperator::Multiply,
-                    right: Box::new(LogicalExpr::Aggregation(
-                        AggregationFunc::Count,
-                        Box::new(LogicalExpr::Column(vec![
+                    right: Box::new(LogicalExpr::Aggregation {
+                        func: AggregationFunc::Count,
+                        args: vec![LogicalExpr::Column(vec![
                             "accounts".to_owned(),
                             "name".to_owned(),
-                        ])),
-                    )),
+                        ])],
+                        distinct: false,
+                    }),
                 },
             ],
             filter: vec![],
+            ctes: vec![],
+            set_operation: None,
             output_schema: Schema::new(vec![
                 ColumnDefinition::new(TypeId::Integer, "count(name)".to_owned(), 0, true),
                 ColumnDefinition::new(TypeId::Integer, "double_count".to_owned(), 1, true),
             ]),
             values: vec![],
+            group_by: vec![],
+            having: vec![],
+            order_by: vec![],
+            distinct: vec![],
+            limit: None,
+            offset: None,
         });
 
         assert_eq!(query, expected_query);
@@ -1024,7 +3052,12 @@ mod tests {
         assert!(result.is_err());
         assert_eq!(
             &result.err().unwrap().root_cause().to_string(),
-            "column 'accounts.id' must be used in an aggregation"
+            "column 'accounts.id' must appear in the GROUP BY clause or be used in an aggregation"
         );
+
+        let sql = "
+            select id, count(name) as double_count from accounts group by id
+        ";
+        assert!(analyzer_test_suite.analyze_query(sql).is_ok());
     }
 }
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fs::File;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
@@ -26,11 +27,11 @@ impl<'a> BufferGuard<'a> {
         self.buffer.page_id()
     }
 
-    pub fn read(&self) -> RwLockReadGuard<[u8]> {
+    pub fn read(&self) -> RwLockReadGuard<'_, [u8]> {
         self.buffer.data().read().unwrap()
     }
 
-    pub fn write(&self) -> RwLockWriteGuard<[u8]> {
+    pub fn write(&self) -> RwLockWriteGuard<'_, [u8]> {
         self.buffer.data().write().unwrap()
     }
 
@@ -113,11 +114,16 @@ impl BufferManager {
         self.file_manager.create_table(table_id)
     }
 
+    /// Creates a temporary file, which is removed once it is dropped. See FileManager
+    pub fn create_temp_file(&self) -> Result<File> {
+        self.file_manager.create_temp_file()
+    }
+
     pub fn allocate_new_page(
         &self,
         table_id: TableId,
         initial_data: &[u8],
-    ) -> Result<Option<BufferGuard>> {
+    ) -> Result<Option<BufferGuard<'_>>> {
         let mut page_id_to_pool_pos = self.page_id_to_pool_pos.lock().unwrap();
         let mut clock_replacer = self.clock_replacer.lock().unwrap();
 
@@ -144,7 +150,7 @@ impl BufferManager {
         }
     }
 
-    pub fn fetch(&self, page_id: PageId) -> Result<Option<BufferGuard>> {
+    pub fn fetch(&self, page_id: PageId) -> Result<Option<BufferGuard<'_>>> {
         let mut page_id_to_pool_pos = self.page_id_to_pool_pos.lock().unwrap();
         let mut clock_replacer = self.clock_replacer.lock().unwrap();
 
@@ -0,0 +1,367 @@
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+use dashmap::DashMap;
+
+use crate::analyzer::logical_plan::{AggregationFunc, ScalarFunc};
+use crate::catalog::schema::TypeId;
+use crate::parser;
+use crate::tuple::cast::{self, CastContext};
+use crate::tuple::value::Value;
+
+/// The implementation of a user-defined function, it is called with values of the declared
+/// argument types and must return a value of the declared return type or NULL
+pub type ScalarFunctionImpl = dyn Fn(&[Value]) -> Result<Value> + Send + Sync;
+
+/// A scalar function implemented by the application embedding the database, see
+/// `Catalog::register_scalar_function`
+#[derive(Clone)]
+pub struct UserDefinedFunction {
+    pub name: String,
+    pub return_type: TypeId,
+    pub implementation: Arc<ScalarFunctionImpl>,
+}
+
+impl UserDefinedFunction {
+    /// Calls the function, making sure that it keeps to its declared return type
+    pub fn call(&self, args: &[Value]) -> Result<Value> {
+        let result = (self.implementation)(args)?;
+        if !result.is_of_type(self.return_type) {
+            return Err(Error::msg(format!(
+                "Function {} returned {}, which is not of its return type {}",
+                self.name, result, self.return_type
+            )));
+        }
+        Ok(result)
+    }
+}
+
+impl Debug for UserDefinedFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserDefinedFunction")
+            .field("name", &self.name)
+            .field("return_type", &self.return_type)
+            .finish()
+    }
+}
+
+/// Two functions are only equal if they share the implementation
+impl PartialEq for UserDefinedFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && Arc::ptr_eq(&self.implementation, &other.implementation)
+    }
+}
+
+/// One signature under which a scalar function can be called
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionOverload {
+    pub func: ScalarFunc,
+    pub arg_types: Vec<TypeId>,
+    /// whether the last argument can be repeated any number of times (including zero)
+    pub variadic: bool,
+    pub return_type: TypeId,
+}
+
+impl FunctionOverload {
+    fn new(func: ScalarFunc, arg_types: &[TypeId], return_type: TypeId) -> Self {
+        Self {
+            func,
+            arg_types: arg_types.to_vec(),
+            variadic: false,
+            return_type,
+        }
+    }
+
+    fn variadic(func: ScalarFunc, arg_types: &[TypeId], return_type: TypeId) -> Self {
+        Self {
+            variadic: true,
+            ..Self::new(func, arg_types, return_type)
+        }
+    }
+
+    /// Returns the types of the parameters for a call with this number of arguments, or None
+    /// if the overload does not accept this number of arguments
+    pub fn parameter_types(&self, num_args: usize) -> Option<Vec<TypeId>> {
+        let (last, fixed) = match (self.variadic, self.arg_types.split_last()) {
+            (true, Some((last, fixed))) if num_args >= fixed.len() => (*last, fixed),
+            _ if num_args == self.arg_types.len() => return Some(self.arg_types.clone()),
+            _ => return None,
+        };
+        let mut types = fixed.to_vec();
+        types.resize(num_args, last);
+        Some(types)
+    }
+}
+
+/// The scalar functions, which can be called by name. A name can have several overloads, which
+/// differ in the number or the types of their arguments
+pub struct FunctionRegistry {
+    functions: DashMap<String, Vec<FunctionOverload>>,
+}
+
+impl Default for FunctionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FunctionRegistry {
+    /// Creates a registry containing the built-in functions
+    pub fn new() -> Self {
+        use ScalarFunc::*;
+        use TypeId::{Bytea, Integer, Json, Text, Uuid};
+
+        let registry = Self {
+            functions: DashMap::new(),
+        };
+        // text comes first, so that it is preferred for string literals
+        let builtins = [
+            ("length", FunctionOverload::new(Length, &[Text], Integer)),
+            ("length", FunctionOverload::new(Length, &[Bytea], Integer)),
+            (
+                "octet_length",
+                FunctionOverload::new(OctetLength, &[Text], Integer),
+            ),
+            (
+                "octet_length",
+                FunctionOverload::new(OctetLength, &[Bytea], Integer),
+            ),
+            ("lower", FunctionOverload::new(Lower, &[Text], Text)),
+            ("upper", FunctionOverload::new(Upper, &[Text], Text)),
+            (
+                "substring",
+                FunctionOverload::new(Substring, &[Text, Integer], Text),
+            ),
+            (
+                "substring",
+                FunctionOverload::new(Substring, &[Text, Integer, Integer], Text),
+            ),
+            ("btrim", FunctionOverload::new(BTrim, &[Text], Text)),
+            ("btrim", FunctionOverload::new(BTrim, &[Text, Text], Text)),
+            ("ltrim", FunctionOverload::new(LTrim, &[Text], Text)),
+            ("ltrim", FunctionOverload::new(LTrim, &[Text, Text], Text)),
+            ("rtrim", FunctionOverload::new(RTrim, &[Text], Text)),
+            ("rtrim", FunctionOverload::new(RTrim, &[Text, Text], Text)),
+            (
+                "replace",
+                FunctionOverload::new(Replace, &[Text, Text, Text], Text),
+            ),
+            (
+                "position",
+                FunctionOverload::new(Position, &[Text, Text], Integer),
+            ),
+            (
+                "like_escape",
+                FunctionOverload::new(LikeEscape, &[Text, Text], Text),
+            ),
+            ("abs", FunctionOverload::new(Abs, &[Integer], Integer)),
+            ("round", FunctionOverload::new(Round, &[Integer], Integer)),
+            (
+                "round",
+                FunctionOverload::new(Round, &[Integer, Integer], Integer),
+            ),
+            (
+                "mod",
+                FunctionOverload::new(Mod, &[Integer, Integer], Integer),
+            ),
+            (
+                "power",
+                FunctionOverload::new(Power, &[Integer, Integer], Integer),
+            ),
+            // Postgres returns a value between 0 and 1, but without a floating point type
+            // random() returns a non-negative integer instead
+            ("random", FunctionOverload::new(Random, &[], Integer)),
+            (
+                "random",
+                FunctionOverload::new(Random, &[Integer, Integer], Integer),
+            ),
+            (
+                "gen_random_uuid",
+                FunctionOverload::new(GenRandomUuid, &[], Uuid),
+            ),
+            (
+                "json_extract_path",
+                FunctionOverload::variadic(JsonExtractPath, &[Json, Text], Json),
+            ),
+            (
+                "jsonb_extract_path",
+                FunctionOverload::variadic(JsonExtractPath, &[Json, Text], Json),
+            ),
+            (
+                "json_extract_path_text",
+                FunctionOverload::variadic(JsonExtractPathText, &[Json, Text], Text),
+            ),
+            (
+                "jsonb_extract_path_text",
+                FunctionOverload::variadic(JsonExtractPathText, &[Json, Text], Text),
+            ),
+        ];
+        for (name, overload) in builtins {
+            registry
+                .functions
+                .entry(name.to_owned())
+                .or_default()
+                .push(overload);
+        }
+        registry
+    }
+
+    /// Adds an overload of a user-defined function. It may neither share its name with a
+    /// function which is not in the registry, like an aggregation, nor its argument types with
+    /// another overload of the same name
+    pub fn register_user_defined(
+        &self,
+        name: &str,
+        arg_types: Vec<TypeId>,
+        return_type: TypeId,
+        implementation: Arc<ScalarFunctionImpl>,
+    ) -> Result<()> {
+        let is_identifier = name.starts_with(|ch: char| ch.is_ascii_lowercase() || ch == '_')
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_');
+        if !is_identifier || parser::is_reserved_keyword(name) {
+            return Err(Error::msg(format!(
+                "Invalid function name '{}', it must be a lowercase identifier and not a keyword",
+                name
+            )));
+        }
+        if ScalarFunc::is_scalar_func(name).is_some()
+            || AggregationFunc::is_aggregation_func(name).is_some()
+        {
+            return Err(Error::msg(format!(
+                "Function {} is built in and cannot be overloaded",
+                name
+            )));
+        }
+        if arg_types.contains(&TypeId::Unknown) || return_type == TypeId::Unknown {
+            return Err(Error::msg(format!(
+                "Function {} must only use known types",
+                name
+            )));
+        }
+
+        let mut overloads = self.functions.entry(name.to_owned()).or_default();
+        if overloads
+            .iter()
+            .any(|overload| overload.parameter_types(arg_types.len()).as_ref() == Some(&arg_types))
+        {
+            let arg_types = arg_types
+                .iter()
+                .map(|arg_type| arg_type.to_string())
+                .collect::<Vec<_>>();
+            return Err(Error::msg(format!(
+                "Function {}({}) already exists",
+                name,
+                arg_types.join(", ")
+            )));
+        }
+        let func = ScalarFunc::UserDefined(UserDefinedFunction {
+            name: name.to_owned(),
+            return_type,
+            implementation,
+        });
+        overloads.push(FunctionOverload::new(func, &arg_types, return_type));
+        Ok(())
+    }
+
+    /// Picks the overload for a call with arguments of these types. Only overloads, to whose
+    /// parameter types all arguments can be implicitly cast, are considered. Of these, the one
+    /// with the most arguments matching exactly wins, ties go to the overload registered first
+    pub fn resolve(&self, name: &str, arg_types: &[TypeId]) -> Result<FunctionOverload> {
+        let overloads = self
+            .functions
+            .get(name)
+            .ok_or_else(|| Error::msg(format!("Cannot find function {}.", name)))?;
+
+        let mut best: Option<(&FunctionOverload, usize)> = None;
+        for overload in overloads.iter() {
+            let param_types = match overload.parameter_types(arg_types.len()) {
+                Some(param_types) => param_types,
+                None => continue,
+            };
+            let applicable = arg_types.iter().zip(&param_types).all(|(&arg, &param)| {
+                cast::cast_context(arg, param) == Some(CastContext::Implicit)
+            });
+            let exact_matches = arg_types
+                .iter()
+                .zip(&param_types)
+                .filter(|(arg, param)| arg == param)
+                .count();
+            if applicable && best.is_none_or(|(_, most)| exact_matches > most) {
+                best = Some((overload, exact_matches));
+            }
+        }
+
+        best.map(|(overload, _)| overload.clone()).ok_or_else(|| {
+            let arg_types = arg_types
+                .iter()
+                .map(|arg_type| arg_type.to_string())
+                .collect::<Vec<_>>();
+            Error::msg(format!(
+                "`{}` cannot be called with arguments of types ({})",
+                name,
+                arg_types.join(", ")
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FunctionRegistry;
+    use crate::analyzer::logical_plan::ScalarFunc;
+    use crate::catalog::schema::TypeId;
+
+    #[test]
+    fn can_resolve_overloads() {
+        let registry = FunctionRegistry::new();
+        let resolve = |name: &str, arg_types: &[TypeId]| {
+            registry
+                .resolve(name, arg_types)
+                .map(|overload| (overload.func, overload.arg_types))
+        };
+
+        assert_eq!(
+            resolve("length", &[TypeId::Bytea]).unwrap(),
+            (ScalarFunc::Length, vec![TypeId::Bytea])
+        );
+        assert_eq!(
+            resolve("length", &[TypeId::Unknown]).unwrap(),
+            (ScalarFunc::Length, vec![TypeId::Text])
+        );
+        assert_eq!(
+            resolve(
+                "substring",
+                &[TypeId::Text, TypeId::Integer, TypeId::Integer]
+            )
+            .unwrap()
+            .1
+            .len(),
+            3
+        );
+        assert_eq!(
+            resolve("round", &[TypeId::Integer, TypeId::Unknown])
+                .unwrap()
+                .1,
+            vec![TypeId::Integer, TypeId::Integer]
+        );
+        let path = [TypeId::Json, TypeId::Text, TypeId::Unknown, TypeId::Text];
+        assert_eq!(
+            resolve("json_extract_path", &path).unwrap().0,
+            ScalarFunc::JsonExtractPath
+        );
+
+        assert_eq!(
+            resolve("random", &[]).unwrap(),
+            (ScalarFunc::Random, vec![])
+        );
+
+        assert!(resolve("json_extract_path", &[]).is_err());
+        assert!(resolve("length", &[TypeId::Integer]).is_err());
+        assert!(resolve("substring", &[TypeId::Text]).is_err());
+        assert!(resolve("abs", &[TypeId::Date]).is_err());
+        assert!(resolve("no_such_function", &[]).is_err());
+    }
+}
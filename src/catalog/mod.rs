@@ -8,6 +8,7 @@ use dashmap::DashMap;
 use lazy_static::lazy_static;
 
 use crate::buffer::buffer_manager::BufferManager;
+use crate::catalog::functions::FunctionRegistry;
 use crate::catalog::schema::{ColumnDefinition, Schema, TypeId};
 use crate::common::{
     TableId, CATALOG_COLUMNS_TABLE_ID, CATALOG_TABLES_TABLE_ID, USER_DATA_TABLE_ID_START,
@@ -17,6 +18,7 @@ use crate::storage::heap::table::Table;
 use crate::tuple::value::Value;
 use crate::tuple::Tuple;
 
+pub mod functions;
 pub mod schema;
 
 const CATALOG_TABLES_NAME: &str = "system_catalog_tables";
@@ -32,7 +34,7 @@ lazy_static! {
         ColumnDefinition::new(TypeId::Text, "column_name".to_owned(), 1, true),
         ColumnDefinition::new(TypeId::Integer, "column_offset".to_owned(), 2, true),
         ColumnDefinition::new(TypeId::Text, "column_type".to_owned(), 3, true),
-        ColumnDefinition::new(TypeId::Boolean, "not_null".to_owned(), 4, true)
+        ColumnDefinition::new(TypeId::Boolean, "not_null".to_owned(), 4, true),
     ]);
 }
 
@@ -44,6 +46,7 @@ pub struct Catalog {
     columns_table: Table,
     table_name_to_id: DashMap<String, TableId>,
     table_id_to_schema: DashMap<TableId, Schema>,
+    functions: FunctionRegistry,
 }
 
 impl Catalog {
@@ -71,6 +74,7 @@ impl Catalog {
             columns_table,
             table_name_to_id: DashMap::new(),
             table_id_to_schema: DashMap::new(),
+            functions: FunctionRegistry::new(),
         };
 
         if bootstrap {
@@ -94,6 +98,28 @@ impl Catalog {
         })
     }
 
+    pub fn functions(&self) -> &FunctionRegistry {
+        &self.functions
+    }
+
+    /// Registers a scalar function implemented in Rust, which can be called from SQL like the
+    /// built-in functions. The arguments are converted to the declared types. If any argument is
+    /// NULL, the function is not called and the result is NULL. Functions of the same name are
+    /// overloads and are told apart by their argument types
+    pub fn register_scalar_function<F>(
+        &self,
+        name: &str,
+        arg_types: Vec<TypeId>,
+        return_type: TypeId,
+        func: F,
+    ) -> Result<()>
+    where
+        F: Fn(&[Value]) -> Result<Value> + Send + Sync + 'static,
+    {
+        self.functions
+            .register_user_defined(name, arg_types, return_type, Arc::new(func))
+    }
+
     fn create_catalog_tables(&self, bootstrap_transaction: &Transaction) -> Result<()> {
         self.buffer_manager.create_table(CATALOG_TABLES_TABLE_ID)?;
         self.buffer_manager.create_table(CATALOG_COLUMNS_TABLE_ID)?;
@@ -200,7 +226,7 @@ impl Catalog {
                 Value::Integer(table_id as i32),
                 Value::String(column.column_name().to_owned()),
                 Value::Integer(column.column_offset() as i32),
-                Value::String(column.type_id().to_string()),
+                Value::String(column.catalog_type_name()),
                 Value::Boolean(column.not_null()),
             ];
             let tuple = Tuple::new(values);
@@ -246,7 +272,7 @@ mod tests {
 
     use super::{Catalog, CATALOG_TABLES_SCHEMA};
     use crate::buffer::buffer_manager::BufferManager;
-    use crate::catalog::schema::{ColumnDefinition, Schema, TypeId};
+    use crate::catalog::schema::{ColumnDefinition, Schema, TextLength, TypeId};
     use crate::catalog::{CATALOG_COLUMNS_NAME, CATALOG_COLUMNS_SCHEMA, CATALOG_TABLES_NAME};
     use crate::concurrency::TransactionManager;
     use crate::storage::file_manager::FileManager;
@@ -295,6 +321,11 @@ mod tests {
             ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, true),
             ColumnDefinition::new(TypeId::Boolean, "blocked".to_owned(), 2, true),
             ColumnDefinition::new(TypeId::Text, "email".to_owned(), 3, false),
+            ColumnDefinition::new(TypeId::Text, "nickname".to_owned(), 4, false)
+                .with_text_length(TextLength::Varying(20)),
+            ColumnDefinition::new(TypeId::Text, "country".to_owned(), 5, true)
+                .with_text_length(TextLength::Fixed(2)),
+            ColumnDefinition::new(TypeId::Bytea, "avatar".to_owned(), 6, false),
         ];
 
         let transaction = transaction_manager.start_transaction(None)?;
@@ -305,6 +336,11 @@ mod tests {
         let fetched_columns = fetched_columns.unwrap();
         assert_eq!(fetched_columns.columns(), &expected_columns);
 
+        let transaction = transaction_manager.start_transaction(None)?;
+        let catalog = Catalog::new(Arc::clone(&buffer_manager), false, &transaction)?;
+        let reloaded_columns = catalog.get_schema("accounts").unwrap();
+        assert_eq!(reloaded_columns.columns(), &expected_columns);
+
         Ok(())
     }
 }
@@ -1,16 +1,28 @@
 use std::fmt::Display;
 use std::str::FromStr;
 
-use anyhow::Error;
+use anyhow::{Error, Result};
 
 use super::Tuple;
 use crate::parser::ast;
+use crate::tuple::value::Value;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TypeId {
     Boolean,
     Integer,
     Text,
+    Date,
+    Time,
+    Timestamp,
+    Interval,
+    Bytea,
+    Uuid,
+    Json,
+    /// INTEGER[]
+    IntegerArray,
+    /// TEXT[]
+    TextArray,
     // still unknown, cannot be specified by a user, only used internally
     Unknown,
 }
@@ -23,6 +35,15 @@ impl FromStr for TypeId {
             "Boolean" => Ok(TypeId::Boolean),
             "Integer" => Ok(TypeId::Integer),
             "Text" => Ok(TypeId::Text),
+            "Date" => Ok(TypeId::Date),
+            "Time" => Ok(TypeId::Time),
+            "Timestamp" => Ok(TypeId::Timestamp),
+            "Interval" => Ok(TypeId::Interval),
+            "Bytea" => Ok(TypeId::Bytea),
+            "Uuid" => Ok(TypeId::Uuid),
+            "Json" => Ok(TypeId::Json),
+            "Integer[]" => Ok(TypeId::IntegerArray),
+            "Text[]" => Ok(TypeId::TextArray),
             s => Err(Error::msg(format!("Invalid TypeId {}", s))),
         }
     }
@@ -32,15 +53,114 @@ impl From<ast::DataType> for TypeId {
     fn from(value: ast::DataType) -> Self {
         match value {
             ast::DataType::Integer => Self::Integer,
-            ast::DataType::Text => Self::Text,
+            ast::DataType::Text | ast::DataType::Varchar(_) | ast::DataType::Char(_) => Self::Text,
             ast::DataType::Boolean => Self::Boolean,
+            ast::DataType::Date => Self::Date,
+            ast::DataType::Time => Self::Time,
+            ast::DataType::Timestamp => Self::Timestamp,
+            ast::DataType::Interval => Self::Interval,
+            ast::DataType::Bytea => Self::Bytea,
+            ast::DataType::Uuid => Self::Uuid,
+            ast::DataType::Json => Self::Json,
+            ast::DataType::Array(element_type) => TypeId::from(*element_type)
+                .array_type()
+                .expect("the parser only allows arrays of supported types"),
+        }
+    }
+}
+
+impl TypeId {
+    /// Returns the type of the elements if this is an array type
+    pub fn element_type(&self) -> Option<TypeId> {
+        match self {
+            Self::IntegerArray => Some(Self::Integer),
+            Self::TextArray => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    /// Returns the type of an array with elements of this type, if such an array type exists
+    pub fn array_type(&self) -> Option<TypeId> {
+        match self {
+            Self::Integer => Some(Self::IntegerArray),
+            Self::Text => Some(Self::TextArray),
+            _ => None,
         }
     }
 }
 
 impl Display for TypeId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self.element_type() {
+            Some(element_type) => write!(f, "{}[]", element_type),
+            None => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// The declared length of a text column
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TextLength {
+    /// VARCHAR(n), values may have at most n characters
+    Varying(u8),
+    /// CHAR(n), values are padded with spaces to exactly n characters
+    Fixed(u8),
+}
+
+impl TextLength {
+    /// Returns the declared length of a VARCHAR(n) or CHAR(n) type
+    pub fn from_data_type(data_type: &ast::DataType) -> Option<Self> {
+        match data_type {
+            ast::DataType::Varchar(len) => Some(Self::Varying(*len)),
+            ast::DataType::Char(len) => Some(Self::Fixed(*len)),
+            _ => None,
+        }
+    }
+
+    pub fn max_chars(&self) -> usize {
+        match self {
+            Self::Varying(len) | Self::Fixed(len) => *len as usize,
+        }
+    }
+
+    /// Converts a text value like an explicit cast to this type does: characters exceeding the
+    /// length are truncated and CHAR(n) values are padded with spaces
+    pub fn cast(&self, val: &str) -> String {
+        let mut val = val.chars().take(self.max_chars()).collect::<String>();
+        if let Self::Fixed(_) = self {
+            let padding = self.max_chars() - val.chars().count();
+            val.extend(std::iter::repeat_n(' ', padding));
+        }
+        val
+    }
+
+    /// the name under which the column type is stored in the catalog, the length is part of it
+    /// so that the catalog schema stays the same as for data directories without lengths
+    fn catalog_type_name(&self) -> String {
+        match self {
+            Self::Varying(len) => format!("Varchar({})", len),
+            Self::Fixed(len) => format!("Char({})", len),
+        }
+    }
+
+    /// parses the name of a column type stored in the catalog, None if it has no length
+    fn parse_catalog_type_name(type_name: &str) -> Option<Self> {
+        let (name, len) = type_name.strip_suffix(')')?.split_once('(')?;
+        let len = len.parse().ok()?;
+        match name {
+            "Varchar" => Some(Self::Varying(len)),
+            "Char" => Some(Self::Fixed(len)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for TextLength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Varying(len) => write!(f, "VARCHAR({})", len),
+            Self::Fixed(len) => write!(f, "CHAR({})", len),
+        }
     }
 }
 
@@ -50,6 +170,8 @@ pub struct ColumnDefinition {
     pub column_name: String,
     pub column_offset: u8,
     pub not_null: bool,
+    /// only set for text columns declared as VARCHAR(n) or CHAR(n)
+    pub text_length: Option<TextLength>,
 }
 
 impl ColumnDefinition {
@@ -59,9 +181,16 @@ impl ColumnDefinition {
             column_name,
             column_offset,
             not_null,
+            text_length: None,
         }
     }
 
+    /// Declares a maximum length for this (text) column
+    pub fn with_text_length(mut self, text_length: TextLength) -> Self {
+        self.text_length = Some(text_length);
+        self
+    }
+
     /// creates a column definition where only the type is known
     pub fn with_type_id(type_id: TypeId) -> Self {
         Self {
@@ -69,6 +198,7 @@ impl ColumnDefinition {
             column_name: String::new(),
             column_offset: 0,
             not_null: type_id != TypeId::Unknown,
+            text_length: None,
         }
     }
 
@@ -87,26 +217,75 @@ impl ColumnDefinition {
     pub fn not_null(&self) -> bool {
         self.not_null
     }
+
+    pub fn text_length(&self) -> Option<TextLength> {
+        self.text_length
+    }
+
+    /// Returns the name under which the column type is stored in the catalog
+    pub fn catalog_type_name(&self) -> String {
+        match self.text_length {
+            Some(text_length) => text_length.catalog_type_name(),
+            None => self.type_id.to_string(),
+        }
+    }
+
+    /// Makes sure that a value which is about to be stored in this column fits the declared
+    /// length. Values for CHAR(n) columns are padded with spaces.
+    /// As in Postgres, trailing spaces exceeding the length are silently truncated
+    pub fn enforce_length(&self, value: Value) -> Result<Value> {
+        let (text_length, val) = match (self.text_length, value) {
+            (Some(text_length), Value::String(val)) => (text_length, val),
+            (_, value) => return Ok(value),
+        };
+
+        if !val.chars().skip(text_length.max_chars()).all(|c| c == ' ') {
+            return Err(Error::msg(format!(
+                "Value too long for column {} of type {}",
+                self.column_name, text_length
+            )));
+        }
+        let val = text_length.cast(&val);
+        if val.len() > u8::MAX as usize {
+            return Err(Error::msg(format!(
+                "Value for column {} exceeds {} bytes",
+                self.column_name,
+                u8::MAX
+            )));
+        }
+        Ok(Value::String(val))
+    }
 }
 
 impl From<Tuple> for ColumnDefinition {
     fn from(tuple: Tuple) -> Self {
+        let type_name = tuple.as_str(3);
+        let (type_id, text_length) = match TextLength::parse_catalog_type_name(type_name) {
+            Some(text_length) => (TypeId::Text, Some(text_length)),
+            None => (type_name.parse().unwrap(), None),
+        };
         Self {
-            type_id: tuple.as_str(3).parse().unwrap(),
+            type_id,
             column_name: tuple.as_str(1).to_owned(),
             column_offset: tuple.as_i32(2) as u8,
             not_null: tuple.as_bool(4),
+            text_length,
         }
     }
 }
 
 impl From<ast::ColumnDefinition> for ColumnDefinition {
     fn from(value: ast::ColumnDefinition) -> Self {
-        Self {
-            type_id: value.data_type.into(),
-            column_name: value.name,
-            column_offset: value.offset,
-            not_null: value.not_null,
+        let text_length = TextLength::from_data_type(&value.data_type);
+        let column = Self::new(
+            value.data_type.into(),
+            value.name,
+            value.offset,
+            value.not_null,
+        );
+        match text_length {
+            Some(text_length) => column.with_text_length(text_length),
+            None => column,
         }
     }
 }
@@ -124,16 +124,16 @@ impl LockManager {
     }
 
     /// Locks a tuple. Waits until the lock can be granted.
-    pub fn lock_tuple(&self, to_lock: (TableId, TupleId), mode: LockMode) -> LockGuard {
+    pub fn lock_tuple(&self, to_lock: (TableId, TupleId), mode: LockMode) -> LockGuard<'_> {
         self.lock(LockTag::Tuple(to_lock), mode)
     }
 
     /// Locks a transaction. Waits until the lock can be granted.
-    pub fn lock_transaction(&self, to_lock: TransactionId, mode: LockMode) -> LockGuard {
+    pub fn lock_transaction(&self, to_lock: TransactionId, mode: LockMode) -> LockGuard<'_> {
         self.lock(LockTag::Transaction(to_lock), mode)
     }
 
-    fn lock(&self, tag: LockTag, mode: LockMode) -> LockGuard {
+    fn lock(&self, tag: LockTag, mode: LockMode) -> LockGuard<'_> {
         match self.lock_table.entry(tag) {
             Entry::Occupied(entry) => {
                 let lock = &*entry.get().clone();
@@ -9,7 +9,7 @@ use self::lock_manager::{LockManager, LockMode};
 use crate::buffer::buffer_manager::{BufferGuard, BufferManager};
 use crate::common::{INVALID_PAGE_NO, PAGE_SIZE, TRANSACTION_LOG_TABLE_ID};
 
-pub mod lock_manager;
+pub(crate) mod lock_manager;
 
 pub type CommandId = u8;
 pub type TransactionId = u32;
@@ -241,11 +241,11 @@ impl TransactionManager {
     pub fn start_transaction(
         &self,
         isolation_level: Option<IsolationLevel>,
-    ) -> Result<Transaction> {
+    ) -> Result<Transaction<'_>> {
         self.create_transaction(false, isolation_level.unwrap_or(DEFAULT_ISOLATION_LEVEL))
     }
 
-    pub fn start_implicit_transaction(&self) -> Result<Transaction> {
+    pub fn start_implicit_transaction(&self) -> Result<Transaction<'_>> {
         self.create_transaction(true, DEFAULT_ISOLATION_LEVEL)
     }
 
@@ -270,7 +270,7 @@ impl TransactionManager {
         &self,
         auto_commit: bool,
         isolation_level: IsolationLevel,
-    ) -> Result<Transaction> {
+    ) -> Result<Transaction<'_>> {
         let tid = self
             .next_tid
             .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |val| {
@@ -305,7 +305,7 @@ impl TransactionManager {
 
     /// Starts a transaction which can be used by other parts to complete bootstrap process.
     /// Should be used only during bootstrap process
-    pub fn bootstrap(&self) -> Transaction {
+    pub fn bootstrap(&self) -> Transaction<'_> {
         self.lock_manager
             .start_transaction(BOOTSTRAP_TRANSACTION_ID);
         Transaction {
@@ -355,7 +355,7 @@ impl TransactionManager {
     }
 
     /// Finds the page for which the status of this transaction id is stored
-    fn get_page(&self, tid: TransactionId) -> Result<Option<BufferGuard>> {
+    fn get_page(&self, tid: TransactionId) -> Result<Option<BufferGuard<'_>>> {
         let array_pos = tid / 4;
         let page = array_pos / (PAGE_SIZE as u32) + 1;
         let highest_log_table_no = self
@@ -478,7 +478,7 @@ mod tests {
         // fill at least a page of the transaction log
         for _ in 0..4 * PAGE_SIZE {
             let t = transaction_manager.start_transaction(None).unwrap();
-            if t.tid % 5 == 0 {
+            if t.tid.is_multiple_of(5) {
                 t.abort().unwrap();
             } else {
                 t.commit().unwrap();
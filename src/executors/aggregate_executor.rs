@@ -3,27 +3,26 @@ use anyhow::Result;
 use super::Executor;
 use crate::catalog::schema::Schema;
 use crate::planner::physical_plan::Aggregation;
-use crate::tuple::value::Value;
 use crate::tuple::Tuple;
 
 pub struct AggregateExecutor<'a> {
     child: Box<dyn Executor + 'a>,
     aggregations: Vec<Aggregation>,
-    agg_results: Vec<Value>,
     done: bool,
+    output_schema: Schema,
 }
 
 impl<'a> AggregateExecutor<'a> {
-    pub fn new(child: Box<dyn Executor + 'a>, aggregations: Vec<Aggregation>) -> Self {
-        let initial_agg_results = aggregations
-            .iter()
-            .map(|agg| agg.initial_accumulator_value())
-            .collect();
+    pub fn new(
+        child: Box<dyn Executor + 'a>,
+        aggregations: Vec<Aggregation>,
+        output_schema: Schema,
+    ) -> Self {
         Self {
             child,
             aggregations,
-            agg_results: initial_agg_results,
             done: false,
+            output_schema,
         }
     }
 
@@ -31,15 +30,24 @@ impl<'a> AggregateExecutor<'a> {
         if self.done {
             Ok(None)
         } else {
+            let mut accumulators = self
+                .aggregations
+                .iter()
+                .map(|agg| agg.initial_accumulator())
+                .collect::<Vec<_>>();
             while let Some(tuple) = self.child.next().transpose()? {
-                for (agg_result, aggregation) in
-                    self.agg_results.iter_mut().zip(self.aggregations.iter())
-                {
-                    aggregation.aggregate(agg_result, &tuple);
+                for (acc, aggregation) in accumulators.iter_mut().zip(self.aggregations.iter()) {
+                    aggregation.aggregate(acc, &tuple)?;
                 }
             }
             self.done = true;
-            Ok(Some(Tuple::new(self.agg_results.clone())))
+
+            let agg_results = accumulators
+                .into_iter()
+                .zip(self.aggregations.iter())
+                .map(|(acc, aggregation)| aggregation.finalize(acc))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Some(Tuple::new(agg_results)))
         }
     }
 }
@@ -50,16 +58,11 @@ impl<'a> Executor for AggregateExecutor<'a> {
     }
 
     fn schema(&self) -> &Schema {
-        unimplemented!()
+        &self.output_schema
     }
 
     fn rewind(&mut self) -> Result<()> {
         self.done = false;
-        self.agg_results = self
-            .aggregations
-            .iter()
-            .map(|agg| agg.initial_accumulator_value())
-            .collect();
         self.child.rewind()
     }
 }
@@ -106,7 +109,7 @@ mod tests {
             Value::Integer(7),
             Value::Integer(3),
         ];
-        assert_eq!(result.get(0).unwrap().values, expected_values);
+        assert_eq!(result.first().unwrap().values, expected_values);
     }
 
     #[test]
@@ -143,7 +146,7 @@ mod tests {
             Value::String("foo".to_owned()),
             Value::Integer(6),
         ];
-        assert_eq!(result.get(0).unwrap().values, expected_values);
+        assert_eq!(result.first().unwrap().values, expected_values);
     }
 
     #[test]
@@ -175,6 +178,130 @@ mod tests {
         assert_eq!(result.len(), 1);
 
         let expected_values = vec![Value::Integer(6), Value::Integer(10)];
-        assert_eq!(result.get(0).unwrap().values, expected_values);
+        assert_eq!(result.first().unwrap().values, expected_values);
+    }
+
+    #[test]
+    fn can_execute_array_aggregations() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "accounts",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+
+        let select = "select array_agg(id) from accounts";
+        let result = test_db.execute_query(select).unwrap();
+        assert_eq!(result.first().unwrap().values, vec![Value::Null]);
+
+        let insert_statement = "
+            insert into accounts values (1, 'foo'), (2, NULL), (3, 'baz')
+        ";
+        test_db.execute_query(insert_statement).unwrap();
+
+        let select = "select array_agg(id), array_agg(name), array_agg(id)[2] from accounts";
+        let result = test_db.execute_query(select).unwrap();
+        assert_eq!(result.len(), 1);
+
+        let expected_values = vec![
+            Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Integer(3),
+            ]),
+            Value::Array(vec![
+                Value::String("foo".to_owned()),
+                Value::Null,
+                Value::String("baz".to_owned()),
+            ]),
+            Value::Integer(2),
+        ];
+        assert_eq!(result.first().unwrap().values, expected_values);
+    }
+
+    #[test]
+    fn can_execute_aggregation_functions() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "accounts",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, false),
+                    ColumnDefinition::new(TypeId::Boolean, "active".to_owned(), 2, false),
+                ],
+            )
+            .unwrap();
+
+        let select = "
+            select
+                count(*), count(distinct name), sum(id), avg(id), min(name),
+                bool_and(active), bool_or(active), string_agg(name, ', ')
+            from accounts
+        ";
+        let result = test_db.execute_query(select).unwrap();
+        let expected_values = vec![
+            Value::Integer(0),
+            Value::Integer(0),
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+        ];
+        assert_eq!(result.first().unwrap().values, expected_values);
+
+        let insert_statement = "
+            insert into accounts values
+                (1, 'foo', true), (2, NULL, NULL), (3, 'baz', false), (4, 'foo', true)
+        ";
+        test_db.execute_query(insert_statement).unwrap();
+
+        let result = test_db.execute_query(select).unwrap();
+        let expected_values = vec![
+            Value::Integer(4),
+            Value::Integer(2),
+            Value::Integer(10),
+            Value::Integer(3),
+            Value::String("baz".to_owned()),
+            Value::Boolean(false),
+            Value::Boolean(true),
+            Value::String("foo, baz, foo".to_owned()),
+        ];
+        assert_eq!(result.first().unwrap().values, expected_values);
+
+        let select = "
+            select sum(distinct id % 2), string_agg(distinct name, '-'), count(*) + count(id)
+            from accounts
+            where active
+        ";
+        let result = test_db.execute_query(select).unwrap();
+        let expected_values = vec![
+            Value::Integer(1),
+            Value::String("foo".to_owned()),
+            Value::Integer(4),
+        ];
+        assert_eq!(result.first().unwrap().values, expected_values);
+
+        test_db
+            .execute_query("insert into accounts values (2147483647, 'max', true)")
+            .unwrap();
+        assert!(test_db
+            .execute_query("select sum(id) from accounts")
+            .is_err());
+        assert!(test_db
+            .execute_query("select sum(name) from accounts")
+            .is_err());
+        assert!(test_db
+            .execute_query("select max(*) from accounts")
+            .is_err());
+        assert!(test_db
+            .execute_query("select length(distinct name) from accounts")
+            .is_err());
     }
 }
@@ -0,0 +1,130 @@
+use std::rc::Rc;
+
+use anyhow::Result;
+
+use super::Executor;
+use crate::catalog::schema::Schema;
+use crate::tuple::value::Value;
+use crate::tuple::Tuple;
+
+/// Returns the materialized rows of a common table expression. The rows are shared by all
+/// scans of the CTE and kept in memory until the statement is executed
+pub struct CteScanExecutor {
+    cursor: usize,
+    rows: Rc<Vec<Vec<Value>>>,
+    schema: Schema,
+}
+
+impl CteScanExecutor {
+    pub fn new(rows: Rc<Vec<Vec<Value>>>, schema: Schema) -> Self {
+        Self {
+            cursor: 0,
+            rows,
+            schema,
+        }
+    }
+}
+
+impl Executor for CteScanExecutor {
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        let values = self.rows.get(self.cursor)?;
+        self.cursor += 1;
+        Some(Ok(Tuple::new(values.clone())))
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.cursor = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::executors::tests::TestDb;
+    use crate::tuple::value::Value;
+
+    #[test]
+    fn can_query_common_table_expressions() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "orders",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "customer".to_owned(), 1, false),
+                    ColumnDefinition::new(TypeId::Integer, "amount".to_owned(), 2, true),
+                ],
+            )
+            .unwrap();
+        let insert_statement = "insert into orders values \
+            (1, 'foo', 10), (2, 'bar', 20), (3, 'foo', 30), (4, null, 40), (5, 'bar', 5)";
+        test_db.execute_query(insert_statement).unwrap();
+
+        let rows = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let string = |s: &str| Value::String(s.to_owned());
+
+        let sql = "with totals as (select customer, sum(amount) total from orders group by 1) \
+                   select customer, total from totals where total > 20 order by customer";
+        assert_eq!(
+            rows(sql),
+            vec![
+                vec![string("bar"), Value::Integer(25)],
+                vec![string("foo"), Value::Integer(40)],
+                vec![Value::Null, Value::Integer(40)],
+            ]
+        );
+        // a CTE referenced more than once is materialized
+        let sql = "with big (order_id, customer) as (select id, customer from orders \
+                   where amount >= 10) \
+                   select a.order_id, b.order_id from big a join big b \
+                   on a.customer = b.customer and a.order_id < b.order_id";
+        assert_eq!(rows(sql), vec![vec![Value::Integer(1), Value::Integer(3)]]);
+        let sql = "with big as (select * from orders where amount >= 20) \
+                   select id from orders where id in (select id from big) \
+                   and amount < (select max(amount) from big) order by id";
+        assert_eq!(
+            rows(sql),
+            vec![vec![Value::Integer(2)], vec![Value::Integer(3)]]
+        );
+        // later CTEs can reference earlier ones, and CTEs shadow tables
+        let sql = "with orders as (select id, amount * 2 amount from orders), \
+                   doubled as (select amount from orders where id = 1) \
+                   select (select amount from doubled), count(*) from orders";
+        assert_eq!(rows(sql), vec![vec![Value::Integer(20), Value::Integer(5)]]);
+        let sql = "with a as (select 1), b as (select * from a union all select 2) \
+                   select * from b union all (with c as (select * from b) select * from c) \
+                   order by 1";
+        assert_eq!(
+            rows(sql),
+            vec![
+                vec![Value::Integer(1)],
+                vec![Value::Integer(1)],
+                vec![Value::Integer(2)],
+                vec![Value::Integer(2)],
+            ]
+        );
+
+        assert!(test_db
+            .execute_query("with a as (select 1), a as (select 2) select * from a")
+            .is_err());
+        assert!(test_db
+            .execute_query("with a (x, y) as (select 1) select * from a")
+            .is_err());
+        // a CTE is not visible outside of its query
+        assert!(test_db
+            .execute_query("select (with a as (select 1) select * from a) from a")
+            .is_err());
+    }
+}
@@ -16,19 +16,19 @@ impl<'a> FilterExecutor<'a> {
         Self { child, filter }
     }
 
-    fn satisfies_filter(&self, tuple: &Tuple) -> bool {
-        self.filter
-            .iter()
-            .all(|expr| match expr.evaluate(&[tuple]) {
-                Value::Boolean(b) => b,
-                _ => false,
-            })
+    fn satisfies_filter(&self, tuple: &Tuple) -> Result<bool> {
+        for expr in self.filter.iter() {
+            if expr.evaluate(&[tuple])? != Value::Boolean(true) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
     }
 
     fn next(&mut self) -> Result<Option<Tuple>> {
         loop {
             if let Some(tuple) = self.child.next().transpose()? {
-                if self.satisfies_filter(&tuple) {
+                if self.satisfies_filter(&tuple)? {
                     return Ok(Some(tuple));
                 }
             } else {
@@ -52,7 +52,8 @@ impl<'a> Executor for FilterExecutor<'a> {
     }
 
     fn re_evaluate_tuple(&self, tuple: &Tuple) -> bool {
-        self.child.re_evaluate_tuple(tuple) && self.satisfies_filter(tuple)
+        // a tuple whose filter fails to evaluate does not qualify anymore
+        self.child.re_evaluate_tuple(tuple) && matches!(self.satisfies_filter(tuple), Ok(true))
     }
 }
 
@@ -60,6 +61,7 @@ impl<'a> Executor for FilterExecutor<'a> {
 mod tests {
     use crate::catalog::schema::{ColumnDefinition, TypeId};
     use crate::executors::tests::TestDb;
+    use crate::tuple::value::Value;
 
     #[test]
     fn can_execute_queries_with_filter_conditions() {
@@ -93,4 +95,168 @@ mod tests {
 
         assert_eq!(result, expected_numbers);
     }
+
+    #[test]
+    fn filters_use_three_valued_logic() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "accounts",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, false),
+                    ColumnDefinition::new(TypeId::Boolean, "active".to_owned(), 2, false),
+                ],
+            )
+            .unwrap();
+
+        let insert_statement =
+            "insert into accounts values (1, 'foo', true), (2, null, false), (3, 'bar', null)";
+        test_db.execute_query(insert_statement).unwrap();
+
+        let select_ids = |filter: &str| {
+            let mut ids = test_db
+                .execute_query(&format!("select id from accounts where {}", filter))
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values()[0].as_i32())
+                .collect::<Vec<i32>>();
+            ids.sort();
+            ids
+        };
+
+        // an optional filter, where the parameter was not given
+        assert_eq!(select_ids("null is null or name = null"), vec![1, 2, 3]);
+        assert_eq!(select_ids("name = 'foo' or active = false"), vec![1, 2]);
+        assert_eq!(select_ids("not active"), vec![2]);
+        assert_eq!(select_ids("active is not true"), vec![2, 3]);
+        assert_eq!(select_ids("active is unknown"), vec![3]);
+        assert_eq!(select_ids("name is distinct from 'foo'"), vec![2, 3]);
+        assert_eq!(select_ids("name is not distinct from null"), vec![2]);
+
+        assert!(test_db
+            .execute_query("select id from accounts where name is true")
+            .is_err());
+        assert!(test_db
+            .execute_query("select id from accounts where not id")
+            .is_err());
+    }
+
+    #[test]
+    fn can_filter_bytea_columns() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "files",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Bytea, "hash".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+
+        let insert_statement =
+            "insert into files values (1, '\\xdeadbeef'), (2, '\\x00ff'), (3, null)";
+        test_db.execute_query(insert_statement).unwrap();
+        test_db
+            .execute_query("insert into files values (4, bytea '\\x0102')")
+            .unwrap();
+
+        let select = "select id, octet_length(hash) from files where hash = '\\xDEADBEEF'";
+        let result = test_db.execute_query(select).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].values(), &[Value::Integer(1), Value::Integer(4)]);
+
+        let select = "select hash from files where hash < '\\x0103'";
+        let mut result = test_db
+            .execute_query(select)
+            .unwrap()
+            .iter()
+            .map(|tuple| tuple.values()[0].to_string())
+            .collect::<Vec<_>>();
+        result.sort();
+        assert_eq!(result, vec!["\\x00ff", "\\x0102"]);
+
+        let result = test_db.execute_query("insert into files values (5, '\\xabc')");
+        assert!(result.is_err());
+
+        // values exceeding a page or the length prefix are rejected before they are written
+        for len in [10_000, 70_000] {
+            let hex = "ab".repeat(len);
+            let insert = format!("insert into files values (6, '\\x{}')", hex);
+            assert!(test_db.execute_query(&insert).is_err());
+            let update = format!("update files set hash = '\\x{}' where id = 1", hex);
+            assert!(test_db.execute_query(&update).is_err());
+        }
+        let result = test_db
+            .execute_query("select id from files where octet_length(hash) > 4")
+            .unwrap();
+        assert!(result.is_empty());
+        let result = test_db.execute_query("select id from files").unwrap();
+        assert_eq!(result.len(), 4);
+    }
+
+    #[test]
+    fn can_filter_json_columns() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "events",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Json, "payload".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+
+        let insert_statement = r#"insert into events values
+            (1, '{"kind": "click", "tags": ["a", "b"], "pos": {"x": 1, "y": 2}}'),
+            (2, '{"kind": "view", "tags": ["b"]}'),
+            (3, null)"#;
+        test_db.execute_query(insert_statement).unwrap();
+
+        let select = r#"select id, payload->>'kind', payload->'tags'->0 from events
+            where payload @> '{"tags": ["a"]}'"#;
+        let result = test_db.execute_query(select).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].values()[0], Value::Integer(1));
+        assert_eq!(result[0].values()[1], Value::String("click".to_owned()));
+        assert_eq!(result[0].values()[2].to_string(), "\"a\"");
+
+        let select = "select json_extract_path(payload, 'pos', 'y'), \
+                      json_extract_path_text(payload, 'kind') from events where id = 2";
+        let result = test_db.execute_query(select).unwrap();
+        assert_eq!(
+            result[0].values(),
+            &[Value::Null, Value::String("view".to_owned())]
+        );
+
+        let select = r#"select id from events where payload = '{"tags": ["b"], "kind": "view"}'"#;
+        let result = test_db.execute_query(select).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].values(), &[Value::Integer(2)]);
+
+        // integers are kept exactly, even above 2^53
+        let result = test_db
+            .execute_query(
+                "select '12345678901234567890'::json, '{\"id\": 9007199254740993}'::json->'id'",
+            )
+            .unwrap();
+        assert_eq!(result[0].values()[0].to_string(), "12345678901234567890");
+        assert_eq!(result[0].values()[1].to_string(), "9007199254740993");
+
+        let result = test_db.execute_query("insert into events values (4, '{\"kind\": }')");
+        assert!(result.is_err());
+        let result = test_db.execute_query("select id from events where payload < payload");
+        assert!(result.is_err());
+
+        // documents exceeding the length prefix are rejected before they are written
+        let insert = format!(
+            r#"insert into events values (5, '["{}"]')"#,
+            "a".repeat(70_000)
+        );
+        assert!(test_db.execute_query(&insert).is_err());
+        let result = test_db.execute_query("select id from events").unwrap();
+        assert_eq!(result.len(), 3);
+    }
 }
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::vec::IntoIter;
+
+use anyhow::Result;
+
+use super::Executor;
+use crate::catalog::schema::Schema;
+use crate::planner::physical_plan::{Accumulator, Aggregation, Expr};
+use crate::tuple::value::Value;
+use crate::tuple::Tuple;
+
+/// Groups the tuples of its child by the values of the grouping expressions and aggregates
+/// every group on its own. Returns one tuple per group, consisting of the group's values
+/// followed by the aggregation results. Groups are returned in the order they were first seen
+pub struct HashAggregateExecutor<'a> {
+    child: Box<dyn Executor + 'a>,
+    group_by: Vec<Expr>,
+    aggregations: Vec<Aggregation>,
+    output_schema: Schema,
+    /// the aggregated groups, None as long as the child has not been consumed
+    groups: Option<IntoIter<Tuple>>,
+}
+
+impl<'a> HashAggregateExecutor<'a> {
+    pub fn new(
+        child: Box<dyn Executor + 'a>,
+        group_by: Vec<Expr>,
+        aggregations: Vec<Aggregation>,
+        output_schema: Schema,
+    ) -> Self {
+        Self {
+            child,
+            group_by,
+            aggregations,
+            output_schema,
+            groups: None,
+        }
+    }
+
+    fn aggregate_groups(&mut self) -> Result<Vec<Tuple>> {
+        let mut group_indexes = HashMap::new();
+        let mut groups = vec![];
+
+        while let Some(tuple) = self.child.next().transpose()? {
+            let key = self
+                .group_by
+                .iter()
+                .map(|expr| expr.evaluate(&[&tuple]))
+                .collect::<Result<Vec<_>>>()?;
+            // NULL values are equal to each other here, so they end up in the same group
+            let group_idx = *group_indexes.entry(key).or_insert_with_key(|key| {
+                let accumulators = self
+                    .aggregations
+                    .iter()
+                    .map(|agg| agg.initial_accumulator())
+                    .collect::<Vec<_>>();
+                groups.push((key.clone(), accumulators));
+                groups.len() - 1
+            });
+
+            let (_, accumulators) = &mut groups[group_idx];
+            for (acc, aggregation) in accumulators.iter_mut().zip(self.aggregations.iter()) {
+                aggregation.aggregate(acc, &tuple)?;
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(
+                |(mut values, accumulators): (Vec<Value>, Vec<Accumulator>)| {
+                    for (acc, aggregation) in accumulators.into_iter().zip(self.aggregations.iter())
+                    {
+                        values.push(aggregation.finalize(acc)?);
+                    }
+                    Ok(Tuple::new(values))
+                },
+            )
+            .collect()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        if self.groups.is_none() {
+            self.groups = Some(self.aggregate_groups()?.into_iter());
+        }
+        Ok(self.groups.as_mut().unwrap().next())
+    }
+}
+
+impl<'a> Executor for HashAggregateExecutor<'a> {
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        self.next().transpose()
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.output_schema
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.groups = None;
+        self.child.rewind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::executors::tests::TestDb;
+    use crate::tuple::value::Value;
+
+    #[test]
+    fn can_group_by_expressions() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "orders",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "customer".to_owned(), 1, false),
+                    ColumnDefinition::new(TypeId::Integer, "amount".to_owned(), 2, true),
+                ],
+            )
+            .unwrap();
+
+        let select = "select customer, count(id) from orders group by customer";
+        assert!(test_db.execute_query(select).unwrap().is_empty());
+
+        let insert_statement = "insert into orders values \
+            (1, 'foo', 10), (2, 'bar', 20), (3, 'foo', 30), (4, null, 40), (5, null, 50)";
+        test_db.execute_query(insert_statement).unwrap();
+
+        let result = test_db.execute_query(select).unwrap();
+        let groups = result
+            .iter()
+            .map(|tuple| tuple.values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            vec![
+                vec![Value::String("foo".to_owned()), Value::Integer(2)],
+                vec![Value::String("bar".to_owned()), Value::Integer(1)],
+                vec![Value::Null, Value::Integer(2)],
+            ]
+        );
+
+        let select = "select customer, max(amount) - 1 from orders \
+                      where id > 1 group by 1 having count(id) > 1 and customer is not null";
+        let result = test_db.execute_query(select).unwrap();
+        assert_eq!(result.len(), 0);
+
+        let select = "select amount % 20 = 0 as even, count(id), max(id) from orders \
+                      group by amount % 20 = 0 having max(amount) >= 40";
+        let result = test_db.execute_query(select).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(
+            result[0].values(),
+            &[Value::Boolean(false), Value::Integer(3), Value::Integer(5)]
+        );
+        assert_eq!(
+            result[1].values(),
+            &[Value::Boolean(true), Value::Integer(2), Value::Integer(4)]
+        );
+
+        // output columns can be referenced by name, but input columns take precedence
+        let select = "select amount % 20 = 0 as even, count(id) from orders group by even";
+        assert_eq!(test_db.execute_query(select).unwrap().len(), 2);
+        let select = "select id % 2 as amount, count(id) from orders group by amount";
+        assert!(test_db.execute_query(select).is_err());
+        assert!(test_db
+            .execute_query("select id % 2 as k, id % 3 as k from orders group by k")
+            .is_err());
+
+        let select = "select count(id) from orders group by customer, amount > 20";
+        assert_eq!(test_db.execute_query(select).unwrap().len(), 4);
+
+        let select = "select count(id) from orders having max(amount) > 100";
+        assert!(test_db.execute_query(select).unwrap().is_empty());
+
+        assert!(test_db
+            .execute_query("select id, count(id) from orders group by customer")
+            .is_err());
+        assert!(test_db
+            .execute_query("select customer from orders group by 2")
+            .is_err());
+        assert!(test_db
+            .execute_query("select customer from orders group by count(id)")
+            .is_err());
+        assert!(test_db
+            .execute_query("select id from orders where count(id) > 1")
+            .is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+
+use super::Executor;
+use crate::catalog::schema::Schema;
+use crate::planner::physical_plan::Expr;
+use crate::tuple::value::Value;
+use crate::tuple::Tuple;
+
+/// Returns the tuples of its child, whose keys have not been seen before. The keys of all
+/// returned tuples are kept in a hash set. NULL values are not distinct from each other here
+pub struct HashDistinctExecutor<'a> {
+    child: Box<dyn Executor + 'a>,
+    keys: Vec<Expr>,
+    seen: HashSet<Vec<Value>>,
+}
+
+impl<'a> HashDistinctExecutor<'a> {
+    pub fn new(child: Box<dyn Executor + 'a>, keys: Vec<Expr>) -> Self {
+        Self {
+            child,
+            keys,
+            seen: HashSet::new(),
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        while let Some(tuple) = self.child.next().transpose()? {
+            let key = self
+                .keys
+                .iter()
+                .map(|expr| expr.evaluate(&[&tuple]))
+                .collect::<Result<Vec<_>>>()?;
+            if self.seen.insert(key) {
+                return Ok(Some(tuple));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a> Executor for HashDistinctExecutor<'a> {
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        self.next().transpose()
+    }
+
+    fn schema(&self) -> &Schema {
+        self.child.schema()
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.seen.clear();
+        self.child.rewind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::executors::tests::TestDb;
+    use crate::tuple::value::Value;
+
+    #[test]
+    fn can_select_distinct_rows() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "orders",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "customer".to_owned(), 1, false),
+                    ColumnDefinition::new(TypeId::Integer, "amount".to_owned(), 2, false),
+                ],
+            )
+            .unwrap();
+
+        let insert_statement = "insert into orders values \
+            (1, 'foo', 10), (2, 'bar', 20), (3, 'foo', 10), (4, null, 40), (5, null, null), \
+            (6, 'bar', 10), (7, null, null)";
+        test_db.execute_query(insert_statement).unwrap();
+
+        let rows = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let foo = || Value::String("foo".to_owned());
+        let bar = || Value::String("bar".to_owned());
+
+        assert_eq!(
+            rows("select distinct customer from orders"),
+            vec![vec![foo()], vec![bar()], vec![Value::Null]]
+        );
+        assert_eq!(
+            rows("select distinct customer, amount from orders where id > 1"),
+            vec![
+                vec![bar(), Value::Integer(20)],
+                vec![foo(), Value::Integer(10)],
+                vec![Value::Null, Value::Integer(40)],
+                vec![Value::Null, Value::Null],
+                vec![bar(), Value::Integer(10)],
+            ]
+        );
+        assert_eq!(
+            rows("select distinct on (amount) amount, id from orders"),
+            vec![
+                vec![Value::Integer(10), Value::Integer(1)],
+                vec![Value::Integer(20), Value::Integer(2)],
+                vec![Value::Integer(40), Value::Integer(4)],
+                vec![Value::Null, Value::Integer(5)],
+            ]
+        );
+        assert_eq!(
+            rows("select distinct count(id) from orders group by customer"),
+            vec![vec![Value::Integer(2)], vec![Value::Integer(3)]]
+        );
+        assert_eq!(
+            rows("select all customer from orders where amount = 10").len(),
+            3
+        );
+        assert_eq!(
+            rows("select distinct amount % 20 from orders limit 1 offset 1"),
+            vec![vec![Value::Integer(0)]]
+        );
+        // intervals of the same length are not distinct
+        let sql = "select distinct interval '1 day' * id - interval '24 hours' * (id - 1) \
+                   from orders";
+        assert_eq!(rows(sql).len(), 1);
+    }
+}
@@ -41,6 +41,13 @@ impl<'a> InsertExecutor<'a> {
 
     fn try_insert(&mut self) -> Result<()> {
         while let Some(tuple) = self.child.next().transpose()? {
+            let values = tuple
+                .values
+                .into_iter()
+                .zip(self.table.schema().columns())
+                .map(|(value, col)| col.enforce_length(value))
+                .collect::<Result<Vec<_>>>()?;
+            let tuple = Tuple::new(values);
             self.table.insert_tuple(&tuple, self.transaction)?;
             self.tuples_inserted += 1;
         }
@@ -88,7 +95,7 @@ impl<'a> Executor for InsertExecutor<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::catalog::schema::{ColumnDefinition, TextLength, TypeId};
     use crate::concurrency::IsolationLevel;
     use crate::executors::tests::TestDb;
 
@@ -167,4 +174,204 @@ mod tests {
 
         assert_eq!(rows, vec![1, 2, 3]);
     }
+
+    #[test]
+    fn failing_expressions_require_a_rollback() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "numbers",
+                vec![ColumnDefinition::new(
+                    TypeId::Integer,
+                    "number".to_owned(),
+                    0,
+                    true,
+                )],
+            )
+            .unwrap();
+        test_db
+            .execute_query("insert into numbers values (0), (1)")
+            .unwrap();
+
+        let transaction = test_db.transaction_manager.start_transaction(None).unwrap();
+        let result = test_db.execute_query_with_transaction(
+            "insert into numbers select 10 / number from numbers",
+            &transaction,
+        );
+        assert_eq!(result.unwrap_err().to_string(), "Division by zero");
+        assert!(transaction.is_rollback_expected());
+        assert!(transaction.commit().is_err());
+        transaction.abort().unwrap();
+
+        let result = test_db.execute_query("insert into numbers values (2147483647 + 1)");
+        assert_eq!(result.unwrap_err().to_string(), "Integer out of range");
+
+        let rows = test_db.execute_query("select * from numbers").unwrap();
+        assert_eq!(rows.len(), 2);
+    }
+
+    #[test]
+    fn enforces_declared_text_lengths() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "countries",
+                vec![
+                    ColumnDefinition::new(TypeId::Text, "code".to_owned(), 0, true)
+                        .with_text_length(TextLength::Fixed(3)),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, true)
+                        .with_text_length(TextLength::Varying(8)),
+                ],
+            )
+            .unwrap();
+
+        test_db
+            .execute_query("insert into countries values ('de', 'Germany  ')")
+            .unwrap();
+
+        let result = test_db.execute_query("insert into countries values ('fr', 'Frankreich')");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Value too long for column name of type VARCHAR(8)"
+        );
+
+        let result = test_db.execute_query("update countries set code = 'deu1'");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Value too long for column code of type CHAR(3)"
+        );
+
+        let rows = test_db
+            .execute_query("select code, name from countries")
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values()[0].as_str(), "de ");
+        assert_eq!(rows[0].values()[1].as_str(), "Germany ");
+
+        // like in Postgres, the padding of CHAR values is ignored in comparisons
+        test_db
+            .execute_query("insert into countries values ('fr', 'France'), ('it ', 'Italy')")
+            .unwrap();
+        let names = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.as_str(0).to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names("select name from countries where code = 'de'"),
+            vec!["Germany "]
+        );
+        assert_eq!(
+            names("select name from countries where code = 'it  ' or code < 'de'"),
+            vec!["Italy"]
+        );
+        assert_eq!(
+            names("select name from countries where code in ('fr', 'it')"),
+            vec!["France", "Italy"]
+        );
+        assert_eq!(
+            names("select name from countries where code between 'e' and 'fr'"),
+            vec!["France"]
+        );
+        assert_eq!(
+            names("select case code when 'fr' then name else 'other' end from countries"),
+            vec!["other", "France", "other"]
+        );
+        assert_eq!(
+            names(
+                "select c.name from countries c join countries d on c.code = rtrim(d.code) \
+                 where d.name = 'Italy'"
+            ),
+            vec!["Italy"]
+        );
+    }
+
+    #[test]
+    fn rejects_text_exceeding_length_prefix() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "notes",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "body".to_owned(), 1, true),
+                ],
+            )
+            .unwrap();
+        test_db
+            .execute_query("insert into notes values (1, 'short')")
+            .unwrap();
+
+        let long = "a".repeat(200);
+        let insert = format!("insert into notes values (2, '{}' || '{}')", long, long);
+        assert_eq!(
+            test_db.execute_query(&insert).unwrap_err().to_string(),
+            "Text value of 400 bytes exceeds the maximum of 255 bytes"
+        );
+        let update = format!("update notes set body = body || '{}{}'", long, long);
+        assert!(test_db.execute_query(&update).is_err());
+
+        // the table is still usable afterwards
+        let insert = format!("insert into notes values (3, '{}')", long);
+        test_db.execute_query(&insert).unwrap();
+        let rows = test_db
+            .execute_query("select id, length(body) from notes")
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].values()[1].as_i32(), 200);
+    }
+
+    #[test]
+    fn can_insert_generated_uuids() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "sessions",
+                vec![
+                    ColumnDefinition::new(TypeId::Uuid, "uuid".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Integer, "user_id".to_owned(), 1, true),
+                ],
+            )
+            .unwrap();
+
+        test_db
+            .execute_query(
+                "insert into sessions values ('a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 1)",
+            )
+            .unwrap();
+        test_db
+            .execute_query(
+                "insert into sessions values (uuid 'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A12', 2)",
+            )
+            .unwrap();
+        test_db
+            .execute_query("insert into sessions select gen_random_uuid(), 3")
+            .unwrap();
+        test_db
+            .execute_query("insert into sessions select gen_random_uuid(), 4")
+            .unwrap();
+
+        let rows = test_db
+            .execute_query(
+                "select uuid from sessions where uuid = 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'",
+            )
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(
+            rows[0].values()[0].to_string(),
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"
+        );
+
+        let rows = test_db
+            .execute_query("select uuid from sessions where user_id > 2")
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_ne!(rows[0].values()[0], rows[1].values()[0]);
+
+        let result = test_db.execute_query("insert into sessions values ('not-a-uuid', 5)");
+        assert!(result.is_err());
+    }
 }
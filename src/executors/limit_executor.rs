@@ -0,0 +1,143 @@
+use anyhow::Result;
+
+use super::Executor;
+use crate::catalog::schema::Schema;
+use crate::tuple::Tuple;
+
+/// Skips the first `offset` tuples of its child and returns at most `limit` tuples afterwards.
+/// Once the limit is reached, no more tuples are pulled from the child
+pub struct LimitExecutor<'a> {
+    child: Box<dyn Executor + 'a>,
+    limit: Option<usize>,
+    offset: usize,
+    skipped: usize,
+    returned: usize,
+}
+
+impl<'a> LimitExecutor<'a> {
+    pub fn new(child: Box<dyn Executor + 'a>, limit: Option<usize>, offset: usize) -> Self {
+        Self {
+            child,
+            limit,
+            offset,
+            skipped: 0,
+            returned: 0,
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        if self.limit.is_some_and(|limit| self.returned >= limit) {
+            return Ok(None);
+        }
+
+        while self.skipped < self.offset {
+            if self.child.next().transpose()?.is_none() {
+                return Ok(None);
+            }
+            self.skipped += 1;
+        }
+
+        let tuple = self.child.next().transpose()?;
+        if tuple.is_some() {
+            self.returned += 1;
+        }
+        Ok(tuple)
+    }
+}
+
+impl<'a> Executor for LimitExecutor<'a> {
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        self.next().transpose()
+    }
+
+    fn schema(&self) -> &Schema {
+        self.child.schema()
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.skipped = 0;
+        self.returned = 0;
+        self.child.rewind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::executors::tests::TestDb;
+
+    #[test]
+    fn can_limit_and_offset_results() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "numbers",
+                vec![ColumnDefinition::new(
+                    TypeId::Integer,
+                    "number".to_owned(),
+                    0,
+                    true,
+                )],
+            )
+            .unwrap();
+        test_db
+            .execute_query("insert into numbers values (1), (2), (3), (0), (5)")
+            .unwrap();
+
+        let numbers = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.as_i32(0))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(numbers("select number from numbers limit 2"), vec![1, 2]);
+        assert_eq!(
+            numbers("select number from numbers limit 2 offset 1"),
+            vec![2, 3]
+        );
+        assert_eq!(
+            numbers("select number from numbers offset 3 rows"),
+            vec![0, 5]
+        );
+        assert_eq!(
+            numbers("select number from numbers offset 4 limit all"),
+            vec![5]
+        );
+        assert_eq!(
+            numbers("select number from numbers offset 1 fetch first 1 + 1 rows only"),
+            vec![2, 3]
+        );
+        assert_eq!(
+            numbers("select number from numbers fetch next row only"),
+            vec![1]
+        );
+        assert!(numbers("select number from numbers limit 0").is_empty());
+        assert!(numbers("select number from numbers offset 10").is_empty());
+        assert_eq!(numbers("select number from numbers limit null").len(), 5);
+
+        // the limit is reached before the division by zero is evaluated
+        assert_eq!(
+            numbers("select 6 / number from numbers limit 3"),
+            vec![6, 3, 2]
+        );
+        assert!(test_db
+            .execute_query("select 6 / number from numbers limit 4")
+            .is_err());
+
+        assert!(test_db
+            .execute_query("select number from numbers limit -1")
+            .is_err());
+        assert!(test_db
+            .execute_query("select number from numbers limit 'a'")
+            .is_err());
+        assert!(test_db
+            .execute_query("select number from numbers limit number")
+            .is_err());
+        assert!(test_db
+            .execute_query("select number from numbers limit 1 limit 2")
+            .is_err());
+    }
+}
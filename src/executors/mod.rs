@@ -1,34 +1,61 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Error, Result};
 
 use self::aggregate_executor::AggregateExecutor;
+use self::cte_scan_executor::CteScanExecutor;
 use self::delete_executor::DeleteExecutor;
 use self::filter_executor::FilterExecutor;
+use self::hash_aggregate_executor::HashAggregateExecutor;
+use self::hash_distinct_executor::HashDistinctExecutor;
 use self::insert_executor::InsertExecutor;
+use self::limit_executor::LimitExecutor;
 use self::nested_loop_join_executor::NestedLoopJoinExecutor;
 use self::projection_executor::ProjectionExecutor;
+use self::recursive_union_executor::RecursiveUnionExecutor;
 use self::seq_scan_executor::SeqScanExecutor;
+use self::set_operation_executor::SetOperationExecutor;
+use self::sort_distinct_executor::SortDistinctExecutor;
+use self::sort_executor::SortExecutor;
+use self::table_function_executor::TableFunctionExecutor;
+use self::top_n_sort_executor::TopNSortExecutor;
 use self::update_executor::UpdateExecutor;
 use self::values_executor::ValuesExecutor;
+use self::window_executor::WindowExecutor;
+use self::working_table_scan_executor::{WorkingTable, WorkingTableScanExecutor};
 use crate::buffer::buffer_manager::BufferManager;
 use crate::catalog::schema::Schema;
 use crate::common::TableId;
 use crate::concurrency::Transaction;
-use crate::planner::physical_plan::PhysicalPlan;
+use crate::planner::physical_plan::{Expr, PhysicalPlan, SubqueryKind};
 use crate::storage::heap::table::Table;
+use crate::tuple::value::Value;
 use crate::tuple::Tuple;
 
 mod aggregate_executor;
+mod cte_scan_executor;
 mod delete_executor;
 mod filter_executor;
+mod hash_aggregate_executor;
+mod hash_distinct_executor;
 mod insert_executor;
+mod limit_executor;
 mod nested_loop_join_executor;
 mod projection_executor;
+mod recursive_union_executor;
 mod seq_scan_executor;
+mod set_operation_executor;
+mod sort_distinct_executor;
+mod sort_executor;
+mod table_function_executor;
+mod top_n_sort_executor;
 mod update_executor;
 mod values_executor;
+mod window_executor;
+mod working_table_scan_executor;
 
 pub trait Executor {
     fn schema(&self) -> &Schema;
@@ -43,27 +70,103 @@ pub trait Executor {
     }
 }
 
+/// Limits the resources, which the executors of a statement may use
+#[derive(Clone, Copy, Debug)]
+pub struct ExecutorSettings {
+    /// memory in bytes which an executor (like a sort) may use before spilling to disk
+    pub work_mem: usize,
+    /// the maximum number of iterations of a recursive CTE, protects against endless recursion
+    pub max_recursive_iterations: usize,
+}
+
 pub struct ExecutorFactory<'a> {
     buffer_manager: Arc<BufferManager>,
     table_id_to_table: HashMap<TableId, Table>,
     transaction: &'a Transaction<'a>,
+    settings: ExecutorSettings,
+    /// the plans of the common table expressions, which have not been materialized yet
+    cte_plans: RefCell<HashMap<usize, PhysicalPlan>>,
+    /// the materialized rows of the common table expressions
+    cte_rows: RefCell<HashMap<usize, Rc<Vec<Vec<Value>>>>>,
+    /// the working tables of the recursive CTEs, shared by the recursive union executor and
+    /// the scans of its recursive term
+    working_tables: RefCell<HashMap<usize, WorkingTable>>,
 }
 
 impl<'a> ExecutorFactory<'a> {
-    pub fn new(buffer_manager: Arc<BufferManager>, transaction: &'a Transaction) -> Self {
+    pub fn new(
+        buffer_manager: Arc<BufferManager>,
+        transaction: &'a Transaction,
+        settings: ExecutorSettings,
+    ) -> Self {
         Self {
             buffer_manager,
             table_id_to_table: HashMap::new(),
             transaction,
+            settings,
+            cte_plans: RefCell::new(HashMap::new()),
+            cte_rows: RefCell::new(HashMap::new()),
+            working_tables: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn create_executor(&'a mut self, mut plan: PhysicalPlan) -> Result<Box<dyn Executor + 'a>> {
+        self.insert_tables_with_subqueries(&mut plan);
+        let factory: &'a Self = self;
+        factory.execute_subqueries(&mut plan)?;
+        factory.create_executor_internal(plan)
+    }
+
+    fn insert_tables_with_subqueries(&mut self, plan: &mut PhysicalPlan) {
+        self.insert_tables(plan);
+        for subquery in plan.subqueries_mut() {
+            if let Expr::Subquery { kind: _, plan } = subquery {
+                self.insert_tables_with_subqueries(plan);
+            }
         }
     }
 
-    pub fn create_executor(&'a mut self, plan: PhysicalPlan) -> Result<Box<dyn Executor + 'a>> {
-        self.insert_tables(&plan);
-        self.create_executor_internal(plan)
+    /// Executes the uncorrelated subqueries of a plan and replaces them by their results, so
+    /// that each of them is executed only once per statement
+    fn execute_subqueries(&'a self, plan: &mut PhysicalPlan) -> Result<()> {
+        for subquery in plan.subqueries_mut() {
+            let (kind, mut subquery_plan) =
+                match std::mem::replace(subquery, Expr::Value(Value::Null)) {
+                    Expr::Subquery { kind, plan } => (kind, *plan),
+                    _ => unreachable!(),
+                };
+            self.execute_subqueries(&mut subquery_plan)?;
+
+            let mut executor = self.create_executor_internal(subquery_plan)?;
+            let value = match kind {
+                SubqueryKind::Scalar => match executor.next().transpose()? {
+                    Some(tuple) => {
+                        if executor.next().transpose()?.is_some() {
+                            return Err(Error::msg(
+                                "More than one row returned by a subquery used as an expression",
+                            ));
+                        }
+                        tuple.values()[0].clone()
+                    }
+                    None => Value::Null,
+                },
+                SubqueryKind::Exists => Value::Boolean(executor.next().transpose()?.is_some()),
+                SubqueryKind::Array => {
+                    let mut elements = vec![];
+                    while let Some(tuple) = executor.next().transpose()? {
+                        elements.push(tuple.values()[0].clone());
+                    }
+                    Value::Array(elements)
+                }
+            };
+            *subquery = Expr::Value(value);
+        }
+        Ok(())
     }
 
-    fn insert_tables(&mut self, plan: &PhysicalPlan) {
+    /// Inserts the tables scanned or modified by the plan. The plans of common table
+    /// expressions are taken out of the plan, as they are executed on their first scan
+    fn insert_tables(&mut self, plan: &mut PhysicalPlan) {
         let (table_id, schema) = match plan {
             PhysicalPlan::SequentialScan {
                 table_id,
@@ -80,9 +183,27 @@ impl<'a> ExecutorFactory<'a> {
                 self.insert_tables(right);
                 return;
             }
+            PhysicalPlan::SetOperation {
+                op: _,
+                all: _,
+                left,
+                right,
+                output_schema: _,
+            } => {
+                self.insert_tables(left);
+                self.insert_tables(right);
+                return;
+            }
             PhysicalPlan::Aggregate {
                 aggregations: _,
                 child,
+                output_schema: _,
+            } => return self.insert_tables(child),
+            PhysicalPlan::HashAggregate {
+                group_by: _,
+                aggregations: _,
+                child,
+                output_schema: _,
             } => return self.insert_tables(child),
             PhysicalPlan::Insert {
                 target,
@@ -98,9 +219,33 @@ impl<'a> ExecutorFactory<'a> {
                 output_schema: _,
             } => return self.insert_tables(child),
             PhysicalPlan::Filter { filter: _, child } => return self.insert_tables(child),
+            PhysicalPlan::Sort {
+                sort_keys: _,
+                child,
+            } => return self.insert_tables(child),
+            PhysicalPlan::TopNSort {
+                sort_keys: _,
+                limit: _,
+                child,
+            } => return self.insert_tables(child),
+            PhysicalPlan::Window {
+                partition_by: _,
+                order_by: _,
+                functions: _,
+                child,
+                output_schema: _,
+            } => return self.insert_tables(child),
+            PhysicalPlan::HashDistinct { keys: _, child } => return self.insert_tables(child),
+            PhysicalPlan::SortDistinct { keys: _, child } => return self.insert_tables(child),
+            PhysicalPlan::Limit {
+                limit: _,
+                offset: _,
+                child,
+            } => return self.insert_tables(child),
             PhysicalPlan::Delete { from: _, child } => return self.insert_tables(child),
             PhysicalPlan::Update {
                 table: _,
+                target_schema: _,
                 set: _,
                 child,
             } => return self.insert_tables(child),
@@ -108,6 +253,37 @@ impl<'a> ExecutorFactory<'a> {
                 values: _,
                 output_schema: _,
             } => return,
+            PhysicalPlan::TableFunctionScan {
+                func: _,
+                args: _,
+                output_schema: _,
+            } => return,
+            PhysicalPlan::CteScan {
+                cte_id: _,
+                output_schema: _,
+            }
+            | PhysicalPlan::WorkingTableScan {
+                cte_id: _,
+                output_schema: _,
+            } => return,
+            PhysicalPlan::RecursiveUnion {
+                cte_id: _,
+                all: _,
+                left,
+                right,
+                output_schema: _,
+            } => {
+                self.insert_tables(left);
+                self.insert_tables(right);
+                return;
+            }
+            PhysicalPlan::With { ctes, child } => {
+                for (cte_id, mut cte_plan) in ctes.drain(..) {
+                    self.insert_tables_with_subqueries(&mut cte_plan);
+                    self.cte_plans.get_mut().insert(cte_id, cte_plan);
+                }
+                return self.insert_tables(child);
+            }
         };
 
         self.insert_table(table_id, schema);
@@ -140,9 +316,28 @@ impl<'a> ExecutorFactory<'a> {
             PhysicalPlan::Aggregate {
                 aggregations,
                 child,
+                output_schema,
             } => {
                 let child = self.create_executor_internal(*child)?;
-                Ok(Box::new(AggregateExecutor::new(child, aggregations)))
+                Ok(Box::new(AggregateExecutor::new(
+                    child,
+                    aggregations,
+                    output_schema,
+                )))
+            }
+            PhysicalPlan::HashAggregate {
+                group_by,
+                aggregations,
+                child,
+                output_schema,
+            } => {
+                let child = self.create_executor_internal(*child)?;
+                Ok(Box::new(HashAggregateExecutor::new(
+                    child,
+                    group_by,
+                    aggregations,
+                    output_schema,
+                )))
             }
             PhysicalPlan::Projection {
                 projections,
@@ -160,6 +355,15 @@ impl<'a> ExecutorFactory<'a> {
                 values,
                 output_schema,
             } => Ok(Box::new(ValuesExecutor::new(values, output_schema))),
+            PhysicalPlan::TableFunctionScan {
+                func,
+                args,
+                output_schema,
+            } => Ok(Box::new(TableFunctionExecutor::new(
+                func,
+                args,
+                output_schema,
+            )?)),
             PhysicalPlan::Insert {
                 target,
                 child,
@@ -177,6 +381,118 @@ impl<'a> ExecutorFactory<'a> {
                 let child = self.create_executor_internal(*child)?;
                 Ok(Box::new(FilterExecutor::new(child, filter)))
             }
+            PhysicalPlan::Window {
+                partition_by,
+                order_by,
+                functions,
+                child,
+                output_schema,
+            } => {
+                let child = self.create_executor_internal(*child)?;
+                Ok(Box::new(WindowExecutor::new(
+                    child,
+                    partition_by,
+                    order_by,
+                    functions,
+                    output_schema,
+                )))
+            }
+            PhysicalPlan::Sort { sort_keys, child } => {
+                let child = self.create_executor_internal(*child)?;
+                Ok(Box::new(SortExecutor::new(
+                    child,
+                    sort_keys,
+                    &self.buffer_manager,
+                    self.settings.work_mem,
+                )))
+            }
+            PhysicalPlan::TopNSort {
+                sort_keys,
+                limit,
+                child,
+            } => {
+                let child = self.create_executor_internal(*child)?;
+                Ok(Box::new(TopNSortExecutor::new(
+                    child,
+                    sort_keys,
+                    limit,
+                    &self.buffer_manager,
+                    self.settings.work_mem,
+                )))
+            }
+            PhysicalPlan::HashDistinct { keys, child } => {
+                let child = self.create_executor_internal(*child)?;
+                Ok(Box::new(HashDistinctExecutor::new(child, keys)))
+            }
+            PhysicalPlan::SortDistinct { keys, child } => {
+                let child = self.create_executor_internal(*child)?;
+                Ok(Box::new(SortDistinctExecutor::new(child, keys)))
+            }
+            PhysicalPlan::Limit {
+                limit,
+                offset,
+                child,
+            } => {
+                let child = self.create_executor_internal(*child)?;
+                Ok(Box::new(LimitExecutor::new(child, limit, offset)))
+            }
+            PhysicalPlan::SetOperation {
+                op,
+                all,
+                left,
+                right,
+                output_schema,
+            } => {
+                let left = self.create_executor_internal(*left)?;
+                let right = self.create_executor_internal(*right)?;
+                Ok(Box::new(SetOperationExecutor::new(
+                    op,
+                    all,
+                    left,
+                    right,
+                    output_schema,
+                )))
+            }
+            PhysicalPlan::With { ctes: _, child } => self.create_executor_internal(*child),
+            PhysicalPlan::RecursiveUnion {
+                cte_id,
+                all,
+                left,
+                right,
+                output_schema,
+            } => {
+                let working_table = Rc::new(RefCell::new(vec![]));
+                self.working_tables
+                    .borrow_mut()
+                    .insert(cte_id, Rc::clone(&working_table));
+                let left = self.create_executor_internal(*left)?;
+                let right = self.create_executor_internal(*right)?;
+                Ok(Box::new(RecursiveUnionExecutor::new(
+                    all,
+                    left,
+                    right,
+                    output_schema,
+                    working_table,
+                    self.settings.max_recursive_iterations,
+                )))
+            }
+            PhysicalPlan::WorkingTableScan {
+                cte_id,
+                output_schema,
+            } => {
+                let working_table = Rc::clone(&self.working_tables.borrow()[&cte_id]);
+                Ok(Box::new(WorkingTableScanExecutor::new(
+                    working_table,
+                    output_schema,
+                )))
+            }
+            PhysicalPlan::CteScan {
+                cte_id,
+                output_schema,
+            } => {
+                let rows = self.materialize_cte(cte_id)?;
+                Ok(Box::new(CteScanExecutor::new(rows, output_schema)))
+            }
             PhysicalPlan::Delete { from, child } => {
                 let child = self.create_executor_internal(*child)?;
                 let table = self.get_table(from);
@@ -186,11 +502,17 @@ impl<'a> ExecutorFactory<'a> {
                     self.transaction,
                 )))
             }
-            PhysicalPlan::Update { table, set, child } => {
+            PhysicalPlan::Update {
+                table,
+                target_schema,
+                set,
+                child,
+            } => {
                 let child = self.create_executor_internal(*child)?;
                 let table = self.get_table(table);
                 Ok(Box::new(UpdateExecutor::new(
                     table,
+                    target_schema,
                     child,
                     set,
                     self.transaction,
@@ -199,6 +521,24 @@ impl<'a> ExecutorFactory<'a> {
         }
     }
 
+    /// Returns the rows of a common table expression, which is executed on its first scan
+    fn materialize_cte(&'a self, cte_id: usize) -> Result<Rc<Vec<Vec<Value>>>> {
+        if let Some(rows) = self.cte_rows.borrow().get(&cte_id) {
+            return Ok(Rc::clone(rows));
+        }
+
+        let mut plan = self.cte_plans.borrow_mut().remove(&cte_id).unwrap();
+        self.execute_subqueries(&mut plan)?;
+        let mut executor = self.create_executor_internal(plan)?;
+        let mut rows = vec![];
+        while let Some(tuple) = executor.next().transpose()? {
+            rows.push(tuple.values);
+        }
+        let rows = Rc::new(rows);
+        self.cte_rows.borrow_mut().insert(cte_id, Rc::clone(&rows));
+        Ok(rows)
+    }
+
     fn insert_table(&mut self, table_id: TableId, schema: Schema) {
         self.table_id_to_table
             .entry(table_id)
@@ -210,20 +550,20 @@ impl<'a> ExecutorFactory<'a> {
         SeqScanExecutor::new(table, self.transaction)
     }
 
-    fn get_table(&'a self, table_id: TableId) -> &Table {
+    fn get_table(&'a self, table_id: TableId) -> &'a Table {
         self.table_id_to_table.get(&table_id).unwrap()
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
 
     use std::sync::Arc;
 
-    use anyhow::Result;
+    use anyhow::{Error, Result};
     use tempfile::{tempdir, TempDir};
 
-    use super::ExecutorFactory;
+    use super::{Executor, ExecutorFactory, ExecutorSettings};
     use crate::analyzer::Analyzer;
     use crate::buffer::buffer_manager::BufferManager;
     use crate::catalog::schema::{ColumnDefinition, TypeId};
@@ -233,6 +573,7 @@ mod tests {
     use crate::parser::parse_sql;
     use crate::planner::Planner;
     use crate::storage::file_manager::FileManager;
+    use crate::tuple::value::Value;
     use crate::tuple::Tuple;
 
     #[allow(dead_code)]
@@ -241,6 +582,8 @@ mod tests {
         buffer_manager: Arc<BufferManager>,
         catalog: Catalog,
         pub transaction_manager: TransactionManager,
+        pub work_mem: usize,
+        pub max_recursive_iterations: usize,
     }
 
     impl TestDb {
@@ -261,6 +604,8 @@ mod tests {
                 buffer_manager,
                 catalog,
                 transaction_manager,
+                work_mem: 4 * 1024 * 1024,
+                max_recursive_iterations: 1000,
             }
         }
 
@@ -281,20 +626,45 @@ mod tests {
             sql: &str,
             transaction: &Transaction,
         ) -> Result<Vec<Tuple>> {
+            self.with_executor_and_transaction(sql, transaction, |mut executor| {
+                let mut tuples = vec![];
+                while let Some(tuple) = executor.next().transpose()? {
+                    tuples.push(tuple);
+                }
+                Ok(tuples)
+            })
+        }
+
+        /// Passes the executor of the query to the function instead of collecting its tuples
+        pub fn with_executor<T>(
+            &self,
+            sql: &str,
+            f: impl FnOnce(Box<dyn Executor + '_>) -> Result<T>,
+        ) -> Result<T> {
+            let transaction = self.transaction_manager.start_implicit_transaction()?;
+            self.with_executor_and_transaction(sql, &transaction, f)
+        }
+
+        fn with_executor_and_transaction<T>(
+            &self,
+            sql: &str,
+            transaction: &Transaction,
+            f: impl FnOnce(Box<dyn Executor + '_>) -> Result<T>,
+        ) -> Result<T> {
             let (_, query) = parse_sql(sql)?;
             let analyzer = Analyzer::new(&self.catalog);
             let logical_plan = analyzer.analyze(query)?;
             let logical_plan = optimize(logical_plan);
             let planner = Planner::new();
             let plan = planner.prepare_logical_plan(logical_plan)?;
+            let settings = ExecutorSettings {
+                work_mem: self.work_mem,
+                max_recursive_iterations: self.max_recursive_iterations,
+            };
             let mut executor_factory =
-                ExecutorFactory::new(Arc::clone(&self.buffer_manager), transaction);
-            let mut executor = executor_factory.create_executor(plan)?;
-            let mut tuples = vec![];
-            while let Some(tuple) = executor.next().transpose()? {
-                tuples.push(tuple);
-            }
-            Ok(tuples)
+                ExecutorFactory::new(Arc::clone(&self.buffer_manager), transaction, settings);
+            let executor = executor_factory.create_executor(plan)?;
+            f(executor)
         }
     }
 
@@ -327,4 +697,242 @@ mod tests {
         let tuples = test_db.execute_query(select_numbers).unwrap();
         assert_eq!(tuples.len(), 3);
     }
+
+    #[test]
+    fn can_execute_uncorrelated_subqueries() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "orders",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Integer, "customer_id".to_owned(), 1, false),
+                    ColumnDefinition::new(TypeId::Integer, "amount".to_owned(), 2, true),
+                ],
+            )
+            .unwrap();
+        test_db
+            .create_table(
+                "customers",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, true),
+                ],
+            )
+            .unwrap();
+        test_db
+            .execute_query(
+                "insert into orders values (1, 1, 10), (2, 1, 30), (3, 2, 20), (4, null, 50)",
+            )
+            .unwrap();
+        test_db
+            .execute_query("insert into customers values (1, 'foo'), (2, 'bar'), (3, 'baz')")
+            .unwrap();
+
+        let ids = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values()[0].clone())
+                .collect::<Vec<_>>()
+        };
+        let integers = |numbers: &[i32]| {
+            numbers
+                .iter()
+                .map(|number| Value::Integer(*number))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ids("select id from orders where amount > (select avg(amount) from orders)"),
+            integers(&[2, 4])
+        );
+        assert_eq!(
+            ids("select (select max(amount) from orders) - amount from orders where id = 1"),
+            integers(&[40])
+        );
+        assert_eq!(
+            ids("select (select amount from orders where id = 5)"),
+            vec![Value::Null]
+        );
+        assert_eq!(
+            ids("select id from customers where id in (select customer_id from orders)"),
+            integers(&[1, 2])
+        );
+        // NOT IN is never true, if the subquery returns NULL
+        assert!(
+            ids("select id from customers where id not in (select customer_id from orders)")
+                .is_empty()
+        );
+        let sql = "select id from customers where id not in \
+                   (select customer_id from orders where customer_id is not null)";
+        assert_eq!(ids(sql), integers(&[3]));
+        assert_eq!(
+            ids("select name from customers where '2' in (select id from customers)"),
+            ["foo", "bar", "baz"]
+                .iter()
+                .map(|name| Value::String(name.to_string()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            ids("select count(*) from customers where exists (select * from orders)"),
+            integers(&[3])
+        );
+        assert_eq!(
+            ids("select not exists (select 1 where false), exists (values (1))"),
+            vec![Value::Boolean(true)]
+        );
+        // subqueries can be nested and combined with set operations
+        let sql = "select id from customers where id in \
+                   (select customer_id from orders where amount = (select min(amount) from orders) \
+                    union select 3) order by id";
+        assert_eq!(ids(sql), integers(&[1, 3]));
+
+        test_db
+            .execute_query("update orders set amount = (select count(*) from customers)")
+            .unwrap();
+        assert_eq!(
+            ids("select sum(amount) from orders where customer_id in (select id from customers)"),
+            integers(&[9])
+        );
+
+        let error = test_db
+            .execute_query("select (select id from orders)")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "More than one row returned by a subquery used as an expression"
+        );
+        assert!(test_db
+            .execute_query("select (select id, amount from orders)")
+            .is_err());
+        assert!(test_db
+            .execute_query("select id from orders where id in (select name from customers)")
+            .is_err());
+        // correlated subqueries are only supported as EXISTS and IN conditions
+        let sql = "select id, (select max(amount) from orders where customer_id = customers.id) \
+                   from customers";
+        assert!(test_db.execute_query(sql).is_err());
+    }
+
+    #[test]
+    fn can_execute_user_defined_functions() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "articles",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "title".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+        test_db
+            .execute_query("insert into articles values (1, 'Hello World'), (2, null)")
+            .unwrap();
+
+        let catalog = &test_db.catalog;
+        catalog
+            .register_scalar_function(
+                "slugify",
+                vec![TypeId::Text],
+                TypeId::Text,
+                |args| match args {
+                    [Value::String(title)] => {
+                        Ok(Value::String(title.to_lowercase().replace(' ', "-")))
+                    }
+                    _ => Err(Error::msg("slugify expects a text argument")),
+                },
+            )
+            .unwrap();
+        // overloads are told apart by their argument types
+        catalog
+            .register_scalar_function("describe", vec![TypeId::Integer], TypeId::Text, |_| {
+                Ok(Value::String("integer".to_owned()))
+            })
+            .unwrap();
+        catalog
+            .register_scalar_function("describe", vec![TypeId::Timestamp], TypeId::Text, |args| {
+                match args {
+                    [Value::Timestamp(_)] => Ok(Value::String("timestamp".to_owned())),
+                    _ => Err(Error::msg("describe expects a timestamp")),
+                }
+            })
+            .unwrap();
+        catalog
+            .register_scalar_function(
+                "checked_div",
+                vec![TypeId::Integer, TypeId::Integer],
+                TypeId::Integer,
+                |args| match args {
+                    [Value::Integer(left), Value::Integer(right)] => left
+                        .checked_div(*right)
+                        .map(Value::Integer)
+                        .ok_or_else(|| Error::msg("cannot divide by zero")),
+                    _ => Err(Error::msg("checked_div expects integers")),
+                },
+            )
+            .unwrap();
+        catalog
+            .register_scalar_function("broken", vec![], TypeId::Text, |_| Ok(Value::Integer(1)))
+            .unwrap();
+
+        let values = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let text = |s: &str| Value::String(s.to_owned());
+        assert_eq!(
+            values("select id, slugify(title) from articles order by id"),
+            vec![
+                vec![Value::Integer(1), text("hello-world")],
+                vec![Value::Integer(2), Value::Null],
+            ]
+        );
+        assert_eq!(
+            values("select id from articles where slugify(title) = 'hello-world'"),
+            vec![vec![Value::Integer(1)]]
+        );
+        // the date is converted to the declared timestamp argument
+        assert_eq!(
+            values("select describe(1), describe(date '2024-01-31'), slugify('A B')"),
+            vec![vec![text("integer"), text("timestamp"), text("a-b")]]
+        );
+        assert_eq!(
+            values("select checked_div(7, 2), checked_div(null, 0)"),
+            vec![vec![Value::Integer(3), Value::Null]]
+        );
+
+        for sql in [
+            "select checked_div(1, 0)",
+            "select broken()",
+            "select slugify(1)",
+            "select describe('a', 'b')",
+        ] {
+            assert!(test_db.execute_query(sql).is_err(), "{}", sql);
+        }
+
+        let noop = |_: &[Value]| Ok(Value::Null);
+        for (name, arg_types) in [
+            ("slugify", vec![TypeId::Text]),
+            ("count", vec![TypeId::Integer]),
+            ("coalesce", vec![TypeId::Integer]),
+            ("Slugify", vec![TypeId::Integer]),
+            ("select", vec![]),
+            ("length", vec![TypeId::Unknown]),
+        ] {
+            assert!(
+                catalog
+                    .register_scalar_function(name, arg_types, TypeId::Text, noop)
+                    .is_err(),
+                "{}",
+                name
+            );
+        }
+    }
 }
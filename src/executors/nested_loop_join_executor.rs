@@ -14,6 +14,13 @@ pub struct NestedLoopJoinExecutor<'a> {
     on: Vec<Expr>,
     left_tuple: Option<Tuple>,
     left_had_match: bool,
+    /// the position of the next right tuple, it identifies the right tuples across the passes
+    right_position: usize,
+    /// for full joins, whether the right tuple at a position had a match
+    right_matches: Vec<bool>,
+    /// whether all left tuples are joined and the unmatched right tuples of a full join are
+    /// returned
+    emitting_unmatched_right: bool,
     schema: Schema,
 }
 
@@ -28,7 +35,9 @@ impl<'a> NestedLoopJoinExecutor<'a> {
         // a right router join is just a left outer join but the left and right tables are swapped
         // we do this here, but have to remember to correct it later
         let (left, right) = match join_type {
-            JoinType::Left | JoinType::Inner => (left_child, right_child),
+            JoinType::Left | JoinType::Inner | JoinType::Full | JoinType::Semi | JoinType::Anti => {
+                (left_child, right_child)
+            }
             JoinType::Right => (right_child, left_child),
         };
         Self {
@@ -38,19 +47,26 @@ impl<'a> NestedLoopJoinExecutor<'a> {
             on,
             left_tuple: None,
             left_had_match: false,
+            right_position: 0,
+            right_matches: vec![],
+            emitting_unmatched_right: false,
             schema,
         }
     }
 
-    fn join_condition_evaluates_to_true(&self, left: &Tuple, right: &Tuple) -> bool {
+    fn join_condition_evaluates_to_true(&self, left: &Tuple, right: &Tuple) -> Result<bool> {
         let tuples = match self.join_type {
-            JoinType::Left | JoinType::Inner => [left, right],
+            JoinType::Left | JoinType::Inner | JoinType::Full | JoinType::Semi | JoinType::Anti => {
+                [left, right]
+            }
             JoinType::Right => [right, left],
         };
-        self.on.iter().all(|expr| match expr.evaluate(&tuples) {
-            Value::Boolean(val) => val,
-            _ => false,
-        })
+        for expr in self.on.iter() {
+            if expr.evaluate(&tuples)? != Value::Boolean(true) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
     }
 
     fn construct_result(
@@ -59,7 +75,7 @@ impl<'a> NestedLoopJoinExecutor<'a> {
         mut right: Vec<Value>,
     ) -> Result<Option<Tuple>> {
         let values = match self.join_type {
-            JoinType::Inner | JoinType::Left => {
+            JoinType::Inner | JoinType::Left | JoinType::Full => {
                 left.append(&mut right);
                 left
             }
@@ -67,25 +83,53 @@ impl<'a> NestedLoopJoinExecutor<'a> {
                 right.append(&mut left);
                 right
             }
+            JoinType::Semi | JoinType::Anti => left,
         };
         Ok(Some(Tuple::new(values)))
     }
 
     fn next(&mut self) -> Result<Option<Tuple>> {
+        if self.emitting_unmatched_right {
+            return self.next_unmatched_right();
+        }
         if self.left_tuple.is_none() {
             self.left_had_match = false;
             self.left_tuple = self.left_child.next().transpose()?;
-            self.right_child.rewind()?;
+            self.rewind_right()?;
         }
         while let Some(ref left_tuple) = self.left_tuple {
             while let Some(right_tuple) = self.right_child.next().transpose()? {
-                if self.join_condition_evaluates_to_true(left_tuple, &right_tuple) {
+                let right_position = self.right_position;
+                self.right_position += 1;
+                if self.join_condition_evaluates_to_true(left_tuple, &right_tuple)? {
                     self.left_had_match = true;
-                    return self.construct_result(left_tuple.values.clone(), right_tuple.values);
+                    match self.join_type {
+                        // the first match decides for semi and anti joins, so the remaining
+                        // right tuples don't have to be checked
+                        JoinType::Semi => {
+                            let left_values = left_tuple.values.clone();
+                            self.left_tuple = None;
+                            return self.construct_result(left_values, vec![]);
+                        }
+                        JoinType::Anti => break,
+                        JoinType::Full => {
+                            if self.right_matches.len() <= right_position {
+                                self.right_matches.resize(right_position + 1, false);
+                            }
+                            self.right_matches[right_position] = true;
+                            return self
+                                .construct_result(left_tuple.values.clone(), right_tuple.values);
+                        }
+                        JoinType::Inner | JoinType::Left | JoinType::Right => {
+                            return self
+                                .construct_result(left_tuple.values.clone(), right_tuple.values);
+                        }
+                    }
                 }
             }
 
-            if !self.left_had_match && self.join_type.is_outer() {
+            let unmatched_result = self.join_type.is_outer() || self.join_type == JoinType::Anti;
+            if !self.left_had_match && unmatched_result {
                 let left_values = left_tuple.values.clone();
                 let right_null_values = (0..self.right_child.schema().columns().len())
                     .map(|_| Value::Null)
@@ -95,11 +139,37 @@ impl<'a> NestedLoopJoinExecutor<'a> {
             } else {
                 self.left_had_match = false;
                 self.left_tuple = self.left_child.next().transpose()?;
-                self.right_child.rewind()?;
+                self.rewind_right()?;
+            }
+        }
+
+        if self.join_type == JoinType::Full {
+            self.emitting_unmatched_right = true;
+            self.rewind_right()?;
+            return self.next_unmatched_right();
+        }
+        Ok(None)
+    }
+
+    /// Returns the next right tuple of a full join which didn't match any left tuple
+    fn next_unmatched_right(&mut self) -> Result<Option<Tuple>> {
+        while let Some(right_tuple) = self.right_child.next().transpose()? {
+            let right_position = self.right_position;
+            self.right_position += 1;
+            if self.right_matches.get(right_position) != Some(&true) {
+                let left_null_values = (0..self.left_child.schema().columns().len())
+                    .map(|_| Value::Null)
+                    .collect();
+                return self.construct_result(left_null_values, right_tuple.values);
             }
         }
         Ok(None)
     }
+
+    fn rewind_right(&mut self) -> Result<()> {
+        self.right_position = 0;
+        self.right_child.rewind()
+    }
 }
 
 impl<'a> Executor for NestedLoopJoinExecutor<'a> {
@@ -112,9 +182,11 @@ impl<'a> Executor for NestedLoopJoinExecutor<'a> {
     }
 
     fn rewind(&mut self) -> Result<()> {
+        self.left_tuple = None;
+        self.right_matches.clear();
+        self.emitting_unmatched_right = false;
         self.left_child.rewind()?;
-        self.right_child.rewind()?;
-        Ok(())
+        self.rewind_right()
     }
 }
 
@@ -194,7 +266,7 @@ mod tests {
         let cross_join =
             "select number, string from numbers, strings where numbers.id = strings.id";
         let mut result = test_db.execute_query(cross_join).unwrap();
-        result.sort_by_key(|tuple| (tuple.values()[0].as_i32()));
+        result.sort_by_key(|tuple| tuple.values()[0].as_i32());
 
         let expected_result = vec![
             Tuple::new(vec![Value::Integer(1), Value::String("foo".to_owned())]),
@@ -212,7 +284,7 @@ mod tests {
 
         let inner_join = "select number, string from numbers n join strings s on n.id = s.id";
         let mut result = test_db.execute_query(inner_join).unwrap();
-        result.sort_by_key(|tuple| (tuple.values()[0].as_i32()));
+        result.sort_by_key(|tuple| tuple.values()[0].as_i32());
 
         let expected_result = vec![
             Tuple::new(vec![Value::Integer(1), Value::String("foo".to_owned())]),
@@ -230,7 +302,7 @@ mod tests {
 
         let left_join = "select number, string from numbers n left join strings s on n.id = s.id";
         let mut result = test_db.execute_query(left_join).unwrap();
-        result.sort_by_key(|tuple| (tuple.values()[0].as_i32()));
+        result.sort_by_key(|tuple| tuple.values()[0].as_i32());
 
         let expected_result = vec![
             Tuple::new(vec![Value::Integer(1), Value::String("foo".to_owned())]),
@@ -249,7 +321,7 @@ mod tests {
 
         let right_join = "select string, number from strings s right join numbers n on n.id = s.id";
         let mut result = test_db.execute_query(right_join).unwrap();
-        result.sort_by_key(|tuple| (tuple.values()[1].as_i32()));
+        result.sort_by_key(|tuple| tuple.values()[1].as_i32());
 
         let expected_result = vec![
             Tuple::new(vec![Value::String("foo".to_owned()), Value::Integer(1)]),
@@ -260,4 +332,181 @@ mod tests {
 
         assert_eq!(expected_result, result);
     }
+
+    #[test]
+    fn can_execute_full_joins() {
+        let test_db = TestDb::new();
+        prepare_tables(&test_db);
+        test_db
+            .execute_query("insert into strings values (5, 'qux')")
+            .unwrap();
+
+        let full_join = "select number, string from numbers n full outer join strings s \
+                         on n.id = s.id";
+        let mut result = test_db.execute_query(full_join).unwrap();
+        result.sort_by_key(|tuple| match tuple.values()[0] {
+            Value::Null => None,
+            ref number => Some(number.as_i32()),
+        });
+
+        let expected_result = vec![
+            Tuple::new(vec![Value::Null, Value::String("qux".to_owned())]),
+            Tuple::new(vec![Value::Integer(1), Value::String("foo".to_owned())]),
+            Tuple::new(vec![Value::Integer(2), Value::String("bar".to_owned())]),
+            Tuple::new(vec![Value::Integer(3), Value::String("baz".to_owned())]),
+            Tuple::new(vec![Value::Integer(4), Value::Null]),
+        ];
+        assert_eq!(expected_result, result);
+
+        // filters on the NULL extended side are not pushed below the join
+        let full_join = "select number, string from numbers n full join strings s \
+                         on n.id = s.id where s.string is null or n.number is null";
+        let mut result = test_db.execute_query(full_join).unwrap();
+        result.sort_by_key(|tuple| match tuple.values()[0] {
+            Value::Null => None,
+            ref number => Some(number.as_i32()),
+        });
+
+        let expected_result = vec![
+            Tuple::new(vec![Value::Null, Value::String("qux".to_owned())]),
+            Tuple::new(vec![Value::Integer(4), Value::Null]),
+        ];
+        assert_eq!(expected_result, result);
+
+        let left_join = "select number from numbers n left join strings s on n.id = s.id \
+                         where s.id is null";
+        let result = test_db.execute_query(left_join).unwrap();
+        assert_eq!(vec![Tuple::new(vec![Value::Integer(4)])], result);
+    }
+
+    #[test]
+    fn can_execute_using_and_natural_joins() {
+        let test_db = TestDb::new();
+        prepare_tables(&test_db);
+        test_db
+            .execute_query("insert into strings values (5, 'qux')")
+            .unwrap();
+
+        let rows = |sql: &str| {
+            let mut result = test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values().to_vec())
+                .collect::<Vec<_>>();
+            result.sort_by_key(|values| values[0].as_i32());
+            result
+        };
+
+        // the columns of USING are merged into a single column, which comes first
+        let expected_result = vec![
+            vec![
+                Value::Integer(1),
+                Value::Integer(1),
+                Value::String("foo".to_owned()),
+            ],
+            vec![
+                Value::Integer(2),
+                Value::Integer(2),
+                Value::String("bar".to_owned()),
+            ],
+            vec![
+                Value::Integer(3),
+                Value::Integer(3),
+                Value::String("baz".to_owned()),
+            ],
+        ];
+        assert_eq!(
+            rows("select * from numbers join strings using (id)"),
+            expected_result
+        );
+        assert_eq!(
+            rows("select * from numbers natural join strings"),
+            expected_result
+        );
+
+        let sql = "select id, numbers.id, strings.id from numbers full join strings using (id)";
+        assert_eq!(
+            rows(sql),
+            vec![
+                vec![Value::Integer(1), Value::Integer(1), Value::Integer(1)],
+                vec![Value::Integer(2), Value::Integer(2), Value::Integer(2)],
+                vec![Value::Integer(3), Value::Integer(3), Value::Integer(3)],
+                vec![Value::Integer(4), Value::Integer(4), Value::Null],
+                vec![Value::Integer(5), Value::Null, Value::Integer(5)],
+            ]
+        );
+        let sql = "select id, number from numbers natural right join strings";
+        assert_eq!(
+            rows(sql),
+            vec![
+                vec![Value::Integer(1), Value::Integer(1)],
+                vec![Value::Integer(2), Value::Integer(2)],
+                vec![Value::Integer(3), Value::Integer(3)],
+                vec![Value::Integer(5), Value::Null],
+            ]
+        );
+
+        for sql in [
+            "select id from numbers join strings on numbers.id = strings.id",
+            "select * from numbers join strings using (number)",
+            "select * from numbers join strings using (id, id)",
+            "select * from numbers join strings using (string)",
+            "select * from numbers natural cross join strings",
+            "select * from numbers natural join strings on numbers.id = strings.id",
+        ] {
+            assert!(test_db.execute_query(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn can_execute_semi_and_anti_joins() {
+        let test_db = TestDb::new();
+        prepare_tables(&test_db);
+        test_db
+            .create_table(
+                "tags",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, false),
+                    ColumnDefinition::new(TypeId::Integer, "number_id".to_owned(), 1, true),
+                ],
+            )
+            .unwrap();
+        test_db
+            .execute_query("insert into tags values (1, 1), (null, 2), (3, 3)")
+            .unwrap();
+
+        let numbers = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values()[0].as_i32())
+                .collect::<Vec<_>>()
+        };
+
+        // correlated EXISTS and IN conditions are rewritten to semi and anti joins
+        let sql = "select number from numbers n where exists \
+                   (select * from strings s where s.id = n.id) order by number";
+        assert_eq!(numbers(sql), vec![1, 2, 3]);
+        let sql = "select number from numbers n where not exists \
+                   (select * from strings s where s.id = n.id)";
+        assert_eq!(numbers(sql), vec![4]);
+        // every left row is returned at most once
+        let sql = "select number from numbers n where n.number > 1 and exists \
+                   (select * from strings s where s.id <= n.id) order by number";
+        assert_eq!(numbers(sql), vec![2, 3, 4]);
+        let sql = "select number from numbers n where n.number - 1 in \
+                   (select s.id from strings s where s.string <> 'bar' and s.id < n.id) \
+                   order by number";
+        assert_eq!(numbers(sql), vec![2, 4]);
+
+        // NOT IN is not true, if a value of the subquery is NULL
+        let sql = "select number from numbers n where n.id not in \
+                   (select t.id from tags t where t.number_id = n.id)";
+        assert_eq!(numbers(sql), vec![4]);
+        let sql = "select number from numbers n where n.id not in \
+                   (select t.id from tags t where t.number_id + 1 = n.id) order by number";
+        assert_eq!(numbers(sql), vec![1, 2, 4]);
+    }
 }
@@ -28,13 +28,13 @@ impl<'a> ProjectionExecutor<'a> {
 impl<'a> Executor for ProjectionExecutor<'a> {
     fn next(&mut self) -> Option<Result<Tuple>> {
         self.child.next().map(|tuple| {
-            tuple.map(|tuple| {
+            tuple.and_then(|tuple| {
                 let values = self
                     .projections
                     .iter()
                     .map(|expr| expr.evaluate(&[&tuple]))
-                    .collect();
-                Tuple::new(values)
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Tuple::new(values))
             })
         })
     }
@@ -51,16 +51,17 @@ impl<'a> Executor for ProjectionExecutor<'a> {
 #[cfg(test)]
 mod tests {
 
-    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::catalog::schema::{ColumnDefinition, TextLength, TypeId};
     use crate::concurrency::IsolationLevel;
     use crate::executors::tests::TestDb;
     use crate::parser::ast::BinaryOperator;
+    use crate::tuple::datetime::{self, Interval};
     use crate::tuple::value::Value;
 
     fn execute_query_expect_single_tuple(sql: &str, test_db: &TestDb, expected: Value) {
         let tuples = test_db.execute_query(sql).unwrap();
         assert_eq!(tuples.len(), 1);
-        let values = tuples.get(0).unwrap().values();
+        let values = tuples.first().unwrap().values();
         assert_eq!(values.len(), 1);
         assert_eq!(values[0], expected, "when evaluating {}", sql);
     }
@@ -159,6 +160,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn arithmetic_errors_fail_the_statement() {
+        let test_db = TestDb::new();
+
+        let sql_and_error = [
+            ("select 1 / 0", "Division by zero"),
+            ("select 1 % 0", "Division by zero"),
+            ("select 2147483647 + 1", "Integer out of range"),
+            ("select -2147483647 - 2", "Integer out of range"),
+            ("select 65536 * 65536", "Integer out of range"),
+            ("select -(-2147483647 - 1)", "Integer out of range"),
+            ("select (-2147483647 - 1) / -1", "Integer out of range"),
+            ("select date '2024-01-01' + 2147483647", "Date out of range"),
+            (
+                "select interval '2147483647 days' + interval '1 day'",
+                "Interval out of range",
+            ),
+        ];
+        for (sql, error) in sql_and_error {
+            let result = test_db.execute_query(sql);
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                error,
+                "when evaluating {}",
+                sql
+            );
+        }
+
+        execute_query_expect_single_tuple(
+            "select -2147483647 - 1",
+            &test_db,
+            Value::Integer(i32::MIN),
+        );
+    }
+
     #[test]
     fn can_execute_or_and_expressions() {
         let test_db = TestDb::new();
@@ -193,12 +229,141 @@ mod tests {
                 Value::Null,
                 Value::Null,
             ),
+            (
+                Value::Null,
+                BinaryOperator::Or,
+                Value::Boolean(true),
+                Value::Boolean(true),
+            ),
+            (
+                Value::Null,
+                BinaryOperator::And,
+                Value::Boolean(false),
+                Value::Boolean(false),
+            ),
+            (
+                Value::Boolean(true),
+                BinaryOperator::And,
+                Value::Null,
+                Value::Null,
+            ),
+            (
+                Value::Null,
+                BinaryOperator::IsDistinctFrom,
+                Value::Null,
+                Value::Boolean(false),
+            ),
+            (
+                Value::Boolean(true),
+                BinaryOperator::IsNotDistinctFrom,
+                Value::Null,
+                Value::Boolean(false),
+            ),
         ];
 
         for (left, op, right, expected) in left_op_right_result {
             let sql = format!("select {} {} {}", left, op, right);
             execute_query_expect_single_tuple(&sql, &test_db, expected);
         }
+
+        let sql_and_expected = [
+            ("select not true", Value::Boolean(false)),
+            ("select not null", Value::Null),
+            ("select not false and false", Value::Boolean(false)),
+            ("select not 1 = 2", Value::Boolean(true)),
+            ("select null is true", Value::Boolean(false)),
+            ("select null is not false", Value::Boolean(true)),
+            ("select (1 = 1) is unknown", Value::Boolean(false)),
+            ("select 1 is distinct from 2", Value::Boolean(true)),
+        ];
+        for (sql, expected) in sql_and_expected {
+            execute_query_expect_single_tuple(sql, &test_db, expected);
+        }
+    }
+
+    #[test]
+    fn can_execute_temporal_expressions() {
+        let test_db = TestDb::new();
+
+        let sql_expected_result = [
+            (
+                "select date '2024-01-31' + 1",
+                Value::Date(datetime::parse_date("2024-02-01").unwrap()),
+            ),
+            (
+                "select date '2024-03-01' - date '2024-02-01'",
+                Value::Integer(29),
+            ),
+            (
+                "select date '2024-01-31' + interval '1 month'",
+                Value::Timestamp(datetime::parse_timestamp("2024-02-29 00:00:00").unwrap()),
+            ),
+            (
+                "select timestamp '2024-01-02 12:00:00' - timestamp '2024-01-01 00:00:00'",
+                Value::Interval(Interval::new(0, 1, 12 * 60 * 60 * 1_000_000)),
+            ),
+            (
+                "select time '23:30:00' + interval '1 hour'",
+                Value::Time(datetime::parse_time("00:30:00").unwrap()),
+            ),
+            (
+                "select -interval '2 days'",
+                Value::Interval(Interval::new(0, -2, 0)),
+            ),
+            (
+                "select extract(year from date '2024-01-31')",
+                Value::Integer(2024),
+            ),
+            (
+                "select extract(minute from time '10:42:00')",
+                Value::Integer(42),
+            ),
+            (
+                "select date '2024-01-31' < timestamp '2024-01-31 00:00:01'",
+                Value::Boolean(true),
+            ),
+            (
+                "select extract(epoch from timestamp '2038-01-19 03:14:07')",
+                Value::Integer(i32::MAX),
+            ),
+        ];
+
+        for (sql, expected) in sql_expected_result {
+            execute_query_expect_single_tuple(sql, &test_db, expected);
+        }
+
+        let result = test_db.execute_query("select extract(epoch from timestamp '2100-01-01')");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn can_filter_temporal_columns() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "events",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Timestamp, "created_at".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+
+        let insert_statement = "insert into events values \
+            (1, timestamp '2024-01-01 08:00:00'), \
+            (2, timestamp '2024-02-01 08:00:00'), \
+            (3, null)";
+        test_db.execute_query(insert_statement).unwrap();
+
+        let select = "select id from events \
+            where created_at > timestamp '2024-01-15 00:00:00' - interval '1 week'";
+        let result = test_db
+            .execute_query(select)
+            .unwrap()
+            .iter()
+            .map(|tuple| tuple.values()[0].as_i32())
+            .collect::<Vec<i32>>();
+        assert_eq!(result, vec![2]);
     }
 
     #[test]
@@ -284,4 +449,439 @@ mod tests {
 
         assert_eq!(result, vec![1, 2, 3, 4, 5]);
     }
+
+    #[test]
+    fn can_execute_array_expressions() {
+        let test_db = TestDb::new();
+
+        let integers =
+            |values: &[i32]| Value::Array(values.iter().map(|val| Value::Integer(*val)).collect());
+        let sql_and_expected = [
+            ("select array[1, 2, 3]", integers(&[1, 2, 3])),
+            ("select array[1, null][2]", Value::Null),
+            ("select (array[4, 5, 6])[3]", Value::Integer(6)),
+            ("select array[4, 5, 6][0]", Value::Null),
+            (
+                "select array['a', 'b'][1 + 1]",
+                Value::String("b".to_owned()),
+            ),
+            ("select 2 = any(array[1, 2])", Value::Boolean(true)),
+            ("select 3 = any(array[1, 2])", Value::Boolean(false)),
+            ("select 3 = any(array[1, null])", Value::Null),
+            ("select 3 > any(array[5, 2])", Value::Boolean(true)),
+            ("select array[1, 2] = array[1, 2]", Value::Boolean(true)),
+            ("select array[1, 2] <> array[2, 1]", Value::Boolean(true)),
+        ];
+        for (sql, expected) in sql_and_expected {
+            execute_query_expect_single_tuple(sql, &test_db, expected);
+        }
+
+        assert!(test_db.execute_query("select array[1, 'a']").is_err());
+        assert!(test_db.execute_query("select array[null]").is_err());
+        assert!(test_db.execute_query("select (1)[1]").is_err());
+        assert!(test_db.execute_query("select 'a' = any(array[1])").is_err());
+        assert!(test_db.execute_query("select array[1] < array[2]").is_err());
+    }
+
+    #[test]
+    fn can_execute_casts() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "accounts",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+
+        let sql_and_expected = [
+            ("select '5'::integer + 1", Value::Integer(6)),
+            ("select cast(42 as text)", Value::String("42".to_owned())),
+            ("select cast(null as integer)", Value::Null),
+            ("select 'on'::boolean", Value::Boolean(true)),
+            ("select 0::boolean", Value::Boolean(false)),
+            (
+                "select '{1,NULL}'::integer[]",
+                Value::Array(vec![Value::Integer(1), Value::Null]),
+            ),
+            (
+                "select (timestamp '2024-01-31 12:00:00')::date",
+                Value::Date(datetime::parse_date("2024-01-31").unwrap()),
+            ),
+        ];
+        for (sql, expected) in sql_and_expected {
+            execute_query_expect_single_tuple(sql, &test_db, expected);
+        }
+
+        test_db
+            .execute_query("insert into accounts values (1, '10'), (2, 'ten')")
+            .unwrap();
+
+        let result = test_db
+            .execute_query("select id from accounts where id = '1'")
+            .unwrap();
+        assert_eq!(result.len(), 1);
+
+        let result = test_db
+            .execute_query("select name::integer from accounts where id = 1")
+            .unwrap();
+        assert_eq!(result[0].values(), &[Value::Integer(10)]);
+
+        // the cast is only known to fail when evaluating the second row
+        assert!(test_db
+            .execute_query("select name::integer from accounts")
+            .is_err());
+        assert!(test_db.execute_query("select 'abc'::integer").is_err());
+        assert!(test_db.execute_query("select 1::date").is_err());
+    }
+
+    #[test]
+    fn can_cast_to_declared_text_lengths() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "countries",
+                vec![
+                    ColumnDefinition::new(TypeId::Text, "code".to_owned(), 0, true)
+                        .with_text_length(TextLength::Fixed(3)),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, true)
+                        .with_text_length(TextLength::Varying(10)),
+                ],
+            )
+            .unwrap();
+        test_db
+            .execute_query("insert into countries values ('de', 'Germany')")
+            .unwrap();
+
+        // like in Postgres, explicit casts truncate instead of failing
+        let text = |s: &str| Value::String(s.to_owned());
+        let sql_and_expected = [
+            ("select 'abcdef'::varchar(3)", text("abc")),
+            ("select 'ab'::varchar(5)", text("ab")),
+            ("select cast('ab' as char(4))", text("ab  ")),
+            ("select cast(12345 as char(3))", text("123")),
+            ("select name::varchar(3) from countries", text("Ger")),
+            ("select name::char(8) from countries", text("Germany ")),
+            ("select code::char(5) from countries", text("de   ")),
+            ("select code::text || '|' from countries", text("de|")),
+            (
+                "select count(*) from countries where name::char(9) = 'Germany'",
+                Value::Integer(1),
+            ),
+        ];
+        for (sql, expected) in sql_and_expected {
+            execute_query_expect_single_tuple(sql, &test_db, expected);
+        }
+    }
+
+    #[test]
+    fn can_execute_conditional_expressions() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "accounts",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Integer, "balance".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+        test_db
+            .execute_query("insert into accounts values (1, 10), (2, -5), (3, null)")
+            .unwrap();
+
+        let text = |s: &str| Value::String(s.to_owned());
+        let sql_and_expected = [
+            (
+                "select case when 1 > 2 then 'a' when 2 > 1 then 'b' end",
+                text("b"),
+            ),
+            ("select case when false then 'a' end", Value::Null),
+            ("select case when null then 1 else 2 end", Value::Integer(2)),
+            (
+                "select case 2 when 1 then 'one' when 2 then 'two' end",
+                text("two"),
+            ),
+            (
+                "select case null when null then 1 else 0 end",
+                Value::Integer(0),
+            ),
+            ("select case when true then null else 1 end", Value::Null),
+            (
+                "select case when true then date '2024-01-31' else '2024-02-01' end",
+                Value::Date(datetime::parse_date("2024-01-31").unwrap()),
+            ),
+            ("select coalesce(null, 2, 3)", Value::Integer(2)),
+            ("select coalesce(null, null)", Value::Null),
+            ("select nullif(1, 1)", Value::Null),
+            ("select nullif(1, 2)", Value::Integer(1)),
+            ("select nullif('a', null)", text("a")),
+            ("select greatest(1, null, 3, 2)", Value::Integer(3)),
+            ("select least('b', 'a', 'c')", text("a")),
+            ("select greatest(null, null)", Value::Null),
+            // the results and arguments which are not needed are not evaluated
+            (
+                "select case when true then 1 else 1 / 0 end",
+                Value::Integer(1),
+            ),
+            ("select coalesce(1, 1 / 0)", Value::Integer(1)),
+        ];
+        for (sql, expected) in sql_and_expected {
+            execute_query_expect_single_tuple(sql, &test_db, expected);
+        }
+
+        let result = test_db
+            .execute_query(
+                "select case when balance < 0 then 'negative' when balance >= 0 then 'positive' \
+                 else 'unknown' end, coalesce(balance, 0) from accounts order by id",
+            )
+            .unwrap()
+            .iter()
+            .map(|tuple| tuple.values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                vec![text("positive"), Value::Integer(10)],
+                vec![text("negative"), Value::Integer(-5)],
+                vec![text("unknown"), Value::Integer(0)],
+            ]
+        );
+        let result = test_db
+            .execute_query(
+                "select sum(case when balance > 0 then balance else 0 end) from accounts",
+            )
+            .unwrap();
+        assert_eq!(result[0].values(), &[Value::Integer(10)]);
+
+        for sql in [
+            "select case when 1 then 2 end",
+            "select case when true then 1 else 'a' end",
+            "select case 1 when 'a' then 2 end",
+            "select case when true then 1 else date '2024-01-31' end",
+            "select coalesce(1, 'a')",
+            "select coalesce()",
+            "select nullif(1)",
+            "select greatest(array[1], array[2])",
+            "select case when true then 1",
+        ] {
+            assert!(test_db.execute_query(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn can_execute_pattern_matching_and_range_predicates() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "users",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+        test_db
+            .execute_query(
+                "insert into users values (1, 'alice'), (2, 'Bob'), (3, 'al_x'), (4, null)",
+            )
+            .unwrap();
+
+        let text = |s: &str| Value::String(s.to_owned());
+        let sql_and_expected = [
+            ("select 2 in (1, 2, 3)", Value::Boolean(true)),
+            ("select 4 in (1, 2, 3)", Value::Boolean(false)),
+            ("select 4 in (1, null)", Value::Null),
+            ("select 1 in (1, null)", Value::Boolean(true)),
+            ("select 4 not in (1, 2)", Value::Boolean(true)),
+            ("select 4 not in (1, null)", Value::Null),
+            ("select 'b' in ('a', 'b')", Value::Boolean(true)),
+            ("select 2 between 1 and 3", Value::Boolean(true)),
+            ("select 3 between 1 and 2", Value::Boolean(false)),
+            ("select 3 not between 1 and 2", Value::Boolean(true)),
+            (
+                "select 2 between 1 + 0 and 3 and true",
+                Value::Boolean(true),
+            ),
+            ("select null between 1 and 2", Value::Null),
+            ("select 'abc' like 'a%'", Value::Boolean(true)),
+            ("select 'abc' like 'a_c'", Value::Boolean(true)),
+            ("select 'abc' like 'a_'", Value::Boolean(false)),
+            ("select 'abc' like '%b%'", Value::Boolean(true)),
+            ("select 'abc' like ''", Value::Boolean(false)),
+            ("select '' like '%'", Value::Boolean(true)),
+            ("select 'abc' not like 'a%'", Value::Boolean(false)),
+            ("select 'ABC' like 'a%'", Value::Boolean(false)),
+            ("select 'ABC' ilike 'a%'", Value::Boolean(true)),
+            ("select 'ABC' not ilike 'a%'", Value::Boolean(false)),
+            ("select null like 'a%'", Value::Null),
+            ("select '50%' like '50\\%'", Value::Boolean(true)),
+            ("select '500' like '50\\%'", Value::Boolean(false)),
+            ("select '50%' like '50!%' escape '!'", Value::Boolean(true)),
+            ("select '500' like '50!%' escape '!'", Value::Boolean(false)),
+            ("select 'a\\b' like 'a\\b' escape ''", Value::Boolean(true)),
+            ("select 'a' || 'b'", text("ab")),
+            ("select 'a' || 1", text("a1")),
+            ("select 1 || 'a' || true", text("1atrue")),
+            ("select 'a' || null", Value::Null),
+        ];
+        for (sql, expected) in sql_and_expected {
+            execute_query_expect_single_tuple(sql, &test_db, expected);
+        }
+
+        let ids = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values()[0].clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids("select id from users where name like 'al%' order by id"),
+            vec![Value::Integer(1), Value::Integer(3)]
+        );
+        assert_eq!(
+            ids("select id from users where name like 'al\\_%' order by id"),
+            vec![Value::Integer(3)]
+        );
+        assert_eq!(
+            ids("select id from users where name ilike 'b%' or id in (4, 5) order by id"),
+            vec![Value::Integer(2), Value::Integer(4)]
+        );
+        assert_eq!(
+            ids("select id from users where id not between 2 and 3 order by id"),
+            vec![Value::Integer(1), Value::Integer(4)]
+        );
+        assert_eq!(
+            ids("select name || '#' || id from users where id in (1, 2) order by id"),
+            vec![text("alice#1"), text("Bob#2")]
+        );
+
+        for sql in [
+            "select 1 in ()",
+            "select 1 in ('a', 2)",
+            "select 1 between 'a' and 2",
+            "select 1 like 'a'",
+            "select 'a' like 'a\\'",
+            "select 'a' like 'a' escape 'ab'",
+            "select 1 || 2",
+            "select 'a' | 'b'",
+        ] {
+            assert!(test_db.execute_query(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn can_execute_scalar_functions() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "products",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, false),
+                    ColumnDefinition::new(TypeId::Integer, "price".to_owned(), 2, false),
+                ],
+            )
+            .unwrap();
+        test_db
+            .execute_query(
+                "insert into products values (1, '  Desk Lamp ', 1249), (2, 'Chair', -35), \
+                 (3, null, null)",
+            )
+            .unwrap();
+
+        let text = |s: &str| Value::String(s.to_owned());
+        let sql_and_expected = [
+            ("select lower('AbC')", text("abc")),
+            ("select upper('abc')", text("ABC")),
+            ("select length('abc')", Value::Integer(3)),
+            ("select length(bytea '\\x0102')", Value::Integer(2)),
+            ("select substring('hello', 2)", text("ello")),
+            ("select substring('hello', 2, 3)", text("ell")),
+            ("select substring('hello', 0, 3)", text("he")),
+            ("select substring('hello' from 4)", text("lo")),
+            ("select substring('hello' from 2 for 2)", text("el")),
+            ("select substring('hello' for 2)", text("he")),
+            ("select substring('hello', 7)", text("")),
+            ("select trim('  a b  ')", text("a b")),
+            ("select trim(leading from '  ab  ')", text("ab  ")),
+            ("select trim(trailing 'x' from 'xxabxx')", text("xxab")),
+            ("select trim(both 'xy' from 'xyabyx')", text("ab")),
+            ("select trim('xxabxx', 'x')", text("ab")),
+            ("select ltrim('xxab', 'x')", text("ab")),
+            ("select rtrim('ab  ')", text("ab")),
+            ("select replace('banana', 'an', 'o')", text("booa")),
+            ("select replace('abc', '', 'x')", text("abc")),
+            ("select position('na' in 'banana')", Value::Integer(3)),
+            ("select position('x' in 'banana')", Value::Integer(0)),
+            ("select abs(-5)", Value::Integer(5)),
+            ("select round(42)", Value::Integer(42)),
+            ("select round(1250, -2)", Value::Integer(1300)),
+            ("select round(-1249, -2)", Value::Integer(-1200)),
+            ("select round(1249, 2)", Value::Integer(1249)),
+            ("select round(1249, -20)", Value::Integer(0)),
+            ("select mod(7, 3)", Value::Integer(1)),
+            ("select mod(-7, 3)", Value::Integer(-1)),
+            ("select power(2, 10)", Value::Integer(1024)),
+            ("select power(5, 0)", Value::Integer(1)),
+            ("select random(4, 4)", Value::Integer(4)),
+            ("select upper(null)", Value::Null),
+            ("select substring('hello', null, 2)", Value::Null),
+            ("select replace('abc', 'b', null)", Value::Null),
+            ("select mod(null, 0)", Value::Null),
+        ];
+        for (sql, expected) in sql_and_expected {
+            execute_query_expect_single_tuple(sql, &test_db, expected);
+        }
+
+        let result = test_db.execute_query("select random()").unwrap();
+        assert!(matches!(result[0].values(), [Value::Integer(val)] if *val >= 0));
+        let result = test_db
+            .execute_query("select random(1, 3) from products")
+            .unwrap();
+        assert!(result
+            .iter()
+            .all(|tuple| matches!(tuple.values(), [Value::Integer(1..=3)])));
+
+        let result = test_db
+            .execute_query(
+                "select upper(trim(name)), abs(round(price, -1)), substring(name, 1, 2) || '.' \
+                 from products order by id",
+            )
+            .unwrap()
+            .iter()
+            .map(|tuple| tuple.values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                vec![text("DESK LAMP"), Value::Integer(1250), text("  .")],
+                vec![text("CHAIR"), Value::Integer(40), text("Ch.")],
+                vec![Value::Null, Value::Null, Value::Null],
+            ]
+        );
+
+        for sql in [
+            "select lower(1)",
+            "select lower('a', 'b')",
+            "select abs('a')",
+            "select abs(-2147483647 - 1)",
+            "select length(date '2024-01-31')",
+            "select substring('abc', 1, -1)",
+            "select substring('abc')",
+            "select mod(1, 0)",
+            "select power(2, -1)",
+            "select power(2, 31)",
+            "select random(2, 1)",
+            "select trim(leading)",
+            "select position('a', 'b' in 'c')",
+            "select no_such_function(1)",
+        ] {
+            assert!(test_db.execute_query(sql).is_err(), "{}", sql);
+        }
+    }
 }
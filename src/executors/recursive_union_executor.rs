@@ -0,0 +1,244 @@
+use std::collections::HashSet;
+
+use anyhow::{Error, Result};
+
+use super::working_table_scan_executor::WorkingTable;
+use super::Executor;
+use crate::catalog::schema::Schema;
+use crate::tuple::value::Value;
+use crate::tuple::Tuple;
+
+/// Evaluates the UNION of a recursive CTE. The tuples of the left child are returned and form
+/// the first working table. Then, the right child is evaluated on the working table, its tuples
+/// are returned and form the next working table, until an iteration returns no tuples.
+/// Without ALL, tuples which have been returned before are neither returned nor added to the
+/// working table, so cycles in the data end the recursion. With ALL, the number of iterations
+/// is limited to protect against endless recursion
+pub struct RecursiveUnionExecutor<'a> {
+    all: bool,
+    left: Box<dyn Executor + 'a>,
+    right: Box<dyn Executor + 'a>,
+    output_schema: Schema,
+    /// the rows scanned by the right child
+    working_table: WorkingTable,
+    /// the rows returned by the current iteration, which form the next working table
+    intermediate_table: Vec<Vec<Value>>,
+    /// whether all tuples of the left child have been returned
+    left_done: bool,
+    /// the number of evaluations of the right child so far
+    iterations: usize,
+    max_iterations: usize,
+    /// the tuples returned so far, if duplicates are removed
+    seen: HashSet<Vec<Value>>,
+}
+
+impl<'a> RecursiveUnionExecutor<'a> {
+    pub fn new(
+        all: bool,
+        left: Box<dyn Executor + 'a>,
+        right: Box<dyn Executor + 'a>,
+        output_schema: Schema,
+        working_table: WorkingTable,
+        max_iterations: usize,
+    ) -> Self {
+        Self {
+            all,
+            left,
+            right,
+            output_schema,
+            working_table,
+            intermediate_table: vec![],
+            left_done: false,
+            iterations: 0,
+            max_iterations,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Returns whether the tuple has not been returned before, always true with ALL
+    fn is_new(&mut self, tuple: &Tuple) -> bool {
+        self.all || self.seen.insert(tuple.values().to_vec())
+    }
+
+    /// Starts the next iteration on the rows of the previous one. Returns false, if the
+    /// previous iteration did not return any rows
+    fn start_iteration(&mut self) -> Result<bool> {
+        if self.intermediate_table.is_empty() {
+            return Ok(false);
+        }
+        if self.iterations == self.max_iterations {
+            return Err(Error::msg(format!(
+                "Recursive query did not finish within {} iterations",
+                self.max_iterations
+            )));
+        }
+        self.iterations += 1;
+        *self.working_table.borrow_mut() = std::mem::take(&mut self.intermediate_table);
+        self.right.rewind()?;
+        Ok(true)
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        while !self.left_done {
+            match self.left.next().transpose()? {
+                Some(tuple) if self.is_new(&tuple) => {
+                    self.intermediate_table.push(tuple.values().to_vec());
+                    return Ok(Some(tuple));
+                }
+                Some(_) => continue,
+                None => {
+                    self.left_done = true;
+                    if !self.start_iteration()? {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+
+        loop {
+            match self.right.next().transpose()? {
+                Some(tuple) if self.is_new(&tuple) => {
+                    self.intermediate_table.push(tuple.values().to_vec());
+                    return Ok(Some(tuple));
+                }
+                Some(_) => continue,
+                None => {
+                    if !self.start_iteration()? {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Executor for RecursiveUnionExecutor<'a> {
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        self.next().transpose()
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.output_schema
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.working_table.borrow_mut().clear();
+        self.intermediate_table.clear();
+        self.left_done = false;
+        self.iterations = 0;
+        self.seen.clear();
+        self.left.rewind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::executors::tests::TestDb;
+    use crate::tuple::value::Value;
+
+    #[test]
+    fn can_query_recursive_common_table_expressions() {
+        let mut test_db = TestDb::new();
+        test_db
+            .create_table(
+                "employees",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, true),
+                    ColumnDefinition::new(TypeId::Integer, "manager".to_owned(), 2, false),
+                ],
+            )
+            .unwrap();
+        let insert_statement = "insert into employees values \
+            (1, 'ceo', null), (2, 'cto', 1), (3, 'cfo', 1), (4, 'dev', 2), (5, 'ops', 2)";
+        test_db.execute_query(insert_statement).unwrap();
+        test_db
+            .create_table(
+                "edges",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "source".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Integer, "target".to_owned(), 1, true),
+                ],
+            )
+            .unwrap();
+        test_db
+            .execute_query("insert into edges values (1, 2), (2, 3), (3, 1)")
+            .unwrap();
+
+        let rows = |test_db: &TestDb, sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let string = |s: &str| Value::String(s.to_owned());
+
+        let sql = "with recursive reports (id, name, depth) as ( \
+                   select id, name, 0 from employees where id = 2 \
+                   union all \
+                   select e.id, e.name, r.depth + 1 from employees e \
+                   join reports r on e.manager = r.id) \
+                   select name, depth from reports order by name";
+        assert_eq!(
+            rows(&test_db, sql),
+            vec![
+                vec![string("cto"), Value::Integer(0)],
+                vec![string("dev"), Value::Integer(1)],
+                vec![string("ops"), Value::Integer(1)],
+            ]
+        );
+        let sql = "with recursive counter (n) as (select 1 union all \
+                   select n + 1 from counter where n < 5) \
+                   select sum(n), count(*) from counter";
+        assert_eq!(
+            rows(&test_db, sql),
+            vec![vec![Value::Integer(15), Value::Integer(5)]]
+        );
+        // without ALL, rows which have been returned before end the recursion on cycles
+        let sql = "with recursive reachable (node) as (select 1 union \
+                   select target from edges join reachable on source = node) \
+                   select node from reachable order by node";
+        assert_eq!(
+            rows(&test_db, sql),
+            vec![
+                vec![Value::Integer(1)],
+                vec![Value::Integer(2)],
+                vec![Value::Integer(3)],
+            ]
+        );
+        // non-recursive CTEs are allowed in WITH RECURSIVE
+        let sql = "with recursive a as (select 1 x union select 2) select x from a order by x";
+        assert_eq!(
+            rows(&test_db, sql),
+            vec![vec![Value::Integer(1)], vec![Value::Integer(2)]]
+        );
+
+        // with ALL, a cycle is stopped by the iteration limit
+        test_db.max_recursive_iterations = 10;
+        let sql = "with recursive reachable (node) as (select 1 union all \
+                   select target from edges join reachable on source = node) \
+                   select count(*) from reachable";
+        assert!(test_db.execute_query(sql).is_err());
+        let sql = "with recursive counter (n) as (select 1 union all \
+                   select n + 1 from counter where n < 10) \
+                   select count(*) from counter";
+        assert_eq!(rows(&test_db, sql), vec![vec![Value::Integer(10)]]);
+
+        let sql = "with recursive counter (n) as (select 1 union all \
+                   select 'a' from counter) select * from counter";
+        assert!(test_db.execute_query(sql).is_err());
+        let sql = "with recursive counter (n) as (select 1 union all \
+                   select a.n from counter a, counter b) select * from counter";
+        assert!(test_db.execute_query(sql).is_err());
+        let sql = "with recursive counter (n) as (select 1 union all \
+                   select 2 where exists (select * from counter)) select * from counter";
+        assert!(test_db.execute_query(sql).is_err());
+        // a recursive reference requires WITH RECURSIVE
+        let sql = "with counter (n) as (select 1 union all \
+                   select n + 1 from counter where n < 5) select * from counter";
+        assert!(test_db.execute_query(sql).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+
+use super::Executor;
+use crate::catalog::schema::Schema;
+use crate::parser::ast::SetOperator;
+use crate::tuple::value::Value;
+use crate::tuple::Tuple;
+
+/// Combines the tuples of two children with UNION, INTERSECT or EXCEPT. For INTERSECT and
+/// EXCEPT, the right child is consumed first to count the occurrences of its tuples, then
+/// the left child is streamed. Without ALL, the returned tuples are kept in a hash set to
+/// remove duplicates. Like for DISTINCT, NULL values are not distinct from each other here
+pub struct SetOperationExecutor<'a> {
+    op: SetOperator,
+    all: bool,
+    left: Box<dyn Executor + 'a>,
+    right: Box<dyn Executor + 'a>,
+    output_schema: Schema,
+    /// for UNION, whether all tuples of the left child have been returned
+    left_done: bool,
+    /// for INTERSECT and EXCEPT, the number of occurrences of each tuple of the right child,
+    /// None as long as the right child has not been consumed
+    right_counts: Option<HashMap<Vec<Value>, usize>>,
+    /// the tuples returned so far, if duplicates are removed
+    seen: HashSet<Vec<Value>>,
+}
+
+impl<'a> SetOperationExecutor<'a> {
+    pub fn new(
+        op: SetOperator,
+        all: bool,
+        left: Box<dyn Executor + 'a>,
+        right: Box<dyn Executor + 'a>,
+        output_schema: Schema,
+    ) -> Self {
+        Self {
+            op,
+            all,
+            left,
+            right,
+            output_schema,
+            left_done: false,
+            right_counts: None,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Returns whether the tuple has not been returned before, always true with ALL
+    fn is_new(&mut self, tuple: &Tuple) -> bool {
+        self.all || self.seen.insert(tuple.values().to_vec())
+    }
+
+    fn next_union(&mut self) -> Result<Option<Tuple>> {
+        while !self.left_done {
+            match self.left.next().transpose()? {
+                Some(tuple) if self.is_new(&tuple) => return Ok(Some(tuple)),
+                Some(_) => continue,
+                None => self.left_done = true,
+            }
+        }
+        while let Some(tuple) = self.right.next().transpose()? {
+            if self.is_new(&tuple) {
+                return Ok(Some(tuple));
+            }
+        }
+        Ok(None)
+    }
+
+    fn count_right_tuples(&mut self) -> Result<HashMap<Vec<Value>, usize>> {
+        let mut counts = HashMap::new();
+        while let Some(tuple) = self.right.next().transpose()? {
+            *counts.entry(tuple.values().to_vec()).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        if self.op == SetOperator::Union {
+            return self.next_union();
+        }
+        if self.right_counts.is_none() {
+            self.right_counts = Some(self.count_right_tuples()?);
+        }
+
+        while let Some(tuple) = self.left.next().transpose()? {
+            let count = self
+                .right_counts
+                .as_mut()
+                .unwrap()
+                .get_mut(tuple.values())
+                .filter(|count| **count > 0);
+            let matched = match count {
+                Some(count) => {
+                    // every right tuple matches at most one left tuple with ALL, e.g.
+                    // INTERSECT ALL returns a tuple min(m, n) times. Without ALL, a tuple is
+                    // returned at most once anyway
+                    if self.all {
+                        *count -= 1;
+                    } else if self.op == SetOperator::Intersect {
+                        *count = 0;
+                    }
+                    true
+                }
+                None => false,
+            };
+
+            let is_result = match self.op {
+                SetOperator::Intersect => matched,
+                SetOperator::Except => !matched && self.is_new(&tuple),
+                SetOperator::Union => unreachable!(),
+            };
+            if is_result {
+                return Ok(Some(tuple));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a> Executor for SetOperationExecutor<'a> {
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        self.next().transpose()
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.output_schema
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.left_done = false;
+        self.right_counts = None;
+        self.seen.clear();
+        self.left.rewind()?;
+        self.right.rewind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::executors::tests::TestDb;
+    use crate::tuple::value::Value;
+
+    #[test]
+    fn can_combine_queries() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "orders",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "customer".to_owned(), 1, false),
+                    ColumnDefinition::new(TypeId::Date, "ordered_at".to_owned(), 2, false),
+                ],
+            )
+            .unwrap();
+
+        let insert_statement = "insert into orders values \
+            (1, 'foo', '2022-01-01'), (2, 'bar', '2022-01-02'), (3, 'foo', '2022-01-03'), \
+            (4, null, null), (5, 'baz', '2022-01-02'), (6, null, '2022-01-04')";
+        test_db.execute_query(insert_statement).unwrap();
+
+        let rows = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let string = |s: &str| Value::String(s.to_owned());
+        let integers = |numbers: &[i32]| {
+            numbers
+                .iter()
+                .map(|number| vec![Value::Integer(*number)])
+                .collect::<Vec<_>>()
+        };
+
+        let sql = "select customer from orders where id < 4 \
+                   union select customer from orders order by 1";
+        assert_eq!(
+            rows(sql),
+            vec![
+                vec![string("bar")],
+                vec![string("baz")],
+                vec![string("foo")],
+                vec![Value::Null]
+            ]
+        );
+        assert_eq!(
+            rows("select customer from orders union all select 'qux'").len(),
+            7
+        );
+        assert_eq!(
+            rows(
+                "values (1), (1), (2), (2), (3) intersect all values (1), (2), (2), (4) order by 1"
+            ),
+            integers(&[1, 2, 2])
+        );
+        assert_eq!(
+            rows("values (1), (1), (2), (3) intersect values (1), (1), (3), (4) order by 1"),
+            integers(&[1, 3])
+        );
+        assert_eq!(
+            rows("values (1), (1), (1), (2), (3) except all values (1), (3), (4) order by 1"),
+            integers(&[1, 1, 2])
+        );
+        assert_eq!(
+            rows("values (1), (1), (2), (3) except values (3) order by 1"),
+            integers(&[1, 2])
+        );
+        // INTERSECT binds more tightly than UNION and EXCEPT
+        assert_eq!(
+            rows("values (1) union values (2) intersect values (3)"),
+            integers(&[1])
+        );
+        assert_eq!(
+            rows("(values (1) union values (2)) intersect values (2)"),
+            integers(&[2])
+        );
+        assert_eq!(
+            rows("values (1), (2), (3) except values (1) except values (2)"),
+            integers(&[3])
+        );
+
+        // types are unified by coercion
+        assert_eq!(
+            rows(
+                "select id from orders where id < 3 union select '3' union select null order by 1"
+            ),
+            vec![
+                vec![Value::Integer(1)],
+                vec![Value::Integer(2)],
+                vec![Value::Integer(3)],
+                vec![Value::Null]
+            ]
+        );
+        assert_eq!(
+            rows("select timestamp '2022-01-02 12:00:00' except select ordered_at from orders")
+                .len(),
+            1
+        );
+        assert!(test_db
+            .execute_query("select id from orders union select customer from orders")
+            .is_err());
+        assert!(test_db
+            .execute_query("select id, customer from orders except select id from orders")
+            .is_err());
+
+        let result = test_db
+            .execute_query(
+                "select customer name, id from orders union select 'qux', 0 \
+                 order by name desc, 2 limit 3 offset 1",
+            )
+            .unwrap();
+        let result = result
+            .iter()
+            .map(|tuple| tuple.values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                vec![Value::Null, Value::Integer(6)],
+                vec![string("qux"), Value::Integer(0)],
+                vec![string("foo"), Value::Integer(1)],
+            ]
+        );
+        assert!(test_db
+            .execute_query("select id from orders union select id from orders order by id + 1")
+            .is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+
+use super::Executor;
+use crate::catalog::schema::Schema;
+use crate::planner::physical_plan::Expr;
+use crate::tuple::value::Value;
+use crate::tuple::Tuple;
+
+/// Returns the tuples of its child, whose keys differ from the keys of the previous tuple.
+/// As the child is sorted by the keys, duplicates are adjacent, so only the keys of the
+/// previous tuple have to be kept
+pub struct SortDistinctExecutor<'a> {
+    child: Box<dyn Executor + 'a>,
+    keys: Vec<Expr>,
+    previous_key: Option<Vec<Value>>,
+}
+
+impl<'a> SortDistinctExecutor<'a> {
+    pub fn new(child: Box<dyn Executor + 'a>, keys: Vec<Expr>) -> Self {
+        Self {
+            child,
+            keys,
+            previous_key: None,
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        while let Some(tuple) = self.child.next().transpose()? {
+            let key = self
+                .keys
+                .iter()
+                .map(|expr| expr.evaluate(&[&tuple]))
+                .collect::<Result<Vec<_>>>()?;
+            if self.previous_key.as_ref() != Some(&key) {
+                self.previous_key = Some(key);
+                return Ok(Some(tuple));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a> Executor for SortDistinctExecutor<'a> {
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        self.next().transpose()
+    }
+
+    fn schema(&self) -> &Schema {
+        self.child.schema()
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.previous_key = None;
+        self.child.rewind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::executors::tests::TestDb;
+    use crate::tuple::value::Value;
+
+    #[test]
+    fn can_select_distinct_sorted_rows() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "orders",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "customer".to_owned(), 1, false),
+                    ColumnDefinition::new(TypeId::Integer, "amount".to_owned(), 2, false),
+                ],
+            )
+            .unwrap();
+
+        let insert_statement = "insert into orders values \
+            (1, 'foo', 10), (2, 'bar', 20), (3, 'foo', 30), (4, null, 40), (5, 'bar', 5), \
+            (6, null, 50)";
+        test_db.execute_query(insert_statement).unwrap();
+
+        let rows = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let foo = || Value::String("foo".to_owned());
+        let bar = || Value::String("bar".to_owned());
+
+        // the largest order of every customer
+        assert_eq!(
+            rows(
+                "select distinct on (customer) customer, id from orders \
+                 order by customer, amount desc"
+            ),
+            vec![
+                vec![bar(), Value::Integer(2)],
+                vec![foo(), Value::Integer(3)],
+                vec![Value::Null, Value::Integer(6)],
+            ]
+        );
+        assert_eq!(
+            rows(
+                "select distinct on (1) customer c, id from orders \
+                 order by c desc, id limit 2"
+            ),
+            vec![
+                vec![Value::Null, Value::Integer(4)],
+                vec![foo(), Value::Integer(1)],
+            ]
+        );
+        assert_eq!(
+            rows("select distinct customer from orders order by customer"),
+            vec![vec![bar()], vec![foo()], vec![Value::Null]]
+        );
+        assert_eq!(
+            rows("select distinct amount > 20 from orders order by 1 desc"),
+            vec![vec![Value::Boolean(true)], vec![Value::Boolean(false)]]
+        );
+
+        assert!(test_db
+            .execute_query("select distinct customer from orders order by id")
+            .is_err());
+        assert!(test_db
+            .execute_query("select distinct on (customer) id from orders order by id")
+            .is_err());
+        assert!(test_db
+            .execute_query("select distinct on (customer, amount) id from orders order by amount")
+            .is_err());
+    }
+}
@@ -0,0 +1,545 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::rc::Rc;
+use std::vec::IntoIter;
+
+use anyhow::{Context, Result};
+
+use super::Executor;
+use crate::buffer::buffer_manager::BufferManager;
+use crate::catalog::schema::Schema;
+use crate::planner::physical_plan::SortKey;
+use crate::tuple::value::Value;
+use crate::tuple::Tuple;
+
+/// A tuple together with the values of its sort keys
+pub(super) struct SortEntry {
+    pub(super) keys: Vec<Value>,
+    pub(super) tuple: Tuple,
+}
+
+impl SortEntry {
+    pub(super) fn new(sort_keys: &[SortKey], tuple: Tuple) -> Result<Self> {
+        let keys = sort_keys
+            .iter()
+            .map(|key| key.expr.evaluate(&[&tuple]))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { keys, tuple })
+    }
+
+    /// Estimates how much memory the entry occupies
+    pub(super) fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self
+                .keys
+                .iter()
+                .chain(self.tuple.values())
+                .map(|val| std::mem::size_of::<Value>() + val.size())
+                .sum::<usize>()
+    }
+
+    /// Writes the entry, prefixed with its length and its number of tuple values
+    fn write(&self, writer: &mut impl Write, buffer: &mut Vec<u8>) -> Result<()> {
+        buffer.clear();
+        buffer.extend_from_slice(&(self.tuple.values().len() as u16).to_be_bytes());
+        for val in self.keys.iter().chain(self.tuple.values()) {
+            val.serialize_tagged(buffer);
+        }
+        writer.write_all(&(buffer.len() as u32).to_be_bytes())?;
+        writer.write_all(buffer)?;
+        Ok(())
+    }
+
+    fn read(reader: &mut impl Read, key_count: usize, buffer: &mut Vec<u8>) -> Result<Self> {
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        buffer.resize(u32::from_be_bytes(len) as usize, 0);
+        reader.read_exact(buffer)?;
+
+        let value_count = u16::from_be_bytes(buffer[..2].try_into().unwrap()) as usize;
+        let mut offset = 2;
+        let mut values = Vec::with_capacity(key_count + value_count);
+        for _ in 0..key_count + value_count {
+            let (val, len) = Value::deserialize_tagged(&buffer[offset..]);
+            values.push(val);
+            offset += len;
+        }
+        let tuple_values = values.split_off(key_count);
+        Ok(Self {
+            keys: values,
+            tuple: Tuple::new(tuple_values),
+        })
+    }
+}
+
+/// The maximum number of runs which are merged at once. Bounds the number of temporary files,
+/// which are open at the same time
+const MERGE_FAN_IN: usize = 64;
+
+/// A sorted run of entries, which has been spilled to a temporary file
+struct Run {
+    reader: BufReader<File>,
+    remaining: usize,
+    /// how often the entries of the run have been merged
+    passes: usize,
+}
+
+impl Run {
+    fn read_next(&mut self, key_count: usize, buffer: &mut Vec<u8>) -> Result<Option<SortEntry>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        SortEntry::read(&mut self.reader, key_count, buffer).map(Some)
+    }
+}
+
+/// Writes sorted entries to a new temporary file
+struct RunWriter {
+    writer: BufWriter<File>,
+    count: usize,
+}
+
+impl RunWriter {
+    fn new(buffer_manager: &BufferManager) -> Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(buffer_manager.create_temp_file()?),
+            count: 0,
+        })
+    }
+
+    fn write(&mut self, entry: &SortEntry, buffer: &mut Vec<u8>) -> Result<()> {
+        entry
+            .write(&mut self.writer, buffer)
+            .context("Failed to spill sorted tuples to a temporary file")?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(self, passes: usize) -> Result<Run> {
+        let mut file = self.writer.into_inner()?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(Run {
+            reader: BufReader::new(file),
+            remaining: self.count,
+            passes,
+        })
+    }
+}
+
+/// The smallest entry of a run which has not been returned yet
+struct MergeHead {
+    entry: SortEntry,
+    run: usize,
+    sort_keys: Rc<[SortKey]>,
+}
+
+/// Reversed, so that the max-heap returns the smallest head first. On ties, earlier runs win to
+/// keep the sort stable
+impl Ord for MergeHead {
+    fn cmp(&self, other: &Self) -> Ordering {
+        SortKey::compare_all(&self.sort_keys, &other.entry.keys, &self.entry.keys)
+            .then(other.run.cmp(&self.run))
+    }
+}
+
+impl PartialOrd for MergeHead {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MergeHead {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for MergeHead {}
+
+/// Merges sorted runs into a single sorted sequence of entries
+pub(super) struct Merger {
+    runs: Vec<Run>,
+    heads: BinaryHeap<MergeHead>,
+    sort_keys: Rc<[SortKey]>,
+    /// reused for reading entries
+    buffer: Vec<u8>,
+}
+
+impl Merger {
+    fn new(mut runs: Vec<Run>, sort_keys: &Rc<[SortKey]>) -> Result<Self> {
+        let mut buffer = vec![];
+        let mut heads = BinaryHeap::with_capacity(runs.len());
+        for (idx, run) in runs.iter_mut().enumerate() {
+            if let Some(entry) = run.read_next(sort_keys.len(), &mut buffer)? {
+                heads.push(MergeHead {
+                    entry,
+                    run: idx,
+                    sort_keys: sort_keys.clone(),
+                });
+            }
+        }
+        Ok(Self {
+            runs,
+            heads,
+            sort_keys: sort_keys.clone(),
+            buffer,
+        })
+    }
+
+    fn next(&mut self) -> Result<Option<SortEntry>> {
+        let head = match self.heads.pop() {
+            Some(head) => head,
+            None => return Ok(None),
+        };
+        let run = &mut self.runs[head.run];
+        if let Some(entry) = run.read_next(self.sort_keys.len(), &mut self.buffer)? {
+            self.heads.push(MergeHead {
+                entry,
+                run: head.run,
+                sort_keys: self.sort_keys.clone(),
+            });
+        }
+        Ok(Some(head.entry))
+    }
+}
+
+pub(super) enum SortedEntries {
+    /// all entries fit into memory
+    InMemory(IntoIter<SortEntry>),
+    /// the entries were spilled to sorted runs, which are merged
+    Runs(Merger),
+}
+
+impl SortedEntries {
+    pub(super) fn next(&mut self) -> Result<Option<Tuple>> {
+        let entry = match self {
+            SortedEntries::InMemory(entries) => entries.next(),
+            SortedEntries::Runs(merger) => merger.next()?,
+        };
+        Ok(entry.map(|entry| entry.tuple))
+    }
+}
+
+/// Sorts the entries pushed to it. As long as all entries fit into the memory budget
+/// (work_mem), they are sorted in memory. Otherwise, sorted runs of entries are spilled to
+/// temporary files, which are merged afterwards. The sort is stable
+pub(super) struct Sorter<'a> {
+    sort_keys: Rc<[SortKey]>,
+    buffer_manager: &'a BufferManager,
+    /// memory in bytes which may be used for sorting before spilling to disk
+    work_mem: usize,
+    /// the entries which have not been spilled yet
+    entries: Vec<SortEntry>,
+    used_memory: usize,
+    /// the spilled runs, in the order of their entries in the input
+    runs: Vec<Run>,
+    /// reused for reading and writing entries
+    buffer: Vec<u8>,
+}
+
+impl<'a> Sorter<'a> {
+    pub(super) fn new(
+        sort_keys: Rc<[SortKey]>,
+        buffer_manager: &'a BufferManager,
+        work_mem: usize,
+    ) -> Self {
+        Self {
+            sort_keys,
+            buffer_manager,
+            work_mem,
+            entries: vec![],
+            used_memory: 0,
+            runs: vec![],
+            buffer: vec![],
+        }
+    }
+
+    pub(super) fn push(&mut self, entry: SortEntry) -> Result<()> {
+        self.used_memory += entry.size();
+        self.entries.push(entry);
+
+        if self.used_memory > self.work_mem {
+            let entries = std::mem::take(&mut self.entries);
+            let run = self.spill(entries)?;
+            self.add_run(run)?;
+            self.used_memory = 0;
+        }
+        Ok(())
+    }
+
+    pub(super) fn finish(mut self) -> Result<SortedEntries> {
+        let mut entries = std::mem::take(&mut self.entries);
+        if self.runs.is_empty() {
+            entries.sort_by(|left, right| self.compare(left, right));
+            return Ok(SortedEntries::InMemory(entries.into_iter()));
+        }
+        if !entries.is_empty() {
+            let run = self.spill(entries)?;
+            self.add_run(run)?;
+        }
+        while self.runs.len() > MERGE_FAN_IN {
+            self.merge_last_runs()?;
+        }
+        let runs = std::mem::take(&mut self.runs);
+        Ok(SortedEntries::Runs(Merger::new(runs, &self.sort_keys)?))
+    }
+
+    fn compare(&self, left: &SortEntry, right: &SortEntry) -> Ordering {
+        SortKey::compare_all(&self.sort_keys, &left.keys, &right.keys)
+    }
+
+    /// Appends a run to the runs. As soon as the last MERGE_FAN_IN runs have been merged
+    /// equally often, they are merged into one. So every entry is merged a logarithmic number
+    /// of times, and the number of open runs grows only logarithmically with the input
+    fn add_run(&mut self, run: Run) -> Result<()> {
+        self.runs.push(run);
+        while self.runs.len() >= MERGE_FAN_IN
+            && self.runs[self.runs.len() - MERGE_FAN_IN].passes
+                == self.runs[self.runs.len() - 1].passes
+        {
+            self.merge_last_runs()?;
+        }
+        Ok(())
+    }
+
+    /// Sorts the entries and writes them to a temporary file
+    fn spill(&mut self, mut entries: Vec<SortEntry>) -> Result<Run> {
+        entries.sort_by(|left, right| self.compare(left, right));
+
+        let mut writer = RunWriter::new(self.buffer_manager)?;
+        for entry in &entries {
+            writer.write(entry, &mut self.buffer)?;
+        }
+        writer.finish(0)
+    }
+
+    /// Replaces the last MERGE_FAN_IN runs by a single merged run
+    fn merge_last_runs(&mut self) -> Result<()> {
+        let runs = self.runs.split_off(self.runs.len() - MERGE_FAN_IN);
+        let passes = runs.iter().map(|run| run.passes).max().unwrap_or(0) + 1;
+        let mut merger = Merger::new(runs, &self.sort_keys)?;
+        let mut writer = RunWriter::new(self.buffer_manager)?;
+        while let Some(entry) = merger.next()? {
+            writer.write(&entry, &mut self.buffer)?;
+        }
+        self.runs.push(writer.finish(passes)?);
+        Ok(())
+    }
+}
+
+/// Sorts the tuples of its child by the sort keys, spilling to temporary files if they do not
+/// fit into the memory budget (work_mem), see `Sorter`
+pub struct SortExecutor<'a> {
+    child: Box<dyn Executor + 'a>,
+    sort_keys: Rc<[SortKey]>,
+    buffer_manager: &'a BufferManager,
+    /// memory in bytes which may be used for sorting before spilling to disk
+    work_mem: usize,
+    /// the sorted entries, None as long as the child has not been consumed
+    sorted: Option<SortedEntries>,
+}
+
+impl<'a> SortExecutor<'a> {
+    pub fn new(
+        child: Box<dyn Executor + 'a>,
+        sort_keys: Vec<SortKey>,
+        buffer_manager: &'a BufferManager,
+        work_mem: usize,
+    ) -> Self {
+        Self {
+            child,
+            sort_keys: sort_keys.into(),
+            buffer_manager,
+            work_mem,
+            sorted: None,
+        }
+    }
+
+    fn sort(&mut self) -> Result<SortedEntries> {
+        let mut sorter = Sorter::new(self.sort_keys.clone(), self.buffer_manager, self.work_mem);
+        while let Some(tuple) = self.child.next().transpose()? {
+            sorter.push(SortEntry::new(&self.sort_keys, tuple)?)?;
+        }
+        sorter.finish()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        if self.sorted.is_none() {
+            self.sorted = Some(self.sort()?);
+        }
+        self.sorted.as_mut().unwrap().next()
+    }
+}
+
+impl<'a> Executor for SortExecutor<'a> {
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        self.next().transpose()
+    }
+
+    fn schema(&self) -> &Schema {
+        self.child.schema()
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.sorted = None;
+        self.child.rewind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::executors::tests::TestDb;
+    use crate::tuple::value::Value;
+
+    #[test]
+    fn can_order_by_multiple_keys() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "accounts",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+
+        let insert_statement = "insert into accounts values \
+            (1, 'foo'), (2, NULL), (3, 'bar'), (4, 'foo'), (5, NULL), (6, 'baz')";
+        test_db.execute_query(insert_statement).unwrap();
+
+        let ids = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.as_i32(0))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ids("select id from accounts order by name, id desc"),
+            vec![3, 6, 4, 1, 5, 2]
+        );
+        assert_eq!(
+            ids("select id from accounts order by name desc, id"),
+            vec![2, 5, 1, 4, 6, 3]
+        );
+        assert_eq!(
+            ids("select id from accounts order by name nulls first, 1 desc"),
+            vec![5, 2, 3, 6, 4, 1]
+        );
+        assert_eq!(
+            ids("select id, name n from accounts where id > 1 order by n desc nulls last, id"),
+            vec![4, 6, 3, 2, 5]
+        );
+        assert_eq!(
+            ids("select id from accounts order by id % 2, id - 10 * (id % 2)"),
+            vec![2, 4, 6, 1, 3, 5]
+        );
+
+        let sql = "select count(*), name from accounts group by name order by count(*) desc, name";
+        let result = test_db.execute_query(sql).unwrap();
+        let names = result
+            .iter()
+            .map(|tuple| tuple.values()[1].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                Value::String("foo".to_owned()),
+                Value::Null,
+                Value::String("bar".to_owned()),
+                Value::String("baz".to_owned()),
+            ]
+        );
+
+        assert!(test_db
+            .execute_query("select id from accounts order by 2")
+            .is_err());
+        assert!(test_db
+            .execute_query("select id from accounts order by array[id]")
+            .is_err());
+        assert!(test_db
+            .execute_query("select count(*) from accounts order by id")
+            .is_err());
+    }
+
+    #[test]
+    fn can_spill_sorted_runs() {
+        let mut test_db = TestDb::new();
+        test_db.work_mem = 1024;
+        test_db
+            .create_table(
+                "numbers",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "number".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+
+        let values = (0..500)
+            .map(|i| format!("({}, 'number {}')", (i * 7919) % 500, i % 3))
+            .collect::<Vec<_>>()
+            .join(", ");
+        test_db
+            .execute_query(&format!("insert into numbers values {}", values))
+            .unwrap();
+
+        let result = test_db
+            .execute_query("select number, name from numbers order by name desc, number")
+            .unwrap();
+        assert_eq!(result.len(), 500);
+        let sorted = result
+            .iter()
+            .map(|tuple| (tuple.as_str(1).to_owned(), tuple.as_i32(0)))
+            .collect::<Vec<_>>();
+        let mut expected = sorted.clone();
+        expected.sort_by(|left, right| right.0.cmp(&left.0).then(left.1.cmp(&right.1)));
+        assert_eq!(sorted, expected);
+        assert_eq!(sorted.first().unwrap().0, "number 2");
+        assert_eq!(sorted.last().unwrap().0, "number 0");
+    }
+
+    #[test]
+    fn can_merge_more_runs_than_the_fan_in() {
+        let mut test_db = TestDb::new();
+        test_db.work_mem = 1024;
+        test_db
+            .create_table(
+                "numbers",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "number".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+
+        // every run holds only a few tuples, so there are several times MERGE_FAN_IN runs
+        let values = (0..5000)
+            .map(|i| format!("({}, 'number {}')", i, (i * 7919) % 5))
+            .collect::<Vec<_>>()
+            .join(", ");
+        test_db
+            .execute_query(&format!("insert into numbers values {}", values))
+            .unwrap();
+
+        let result = test_db
+            .execute_query("select number, name from numbers order by name")
+            .unwrap();
+        assert_eq!(result.len(), 5000);
+        let sorted = result
+            .iter()
+            .map(|tuple| (tuple.as_str(1).to_owned(), tuple.as_i32(0)))
+            .collect::<Vec<_>>();
+        // the sort is stable, so tuples with the same name keep their insertion order
+        let mut expected = sorted.clone();
+        expected.sort();
+        assert_eq!(sorted, expected);
+    }
+}
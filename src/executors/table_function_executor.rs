@@ -0,0 +1,108 @@
+use anyhow::Result;
+
+use super::Executor;
+use crate::analyzer::logical_plan::TableFunc;
+use crate::catalog::schema::Schema;
+use crate::planner::physical_plan::Expr;
+use crate::tuple::value::Value;
+use crate::tuple::Tuple;
+
+pub struct TableFunctionExecutor {
+    cursor: usize,
+    rows: Vec<Value>,
+    schema: Schema,
+}
+
+impl TableFunctionExecutor {
+    pub fn new(func: TableFunc, args: Vec<Expr>, schema: Schema) -> Result<Self> {
+        let args = args
+            .iter()
+            .map(|arg| arg.evaluate(&[]))
+            .collect::<Result<Vec<_>>>()?;
+        let rows = match (func, args.as_slice()) {
+            (TableFunc::Unnest, [Value::Array(elements)]) => elements.clone(),
+            // unnest(NULL) returns no rows
+            (TableFunc::Unnest, _) => vec![],
+        };
+
+        Ok(Self {
+            cursor: 0,
+            rows,
+            schema,
+        })
+    }
+}
+
+impl Executor for TableFunctionExecutor {
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        if let Some(value) = self.rows.get(self.cursor) {
+            self.cursor += 1;
+            Some(Ok(Tuple::new(vec![value.clone()])))
+        } else {
+            None
+        }
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.cursor = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::executors::tests::TestDb;
+    use crate::tuple::value::Value;
+
+    #[test]
+    fn can_unnest_arrays() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "posts",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::TextArray, "tags".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+
+        let result = test_db
+            .execute_query("select * from unnest(array[3, null, 1]) as n")
+            .unwrap();
+        let values = result
+            .iter()
+            .map(|tuple| tuple.values()[0].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![Value::Integer(3), Value::Null, Value::Integer(1)]
+        );
+
+        test_db
+            .execute_query(
+                "insert into posts values (1, array['db', 'rust']), (2, array['sql']), (3, null)",
+            )
+            .unwrap();
+
+        let select = "select id from posts join unnest(array['rust', 'go']) lang \
+                      on lang = any(tags)";
+        let result = test_db.execute_query(select).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].values(), &[Value::Integer(1)]);
+
+        let result = test_db
+            .execute_query("select unnest.unnest from unnest(array[1, 2]) where unnest > 1")
+            .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].values(), &[Value::Integer(2)]);
+
+        assert!(test_db.execute_query("select * from unnest(1)").is_err());
+        assert!(test_db.execute_query("select * from generate(1)").is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use anyhow::Result;
+
+use super::sort_executor::{SortEntry, SortedEntries, Sorter};
+use super::Executor;
+use crate::buffer::buffer_manager::BufferManager;
+use crate::catalog::schema::Schema;
+use crate::planner::physical_plan::SortKey;
+use crate::tuple::Tuple;
+
+/// A tuple together with the values of its sort keys and its position in the input
+struct TopNEntry {
+    entry: SortEntry,
+    position: usize,
+}
+
+/// Returns the first `limit` tuples of its child in sorted order. Instead of sorting all
+/// tuples, a bounded max-heap keeps the smallest tuples seen so far. Ties are broken by the
+/// input position, so the result is the same as for a stable sort followed by a limit. If the
+/// kept tuples exceed the memory budget (work_mem), it falls back to the spilling sort
+pub struct TopNSortExecutor<'a> {
+    child: Box<dyn Executor + 'a>,
+    sort_keys: Rc<[SortKey]>,
+    limit: usize,
+    buffer_manager: &'a BufferManager,
+    /// memory in bytes which may be used for the kept tuples
+    work_mem: usize,
+    /// the sorted tuples, None as long as the child has not been consumed
+    sorted: Option<SortedEntries>,
+    /// the number of tuples returned since the child has been consumed
+    returned: usize,
+}
+
+impl<'a> TopNSortExecutor<'a> {
+    pub fn new(
+        child: Box<dyn Executor + 'a>,
+        sort_keys: Vec<SortKey>,
+        limit: usize,
+        buffer_manager: &'a BufferManager,
+        work_mem: usize,
+    ) -> Self {
+        Self {
+            child,
+            sort_keys: sort_keys.into(),
+            limit,
+            buffer_manager,
+            work_mem,
+            sorted: None,
+            returned: 0,
+        }
+    }
+
+    fn sort(&mut self) -> Result<SortedEntries> {
+        let mut heap: Vec<TopNEntry> = Vec::with_capacity(self.limit.min(1024));
+        if self.limit == 0 {
+            return Ok(SortedEntries::InMemory(vec![].into_iter()));
+        }
+
+        let sort_keys = self.sort_keys.clone();
+        let compare = |left: &TopNEntry, right: &TopNEntry| {
+            SortKey::compare_all(&sort_keys, &left.entry.keys, &right.entry.keys)
+                .then(left.position.cmp(&right.position))
+        };
+
+        let mut used_memory = 0;
+        let mut position = 0;
+        while let Some(tuple) = self.child.next().transpose()? {
+            let entry = TopNEntry {
+                entry: SortEntry::new(&sort_keys, tuple)?,
+                position,
+            };
+            position += 1;
+
+            if heap.len() < self.limit {
+                used_memory += entry.entry.size();
+                heap.push(entry);
+                sift_up(&mut heap, compare);
+            } else if compare(&entry, &heap[0]).is_lt() {
+                // replace the largest of the kept tuples
+                used_memory = used_memory + entry.entry.size() - heap[0].entry.size();
+                heap[0] = entry;
+                sift_down(&mut heap, compare);
+            }
+
+            if used_memory > self.work_mem {
+                return self.sort_spilling(heap);
+            }
+        }
+
+        heap.sort_by(compare);
+        let entries = heap
+            .into_iter()
+            .map(|entry| entry.entry)
+            .collect::<Vec<_>>();
+        Ok(SortedEntries::InMemory(entries.into_iter()))
+    }
+
+    /// Sorts the kept and the remaining tuples of the child with the spilling sort
+    fn sort_spilling(&mut self, mut heap: Vec<TopNEntry>) -> Result<SortedEntries> {
+        // the sorter is stable, so the tuples are passed in input order
+        heap.sort_by_key(|entry| entry.position);
+        let mut sorter = Sorter::new(self.sort_keys.clone(), self.buffer_manager, self.work_mem);
+        for entry in heap {
+            sorter.push(entry.entry)?;
+        }
+        while let Some(tuple) = self.child.next().transpose()? {
+            sorter.push(SortEntry::new(&self.sort_keys, tuple)?)?;
+        }
+        sorter.finish()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        if self.sorted.is_none() {
+            self.sorted = Some(self.sort()?);
+            self.returned = 0;
+        }
+        if self.returned == self.limit {
+            return Ok(None);
+        }
+        self.returned += 1;
+        self.sorted.as_mut().unwrap().next()
+    }
+}
+
+/// Restores the max-heap property after pushing an entry
+fn sift_up<T>(heap: &mut [T], compare: impl Fn(&T, &T) -> Ordering) {
+    let mut idx = heap.len() - 1;
+    while idx > 0 {
+        let parent = (idx - 1) / 2;
+        if compare(&heap[idx], &heap[parent]).is_le() {
+            break;
+        }
+        heap.swap(idx, parent);
+        idx = parent;
+    }
+}
+
+/// Restores the max-heap property after replacing the root
+fn sift_down<T>(heap: &mut [T], compare: impl Fn(&T, &T) -> Ordering) {
+    let mut idx = 0;
+    loop {
+        let mut largest = idx;
+        for child in [2 * idx + 1, 2 * idx + 2] {
+            if child < heap.len() && compare(&heap[child], &heap[largest]).is_gt() {
+                largest = child;
+            }
+        }
+        if largest == idx {
+            break;
+        }
+        heap.swap(idx, largest);
+        idx = largest;
+    }
+}
+
+impl<'a> Executor for TopNSortExecutor<'a> {
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        self.next().transpose()
+    }
+
+    fn schema(&self) -> &Schema {
+        self.child.schema()
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.sorted = None;
+        self.child.rewind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::executors::tests::TestDb;
+
+    #[test]
+    fn falls_back_to_spilling_sort() {
+        let mut test_db = TestDb::new();
+        test_db.work_mem = 1024;
+        test_db
+            .create_table(
+                "numbers",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Integer, "number".to_owned(), 1, false),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 2, false),
+                ],
+            )
+            .unwrap();
+
+        let values = (0..1000)
+            .map(|i| format!("({}, {}, 'number {}')", i, (i * 37) % 50, i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        test_db
+            .execute_query(&format!("insert into numbers values {}", values))
+            .unwrap();
+
+        let rows = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| (tuple.as_i32(0), tuple.as_i32(1)))
+                .collect::<Vec<_>>()
+        };
+
+        // the kept tuples exceed work_mem, the result is still the one of a stable sort
+        let full_sort = rows("select id, number from numbers order by number desc");
+        let top_n = rows("select id, number from numbers order by number desc limit 300");
+        assert_eq!(top_n, full_sort[..300]);
+        let top_n =
+            rows("select id, number from numbers order by number desc limit 200 offset 500");
+        assert_eq!(top_n, full_sort[500..700]);
+        let top_n = rows("select id, number from numbers order by number desc limit 2000");
+        assert_eq!(top_n, full_sort);
+    }
+
+    #[test]
+    fn can_sort_top_n_tuples() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "numbers",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Integer, "number".to_owned(), 1, false),
+                ],
+            )
+            .unwrap();
+
+        let values = (0..200)
+            .map(|i| format!("({}, {})", i, (i * 37) % 50))
+            .collect::<Vec<_>>()
+            .join(", ");
+        test_db
+            .execute_query(&format!(
+                "insert into numbers values {}, (200, null)",
+                values
+            ))
+            .unwrap();
+
+        let rows = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| (tuple.as_i32(0), tuple.values()[1].clone()))
+                .collect::<Vec<_>>()
+        };
+
+        // ties are returned in input order, like with a stable sort
+        let full_sort = rows("select id, number from numbers order by number desc");
+        let top_n = rows("select id, number from numbers order by number desc limit 7");
+        assert_eq!(top_n, full_sort[..7]);
+        assert_eq!(top_n[0].0, 200);
+
+        let top_n =
+            rows("select id, number from numbers order by number, id desc limit 5 offset 3");
+        let full_sort = rows("select id, number from numbers order by number, id desc");
+        assert_eq!(top_n, full_sort[3..8]);
+
+        let top_n = rows("select id, number from numbers order by number limit 500");
+        assert_eq!(top_n.len(), 201);
+        assert!(rows("select id, number from numbers order by number limit 0").is_empty());
+
+        let sql = "select number % 10, count(id) from numbers \
+                   group by 1 order by count(id) desc, 1 limit 2";
+        let result = test_db.execute_query(sql).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].as_i32(0), 0);
+        assert_eq!(result[0].as_i32(1), 20);
+    }
+}
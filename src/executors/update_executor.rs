@@ -23,6 +23,8 @@ lazy_static! {
 
 pub struct UpdateExecutor<'a> {
     table: &'a Table,
+    /// the schema of the table with the column names of the catalog
+    target_schema: Schema,
     child: Box<dyn Executor + 'a>,
     transaction: &'a Transaction<'a>,
     set_expressions: HashMap<usize, Expr>,
@@ -33,12 +35,14 @@ pub struct UpdateExecutor<'a> {
 impl<'a> UpdateExecutor<'a> {
     pub fn new(
         table: &'a Table,
+        target_schema: Schema,
         child: Box<dyn Executor + 'a>,
         set_expressions: HashMap<usize, Expr>,
         transaction: &'a Transaction,
     ) -> Self {
         Self {
             table,
+            target_schema,
             child,
             transaction,
             set_expressions,
@@ -53,13 +57,16 @@ impl<'a> UpdateExecutor<'a> {
                 .values
                 .iter()
                 .enumerate()
-                .map(|(col_idx, col_value)| {
-                    self.set_expressions
-                        .get(&col_idx)
-                        .map(|expr| expr.evaluate(&[&tuple]))
-                        .unwrap_or_else(|| col_value.clone())
-                })
-                .collect();
+                .map(
+                    |(col_idx, col_value)| match self.set_expressions.get(&col_idx) {
+                        Some(expr) => {
+                            let col = &self.target_schema.columns()[col_idx];
+                            col.enforce_length(expr.evaluate(&[&tuple])?)
+                        }
+                        None => Ok(col_value.clone()),
+                    },
+                )
+                .collect::<Result<Vec<_>>>()?;
             let updated_tuple = Tuple::new(values);
 
             let update_result =
@@ -28,9 +28,12 @@ impl Executor for ValuesExecutor {
 
     fn next(&mut self) -> Option<Result<Tuple>> {
         if let Some(exprs) = self.values.get(self.cursor) {
-            let values = exprs.iter().map(|expr| expr.evaluate(&[])).collect();
             self.cursor += 1;
-            Some(Ok(Tuple::new(values)))
+            let values = exprs
+                .iter()
+                .map(|expr| expr.evaluate(&[]))
+                .collect::<Result<Vec<_>>>();
+            Some(values.map(Tuple::new))
         } else {
             None
         }
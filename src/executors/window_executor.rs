@@ -0,0 +1,467 @@
+use std::collections::VecDeque;
+
+use anyhow::Result;
+
+use super::Executor;
+use crate::analyzer::logical_plan::WindowFunc;
+use crate::catalog::schema::Schema;
+use crate::parser::ast::{FrameBound, WindowFrame};
+use crate::planner::physical_plan::{Accumulator, Aggregation, Expr, WindowFunction};
+use crate::tuple::value::Value;
+use crate::tuple::Tuple;
+
+/// A window function prepared for the evaluation over a partition
+enum Evaluator {
+    RowNumber,
+    Rank,
+    DenseRank,
+    /// lag and lead, i.e. the value of the row at an offset from the current row
+    Offset {
+        lag: bool,
+        args: Vec<Expr>,
+    },
+    FirstValue {
+        arg: Expr,
+        frame: Option<WindowFrame<usize>>,
+    },
+    LastValue {
+        arg: Expr,
+        frame: Option<WindowFrame<usize>>,
+    },
+    Aggregation {
+        aggregation: Aggregation,
+        frame: Option<WindowFrame<usize>>,
+    },
+}
+
+impl Evaluator {
+    fn new(function: WindowFunction) -> Self {
+        let WindowFunction {
+            func,
+            mut args,
+            frame,
+        } = function;
+        match func {
+            WindowFunc::RowNumber => Self::RowNumber,
+            WindowFunc::Rank => Self::Rank,
+            WindowFunc::DenseRank => Self::DenseRank,
+            WindowFunc::Lag | WindowFunc::Lead => Self::Offset {
+                lag: func == WindowFunc::Lag,
+                args,
+            },
+            WindowFunc::FirstValue => Self::FirstValue {
+                arg: args.remove(0),
+                frame,
+            },
+            WindowFunc::LastValue => Self::LastValue {
+                arg: args.remove(0),
+                frame,
+            },
+            WindowFunc::Aggregation(func) => Self::Aggregation {
+                aggregation: Aggregation::new(func, args, false),
+                frame,
+            },
+        }
+    }
+}
+
+/// The rows of a partition, which share the same partition keys
+struct Partition {
+    tuples: Vec<Tuple>,
+    /// for every row, the index of its first and its last peer, i.e. the rows with the same
+    /// order keys
+    peers: Vec<(usize, usize)>,
+    /// whether the rows are ordered, otherwise all rows are peers
+    ordered: bool,
+}
+
+impl Partition {
+    /// Returns the first and the last row of the frame of a row, or None if it is empty
+    fn frame(&self, frame: &Option<WindowFrame<usize>>, row: usize) -> Option<(usize, usize)> {
+        let last = self.tuples.len() - 1;
+        let frame = match frame {
+            Some(frame) => frame,
+            None if self.ordered => return Some((0, self.peers[row].1)),
+            None => return Some((0, last)),
+        };
+        let start = match frame.start {
+            FrameBound::UnboundedPreceding => 0,
+            FrameBound::Preceding(offset) => row.saturating_sub(offset),
+            FrameBound::CurrentRow => row,
+            FrameBound::Following(offset) => row.saturating_add(offset),
+            FrameBound::UnboundedFollowing => unreachable!(),
+        };
+        let end = match frame.end {
+            FrameBound::UnboundedPreceding => unreachable!(),
+            FrameBound::Preceding(offset) => row.checked_sub(offset)?,
+            FrameBound::CurrentRow => row,
+            FrameBound::Following(offset) => row.saturating_add(offset).min(last),
+            FrameBound::UnboundedFollowing => last,
+        };
+        if start > end {
+            None
+        } else {
+            Some((start, end))
+        }
+    }
+
+    /// Evaluates the window function for every row of the partition
+    fn evaluate(&self, evaluator: &Evaluator) -> Result<Vec<Value>> {
+        let rows = 0..self.tuples.len();
+        let values = match evaluator {
+            Evaluator::RowNumber => rows.map(|row| Value::Integer(row as i32 + 1)).collect(),
+            Evaluator::Rank => rows
+                .map(|row| Value::Integer(self.peers[row].0 as i32 + 1))
+                .collect(),
+            Evaluator::DenseRank => {
+                let mut rank = 0;
+                rows.map(|row| {
+                    if self.peers[row].0 == row {
+                        rank += 1;
+                    }
+                    Value::Integer(rank)
+                })
+                .collect()
+            }
+            Evaluator::Offset { lag, args } => rows
+                .map(|row| self.evaluate_offset(*lag, args, row))
+                .collect::<Result<Vec<_>>>()?,
+            Evaluator::FirstValue { arg, frame } => rows
+                .map(|row| match self.frame(frame, row) {
+                    Some((start, _)) => arg.evaluate(&[&self.tuples[start]]),
+                    None => Ok(Value::Null),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Evaluator::LastValue { arg, frame } => rows
+                .map(|row| match self.frame(frame, row) {
+                    Some((_, end)) => arg.evaluate(&[&self.tuples[end]]),
+                    None => Ok(Value::Null),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Evaluator::Aggregation { aggregation, frame } => {
+                self.evaluate_aggregation(aggregation, frame)?
+            }
+        };
+        Ok(values)
+    }
+
+    /// Returns the value of the row at the offset before (lag) or after (lead) the row. The
+    /// offset defaults to 1, rows outside of the partition evaluate to the default or NULL
+    fn evaluate_offset(&self, lag: bool, args: &[Expr], row: usize) -> Result<Value> {
+        let tuple = &self.tuples[row];
+        let offset = match args.get(1) {
+            Some(offset) => match offset.evaluate(&[tuple])? {
+                Value::Integer(offset) => offset as i64,
+                _ => return Ok(Value::Null),
+            },
+            None => 1,
+        };
+        let target = if lag {
+            row as i64 - offset
+        } else {
+            row as i64 + offset
+        };
+        if target >= 0 && (target as usize) < self.tuples.len() {
+            args[0].evaluate(&[&self.tuples[target as usize]])
+        } else {
+            match args.get(2) {
+                Some(default) => default.evaluate(&[tuple]),
+                None => Ok(Value::Null),
+            }
+        }
+    }
+
+    /// Aggregates the frame of every row. As long as consecutive frames have the same start,
+    /// the aggregation of the previous frame is continued instead of starting over
+    fn evaluate_aggregation(
+        &self,
+        aggregation: &Aggregation,
+        frame: &Option<WindowFrame<usize>>,
+    ) -> Result<Vec<Value>> {
+        let mut values = vec![];
+        // the accumulated frame, its start and the row after its end
+        let mut accumulated: Option<(Accumulator, usize, usize)> = None;
+        for row in 0..self.tuples.len() {
+            let (start, end) = match self.frame(frame, row) {
+                Some(bounds) => bounds,
+                None => {
+                    let acc = aggregation.initial_accumulator();
+                    values.push(aggregation.finalize(acc)?);
+                    continue;
+                }
+            };
+            let (mut acc, next) = match accumulated.take() {
+                Some((acc, acc_start, next)) if acc_start == start && next <= end + 1 => {
+                    (acc, next)
+                }
+                _ => (aggregation.initial_accumulator(), start),
+            };
+            for tuple in &self.tuples[next..=end] {
+                aggregation.aggregate(&mut acc, tuple)?;
+            }
+            values.push(aggregation.finalize(acc.clone())?);
+            accumulated = Some((acc, start, end + 1));
+        }
+        Ok(values)
+    }
+}
+
+/// Evaluates window functions over the partitions of its child, which is sorted by the
+/// partition keys followed by the order keys. Each partition is read completely, before its
+/// tuples are returned with the results of the window functions appended
+pub struct WindowExecutor<'a> {
+    child: Box<dyn Executor + 'a>,
+    partition_by: Vec<Expr>,
+    order_by: Vec<Expr>,
+    evaluators: Vec<Evaluator>,
+    output_schema: Schema,
+    /// the first tuple of the next partition with its partition keys, which has been read to
+    /// find the end of the current partition
+    next_partition: Option<(Vec<Value>, Tuple)>,
+    /// the evaluated tuples of the current partition, which have not been returned yet
+    output: VecDeque<Tuple>,
+}
+
+impl<'a> WindowExecutor<'a> {
+    pub fn new(
+        child: Box<dyn Executor + 'a>,
+        partition_by: Vec<Expr>,
+        order_by: Vec<Expr>,
+        functions: Vec<WindowFunction>,
+        output_schema: Schema,
+    ) -> Self {
+        Self {
+            child,
+            partition_by,
+            order_by,
+            evaluators: functions.into_iter().map(Evaluator::new).collect(),
+            output_schema,
+            next_partition: None,
+            output: VecDeque::new(),
+        }
+    }
+
+    fn evaluate_keys(keys: &[Expr], tuple: &Tuple) -> Result<Vec<Value>> {
+        keys.iter().map(|expr| expr.evaluate(&[tuple])).collect()
+    }
+
+    /// Reads the tuples of the next partition, returns None if there are no more tuples
+    fn read_partition(&mut self) -> Result<Option<Partition>> {
+        let (partition_key, first_tuple) = match self.next_partition.take() {
+            Some(next_partition) => next_partition,
+            None => match self.child.next().transpose()? {
+                Some(tuple) => (Self::evaluate_keys(&self.partition_by, &tuple)?, tuple),
+                None => return Ok(None),
+            },
+        };
+
+        let mut tuples = vec![first_tuple];
+        while let Some(tuple) = self.child.next().transpose()? {
+            let key = Self::evaluate_keys(&self.partition_by, &tuple)?;
+            if key != partition_key {
+                self.next_partition = Some((key, tuple));
+                break;
+            }
+            tuples.push(tuple);
+        }
+
+        let order_keys = tuples
+            .iter()
+            .map(|tuple| Self::evaluate_keys(&self.order_by, tuple))
+            .collect::<Result<Vec<_>>>()?;
+        let mut peers = vec![(0, 0); tuples.len()];
+        let mut first_peer = 0;
+        for row in 1..=tuples.len() {
+            if row == tuples.len() || order_keys[row] != order_keys[first_peer] {
+                for peer in &mut peers[first_peer..row] {
+                    *peer = (first_peer, row - 1);
+                }
+                first_peer = row;
+            }
+        }
+
+        Ok(Some(Partition {
+            tuples,
+            peers,
+            ordered: !self.order_by.is_empty(),
+        }))
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        if self.output.is_empty() {
+            let partition = match self.read_partition()? {
+                Some(partition) => partition,
+                None => return Ok(None),
+            };
+            let results = self
+                .evaluators
+                .iter()
+                .map(|evaluator| partition.evaluate(evaluator))
+                .collect::<Result<Vec<_>>>()?;
+            for (row, tuple) in partition.tuples.into_iter().enumerate() {
+                let mut values = tuple.values;
+                values.extend(results.iter().map(|result| result[row].clone()));
+                self.output.push_back(Tuple::new(values));
+            }
+        }
+        Ok(self.output.pop_front())
+    }
+}
+
+impl<'a> Executor for WindowExecutor<'a> {
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        self.next().transpose()
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.output_schema
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.next_partition = None;
+        self.output.clear();
+        self.child.rewind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::executors::tests::TestDb;
+    use crate::tuple::value::Value;
+
+    #[test]
+    fn can_evaluate_window_functions() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "salaries",
+                vec![
+                    ColumnDefinition::new(TypeId::Integer, "id".to_owned(), 0, true),
+                    ColumnDefinition::new(TypeId::Text, "dept".to_owned(), 1, false),
+                    ColumnDefinition::new(TypeId::Integer, "salary".to_owned(), 2, false),
+                ],
+            )
+            .unwrap();
+        let insert_statement = "insert into salaries values \
+            (1, 'dev', 100), (2, 'dev', 200), (3, 'dev', 200), (4, 'ops', 50), (5, 'ops', 80), \
+            (6, null, 10)";
+        test_db.execute_query(insert_statement).unwrap();
+
+        let rows = |sql: &str| {
+            test_db
+                .execute_query(sql)
+                .unwrap()
+                .iter()
+                .map(|tuple| tuple.values().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let int = |values: &[i32]| {
+            values
+                .iter()
+                .map(|v| Value::Integer(*v))
+                .collect::<Vec<_>>()
+        };
+
+        let sql = "select id, row_number() over (partition by dept order by salary desc, id), \
+                   rank() over (partition by dept order by salary desc), \
+                   dense_rank() over (order by salary desc) \
+                   from salaries order by id";
+        assert_eq!(
+            rows(sql),
+            vec![
+                int(&[1, 3, 3, 2]),
+                int(&[2, 1, 1, 1]),
+                int(&[3, 2, 1, 1]),
+                int(&[4, 2, 2, 4]),
+                int(&[5, 1, 1, 3]),
+                int(&[6, 1, 1, 5]),
+            ]
+        );
+        let sql = "select id, lag(salary) over (order by id), \
+                   lead(salary, 2, 0) over (order by id), \
+                   first_value(id) over (partition by dept order by salary), \
+                   last_value(id) over (partition by dept order by salary) \
+                   from salaries where dept is not null order by id";
+        assert_eq!(
+            rows(sql),
+            vec![
+                vec![
+                    Value::Integer(1),
+                    Value::Null,
+                    Value::Integer(200),
+                    Value::Integer(1),
+                    Value::Integer(1),
+                ],
+                int(&[2, 100, 50, 1, 3]),
+                int(&[3, 200, 80, 1, 3]),
+                int(&[4, 200, 0, 4, 4]),
+                int(&[5, 50, 0, 4, 5]),
+            ]
+        );
+        // the default frame ends with the last peer of the current row
+        let sql = "select id, sum(salary) over (order by salary), \
+                   sum(salary) over (order by salary rows unbounded preceding), \
+                   count(*) over (), \
+                   sum(salary) over (order by id rows between 1 preceding and 1 following), \
+                   max(salary) over (order by id rows between 2 following and unbounded following) \
+                   from salaries where dept = 'dev' order by id";
+        assert_eq!(
+            rows(sql),
+            vec![
+                int(&[1, 100, 100, 3, 300, 200]),
+                vec![
+                    Value::Integer(2),
+                    Value::Integer(500),
+                    Value::Integer(300),
+                    Value::Integer(3),
+                    Value::Integer(500),
+                    Value::Null,
+                ],
+                vec![
+                    Value::Integer(3),
+                    Value::Integer(500),
+                    Value::Integer(500),
+                    Value::Integer(3),
+                    Value::Integer(400),
+                    Value::Null,
+                ],
+            ]
+        );
+        // window functions are evaluated after grouping
+        let sql = "select dept, sum(salary), rank() over (order by sum(salary) desc) \
+                   from salaries group by dept order by 3";
+        assert_eq!(
+            rows(sql),
+            vec![
+                vec![
+                    Value::String("dev".to_owned()),
+                    Value::Integer(500),
+                    Value::Integer(1),
+                ],
+                vec![
+                    Value::String("ops".to_owned()),
+                    Value::Integer(130),
+                    Value::Integer(2),
+                ],
+                vec![Value::Null, Value::Integer(10), Value::Integer(3)],
+            ]
+        );
+        let sql = "select id from salaries \
+                   order by row_number() over (order by salary desc, id) limit 2";
+        assert_eq!(rows(sql), vec![int(&[2]), int(&[3])]);
+
+        for sql in [
+            "select id from salaries where rank() over (order by id) = 1",
+            "select dept from salaries group by dept having count(*) over () > 1",
+            "select sum(rank() over (order by id)) from salaries",
+            "select rank() over (order by row_number() over ()) from salaries",
+            "select count(distinct id) over () from salaries",
+            "select length(id) over () from salaries",
+            "select sum(salary) over (rows between current row and 1 preceding) from salaries",
+            "select lag(salary, 1, 'a') over () from salaries",
+        ] {
+            assert!(test_db.execute_query(sql).is_err(), "{}", sql);
+        }
+    }
+}
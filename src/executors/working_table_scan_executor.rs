@@ -0,0 +1,47 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::Result;
+
+use super::Executor;
+use crate::catalog::schema::Schema;
+use crate::tuple::value::Value;
+use crate::tuple::Tuple;
+
+/// The rows of a recursive CTE, which are scanned by the current iteration
+pub type WorkingTable = Rc<RefCell<Vec<Vec<Value>>>>;
+
+/// Returns the rows added by the previous iteration of a recursive CTE. The working table is
+/// replaced by the recursive union executor before each iteration, which rewinds this scan
+pub struct WorkingTableScanExecutor {
+    cursor: usize,
+    working_table: WorkingTable,
+    schema: Schema,
+}
+
+impl WorkingTableScanExecutor {
+    pub fn new(working_table: WorkingTable, schema: Schema) -> Self {
+        Self {
+            cursor: 0,
+            working_table,
+            schema,
+        }
+    }
+}
+
+impl Executor for WorkingTableScanExecutor {
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn next(&mut self) -> Option<Result<Tuple>> {
+        let values = self.working_table.borrow().get(self.cursor)?.clone();
+        self.cursor += 1;
+        Some(Ok(Tuple::new(values)))
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.cursor = 0;
+        Ok(())
+    }
+}
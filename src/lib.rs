@@ -0,0 +1,128 @@
+pub mod analyzer;
+pub mod buffer;
+pub mod catalog;
+pub mod common;
+pub mod concurrency;
+pub mod executors;
+pub mod optimizer;
+pub mod parser;
+pub mod planner;
+pub mod printer;
+pub mod storage;
+pub mod tuple;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+
+use crate::buffer::buffer_manager::BufferManager;
+use crate::catalog::schema::TypeId;
+use crate::catalog::Catalog;
+use crate::concurrency::TransactionManager;
+use crate::storage::file_manager::FileManager;
+use crate::tuple::value::Value;
+
+/// A database stored in a data directory. It is shared by all connections, and applications
+/// embedding the database use it to extend it, e.g. with their own functions
+pub struct Database {
+    buffer_manager: Arc<BufferManager>,
+    transaction_manager: TransactionManager,
+    catalog: Catalog,
+}
+
+impl Database {
+    /// Opens the database in the data directory. If `new` is set, the directory is assumed to
+    /// be empty and is initialized
+    pub fn open(data_directory: impl Into<PathBuf>, pool_size: usize, new: bool) -> Result<Self> {
+        let file_manager = FileManager::new(data_directory)?;
+        let buffer_manager = Arc::new(BufferManager::new(file_manager, pool_size));
+        let transaction_manager = TransactionManager::new(Arc::clone(&buffer_manager), new)
+            .with_context(|| "Failed to create transaction manager")?;
+
+        let bootstrap_transaction = transaction_manager.bootstrap();
+        let catalog = Catalog::new(Arc::clone(&buffer_manager), new, &bootstrap_transaction)
+            .with_context(|| "Failed to create catalog")?;
+        if new {
+            bootstrap_transaction
+                .commit()
+                .with_context(|| "Failed to commit bootstrap transaction")?;
+        }
+        drop(bootstrap_transaction);
+
+        Ok(Self {
+            buffer_manager,
+            transaction_manager,
+            catalog,
+        })
+    }
+
+    pub fn buffer_manager(&self) -> &Arc<BufferManager> {
+        &self.buffer_manager
+    }
+
+    pub fn transaction_manager(&self) -> &TransactionManager {
+        &self.transaction_manager
+    }
+
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    /// Registers a scalar function implemented in Rust, see `Catalog::register_scalar_function`
+    pub fn register_scalar_function<F>(
+        &self,
+        name: &str,
+        arg_types: Vec<TypeId>,
+        return_type: TypeId,
+        func: F,
+    ) -> Result<()>
+    where
+        F: Fn(&[Value]) -> Result<Value> + Send + Sync + 'static,
+    {
+        self.catalog
+            .register_scalar_function(name, arg_types, return_type, func)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use tempfile::tempdir;
+
+    use super::Database;
+    use crate::catalog::schema::TypeId;
+    use crate::tuple::value::Value;
+
+    #[test]
+    fn can_open_database_and_register_functions() -> Result<()> {
+        let data_dir = tempdir()?;
+        let database = Database::open(data_dir.path(), 2, true)?;
+        database.register_scalar_function("answer", vec![], TypeId::Integer, |_| {
+            Ok(Value::Integer(42))
+        })?;
+        assert!(database
+            .register_scalar_function("answer", vec![], TypeId::Text, |_| Ok(Value::Null))
+            .is_err());
+        assert!(database
+            .catalog()
+            .functions()
+            .resolve("answer", &[])
+            .is_ok());
+        drop(database);
+
+        // functions are not persisted, the application registers them whenever it opens the
+        // database
+        let database = Database::open(data_dir.path(), 2, false)?;
+        assert!(database
+            .catalog()
+            .get_schema("system_catalog_tables")
+            .is_some());
+        assert!(database
+            .catalog()
+            .functions()
+            .resolve("answer", &[])
+            .is_err());
+        Ok(())
+    }
+}
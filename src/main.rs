@@ -1,36 +1,21 @@
-mod analyzer;
-mod buffer;
-mod catalog;
-mod common;
-mod concurrency;
-mod executors;
-mod optimizer;
-mod parser;
-mod planner;
-mod printer;
-mod storage;
-mod tuple;
-
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::net::{Shutdown, TcpListener, TcpStream};
 use std::sync::Arc;
 use std::thread;
 
-use analyzer::Analyzer;
 use anyhow::{Context, Error, Result};
-use buffer::buffer_manager::BufferManager;
-use catalog::Catalog;
 use clap::{Arg, Command, Parser};
-use concurrency::Transaction;
-use executors::ExecutorFactory;
-use optimizer::optimize;
-use parser::ast::Statement;
-use parser::parse_sql;
-use planner::Planner;
-use printer::Printer;
-use storage::file_manager::FileManager;
-
-use crate::concurrency::TransactionManager;
+use erdb::analyzer::Analyzer;
+use erdb::buffer::buffer_manager::BufferManager;
+use erdb::catalog::Catalog;
+use erdb::concurrency::{Transaction, TransactionManager};
+use erdb::executors::{ExecutorFactory, ExecutorSettings};
+use erdb::optimizer::optimize;
+use erdb::parser::ast::Statement;
+use erdb::parser::parse_sql;
+use erdb::planner::Planner;
+use erdb::printer::Printer;
+use erdb::Database;
 
 #[derive(Parser)]
 struct ServerConfig {
@@ -48,6 +33,20 @@ struct ServerConfig {
 
     #[arg(long, default_value_t = 8, help = "Size of buffer pool")]
     pool_size: usize,
+
+    #[arg(
+        long,
+        default_value_t = 4096,
+        help = "Memory in KiB which a sort may use before spilling to temporary files"
+    )]
+    work_mem: usize,
+
+    #[arg(
+        long,
+        default_value_t = 10000,
+        help = "Maximum number of iterations of a recursive query"
+    )]
+    max_recursive_iterations: usize,
 }
 
 fn metacommand() -> Command {
@@ -136,13 +135,14 @@ fn get_transaction<'a, 'b>(
     Ok(current)
 }
 
-fn handle_sql_statement<'a, 'b>(
+fn handle_sql_statement<'a>(
     writer: &mut BufWriter<&TcpStream>,
     sql: &str,
     buffer_manager: Arc<BufferManager>,
     transaction_manager: &'a TransactionManager,
     catalog: &Catalog,
-    transaction: &'b mut Option<Transaction<'a>>,
+    transaction: &mut Option<Transaction<'a>>,
+    settings: ExecutorSettings,
 ) -> Result<()> {
     let (explain, statement) = parse_sql(sql)?;
     match statement {
@@ -191,10 +191,23 @@ fn handle_sql_statement<'a, 'b>(
                 writer.write_all(format!("{}", plan).as_bytes())?;
             } else {
                 let transaction = get_transaction(transaction_manager, transaction)?;
-                let mut executor_factory = ExecutorFactory::new(buffer_manager, transaction);
-                let executor = executor_factory.create_executor(plan)?;
-                let mut printer = Printer::new(executor);
-                printer.print_all_tuples(writer)?;
+                let mut executor_factory =
+                    ExecutorFactory::new(buffer_manager, transaction, settings);
+                let result = executor_factory
+                    .create_executor(plan)
+                    .and_then(|executor| Printer::new(executor).print_all_tuples(writer));
+                if let Err(e) = result {
+                    // a statement failing during execution (e.g. with a division by zero)
+                    // invalidates the transaction. Modifying executors already handle this
+                    if !transaction.has_ended() {
+                        if transaction.auto_commit() {
+                            transaction.abort()?;
+                        } else {
+                            transaction.expect_rollback();
+                        }
+                    }
+                    return Err(e);
+                }
             }
         }
     }
@@ -206,6 +219,7 @@ fn handle_client(
     catalog: &Catalog,
     buffer_manager: Arc<BufferManager>,
     transaction_manager: &TransactionManager,
+    settings: ExecutorSettings,
 ) -> Result<()> {
     stream.write_all("Welcome to erdb".as_bytes())?;
     stream.write_all("\n> ".as_bytes())?;
@@ -222,7 +236,7 @@ fn handle_client(
         writer.flush()?;
         reader.read_line(&mut line)?;
 
-        if line.as_bytes().is_empty() {
+        if line.is_empty() {
             // Client didn't send anything. Connection lost?
             return Ok(());
         }
@@ -244,6 +258,7 @@ fn handle_client(
                     transaction_manager,
                     catalog,
                     &mut transaction,
+                    settings,
                 ) {
                     Ok(()) => (),
                     Err(e) => {
@@ -269,37 +284,30 @@ fn main() -> Result<()> {
     println!("Welcome to erdb.");
     let config = ServerConfig::parse();
 
-    let file_manager = FileManager::new(config.data)?;
-    let buffer_manager = Arc::new(BufferManager::new(file_manager, config.pool_size));
-    let transaction_manager = TransactionManager::new(Arc::clone(&buffer_manager), config.new)
-        .with_context(|| "Failed to create transaction manager")?;
-    let bootstrap_transaction = transaction_manager.bootstrap();
-
-    let catalog = Catalog::new(
-        Arc::clone(&buffer_manager),
-        config.new,
-        &bootstrap_transaction,
-    )
-    .with_context(|| "Failed to create catalog")?;
-
-    if config.new {
-        bootstrap_transaction
-            .commit()
-            .with_context(|| "Failed to commit bootstrap transaction")?;
-    }
+    let database = Database::open(config.data, config.pool_size, config.new)?;
 
     let listener = TcpListener::bind(("localhost", config.port))?;
+    let settings = ExecutorSettings {
+        work_mem: config.work_mem * 1024,
+        max_recursive_iterations: config.max_recursive_iterations,
+    };
 
     thread::scope(|scope| {
-        let transaction_manager = &transaction_manager;
-        let catalog = &catalog;
+        let transaction_manager = database.transaction_manager();
+        let catalog = database.catalog();
 
         for stream in listener.incoming() {
-            let buffer_manager = Arc::clone(&buffer_manager);
+            let buffer_manager = Arc::clone(database.buffer_manager());
             match stream {
                 Ok(stream) => {
                     scope.spawn(move || {
-                        match handle_client(stream, catalog, buffer_manager, transaction_manager) {
+                        match handle_client(
+                            stream,
+                            catalog,
+                            buffer_manager,
+                            transaction_manager,
+                            settings,
+                        ) {
                             Ok(()) => (),
                             Err(e) => println!("Failed to handle client. Cause: {e}"),
                         }
@@ -1,11 +1,24 @@
-use crate::analyzer::logical_plan::{LogicalExpr, LogicalPlan, Query, TableReference};
+use crate::analyzer::logical_plan::{
+    LogicalExpr, LogicalPlan, Query, SetOperation, TableReference,
+};
+use crate::parser::ast::{BinaryOperator, JoinType, UnaryOperator};
 
 fn find_all_referenced_columns(expr: &LogicalExpr) -> Vec<Vec<String>> {
+    find_referenced_columns(expr, false)
+}
+
+/// Returns the columns of the enclosing queries, which are referenced by a correlated subquery
+fn find_all_outer_columns(expr: &LogicalExpr) -> Vec<Vec<String>> {
+    find_referenced_columns(expr, true)
+}
+
+fn find_referenced_columns(expr: &LogicalExpr, outer: bool) -> Vec<Vec<String>> {
     let mut columns = vec![];
     let mut to_visit = vec![expr];
     while let Some(expr) = to_visit.pop() {
         match expr {
-            LogicalExpr::Column(path) => columns.push(path.clone()),
+            LogicalExpr::Column(path) if !outer => columns.push(path.clone()),
+            LogicalExpr::OuterColumn(path) if outer => columns.push(path.clone()),
             LogicalExpr::Unary { op: _, expr } => to_visit.push(expr),
             LogicalExpr::Binary { left, op: _, right } => {
                 to_visit.push(left);
@@ -13,6 +26,34 @@ fn find_all_referenced_columns(expr: &LogicalExpr) -> Vec<Vec<String>> {
             }
             LogicalExpr::IsNull(child) => to_visit.push(child),
             LogicalExpr::IsNotNull(child) => to_visit.push(child),
+            LogicalExpr::Extract { field: _, expr } => to_visit.push(expr),
+            LogicalExpr::Cast {
+                expr,
+                type_id: _,
+                text_length: _,
+            } => to_visit.push(expr),
+            LogicalExpr::Case {
+                operand: _,
+                conditions: _,
+                else_result: _,
+            } => to_visit.extend(expr.case_children()),
+            LogicalExpr::ScalarFunction(_, args) => to_visit.extend(args),
+            LogicalExpr::Array(elements) => to_visit.extend(elements),
+            LogicalExpr::Subscript { expr, index } => {
+                to_visit.push(expr);
+                to_visit.push(index);
+            }
+            LogicalExpr::AnyComparison { left, op: _, array } => {
+                to_visit.push(left);
+                to_visit.push(array);
+            }
+            // the columns within subqueries are not visited, correlated subqueries which
+            // can't be rewritten to joins are rejected by the planner anyway
+            LogicalExpr::InSubquery {
+                expr,
+                query: _,
+                negated: _,
+            } => to_visit.push(expr),
             _ => (),
         }
     }
@@ -20,6 +61,94 @@ fn find_all_referenced_columns(expr: &LogicalExpr) -> Vec<Vec<String>> {
     columns
 }
 
+/// Replaces the references to outer columns with regular column references, used once the
+/// correlated subquery has been joined with the outer query
+fn convert_outer_columns(expr: &mut LogicalExpr) {
+    let mut to_visit = vec![expr];
+    while let Some(expr) = to_visit.pop() {
+        match expr {
+            LogicalExpr::OuterColumn(path) => *expr = LogicalExpr::Column(std::mem::take(path)),
+            LogicalExpr::Unary { op: _, expr } => to_visit.push(expr),
+            LogicalExpr::Binary { left, op: _, right } => {
+                to_visit.push(left);
+                to_visit.push(right);
+            }
+            LogicalExpr::IsNull(child) => to_visit.push(child),
+            LogicalExpr::IsNotNull(child) => to_visit.push(child),
+            LogicalExpr::Extract { field: _, expr } => to_visit.push(expr),
+            LogicalExpr::Cast {
+                expr,
+                type_id: _,
+                text_length: _,
+            } => to_visit.push(expr),
+            LogicalExpr::Case {
+                operand,
+                conditions,
+                else_result,
+            } => {
+                to_visit.extend(operand.as_deref_mut());
+                for (condition, result) in conditions {
+                    to_visit.push(condition);
+                    to_visit.push(result);
+                }
+                to_visit.extend(else_result.as_deref_mut());
+            }
+            LogicalExpr::ScalarFunction(_, args) => to_visit.extend(args),
+            LogicalExpr::Array(elements) => to_visit.extend(elements),
+            LogicalExpr::Subscript { expr, index } => {
+                to_visit.push(expr);
+                to_visit.push(index);
+            }
+            LogicalExpr::AnyComparison { left, op: _, array } => {
+                to_visit.push(left);
+                to_visit.push(array);
+            }
+            LogicalExpr::InSubquery {
+                expr,
+                query: _,
+                negated: _,
+            } => to_visit.push(expr),
+            _ => (),
+        }
+    }
+}
+
+/// Collects the names of the tables and table functions of a table reference
+fn collect_table_names<'a>(table_reference: &'a TableReference, names: &mut Vec<&'a str>) {
+    match table_reference {
+        TableReference::EmptyTable => (),
+        TableReference::BaseTable {
+            table_id: _,
+            name,
+            schema: _,
+            filter: _,
+        }
+        | TableReference::TableFunction {
+            func: _,
+            args: _,
+            name,
+            schema: _,
+            filter: _,
+        }
+        | TableReference::Cte {
+            cte_id: _,
+            name,
+            schema: _,
+            filter: _,
+        } => names.push(name),
+        TableReference::Join {
+            left,
+            right,
+            join_type: _,
+            on: _,
+            using: _,
+        } => {
+            collect_table_names(left, names);
+            collect_table_names(right, names);
+        }
+    }
+}
+
 fn count_referenced_columns(table_reference: &TableReference, columns: &[Vec<String>]) -> usize {
     match table_reference {
         TableReference::EmptyTable => 0,
@@ -28,15 +157,29 @@ fn count_referenced_columns(table_reference: &TableReference, columns: &[Vec<Str
             name,
             schema: _,
             filter: _,
+        }
+        | TableReference::TableFunction {
+            func: _,
+            args: _,
+            name,
+            schema: _,
+            filter: _,
+        }
+        | TableReference::Cte {
+            cte_id: _,
+            name,
+            schema: _,
+            filter: _,
         } => columns
             .iter()
-            .filter(|col| col.get(0).unwrap() == name)
+            .filter(|col| col.first().unwrap() == name)
             .count(),
         TableReference::Join {
             left,
             right,
             join_type: _,
             on: _,
+            using: _,
         } => count_referenced_columns(left, columns) + count_referenced_columns(right, columns),
     }
 }
@@ -65,21 +208,41 @@ fn push_down_filter(
             name: _,
             schema: _,
             filter,
+        }
+        | TableReference::TableFunction {
+            func: _,
+            args: _,
+            name: _,
+            schema: _,
+            filter,
+        }
+        | TableReference::Cte {
+            cte_id: _,
+            name: _,
+            schema: _,
+            filter,
         } => {
             filter.push(expr);
         }
         TableReference::Join {
             left,
             right,
-            join_type: _,
+            join_type,
             on,
+            using: _,
         } => {
+            // the filter must not remove the NULL extended rows of outer joins, so it can only
+            // be pushed down to the preserved side
             let all_left = all_columns_match_table_reference(&*left, &columns);
             let all_right = all_columns_match_table_reference(&*right, &columns);
-            if all_left {
-                push_down_filter(&mut *left, expr);
-            } else if all_right {
-                push_down_filter(&mut *right, expr);
+            let preserves_left = matches!(join_type, JoinType::Left | JoinType::Full);
+            let preserves_right = matches!(join_type, JoinType::Right | JoinType::Full);
+            if all_left && !preserves_right {
+                return push_down_filter(&mut *left, expr);
+            } else if all_right && !preserves_left && !join_type.returns_left_only() {
+                return push_down_filter(&mut *right, expr);
+            } else if join_type.is_outer() {
+                return Some(expr);
             } else {
                 on.push(expr);
             }
@@ -90,7 +253,158 @@ fn push_down_filter(
     None
 }
 
+/// Tries to rewrite a condition of the WHERE clause with a correlated EXISTS or IN subquery
+/// into a semi join, or into an anti join if it is negated. The subquery is then executed
+/// once, instead of once per row of the outer query.
+/// Returns None if it was successful, else returns the very same expression back
+fn decorrelate_subquery(from: &mut TableReference, expr: LogicalExpr) -> Option<LogicalExpr> {
+    let (query, in_expr, negated) = match expr {
+        LogicalExpr::Exists(query) => (query, None, false),
+        LogicalExpr::Unary {
+            op: UnaryOperator::Not,
+            expr,
+        } if matches!(*expr, LogicalExpr::Exists(_)) => match *expr {
+            LogicalExpr::Exists(query) => (query, None, true),
+            _ => unreachable!(),
+        },
+        LogicalExpr::InSubquery {
+            expr,
+            query,
+            negated,
+        } => (query, Some(expr), negated),
+        expr => return Some(expr),
+    };
+    let restore = |query: Box<Query>, in_expr: Option<Box<LogicalExpr>>| match in_expr {
+        Some(expr) => LogicalExpr::InSubquery {
+            expr,
+            query,
+            negated,
+        },
+        None if negated => LogicalExpr::Unary {
+            op: UnaryOperator::Not,
+            expr: Box::new(LogicalExpr::Exists(query)),
+        },
+        None => LogicalExpr::Exists(query),
+    };
+
+    // only the rows of the subquery are joined, so it must not compute anything on top of them
+    let is_simple = query.ctes.is_empty()
+        && query.values.is_empty()
+        && query.group_by.is_empty()
+        && query.having.is_empty()
+        && query.order_by.is_empty()
+        && query.distinct.is_empty()
+        && query.limit.is_none()
+        && query.offset.is_none()
+        && query.set_operation.is_none()
+        && !query.projections.iter().any(|expr| expr.has_aggregation())
+        && !query
+            .projections
+            .iter()
+            .any(|expr| expr.has_window_function());
+    let outer_columns = query
+        .filter
+        .iter()
+        .chain(query.projections.iter())
+        .flat_map(find_all_outer_columns)
+        .collect::<Vec<_>>();
+    // the columns of both inputs are referenced by name, so they must not be ambiguous
+    let mut outer_names = vec![];
+    collect_table_names(from, &mut outer_names);
+    let mut inner_names = vec![];
+    collect_table_names(&query.from, &mut inner_names);
+    let has_ambiguous_names = inner_names.iter().any(|name| outer_names.contains(name));
+    if !is_simple
+        || outer_columns.is_empty()
+        || !all_columns_match_table_reference(from, &outer_columns)
+        || has_ambiguous_names
+    {
+        return Some(restore(query, in_expr));
+    }
+
+    let Query {
+        ctes: _,
+        values: _,
+        from: inner_from,
+        mut projections,
+        filter,
+        group_by: _,
+        having: _,
+        order_by: _,
+        distinct: _,
+        limit: _,
+        offset: _,
+        set_operation: _,
+        output_schema,
+    } = *query;
+    let (mut on, inner_filter): (Vec<_>, Vec<_>) = filter
+        .into_iter()
+        .partition(|expr| !find_all_outer_columns(expr).is_empty());
+    on.iter_mut().for_each(convert_outer_columns);
+
+    // the uncorrelated conditions are pushed down into the subquery's tables
+    let inner_query = push_down_query_filters(Query {
+        values: vec![],
+        from: inner_from,
+        projections: vec![],
+        filter: inner_filter,
+        group_by: vec![],
+        having: vec![],
+        order_by: vec![],
+        distinct: vec![],
+        limit: None,
+        offset: None,
+        ctes: vec![],
+        set_operation: None,
+        output_schema,
+    });
+    on.extend(inner_query.filter);
+
+    if let Some(in_expr) = in_expr {
+        let mut projection = projections.remove(0);
+        convert_outer_columns(&mut projection);
+        let comparison = LogicalExpr::Binary {
+            left: in_expr,
+            op: BinaryOperator::Eq,
+            right: Box::new(projection),
+        };
+        // x NOT IN (...) is not true as soon as a comparison is NULL, so NULL comparisons
+        // have to exclude the row just like matches. This is the NULL-aware anti join
+        if negated {
+            on.push(LogicalExpr::Binary {
+                left: Box::new(comparison),
+                op: BinaryOperator::IsDistinctFrom,
+                right: Box::new(LogicalExpr::Boolean(false)),
+            });
+        } else {
+            on.push(comparison);
+        }
+    }
+
+    let left = std::mem::replace(from, TableReference::EmptyTable);
+    *from = TableReference::Join {
+        left: Box::new(left),
+        right: Box::new(inner_query.from),
+        join_type: if negated {
+            JoinType::Anti
+        } else {
+            JoinType::Semi
+        },
+        on,
+        using: vec![],
+    };
+    None
+}
+
 fn push_down_query_filters(mut query: Query) -> Query {
+    let mut filters = vec![];
+    for filter in query.filter {
+        if let Some(expr) = decorrelate_subquery(&mut query.from, filter) {
+            filters.push(expr);
+        }
+    }
+    query.filter = filters;
+
     let mut filters = vec![];
     for filter in query.filter {
         if let Some(expr) = push_down_filter(&mut query.from, filter) {
@@ -98,6 +412,33 @@ fn push_down_query_filters(mut query: Query) -> Query {
         }
     }
     query.filter = filters;
+
+    query.ctes = query
+        .ctes
+        .into_iter()
+        .map(|mut cte| {
+            cte.query = push_down_query_filters(cte.query);
+            cte
+        })
+        .collect();
+
+    // the inputs of a set operation are optimized independently
+    query.set_operation = query.set_operation.map(|set_operation| {
+        let SetOperation {
+            op,
+            all,
+            left,
+            right,
+            working_table,
+        } = *set_operation;
+        Box::new(SetOperation {
+            op,
+            all,
+            left: push_down_query_filters(left),
+            right: push_down_query_filters(right),
+            working_table,
+        })
+    });
     query
 }
 
@@ -116,7 +457,7 @@ mod tests {
     use super::optimize;
     use crate::analyzer::logical_plan::{LogicalExpr, LogicalPlan, Query, TableReference};
     use crate::catalog::schema::Schema;
-    use crate::parser::ast::{BinaryOperator, JoinType};
+    use crate::parser::ast::{BinaryOperator, JoinType, UnaryOperator};
 
     #[test]
     fn can_push_down_cross_joins() {
@@ -137,6 +478,7 @@ mod tests {
                 }),
                 join_type: JoinType::Inner,
                 on: vec![],
+                using: vec![],
             },
             projections: vec![],
             filter: vec![
@@ -160,7 +502,15 @@ mod tests {
                     right: Box::new(LogicalExpr::Integer(3)),
                 },
             ],
+            ctes: vec![],
+            set_operation: None,
             output_schema: Schema::new(vec![]),
+            group_by: vec![],
+            having: vec![],
+            order_by: vec![],
+            distinct: vec![],
+            limit: None,
+            offset: None,
         });
 
         let optimized_plan = optimize(plan);
@@ -198,12 +548,97 @@ mod tests {
                         "table_a_id".to_owned(),
                     ])),
                 }],
+                using: vec![],
             },
             projections: vec![],
             filter: vec![],
+            ctes: vec![],
+            set_operation: None,
             output_schema: Schema::new(vec![]),
+            group_by: vec![],
+            having: vec![],
+            order_by: vec![],
+            distinct: vec![],
+            limit: None,
+            offset: None,
         });
 
         assert_eq!(optimized_plan, expected_plan);
     }
+
+    #[test]
+    fn can_decorrelate_exists_subqueries() {
+        let table = |table_id, name: &str, filter| TableReference::BaseTable {
+            table_id,
+            name: name.to_owned(),
+            schema: Schema::new(vec![]),
+            filter,
+        };
+        let query = |from, filter| Query {
+            values: vec![],
+            from,
+            projections: vec![],
+            filter,
+            ctes: vec![],
+            set_operation: None,
+            output_schema: Schema::new(vec![]),
+            group_by: vec![],
+            having: vec![],
+            order_by: vec![],
+            distinct: vec![],
+            limit: None,
+            offset: None,
+        };
+        let correlation = |outer_column| LogicalExpr::Binary {
+            left: Box::new(LogicalExpr::Column(vec![
+                "table_b".to_owned(),
+                "table_a_id".to_owned(),
+            ])),
+            op: BinaryOperator::Eq,
+            right: Box::new(outer_column),
+        };
+        let inner_condition = LogicalExpr::Binary {
+            left: Box::new(LogicalExpr::Column(vec![
+                "table_b".to_owned(),
+                "count".to_owned(),
+            ])),
+            op: BinaryOperator::Greater,
+            right: Box::new(LogicalExpr::Integer(3)),
+        };
+
+        let subquery = query(
+            table(2, "table_b", vec![]),
+            vec![
+                correlation(LogicalExpr::OuterColumn(vec![
+                    "table_a".to_owned(),
+                    "id".to_owned(),
+                ])),
+                inner_condition.clone(),
+            ],
+        );
+        let plan = LogicalPlan::Select(query(
+            table(1, "table_a", vec![]),
+            vec![LogicalExpr::Unary {
+                op: UnaryOperator::Not,
+                expr: Box::new(LogicalExpr::Exists(Box::new(subquery))),
+            }],
+        ));
+
+        let optimized_plan = optimize(plan);
+        let expected_plan = LogicalPlan::Select(query(
+            TableReference::Join {
+                left: Box::new(table(1, "table_a", vec![])),
+                right: Box::new(table(2, "table_b", vec![inner_condition])),
+                join_type: JoinType::Anti,
+                on: vec![correlation(LogicalExpr::Column(vec![
+                    "table_a".to_owned(),
+                    "id".to_owned(),
+                ]))],
+                using: vec![],
+            },
+            vec![],
+        ));
+
+        assert_eq!(optimized_plan, expected_plan);
+    }
 }
@@ -1,13 +1,50 @@
 use std::collections::{HashMap, VecDeque};
 use std::fmt::Display;
 
+use anyhow::Result;
+
 use crate::concurrency::IsolationLevel;
+use crate::tuple::datetime::DateTimeField;
 
 #[derive(Debug, PartialEq)]
 pub enum DataType {
     Integer,
     Text,
+    /// VARCHAR(n)
+    Varchar(u8),
+    /// CHAR(n)
+    Char(u8),
     Boolean,
+    Date,
+    Time,
+    Timestamp,
+    Interval,
+    Bytea,
+    Uuid,
+    /// JSON and JSONB, both are stored in a binary form
+    Json,
+    /// a one-dimensional array, e.g. INTEGER[]
+    Array(Box<DataType>),
+}
+
+impl Display for DataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Integer => write!(f, "INTEGER"),
+            Self::Text => write!(f, "TEXT"),
+            Self::Varchar(len) => write!(f, "VARCHAR({})", len),
+            Self::Char(len) => write!(f, "CHAR({})", len),
+            Self::Boolean => write!(f, "BOOLEAN"),
+            Self::Date => write!(f, "DATE"),
+            Self::Time => write!(f, "TIME"),
+            Self::Timestamp => write!(f, "TIMESTAMP"),
+            Self::Interval => write!(f, "INTERVAL"),
+            Self::Bytea => write!(f, "BYTEA"),
+            Self::Uuid => write!(f, "UUID"),
+            Self::Json => write!(f, "JSON"),
+            Self::Array(element_type) => write!(f, "{}[]", element_type),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -18,10 +55,11 @@ pub struct ColumnDefinition {
     pub not_null: bool,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum UnaryOperator {
     Plus,
     Minus,
+    Not,
 }
 
 impl Display for UnaryOperator {
@@ -29,6 +67,7 @@ impl Display for UnaryOperator {
         match self {
             Self::Plus => write!(f, "+"),
             Self::Minus => write!(f, "-"),
+            Self::Not => write!(f, "NOT "),
         }
     }
 }
@@ -48,6 +87,23 @@ pub enum BinaryOperator {
     NotEq,
     And,
     Or,
+    /// like `<>`, but treats NULL as a comparable value
+    IsDistinctFrom,
+    /// like `=`, but treats NULL as a comparable value
+    IsNotDistinctFrom,
+    /// `->`, returns a JSON object field or array element
+    JsonGet,
+    /// `->>`, like `->` but returns text
+    JsonGetText,
+    /// `@>`
+    JsonContains,
+    /// `||`, concatenates text
+    Concat,
+    /// LIKE, matches a pattern with `%` for any sequence of characters and `_` for a single
+    /// character, which can be escaped with a backslash
+    Like,
+    /// ILIKE, the case insensitive LIKE
+    ILike,
 }
 
 impl Display for BinaryOperator {
@@ -66,6 +122,14 @@ impl Display for BinaryOperator {
             Self::NotEq => write!(f, "<>"),
             Self::And => write!(f, "AND"),
             Self::Or => write!(f, "OR"),
+            Self::IsDistinctFrom => write!(f, "IS DISTINCT FROM"),
+            Self::IsNotDistinctFrom => write!(f, "IS NOT DISTINCT FROM"),
+            Self::JsonGet => write!(f, "->"),
+            Self::JsonGetText => write!(f, "->>"),
+            Self::JsonContains => write!(f, "@>"),
+            Self::Concat => write!(f, "||"),
+            Self::Like => write!(f, "LIKE"),
+            Self::ILike => write!(f, "ILIKE"),
         }
     }
 }
@@ -78,6 +142,11 @@ pub enum ExprNode {
     Number(String),
     String(String),
     Boolean(bool),
+    /// a literal of a given type, e.g. DATE '2024-01-31'
+    TypedString {
+        data_type: DataType,
+        value: String,
+    },
     // an expression in parenthesis, e.g. (1+1)
     Grouping(Box<ExprNode>),
     Binary {
@@ -91,11 +160,88 @@ pub enum ExprNode {
     },
     IsNull(Box<ExprNode>),
     IsNotNull(Box<ExprNode>),
+    /// tests a boolean against a truth value, e.g. expr IS NOT TRUE.
+    /// A value of None stands for UNKNOWN
+    IsTruthValue {
+        expr: Box<ExprNode>,
+        value: Option<bool>,
+        negated: bool,
+    },
+    /// an array constructor, e.g. ARRAY[1, 2, 3]
+    Array(Vec<ExprNode>),
+    /// an array element, e.g. col_a[1]
+    Subscript {
+        expr: Box<ExprNode>,
+        index: Box<ExprNode>,
+    },
+    /// a comparison with the elements of an array, e.g. col_a = ANY(col_b)
+    AnyComparison {
+        left: Box<ExprNode>,
+        op: BinaryOperator,
+        array: Box<ExprNode>,
+    },
+    /// CASE [operand] WHEN condition THEN result [...] [ELSE else_result] END. With an operand,
+    /// the WHEN values are compared to it, e.g. CASE col_a WHEN 1 THEN 'one' END
+    Case {
+        operand: Option<Box<ExprNode>>,
+        conditions: Vec<(ExprNode, ExprNode)>,
+        else_result: Option<Box<ExprNode>>,
+    },
+    /// CAST(expr AS data_type) or expr::data_type
+    Cast {
+        expr: Box<ExprNode>,
+        data_type: DataType,
+    },
     /// a function call, e.g. min(col_a)
     FunctionCall {
         name: String,
+        args: Vec<ExprNode>,
+        /// only aggregate distinct values, e.g. count(DISTINCT col_a)
+        distinct: bool,
+        /// called with `*` instead of arguments, e.g. count(*)
+        wildcard: bool,
+        /// the window of a window function, e.g. rank() OVER (ORDER BY col_a)
+        over: Option<WindowSpec>,
+    },
+    /// EXTRACT(field FROM expr)
+    Extract {
+        field: DateTimeField,
+        expr: Box<ExprNode>,
+    },
+    /// a subquery returning a single value, e.g. (SELECT max(col_a) FROM table_a)
+    Subquery(Box<SelectStatement>),
+    /// EXISTS (SELECT ...)
+    Exists(Box<SelectStatement>),
+    /// expr [NOT] IN (SELECT ...)
+    InSubquery {
         expr: Box<ExprNode>,
+        subquery: Box<SelectStatement>,
+        negated: bool,
     },
+    /// expr [NOT] IN (value, ...)
+    InList {
+        expr: Box<ExprNode>,
+        list: Vec<ExprNode>,
+        negated: bool,
+    },
+    /// expr [NOT] BETWEEN low AND high
+    Between {
+        expr: Box<ExprNode>,
+        low: Box<ExprNode>,
+        high: Box<ExprNode>,
+        negated: bool,
+    },
+    /// expr [NOT] LIKE pattern [ESCAPE escape], or ILIKE if case_insensitive
+    Like {
+        expr: Box<ExprNode>,
+        pattern: Box<ExprNode>,
+        escape: Option<Box<ExprNode>>,
+        case_insensitive: bool,
+        negated: bool,
+    },
+    CurrentDate,
+    /// CURRENT_TIMESTAMP or now()
+    CurrentTimestamp,
     Null,
 }
 
@@ -107,31 +253,170 @@ impl Display for ExprNode {
             Self::Number(num) => write!(f, "{}", num),
             Self::String(s) => write!(f, "'{}'", s),
             Self::Boolean(b) => write!(f, "{}", b),
+            Self::TypedString { data_type, value } => write!(f, "{} '{}'", data_type, value),
             Self::Grouping(expr) => write!(f, "({})", expr),
             Self::Binary { left, op, right } => write!(f, "{} {} {}", left, op, right),
             Self::Unary { op, expr } => write!(f, "{}{}", op, expr),
             Self::IsNull(expr) => write!(f, "{} IS NULL", expr),
             Self::IsNotNull(expr) => write!(f, "{} IS NOT NULL", expr),
-            Self::FunctionCall { name, expr } => write!(f, "{}({})", name, expr),
+            Self::IsTruthValue {
+                expr,
+                value,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                let value = match value {
+                    Some(true) => "TRUE",
+                    Some(false) => "FALSE",
+                    None => "UNKNOWN",
+                };
+                write!(f, "{} IS {}{}", expr, not, value)
+            }
+            Self::Array(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "ARRAY[{}]", elements)
+            }
+            Self::Subscript { expr, index } => write!(f, "{}[{}]", expr, index),
+            Self::AnyComparison { left, op, array } => {
+                write!(f, "{} {} ANY({})", left, op, array)
+            }
+            Self::Case {
+                operand,
+                conditions,
+                else_result,
+            } => {
+                f.write_str("CASE")?;
+                if let Some(operand) = operand {
+                    write!(f, " {}", operand)?;
+                }
+                for (condition, result) in conditions {
+                    write!(f, " WHEN {} THEN {}", condition, result)?;
+                }
+                if let Some(else_result) = else_result {
+                    write!(f, " ELSE {}", else_result)?;
+                }
+                f.write_str(" END")
+            }
+            Self::Cast { expr, data_type } => write!(f, "CAST({} AS {})", expr, data_type),
+            Self::FunctionCall {
+                name,
+                args,
+                distinct,
+                wildcard,
+                over,
+            } => {
+                let args = if *wildcard {
+                    "*".to_owned()
+                } else {
+                    args.iter()
+                        .map(|arg| arg.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                let distinct = if *distinct { "DISTINCT " } else { "" };
+                write!(f, "{}({}{})", name, distinct, args)?;
+                if let Some(over) = over {
+                    write!(f, " OVER ({})", over)?;
+                }
+                Ok(())
+            }
+            Self::Extract { field, expr } => write!(f, "extract({} from {})", field, expr),
+            Self::Subquery(_) => write!(f, "(subquery)"),
+            Self::Exists(_) => write!(f, "EXISTS(subquery)"),
+            Self::InSubquery {
+                expr,
+                subquery: _,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{} {}IN (subquery)", expr, not)
+            }
+            Self::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                let list = list
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "{} {}IN ({})", expr, not, list)
+            }
+            Self::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{} {}BETWEEN {} AND {}", expr, not, low, high)
+            }
+            Self::Like {
+                expr,
+                pattern,
+                escape,
+                case_insensitive,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                let like = if *case_insensitive { "ILIKE" } else { "LIKE" };
+                write!(f, "{} {}{} {}", expr, not, like, pattern)?;
+                if let Some(escape) = escape {
+                    write!(f, " ESCAPE {}", escape)?;
+                }
+                Ok(())
+            }
+            Self::CurrentDate => write!(f, "current_date"),
+            Self::CurrentTimestamp => write!(f, "current_timestamp"),
             Self::Null => write!(f, "NULL"),
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum JoinType {
     Inner,
     Left,
     Right,
+    Full,
+    /// returns the left rows with at least one matching right row, only the left columns are
+    /// returned. It can't be written in SQL, but EXISTS and IN subqueries are rewritten to it
+    Semi,
+    /// returns the left rows without any matching right row, used for NOT EXISTS and NOT IN
+    Anti,
 }
 
 impl JoinType {
     pub fn is_outer(&self) -> bool {
         match self {
-            Self::Inner => false,
-            Self::Left | Self::Right => true,
+            Self::Inner | Self::Semi | Self::Anti => false,
+            Self::Left | Self::Right | Self::Full => true,
         }
     }
+
+    /// Returns whether only the columns of the left input are returned
+    pub fn returns_left_only(&self) -> bool {
+        match self {
+            Self::Semi | Self::Anti => true,
+            Self::Inner | Self::Left | Self::Right | Self::Full => false,
+        }
+    }
+}
+
+/// How the rows of the joined tables are matched
+#[derive(Debug, PartialEq)]
+pub enum JoinConstraint {
+    On(ExprNode),
+    /// the columns must be equal, every pair of columns is merged into a single column
+    Using(Vec<String>),
+    /// USING all the columns with the same name in both tables
+    Natural,
 }
 
 #[derive(Debug, PartialEq)]
@@ -140,6 +425,12 @@ pub enum TableNode {
         name: String,
         alias: Option<String>,
     },
+    /// a function returning a set of rows, e.g. FROM unnest(ARRAY[1, 2])
+    TableFunction {
+        name: String,
+        args: Vec<ExprNode>,
+        alias: Option<String>,
+    },
     CrossJoin {
         left: Box<TableNode>,
         right: Box<TableNode>,
@@ -148,7 +439,7 @@ pub enum TableNode {
         left: Box<TableNode>,
         right: Box<TableNode>,
         join_type: JoinType,
-        on: ExprNode,
+        constraint: JoinConstraint,
     },
 }
 
@@ -189,8 +480,225 @@ pub enum Statement {
 
 #[derive(Debug, PartialEq)]
 pub struct SelectStatement {
+    /// WITH clause
+    pub with: Vec<CommonTableExpression>,
     pub values: Option<Vec<Vec<ExprNode>>>,
+    /// UNION, INTERSECT or EXCEPT of two queries. ORDER BY, LIMIT and OFFSET apply to the
+    /// combined rows, all other clauses are empty
+    pub set_operation: Option<SetOperation>,
+    /// SELECT DISTINCT or DISTINCT ON
+    pub distinct: Option<Distinct>,
     pub projections: Vec<Projection>,
     pub from: VecDeque<TableNode>,
     pub filter: Option<ExprNode>,
+    /// GROUP BY clause
+    pub group_by: Vec<ExprNode>,
+    /// HAVING clause
+    pub having: Option<ExprNode>,
+    /// ORDER BY clause
+    pub order_by: Vec<OrderByExpr>,
+    /// LIMIT or FETCH FIRST clause
+    pub limit: Option<ExprNode>,
+    /// OFFSET clause
+    pub offset: Option<ExprNode>,
+}
+
+/// A named query of a WITH clause, e.g. `WITH name (col_a, col_b) AS (SELECT ...)`
+#[derive(Debug, PartialEq)]
+pub struct CommonTableExpression {
+    pub name: String,
+    /// renames the output columns of the query, may be shorter than its output
+    pub columns: Vec<String>,
+    pub query: SelectStatement,
+    /// whether the WITH clause is RECURSIVE, so that the query can reference itself
+    pub recursive: bool,
+}
+
+impl SelectStatement {
+    pub fn from_set_operation(
+        op: SetOperator,
+        all: bool,
+        left: SelectStatement,
+        right: SelectStatement,
+    ) -> Self {
+        Self {
+            with: vec![],
+            values: None,
+            set_operation: Some(SetOperation {
+                op,
+                all,
+                left: Box::new(left),
+                right: Box::new(right),
+            }),
+            distinct: None,
+            projections: vec![],
+            from: VecDeque::new(),
+            filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SetOperation {
+    pub op: SetOperator,
+    /// whether duplicate rows are kept, e.g. UNION ALL
+    pub all: bool,
+    pub left: Box<SelectStatement>,
+    pub right: Box<SelectStatement>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SetOperator {
+    Union,
+    Intersect,
+    Except,
+}
+
+impl Display for SetOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Union => write!(f, "UNION"),
+            Self::Intersect => write!(f, "INTERSECT"),
+            Self::Except => write!(f, "EXCEPT"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Distinct {
+    /// SELECT DISTINCT, which removes duplicate rows
+    AllColumns,
+    /// SELECT DISTINCT ON (expressions), which keeps the first row for each value of the
+    /// expressions
+    On(Vec<ExprNode>),
+}
+
+/// An expression of the ORDER BY clause, e.g. `col_a DESC NULLS LAST`
+#[derive(Debug, PartialEq)]
+pub struct OrderByExpr {
+    pub expr: ExprNode,
+    /// ASC or DESC, if specified
+    pub asc: Option<bool>,
+    /// NULLS FIRST or NULLS LAST, if specified
+    pub nulls_first: Option<bool>,
+}
+
+impl Display for OrderByExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expr)?;
+        match self.asc {
+            Some(true) => write!(f, " ASC")?,
+            Some(false) => write!(f, " DESC")?,
+            None => (),
+        }
+        match self.nulls_first {
+            Some(true) => write!(f, " NULLS FIRST"),
+            Some(false) => write!(f, " NULLS LAST"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The window of a window function, e.g. `OVER (PARTITION BY col_a ORDER BY col_b)`
+#[derive(Debug, PartialEq)]
+pub struct WindowSpec {
+    pub partition_by: Vec<ExprNode>,
+    pub order_by: Vec<OrderByExpr>,
+    /// ROWS clause, if specified
+    pub frame: Option<WindowFrame<Box<ExprNode>>>,
+}
+
+impl Display for WindowSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut clauses = vec![];
+        if !self.partition_by.is_empty() {
+            let partition_by = self
+                .partition_by
+                .iter()
+                .map(|expr| expr.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            clauses.push(format!("PARTITION BY {}", partition_by));
+        }
+        if !self.order_by.is_empty() {
+            let order_by = self
+                .order_by
+                .iter()
+                .map(|order| order.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            clauses.push(format!("ORDER BY {}", order_by));
+        }
+        if let Some(frame) = &self.frame {
+            clauses.push(frame.to_string());
+        }
+        write!(f, "{}", clauses.join(" "))
+    }
+}
+
+/// The rows of a partition, which a window function is evaluated on, relative to the current
+/// row, e.g. `ROWS BETWEEN 2 PRECEDING AND CURRENT ROW`. The offsets are expressions, until
+/// the planner evaluates them to row counts
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowFrame<T> {
+    pub start: FrameBound<T>,
+    pub end: FrameBound<T>,
+}
+
+impl<T> WindowFrame<T> {
+    /// Transforms the offsets of both bounds
+    pub fn try_map<U>(self, mut f: impl FnMut(T) -> Result<U>) -> Result<WindowFrame<U>> {
+        Ok(WindowFrame {
+            start: self.start.try_map(&mut f)?,
+            end: self.end.try_map(&mut f)?,
+        })
+    }
+}
+
+impl<T: Display> Display for WindowFrame<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ROWS BETWEEN {} AND {}", self.start, self.end)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameBound<T> {
+    UnboundedPreceding,
+    /// the given number of rows before the current row
+    Preceding(T),
+    CurrentRow,
+    /// the given number of rows after the current row
+    Following(T),
+    UnboundedFollowing,
+}
+
+impl<T> FrameBound<T> {
+    /// Transforms the offset of the bound
+    pub fn try_map<U>(self, f: impl FnOnce(T) -> Result<U>) -> Result<FrameBound<U>> {
+        let bound = match self {
+            Self::UnboundedPreceding => FrameBound::UnboundedPreceding,
+            Self::Preceding(offset) => FrameBound::Preceding(f(offset)?),
+            Self::CurrentRow => FrameBound::CurrentRow,
+            Self::Following(offset) => FrameBound::Following(f(offset)?),
+            Self::UnboundedFollowing => FrameBound::UnboundedFollowing,
+        };
+        Ok(bound)
+    }
+}
+
+impl<T: Display> Display for FrameBound<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnboundedPreceding => write!(f, "UNBOUNDED PRECEDING"),
+            Self::Preceding(offset) => write!(f, "{} PRECEDING", offset),
+            Self::CurrentRow => write!(f, "CURRENT ROW"),
+            Self::Following(offset) => write!(f, "{} FOLLOWING", offset),
+            Self::UnboundedFollowing => write!(f, "UNBOUNDED FOLLOWING"),
+        }
+    }
 }
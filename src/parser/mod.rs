@@ -4,11 +4,13 @@ use std::collections::{HashMap, VecDeque};
 use anyhow::{Error, Result};
 
 use self::ast::{
-    BinaryOperator, ColumnDefinition, DataType, ExprNode, JoinType, Projection, SelectStatement,
-    Statement, TableNode, UnaryOperator,
+    BinaryOperator, ColumnDefinition, CommonTableExpression, DataType, Distinct, ExprNode,
+    FrameBound, JoinConstraint, JoinType, OrderByExpr, Projection, SelectStatement, SetOperator,
+    Statement, TableNode, UnaryOperator, WindowFrame, WindowSpec,
 };
 use self::token::{tokenize, Keyword, Token};
 use crate::concurrency::IsolationLevel;
+use crate::tuple::datetime::DateTimeField;
 
 pub mod ast;
 mod token;
@@ -17,10 +19,16 @@ mod token;
 pub(in self::super) mod precedence {
     pub const OR: u8 = 1;
     pub const AND: u8 = 2;
+    pub const NOT: u8 = 3;
     pub const IS: u8 = 4;
     pub const COMPARISON: u8 = 5;
+    pub const IN: u8 = 6;
+    /// operators without a special precedence in SQL, like the JSON operators
+    pub const OTHER: u8 = 7;
     pub const PLUS_MINUS: u8 = 8;
     pub const PRODUCT_DIVISION_MODULO: u8 = 9;
+    pub const SUBSCRIPT: u8 = 10;
+    pub const CAST: u8 = 11;
 }
 pub struct Parser {
     tokens: VecDeque<Token>,
@@ -63,20 +71,22 @@ impl Parser {
             }
             _ => false,
         };
-        let statement = match self.next_token() {
-            Token::Keyword(keyword) => match keyword {
-                Keyword::Create => self.parse_create_statement()?,
-                Keyword::Select => Statement::Select(self.parse_select_statement()?),
-                Keyword::Values => Statement::Select(self.parse_values()?),
-                Keyword::Insert => self.parse_insert()?,
-                Keyword::Update => self.parse_update()?,
-                Keyword::Delete => self.parse_delete()?,
-                Keyword::Start => self.parse_start_transaction()?,
-                Keyword::Commit => Statement::Commit,
-                Keyword::Rollback => Statement::Rollback,
-                found => self.wrong_keyword("a statement", found)?,
+        let statement = match self.peek_token() {
+            Token::Keyword(Keyword::Select | Keyword::Values | Keyword::With)
+            | Token::LeftParen => Statement::Select(self.parse_query()?),
+            _ => match self.next_token() {
+                Token::Keyword(keyword) => match keyword {
+                    Keyword::Create => self.parse_create_statement()?,
+                    Keyword::Insert => self.parse_insert()?,
+                    Keyword::Update => self.parse_update()?,
+                    Keyword::Delete => self.parse_delete()?,
+                    Keyword::Start => self.parse_start_transaction()?,
+                    Keyword::Commit => Statement::Commit,
+                    Keyword::Rollback => Statement::Rollback,
+                    found => self.wrong_keyword("a statement", found)?,
+                },
+                found => self.wrong_token("a statement", found)?,
             },
-            found => self.wrong_token("a statement", found)?,
         };
 
         self.expect_end()?;
@@ -189,9 +199,9 @@ impl Parser {
             alias: None,
         };
 
-        let select = match self.next_token() {
-            Token::Keyword(Keyword::Values) => self.parse_values()?,
-            Token::Keyword(Keyword::Select) => self.parse_select_statement()?,
+        let select = match self.peek_token() {
+            Token::Keyword(Keyword::Select | Keyword::Values | Keyword::With)
+            | Token::LeftParen => self.parse_query()?,
             found => {
                 return Err(Error::msg(format!(
                     "Expected a query after `INSERT INTO <table_name>`, but found {:?}",
@@ -225,63 +235,362 @@ impl Parser {
             }
             values.push(current_values);
 
-            match self.next_token() {
-                Token::Comma => continue,
-                Token::Semicolon | Token::End => break,
-                found => self.wrong_token(
-                    "Expected ',' followed by more expressions or end of statement",
-                    found,
-                )?,
+            if self.peek_token() == &Token::Comma {
+                self.next_token();
+            } else {
+                break;
             }
         }
 
         Ok(SelectStatement {
+            with: vec![],
             values: Some(values),
+            distinct: None,
+            set_operation: None,
             projections: vec![],
             from: vec![].into(),
             filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
         })
     }
 
     fn parse_select_statement(&mut self) -> Result<SelectStatement> {
+        let distinct = self.parse_distinct()?;
         let projections = self.parse_projections()?;
 
         let from = self.parse_from()?;
         let filter = self.parse_filter()?;
+        let group_by = self.parse_group_by()?;
+        let having = if self.peek_token() == &Token::Keyword(Keyword::Having) {
+            self.next_token();
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
 
         Ok(SelectStatement {
+            with: vec![],
             values: None,
+            distinct,
+            set_operation: None,
             projections,
             from,
             filter,
+            group_by,
+            having,
+            order_by: vec![],
+            limit: None,
+            offset: None,
         })
     }
 
-    fn parse_filter(&mut self) -> Result<Option<ExprNode>> {
+    /// Parses a SELECT or VALUES query, which can be combined with other queries by UNION,
+    /// INTERSECT and EXCEPT, followed by ORDER BY, LIMIT and OFFSET for the combined rows
+    fn parse_query(&mut self) -> Result<SelectStatement> {
+        let mut with = self.parse_with()?;
+        let mut query = self.parse_intersection()?;
+        loop {
+            let op = match self.peek_token() {
+                Token::Keyword(Keyword::Union) => SetOperator::Union,
+                Token::Keyword(Keyword::Except) => SetOperator::Except,
+                _ => break,
+            };
+            self.next_token();
+            let all = self.parse_set_quantifier();
+            let right = self.parse_intersection()?;
+            query = SelectStatement::from_set_operation(op, all, query, right);
+        }
+
+        let order_by = self.parse_order_by()?;
+        let (limit, offset) = self.parse_limit_and_offset()?;
+        // a parenthesized query can already have these clauses, e.g. (SELECT 1 LIMIT 1)
+        if !order_by.is_empty() {
+            if !query.order_by.is_empty() {
+                return Err(Error::msg("Multiple ORDER BY clauses are not allowed"));
+            }
+            query.order_by = order_by;
+        }
+        if limit.is_some() {
+            if query.limit.is_some() {
+                return Err(Error::msg("Multiple LIMIT clauses are not allowed"));
+            }
+            query.limit = limit;
+        }
+        if offset.is_some() {
+            if query.offset.is_some() {
+                return Err(Error::msg("Multiple OFFSET clauses are not allowed"));
+            }
+            query.offset = offset;
+        }
+        // a parenthesized query can have its own WITH clause, which can reference these CTEs
+        with.append(&mut query.with);
+        query.with = with;
+        Ok(query)
+    }
+
+    /// Parses the optional WITH clause in front of a query
+    fn parse_with(&mut self) -> Result<Vec<CommonTableExpression>> {
+        if self.peek_token() != &Token::Keyword(Keyword::With) {
+            return Ok(vec![]);
+        }
+        self.next_token();
+        let recursive = if self.peek_token() == &Token::Keyword(Keyword::Recursive) {
+            self.next_token();
+            true
+        } else {
+            false
+        };
+
+        let mut ctes = vec![];
+        loop {
+            let name = self.parse_identifier()?;
+            let mut columns = vec![];
+            if self.peek_token() == &Token::LeftParen {
+                self.next_token();
+                loop {
+                    columns.push(self.parse_identifier()?);
+                    match self.next_token() {
+                        Token::Comma => continue,
+                        Token::RightParen => break,
+                        found => self.wrong_token("',' followed by a column name or ')'", found)?,
+                    }
+                }
+            }
+            self.expect(Token::Keyword(Keyword::As))?;
+            let query = self.parse_subquery()?;
+            ctes.push(CommonTableExpression {
+                name,
+                columns,
+                query,
+                recursive,
+            });
+
+            if self.peek_token() == &Token::Comma {
+                self.next_token();
+            } else {
+                break;
+            }
+        }
+        Ok(ctes)
+    }
+
+    /// INTERSECT binds more tightly than UNION and EXCEPT
+    fn parse_intersection(&mut self) -> Result<SelectStatement> {
+        let mut query = self.parse_query_operand()?;
+        while self.peek_token() == &Token::Keyword(Keyword::Intersect) {
+            self.next_token();
+            let all = self.parse_set_quantifier();
+            let right = self.parse_query_operand()?;
+            query = SelectStatement::from_set_operation(SetOperator::Intersect, all, query, right);
+        }
+        Ok(query)
+    }
+
+    fn parse_query_operand(&mut self) -> Result<SelectStatement> {
         match self.next_token() {
-            Token::Keyword(Keyword::Where) => Ok(Some(self.parse_expression()?)),
-            Token::End | Token::Semicolon => Ok(None),
-            found => self.wrong_token("end of statement or WHERE", found),
+            Token::Keyword(Keyword::Select) => self.parse_select_statement(),
+            Token::Keyword(Keyword::Values) => self.parse_values(),
+            Token::LeftParen => {
+                let query = self.parse_query()?;
+                self.expect(Token::RightParen)?;
+                Ok(query)
+            }
+            found => self.wrong_token("SELECT, VALUES or '('", found),
+        }
+    }
+
+    /// Parses ALL or DISTINCT after a set operator, returns whether duplicates are kept
+    fn parse_set_quantifier(&mut self) -> bool {
+        match self.peek_token() {
+            Token::Keyword(Keyword::All) => {
+                self.next_token();
+                true
+            }
+            Token::Keyword(Keyword::Distinct) => {
+                self.next_token();
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Parses DISTINCT or DISTINCT ON (expressions). SELECT ALL is the same as omitting both
+    fn parse_distinct(&mut self) -> Result<Option<Distinct>> {
+        match self.peek_token() {
+            Token::Keyword(Keyword::All) => {
+                self.next_token();
+                return Ok(None);
+            }
+            Token::Keyword(Keyword::Distinct) => self.next_token(),
+            _ => return Ok(None),
+        };
+        if self.peek_token() != &Token::Keyword(Keyword::On) {
+            return Ok(Some(Distinct::AllColumns));
+        }
+        self.next_token();
+
+        self.expect(Token::LeftParen)?;
+        let mut expressions = vec![];
+        loop {
+            expressions.push(self.parse_expression()?);
+            match self.next_token() {
+                Token::Comma => continue,
+                Token::RightParen => break,
+                found => self.wrong_token("',' or ')'", found)?,
+            }
+        }
+        Ok(Some(Distinct::On(expressions)))
+    }
+
+    fn parse_filter(&mut self) -> Result<Option<ExprNode>> {
+        if self.peek_token() == &Token::Keyword(Keyword::Where) {
+            self.next_token();
+            Ok(Some(self.parse_expression()?))
+        } else {
+            Ok(None)
         }
     }
 
+    fn parse_group_by(&mut self) -> Result<Vec<ExprNode>> {
+        let mut group_by = vec![];
+        if !self.peek_keywords_match(&[Keyword::Group, Keyword::By]) {
+            return Ok(group_by);
+        }
+        self.advance(2);
+
+        loop {
+            group_by.push(self.parse_expression()?);
+            if self.peek_token() == &Token::Comma {
+                self.next_token();
+            } else {
+                break;
+            }
+        }
+        Ok(group_by)
+    }
+
+    fn parse_order_by(&mut self) -> Result<Vec<OrderByExpr>> {
+        let mut order_by = vec![];
+        if !self.peek_keywords_match(&[Keyword::Order, Keyword::By]) {
+            return Ok(order_by);
+        }
+        self.advance(2);
+
+        loop {
+            let expr = self.parse_expression()?;
+            let asc = match self.peek_token() {
+                Token::Keyword(Keyword::Asc) => Some(true),
+                Token::Keyword(Keyword::Desc) => Some(false),
+                _ => None,
+            };
+            if asc.is_some() {
+                self.next_token();
+            }
+            let nulls_first = if self.peek_token() == &Token::Keyword(Keyword::Nulls) {
+                self.next_token();
+                match self.next_token() {
+                    Token::Keyword(Keyword::First) => Some(true),
+                    Token::Keyword(Keyword::Last) => Some(false),
+                    found => self.wrong_token("FIRST or LAST", found)?,
+                }
+            } else {
+                None
+            };
+            order_by.push(OrderByExpr {
+                expr,
+                asc,
+                nulls_first,
+            });
+
+            if self.peek_token() == &Token::Comma {
+                self.next_token();
+            } else {
+                break;
+            }
+        }
+        Ok(order_by)
+    }
+
+    /// Parses LIMIT, OFFSET and FETCH FIRST, which can be given in any order
+    fn parse_limit_and_offset(&mut self) -> Result<(Option<ExprNode>, Option<ExprNode>)> {
+        let mut limit = None;
+        let mut offset = None;
+
+        loop {
+            match self.peek_token() {
+                Token::Keyword(Keyword::Limit | Keyword::Fetch) if limit.is_some() => {
+                    return Err(Error::msg("Multiple LIMIT clauses are not allowed"));
+                }
+                Token::Keyword(Keyword::Offset) if offset.is_some() => {
+                    return Err(Error::msg("Multiple OFFSET clauses are not allowed"));
+                }
+                Token::Keyword(Keyword::Limit) => {
+                    self.next_token();
+                    // like in Postgres, LIMIT ALL and LIMIT NULL both mean no limit
+                    limit = if self.peek_token() == &Token::Keyword(Keyword::All) {
+                        self.next_token();
+                        Some(ExprNode::Null)
+                    } else {
+                        Some(self.parse_expression()?)
+                    };
+                }
+                Token::Keyword(Keyword::Offset) => {
+                    self.next_token();
+                    offset = Some(self.parse_expression()?);
+                    if let Token::Keyword(Keyword::Row | Keyword::Rows) = self.peek_token() {
+                        self.next_token();
+                    }
+                }
+                Token::Keyword(Keyword::Fetch) => {
+                    self.next_token();
+                    match self.next_token() {
+                        Token::Keyword(Keyword::First | Keyword::Next) => {}
+                        found => self.wrong_token("FIRST or NEXT", found)?,
+                    }
+                    // FETCH FIRST ROW ONLY returns a single row
+                    limit = match self.peek_token() {
+                        Token::Keyword(Keyword::Row | Keyword::Rows) => {
+                            Some(ExprNode::Number("1".to_owned()))
+                        }
+                        _ => Some(self.parse_expression()?),
+                    };
+                    match self.next_token() {
+                        Token::Keyword(Keyword::Row | Keyword::Rows) => {}
+                        found => self.wrong_token("ROW or ROWS", found)?,
+                    }
+                    match self.next_token() {
+                        Token::Keyword(Keyword::Only) => {}
+                        found => self.wrong_token("ONLY", found)?,
+                    }
+                }
+                _ => break,
+            }
+        }
+        Ok((limit, offset))
+    }
+
     fn parse_from(&mut self) -> Result<VecDeque<TableNode>> {
         let mut tables = VecDeque::new();
 
-        if self.peek_token() == &Token::Semicolon || self.peek_token() == &Token::End {
+        if self.peek_token() != &Token::Keyword(Keyword::From) {
             return Ok(tables);
         }
-
-        self.expect(Token::Keyword(Keyword::From))?;
+        self.next_token();
 
         loop {
-            let mut table = self.parse_table()?;
+            let mut table = self.parse_from_item()?;
             while [
                 Token::Keyword(Keyword::Cross),
                 Token::Keyword(Keyword::Inner),
                 Token::Keyword(Keyword::Join),
                 Token::Keyword(Keyword::Left),
                 Token::Keyword(Keyword::Right),
+                Token::Keyword(Keyword::Full),
+                Token::Keyword(Keyword::Natural),
             ]
             .contains(self.peek_token())
             {
@@ -303,6 +612,31 @@ impl Parser {
 
     fn parse_table(&mut self) -> Result<TableNode> {
         let table_name = self.parse_identifier()?;
+        let alias = self.parse_table_alias()?;
+
+        Ok(TableNode::TableReference {
+            name: table_name,
+            alias,
+        })
+    }
+
+    /// Parses an entry of the FROM clause, which is either a table or a table function
+    fn parse_from_item(&mut self) -> Result<TableNode> {
+        if !matches!(self.peek_token(), Token::Identifier(_))
+            || self.peek_token_ahead(1) != &Token::LeftParen
+        {
+            return self.parse_table();
+        }
+
+        let name = self.parse_identifier()?;
+        let _left_paren = self.next_token();
+        let args = self.parse_function_arguments()?;
+        let alias = self.parse_table_alias()?;
+
+        Ok(TableNode::TableFunction { name, args, alias })
+    }
+
+    fn parse_table_alias(&mut self) -> Result<Option<String>> {
         let alias = if self.peek_token() == &Token::Keyword(Keyword::As) {
             self.next_token();
             Some(self.parse_identifier()?)
@@ -312,20 +646,21 @@ impl Parser {
             None
         };
 
-        Ok(TableNode::TableReference {
-            name: table_name,
-            alias,
-        })
+        Ok(alias)
     }
 
     fn parse_join(&mut self, left: TableNode) -> Result<TableNode> {
+        let natural = self.peek_token() == &Token::Keyword(Keyword::Natural);
+        if natural {
+            self.next_token();
+        }
         let (join_type, is_cross_join) = match *self.peek_token() {
             Token::Keyword(Keyword::Inner) => {
                 self.next_token();
                 (JoinType::Inner, false)
             }
             Token::Keyword(Keyword::Join) => (JoinType::Inner, false),
-            Token::Keyword(Keyword::Cross) => {
+            Token::Keyword(Keyword::Cross) if !natural => {
                 self.next_token();
                 (JoinType::Inner, true)
             }
@@ -343,27 +678,53 @@ impl Parser {
                 }
                 (JoinType::Right, false)
             }
-            _ => unreachable!(),
+            Token::Keyword(Keyword::Full) => {
+                self.next_token();
+                if self.peek_token() == &Token::Keyword(Keyword::Outer) {
+                    self.next_token();
+                }
+                (JoinType::Full, false)
+            }
+            _ => {
+                let found = self.next_token();
+                self.wrong_token("JOIN", found)?
+            }
         };
         self.expect(Token::Keyword(Keyword::Join))?;
 
-        let right = self.parse_table()?;
+        let right = self.parse_from_item()?;
 
         if is_cross_join {
-            Ok(TableNode::CrossJoin {
+            return Ok(TableNode::CrossJoin {
                 left: Box::new(left),
                 right: Box::new(right),
-            })
+            });
+        }
+        let constraint = if natural {
+            JoinConstraint::Natural
+        } else if self.peek_token() == &Token::Keyword(Keyword::Using) {
+            self.next_token();
+            self.expect(Token::LeftParen)?;
+            let mut columns = vec![];
+            loop {
+                columns.push(self.parse_identifier()?);
+                match self.next_token() {
+                    Token::Comma => continue,
+                    Token::RightParen => break,
+                    found => self.wrong_token("',' followed by a column name or ')'", found)?,
+                }
+            }
+            JoinConstraint::Using(columns)
         } else {
             self.expect(Token::Keyword(Keyword::On))?;
-            let on = self.parse_expression()?;
-            Ok(TableNode::Join {
-                left: Box::new(left),
-                right: Box::new(right),
-                join_type,
-                on,
-            })
-        }
+            JoinConstraint::On(self.parse_expression()?)
+        };
+        Ok(TableNode::Join {
+            left: Box::new(left),
+            right: Box::new(right),
+            join_type,
+            constraint,
+        })
     }
 
     fn parse_projections(&mut self) -> Result<Vec<Projection>> {
@@ -442,14 +803,33 @@ impl Parser {
                     let _dot = self.next_token();
                     let col = self.parse_identifier()?;
                     Ok(ExprNode::QualifiedIdentifier(id, col))
+                } else if id == "now"
+                    && self.peek_token() == &Token::LeftParen
+                    && self.peek_token_ahead(1) == &Token::RightParen
+                {
+                    self.advance(2);
+                    Ok(ExprNode::CurrentTimestamp)
+                } else if matches!(id.as_str(), "uuid" | "json" | "jsonb")
+                    && matches!(self.peek_token(), Token::QuotedString(_))
+                {
+                    let data_type = match id.as_str() {
+                        "uuid" => DataType::Uuid,
+                        _ => DataType::Json,
+                    };
+                    match self.next_token() {
+                        Token::QuotedString(value) => {
+                            Ok(ExprNode::TypedString { data_type, value })
+                        }
+                        _ => unreachable!(),
+                    }
                 } else if self.peek_token() == &Token::LeftParen {
                     let _left_paren = self.next_token();
-                    let expr = self.parse_expression()?;
-                    self.expect(Token::RightParen)?;
-                    Ok(ExprNode::FunctionCall {
-                        name: id,
-                        expr: Box::new(expr),
-                    })
+                    match id.as_str() {
+                        "position" => self.parse_position(),
+                        "substring" => self.parse_substring(),
+                        "trim" => self.parse_trim(),
+                        _ => self.parse_function_call(id),
+                    }
                 } else {
                     Ok(ExprNode::Identifier(id))
                 }
@@ -458,6 +838,60 @@ impl Parser {
             Token::QuotedString(s) => Ok(ExprNode::String(s)),
             Token::Keyword(Keyword::True) => Ok(ExprNode::Boolean(true)),
             Token::Keyword(Keyword::False) => Ok(ExprNode::Boolean(false)),
+            Token::Keyword(
+                keyword @ (Keyword::Date
+                | Keyword::Time
+                | Keyword::Timestamp
+                | Keyword::Interval
+                | Keyword::Bytea),
+            ) => {
+                let data_type = match keyword {
+                    Keyword::Date => DataType::Date,
+                    Keyword::Time => DataType::Time,
+                    Keyword::Timestamp => DataType::Timestamp,
+                    Keyword::Interval => DataType::Interval,
+                    Keyword::Bytea => DataType::Bytea,
+                    _ => unreachable!(),
+                };
+                match self.next_token() {
+                    Token::QuotedString(value) => Ok(ExprNode::TypedString { data_type, value }),
+                    found => self.wrong_token(&format!("a quoted {} literal", data_type), found),
+                }
+            }
+            Token::Keyword(Keyword::Extract) => self.parse_extract(),
+            Token::Keyword(Keyword::Case) => self.parse_case(),
+            Token::Keyword(Keyword::Cast) => {
+                self.expect(Token::LeftParen)?;
+                let expr = self.parse_expression()?;
+                self.expect(Token::Keyword(Keyword::As))?;
+                let data_type = self.parse_data_type()?;
+                self.expect(Token::RightParen)?;
+                Ok(ExprNode::Cast {
+                    expr: Box::new(expr),
+                    data_type,
+                })
+            }
+            Token::Keyword(Keyword::Array) => {
+                self.expect(Token::LeftBracket)?;
+                let mut elements = vec![];
+                if self.peek_token() == &Token::RightBracket {
+                    self.next_token();
+                    return Ok(ExprNode::Array(elements));
+                }
+                loop {
+                    elements.push(self.parse_expression()?);
+                    match self.next_token() {
+                        Token::Comma => continue,
+                        Token::RightBracket => break,
+                        found => {
+                            self.wrong_token("',' followed by another element or ']'", found)?
+                        }
+                    }
+                }
+                Ok(ExprNode::Array(elements))
+            }
+            Token::Keyword(Keyword::CurrentDate) => Ok(ExprNode::CurrentDate),
+            Token::Keyword(Keyword::CurrentTimestamp) => Ok(ExprNode::CurrentTimestamp),
             Token::Minus => {
                 let expr = self.parse_expression_with_precedence(precedence::PLUS_MINUS)?;
                 Ok(ExprNode::Unary {
@@ -466,6 +900,13 @@ impl Parser {
                 })
             }
             Token::Keyword(Keyword::Null) => Ok(ExprNode::Null),
+            Token::Keyword(Keyword::Not) => {
+                let expr = self.parse_expression_with_precedence(precedence::NOT)?;
+                Ok(ExprNode::Unary {
+                    op: UnaryOperator::Not,
+                    expr: Box::new(expr),
+                })
+            }
             Token::Plus => {
                 let expr = self.parse_expression_with_precedence(precedence::PLUS_MINUS)?;
                 Ok(ExprNode::Unary {
@@ -474,14 +915,301 @@ impl Parser {
                 })
             }
             Token::LeftParen => {
+                if let Token::Keyword(Keyword::Select | Keyword::Values | Keyword::With) =
+                    self.peek_token()
+                {
+                    let subquery = self.parse_query()?;
+                    self.expect(Token::RightParen)?;
+                    return Ok(ExprNode::Subquery(Box::new(subquery)));
+                }
                 let expr = self.parse_expression()?;
                 self.expect(Token::RightParen)?;
                 Ok(ExprNode::Grouping(Box::new(expr)))
             }
+            Token::Keyword(Keyword::Exists) => {
+                let subquery = self.parse_subquery()?;
+                Ok(ExprNode::Exists(Box::new(subquery)))
+            }
             found => self.wrong_token("an expression", found)?,
         }
     }
 
+    /// Parses the arguments of a function call, expects the left parenthesis to be consumed
+    /// Parses the arguments of a function call, which may also be `*` or be preceded by DISTINCT,
+    /// followed by the OVER clause of a window function
+    fn parse_function_call(&mut self, name: String) -> Result<ExprNode> {
+        if self.peek_token() == &Token::Star && self.peek_token_ahead(1) == &Token::RightParen {
+            self.advance(2);
+            return Ok(ExprNode::FunctionCall {
+                name,
+                args: vec![],
+                distinct: false,
+                wildcard: true,
+                over: self.parse_over()?,
+            });
+        }
+
+        let distinct = self.peek_token() == &Token::Keyword(Keyword::Distinct);
+        if distinct {
+            self.next_token();
+            if self.peek_token() == &Token::RightParen {
+                let found = self.next_token();
+                return self.wrong_token("an argument after DISTINCT", found);
+            }
+        }
+        let args = self.parse_function_arguments()?;
+
+        Ok(ExprNode::FunctionCall {
+            name,
+            args,
+            distinct,
+            wildcard: false,
+            over: self.parse_over()?,
+        })
+    }
+
+    /// Parses the OVER clause of a window function, if present
+    fn parse_over(&mut self) -> Result<Option<WindowSpec>> {
+        if self.peek_token() != &Token::Keyword(Keyword::Over) {
+            return Ok(None);
+        }
+        self.next_token();
+        self.expect(Token::LeftParen)?;
+
+        let mut partition_by = vec![];
+        if self.peek_keywords_match(&[Keyword::Partition, Keyword::By]) {
+            self.advance(2);
+            loop {
+                partition_by.push(self.parse_expression()?);
+                if self.peek_token() == &Token::Comma {
+                    self.next_token();
+                } else {
+                    break;
+                }
+            }
+        }
+        let order_by = self.parse_order_by()?;
+        let frame = if self.peek_token() == &Token::Keyword(Keyword::Rows) {
+            self.next_token();
+            Some(self.parse_window_frame()?)
+        } else {
+            None
+        };
+        self.expect(Token::RightParen)?;
+
+        Ok(Some(WindowSpec {
+            partition_by,
+            order_by,
+            frame,
+        }))
+    }
+
+    /// Parses the frame after ROWS, either `BETWEEN start AND end` or only the start, in which
+    /// case the frame ends with the current row
+    fn parse_window_frame(&mut self) -> Result<WindowFrame<Box<ExprNode>>> {
+        let frame = if self.peek_token() == &Token::Keyword(Keyword::Between) {
+            self.next_token();
+            let start = self.parse_frame_bound()?;
+            self.expect(Token::Keyword(Keyword::And))?;
+            let end = self.parse_frame_bound()?;
+            WindowFrame { start, end }
+        } else {
+            WindowFrame {
+                start: self.parse_frame_bound()?,
+                end: FrameBound::CurrentRow,
+            }
+        };
+
+        let error = match (&frame.start, &frame.end) {
+            (FrameBound::UnboundedFollowing, _) => "frame start cannot be UNBOUNDED FOLLOWING",
+            (_, FrameBound::UnboundedPreceding) => "frame end cannot be UNBOUNDED PRECEDING",
+            (FrameBound::CurrentRow, FrameBound::Preceding(_)) => {
+                "frame starting from current row cannot have preceding rows"
+            }
+            (FrameBound::Following(_), FrameBound::Preceding(_) | FrameBound::CurrentRow) => {
+                "frame starting from following row cannot have preceding rows"
+            }
+            _ => return Ok(frame),
+        };
+        Err(Error::msg(error))
+    }
+
+    fn parse_frame_bound(&mut self) -> Result<FrameBound<Box<ExprNode>>> {
+        match self.peek_token() {
+            Token::Keyword(Keyword::Unbounded) => {
+                self.next_token();
+                match self.next_token() {
+                    Token::Keyword(Keyword::Preceding) => Ok(FrameBound::UnboundedPreceding),
+                    Token::Keyword(Keyword::Following) => Ok(FrameBound::UnboundedFollowing),
+                    found => self.wrong_token("PRECEDING or FOLLOWING", found),
+                }
+            }
+            Token::Keyword(Keyword::Current) => {
+                self.next_token();
+                self.expect(Token::Keyword(Keyword::Row))?;
+                Ok(FrameBound::CurrentRow)
+            }
+            _ => {
+                let offset = Box::new(self.parse_expression()?);
+                match self.next_token() {
+                    Token::Keyword(Keyword::Preceding) => Ok(FrameBound::Preceding(offset)),
+                    Token::Keyword(Keyword::Following) => Ok(FrameBound::Following(offset)),
+                    found => self.wrong_token("PRECEDING or FOLLOWING", found),
+                }
+            }
+        }
+    }
+
+    fn parse_function_arguments(&mut self) -> Result<Vec<ExprNode>> {
+        let mut args = vec![];
+        if self.peek_token() == &Token::RightParen {
+            self.next_token();
+            return Ok(args);
+        }
+
+        loop {
+            args.push(self.parse_expression()?);
+            match self.next_token() {
+                Token::Comma => continue,
+                Token::RightParen => break,
+                found => self.wrong_token("',' followed by another argument or ')'", found)?,
+            }
+        }
+
+        Ok(args)
+    }
+
+    /// Parses a CASE expression, expects CASE to be consumed
+    fn parse_case(&mut self) -> Result<ExprNode> {
+        let operand = if self.peek_token() == &Token::Keyword(Keyword::When) {
+            None
+        } else {
+            Some(Box::new(self.parse_expression()?))
+        };
+        let mut conditions = vec![];
+        while self.peek_token() == &Token::Keyword(Keyword::When) {
+            self.next_token();
+            let condition = self.parse_expression()?;
+            self.expect(Token::Keyword(Keyword::Then))?;
+            let result = self.parse_expression()?;
+            conditions.push((condition, result));
+        }
+        if conditions.is_empty() {
+            let found = self.next_token();
+            return self.wrong_token("WHEN", found);
+        }
+        let else_result = if self.peek_token() == &Token::Keyword(Keyword::Else) {
+            self.next_token();
+            Some(Box::new(self.parse_expression()?))
+        } else {
+            None
+        };
+        self.expect(Token::Keyword(Keyword::End))?;
+
+        Ok(ExprNode::Case {
+            operand,
+            conditions,
+            else_result,
+        })
+    }
+
+    /// Parses `position(substring IN string)`, expects the left parenthesis to be consumed.
+    /// The function itself takes the string first
+    fn parse_position(&mut self) -> Result<ExprNode> {
+        let substring = self.parse_expression_with_precedence(precedence::IN)?;
+        self.expect(Token::Keyword(Keyword::In))?;
+        let string = self.parse_expression()?;
+        self.expect(Token::RightParen)?;
+        Ok(Self::function_call("position", vec![string, substring]))
+    }
+
+    /// Parses `substring(string FROM start FOR count)`, where either FROM or FOR may be omitted,
+    /// or the arguments separated by commas. Expects the left parenthesis to be consumed
+    fn parse_substring(&mut self) -> Result<ExprNode> {
+        let mut args = vec![self.parse_expression()?];
+        if self.peek_token() == &Token::Comma {
+            self.next_token();
+            args.extend(self.parse_function_arguments()?);
+            return Ok(Self::function_call("substring", args));
+        }
+
+        if self.peek_token() == &Token::Keyword(Keyword::From) {
+            self.next_token();
+            args.push(self.parse_expression()?);
+        }
+        if self.peek_token() == &Token::Keyword(Keyword::For) {
+            self.next_token();
+            if args.len() == 1 {
+                args.push(ExprNode::Number("1".to_owned()));
+            }
+            args.push(self.parse_expression()?);
+        }
+        self.expect(Token::RightParen)?;
+        Ok(Self::function_call("substring", args))
+    }
+
+    /// Parses `trim([LEADING | TRAILING | BOTH] [characters] FROM string)`, or the string and
+    /// the characters separated by commas. Expects the left parenthesis to be consumed
+    fn parse_trim(&mut self) -> Result<ExprNode> {
+        let name = match self.peek_token() {
+            Token::Keyword(Keyword::Leading) => "ltrim",
+            Token::Keyword(Keyword::Trailing) => "rtrim",
+            Token::Keyword(Keyword::Both) => "btrim",
+            _ => "",
+        };
+        let name = if name.is_empty() {
+            "btrim"
+        } else {
+            self.next_token();
+            name
+        };
+
+        if self.peek_token() == &Token::Keyword(Keyword::From) {
+            self.next_token();
+            let string = self.parse_expression()?;
+            self.expect(Token::RightParen)?;
+            return Ok(Self::function_call(name, vec![string]));
+        }
+        let mut args = vec![self.parse_expression()?];
+        match self.next_token() {
+            Token::Keyword(Keyword::From) => {
+                let string = self.parse_expression()?;
+                self.expect(Token::RightParen)?;
+                args.insert(0, string);
+            }
+            Token::Comma => args.extend(self.parse_function_arguments()?),
+            Token::RightParen => (),
+            found => self.wrong_token("FROM, ',' or ')'", found)?,
+        }
+        Ok(Self::function_call(name, args))
+    }
+
+    /// A call of a scalar function, for functions with a special syntax
+    fn function_call(name: &str, args: Vec<ExprNode>) -> ExprNode {
+        ExprNode::FunctionCall {
+            name: name.to_owned(),
+            args,
+            distinct: false,
+            wildcard: false,
+            over: None,
+        }
+    }
+
+    fn parse_extract(&mut self) -> Result<ExprNode> {
+        self.expect(Token::LeftParen)?;
+        let field = self.parse_identifier()?;
+        let field = DateTimeField::is_field(&field)
+            .ok_or_else(|| Error::msg(format!("Cannot extract unknown field '{}'", field)))?;
+        self.expect(Token::Keyword(Keyword::From))?;
+        let expr = self.parse_expression()?;
+        self.expect(Token::RightParen)?;
+
+        Ok(ExprNode::Extract {
+            field,
+            expr: Box::new(expr),
+        })
+    }
+
     fn parse_infix_expression(&mut self, left: ExprNode, precedence: u8) -> Result<ExprNode> {
         match self.next_token() {
             token @ (Token::Plus
@@ -495,8 +1223,15 @@ impl Parser {
             | Token::LessEq
             | Token::Greater
             | Token::GreaterEq
+            | Token::Arrow
+            | Token::LongArrow
+            | Token::AtGreater
+            | Token::Concat
             | Token::Keyword(Keyword::And)
             | Token::Keyword(Keyword::Or)) => {
+                if self.peek_token() == &Token::Keyword(Keyword::Any) {
+                    return self.parse_any_comparison(left, token);
+                }
                 let right = self.parse_expression_with_precedence(precedence)?;
                 let binary_op = match token {
                     Token::Plus => BinaryOperator::Plus,
@@ -510,6 +1245,10 @@ impl Parser {
                     Token::LessEq => BinaryOperator::LessEq,
                     Token::Greater => BinaryOperator::Greater,
                     Token::GreaterEq => BinaryOperator::GreaterEq,
+                    Token::Arrow => BinaryOperator::JsonGet,
+                    Token::LongArrow => BinaryOperator::JsonGetText,
+                    Token::AtGreater => BinaryOperator::JsonContains,
+                    Token::Concat => BinaryOperator::Concat,
                     Token::Keyword(Keyword::And) => BinaryOperator::And,
                     Token::Keyword(Keyword::Or) => BinaryOperator::Or,
                     _ => unreachable!(),
@@ -520,27 +1259,172 @@ impl Parser {
                     right: Box::new(right),
                 })
             }
+            Token::DoubleColon => {
+                let data_type = self.parse_data_type()?;
+                Ok(ExprNode::Cast {
+                    expr: Box::new(left),
+                    data_type,
+                })
+            }
+            Token::LeftBracket => {
+                let index = self.parse_expression()?;
+                self.expect(Token::RightBracket)?;
+                Ok(ExprNode::Subscript {
+                    expr: Box::new(left),
+                    index: Box::new(index),
+                })
+            }
+            token @ Token::Keyword(
+                Keyword::In | Keyword::Between | Keyword::Like | Keyword::Ilike,
+            ) => self.parse_negatable_predicate(left, token, false),
+            Token::Keyword(Keyword::Not) => {
+                let token = self.next_token();
+                self.parse_negatable_predicate(left, token, true)
+            }
             Token::Keyword(Keyword::Is) => {
-                if self.peek_keywords_match(&[Keyword::Null]) {
+                let negated = self.peek_keywords_match(&[Keyword::Not]);
+                if negated {
                     self.advance(1);
-                    Ok(ExprNode::IsNull(Box::new(left)))
-                } else if self.peek_keywords_match(&[Keyword::Not, Keyword::Null]) {
-                    self.advance(2);
-                    Ok(ExprNode::IsNotNull(Box::new(left)))
-                } else {
-                    Err(Error::msg(format!(
-                        "Expected 'NULL' or 'NOT NULL' but found {:?}",
-                        self.next_token()
-                    )))
                 }
+                let value = match self.next_token() {
+                    Token::Keyword(Keyword::Null) if negated => {
+                        return Ok(ExprNode::IsNotNull(Box::new(left)))
+                    }
+                    Token::Keyword(Keyword::Null) => return Ok(ExprNode::IsNull(Box::new(left))),
+                    Token::Keyword(Keyword::Distinct) => {
+                        self.expect(Token::Keyword(Keyword::From))?;
+                        let right = self.parse_expression_with_precedence(precedence::IS)?;
+                        let op = if negated {
+                            BinaryOperator::IsNotDistinctFrom
+                        } else {
+                            BinaryOperator::IsDistinctFrom
+                        };
+                        return Ok(ExprNode::Binary {
+                            left: Box::new(left),
+                            op,
+                            right: Box::new(right),
+                        });
+                    }
+                    Token::Keyword(Keyword::True) => Some(true),
+                    Token::Keyword(Keyword::False) => Some(false),
+                    Token::Keyword(Keyword::Unknown) => None,
+                    found => self.wrong_token(
+                        "NULL, TRUE, FALSE, UNKNOWN or DISTINCT FROM after IS [NOT]",
+                        found,
+                    )?,
+                };
+                Ok(ExprNode::IsTruthValue {
+                    expr: Box::new(left),
+                    value,
+                    negated,
+                })
+            }
+            found => Err(Error::msg(format!(
+                "Could not parse infix expression for {:?}",
+                found
+            ))),
+        }
+    }
+
+    /// Parses the predicates which can be negated with a preceding NOT, i.e. IN, BETWEEN, LIKE
+    /// and ILIKE. Expects the keyword to be consumed
+    fn parse_negatable_predicate(
+        &mut self,
+        left: ExprNode,
+        token: Token,
+        negated: bool,
+    ) -> Result<ExprNode> {
+        let expr = Box::new(left);
+        match token {
+            Token::Keyword(Keyword::In) => {
+                let is_subquery = self.peek_token() == &Token::LeftParen
+                    && matches!(
+                        self.peek_token_ahead(1),
+                        Token::Keyword(Keyword::Select | Keyword::Values | Keyword::With)
+                    );
+                if is_subquery {
+                    return Ok(ExprNode::InSubquery {
+                        expr,
+                        subquery: Box::new(self.parse_subquery()?),
+                        negated,
+                    });
+                }
+                self.expect(Token::LeftParen)?;
+                let list = self.parse_function_arguments()?;
+                if list.is_empty() {
+                    return Err(Error::msg("Expected at least one value in IN list"));
+                }
+                Ok(ExprNode::InList {
+                    expr,
+                    list,
+                    negated,
+                })
+            }
+            Token::Keyword(Keyword::Between) => {
+                // the AND belongs to BETWEEN, so it must not be parsed as a conjunction
+                let low = self.parse_expression_with_precedence(precedence::IN)?;
+                self.expect(Token::Keyword(Keyword::And))?;
+                let high = self.parse_expression_with_precedence(precedence::IN)?;
+                Ok(ExprNode::Between {
+                    expr,
+                    low: Box::new(low),
+                    high: Box::new(high),
+                    negated,
+                })
+            }
+            Token::Keyword(keyword @ (Keyword::Like | Keyword::Ilike)) => {
+                let pattern = self.parse_expression_with_precedence(precedence::IN)?;
+                let escape = if self.peek_token() == &Token::Keyword(Keyword::Escape) {
+                    self.next_token();
+                    Some(Box::new(
+                        self.parse_expression_with_precedence(precedence::IN)?,
+                    ))
+                } else {
+                    None
+                };
+                Ok(ExprNode::Like {
+                    expr,
+                    pattern: Box::new(pattern),
+                    escape,
+                    case_insensitive: keyword == Keyword::Ilike,
+                    negated,
+                })
             }
-            found => Err(Error::msg(format!(
-                "Could not parse infix expression for {:?}",
-                found
-            ))),
+            found => self.wrong_token("IN, BETWEEN, LIKE or ILIKE", found),
         }
     }
 
+    /// Parses a parenthesized query, e.g. after EXISTS or IN
+    fn parse_subquery(&mut self) -> Result<SelectStatement> {
+        self.expect(Token::LeftParen)?;
+        let subquery = self.parse_query()?;
+        self.expect(Token::RightParen)?;
+        Ok(subquery)
+    }
+
+    /// Parses `ANY(array)` on the right side of a comparison operator
+    fn parse_any_comparison(&mut self, left: ExprNode, op_token: Token) -> Result<ExprNode> {
+        let op = match op_token {
+            Token::Eq => BinaryOperator::Eq,
+            Token::NotEq => BinaryOperator::NotEq,
+            Token::Less => BinaryOperator::Less,
+            Token::LessEq => BinaryOperator::LessEq,
+            Token::Greater => BinaryOperator::Greater,
+            Token::GreaterEq => BinaryOperator::GreaterEq,
+            found => self.wrong_token("a comparison operator before ANY", found)?,
+        };
+        self.expect(Token::Keyword(Keyword::Any))?;
+        self.expect(Token::LeftParen)?;
+        let array = self.parse_expression()?;
+        self.expect(Token::RightParen)?;
+
+        Ok(ExprNode::AnyComparison {
+            left: Box::new(left),
+            op,
+            array: Box::new(array),
+        })
+    }
+
     fn next_precedence(&self) -> u8 {
         match self.peek_token() {
             Token::Plus | Token::Minus => precedence::PLUS_MINUS,
@@ -551,7 +1435,22 @@ impl Parser {
             | Token::LessEq
             | Token::Greater
             | Token::GreaterEq => precedence::COMPARISON,
+            Token::Arrow | Token::LongArrow | Token::AtGreater => precedence::OTHER,
+            Token::LeftBracket => precedence::SUBSCRIPT,
+            Token::DoubleColon => precedence::CAST,
             Token::Keyword(Keyword::Is) => precedence::IS,
+            Token::Concat => precedence::OTHER,
+            Token::Keyword(Keyword::In | Keyword::Between | Keyword::Like | Keyword::Ilike) => {
+                precedence::IN
+            }
+            Token::Keyword(Keyword::Not)
+                if matches!(
+                    self.peek_token_ahead(1),
+                    Token::Keyword(Keyword::In | Keyword::Between | Keyword::Like | Keyword::Ilike)
+                ) =>
+            {
+                precedence::IN
+            }
             Token::Keyword(Keyword::And) => precedence::AND,
             Token::Keyword(Keyword::Or) => precedence::OR,
             _ => 0,
@@ -639,14 +1538,59 @@ impl Parser {
                 Keyword::Boolean => DataType::Boolean,
                 Keyword::Integer => DataType::Integer,
                 Keyword::Text => DataType::Text,
+                Keyword::Varchar => DataType::Varchar(self.parse_type_length("VARCHAR")?),
+                Keyword::Char => DataType::Char(self.parse_type_length("CHAR")?),
+                Keyword::Date => DataType::Date,
+                Keyword::Time => DataType::Time,
+                Keyword::Timestamp => DataType::Timestamp,
+                Keyword::Interval => DataType::Interval,
+                Keyword::Bytea => DataType::Bytea,
                 found => self.wrong_keyword("a data type", found)?,
             },
+            // not keywords, as these are common column names
+            Token::Identifier(id) if id == "uuid" => DataType::Uuid,
+            Token::Identifier(id) if id == "json" || id == "jsonb" => DataType::Json,
             found => self.wrong_token("a data type", found)?,
         };
 
+        if self.peek_token() == &Token::LeftBracket {
+            self.next_token();
+            self.expect(Token::RightBracket)?;
+            if !matches!(data_type, DataType::Integer | DataType::Text) {
+                return Err(Error::msg(format!(
+                    "Arrays of type {} are not supported",
+                    data_type
+                )));
+            }
+            return Ok(DataType::Array(Box::new(data_type)));
+        }
+
         Ok(data_type)
     }
 
+    /// Parses the declared length of a character type, like the `(10)` in `VARCHAR(10)`
+    fn parse_type_length(&mut self, type_name: &str) -> Result<u8> {
+        self.expect(Token::LeftParen)?;
+        let length = match self.next_token() {
+            Token::Number(num) => {
+                num.parse::<u8>()
+                    .ok()
+                    .filter(|len| *len > 0)
+                    .ok_or_else(|| {
+                        Error::msg(format!(
+                            "Length of {} must be between 1 and {}, found {}",
+                            type_name,
+                            u8::MAX,
+                            num
+                        ))
+                    })?
+            }
+            found => self.wrong_token("a length", found)?,
+        };
+        self.expect(Token::RightParen)?;
+        Ok(length)
+    }
+
     fn parse_identifier(&mut self) -> Result<String> {
         match self.next_token() {
             Token::Identifier(s) => Ok(s),
@@ -695,6 +1639,11 @@ impl Parser {
     }
 }
 
+/// Returns whether the (lowercase) string is a keyword, which cannot be used as a name
+pub fn is_reserved_keyword(s: &str) -> bool {
+    Keyword::is_keyword(s).is_some()
+}
+
 /// Parses an sql query
 /// Returns whether the execution plan should be shown (i.e. explained) and the parsed statement
 pub fn parse_sql(sql: &str) -> Result<(bool, Statement)> {
@@ -707,11 +1656,13 @@ mod tests {
     use std::collections::{HashMap, VecDeque};
 
     use super::ast::{
-        BinaryOperator, ColumnDefinition, DataType, ExprNode, Projection, Statement, TableNode,
-        UnaryOperator,
+        BinaryOperator, ColumnDefinition, CommonTableExpression, DataType, Distinct, ExprNode,
+        FrameBound, OrderByExpr, Projection, Statement, TableNode, UnaryOperator, WindowFrame,
+        WindowSpec,
     };
     use super::parse_sql;
     use crate::parser::ast::SelectStatement;
+    use crate::tuple::datetime::DateTimeField;
 
     #[test]
     fn can_parse_create_table_statements() {
@@ -720,7 +1671,9 @@ mod tests {
                 id integer not null,
                 name text not null,
                 active boolean null,
-                email text
+                email text,
+                country char(2) not null,
+                nickname varchar(20)
             );
         ";
 
@@ -752,6 +1705,18 @@ mod tests {
                     offset: 3,
                     not_null: false,
                 },
+                ColumnDefinition {
+                    name: "country".to_owned(),
+                    data_type: DataType::Char(2),
+                    offset: 4,
+                    not_null: true,
+                },
+                ColumnDefinition {
+                    name: "nickname".to_owned(),
+                    data_type: DataType::Varchar(20),
+                    offset: 5,
+                    not_null: false,
+                },
             ],
         };
 
@@ -767,7 +1732,10 @@ mod tests {
         let _statement = parse_sql(sql).unwrap();
         let (_, statement) = parse_sql(sql).unwrap();
         let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
             values: None,
+            distinct: None,
+            set_operation: None,
             projections: vec![Projection::Wildcard],
             from: vec![TableNode::TableReference {
                 name: "accounts".to_owned(),
@@ -775,6 +1743,11 @@ mod tests {
             }]
             .into(),
             filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
         });
 
         assert_eq!(statement, expected_statement);
@@ -790,7 +1763,10 @@ mod tests {
         let _statement = parse_sql(sql).unwrap();
         let (_, statement) = parse_sql(sql).unwrap();
         let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
             values: None,
+            distinct: None,
+            set_operation: None,
             projections: vec![
                 Projection::UnnamedExpr(ExprNode::Identifier("id".to_owned())),
                 Projection::NamedExpr {
@@ -808,6 +1784,11 @@ mod tests {
             }]
             .into(),
             filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
         });
 
         assert_eq!(statement, expected_statement);
@@ -821,7 +1802,10 @@ mod tests {
 
         let (_, statement) = parse_sql(sql).unwrap();
         let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
             values: None,
+            distinct: None,
+            set_operation: None,
             projections: vec![Projection::UnnamedExpr(ExprNode::Binary {
                 left: Box::new(ExprNode::Unary {
                     op: UnaryOperator::Minus,
@@ -844,6 +1828,11 @@ mod tests {
             }]
             .into(),
             filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
         });
 
         assert_eq!(statement, expected_statement);
@@ -864,7 +1853,10 @@ mod tests {
             let sql = format!("select id {} 42 from table_name;", op);
             let (_, statement) = parse_sql(&sql).unwrap();
             let expected_statement = Statement::Select(SelectStatement {
+                with: vec![],
                 values: None,
+                distinct: None,
+                set_operation: None,
                 projections: vec![Projection::UnnamedExpr(ExprNode::Binary {
                     left: Box::new(ExprNode::Identifier("id".to_owned())),
                     op,
@@ -876,6 +1868,11 @@ mod tests {
                 }]
                 .into(),
                 filter: None,
+                group_by: vec![],
+                having: None,
+                order_by: vec![],
+                limit: None,
+                offset: None,
             });
 
             assert_eq!(statement, expected_statement);
@@ -890,7 +1887,10 @@ mod tests {
 
         let (_, statement) = parse_sql(sql).unwrap();
         let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
             values: None,
+            distinct: None,
+            set_operation: None,
             projections: vec![Projection::UnnamedExpr(ExprNode::Binary {
                 left: Box::new(ExprNode::Unary {
                     op: UnaryOperator::Minus,
@@ -909,6 +1909,11 @@ mod tests {
             })],
             from: vec![].into(),
             filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
         });
 
         assert_eq!(statement, expected_statement);
@@ -922,6 +1927,9 @@ mod tests {
 
         let (_, statement) = parse_sql(sql).unwrap();
         let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
+            distinct: None,
+            set_operation: None,
             values: Some(vec![
                 vec![
                     ExprNode::Number("1".to_owned()),
@@ -937,6 +1945,11 @@ mod tests {
             projections: vec![],
             from: vec![].into(),
             filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
         });
 
         assert_eq!(statement, expected_statement);
@@ -955,6 +1968,9 @@ mod tests {
                 alias: None,
             },
             select: SelectStatement {
+                with: vec![],
+                distinct: None,
+                set_operation: None,
                 values: Some(vec![
                     vec![
                         ExprNode::Number("1".to_owned()),
@@ -970,6 +1986,11 @@ mod tests {
                 projections: vec![],
                 from: vec![].into(),
                 filter: None,
+                group_by: vec![],
+                having: None,
+                order_by: vec![],
+                limit: None,
+                offset: None,
             },
         };
 
@@ -989,7 +2010,10 @@ mod tests {
                 alias: None,
             },
             select: SelectStatement {
+                with: vec![],
                 values: None,
+                distinct: None,
+                set_operation: None,
                 projections: vec![Projection::Wildcard],
                 from: vec![TableNode::TableReference {
                     name: "old_table".to_owned(),
@@ -997,6 +2021,11 @@ mod tests {
                 }]
                 .into(),
                 filter: None,
+                group_by: vec![],
+                having: None,
+                order_by: vec![],
+                limit: None,
+                offset: None,
             },
         };
 
@@ -1046,16 +2075,22 @@ mod tests {
     #[test]
     fn can_parse_function_calls() {
         let sql = "
-            select foo(col), 2 + bar(col2) as baz
+            select foo(col), 2 + bar(col2, 3) as baz, gen(), count(*), count(distinct col)
         ";
 
         let (_, statement) = parse_sql(sql).unwrap();
         let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
             values: None,
+            distinct: None,
+            set_operation: None,
             projections: vec![
                 Projection::UnnamedExpr(ExprNode::FunctionCall {
                     name: "foo".to_owned(),
-                    expr: Box::new(ExprNode::Identifier("col".to_owned())),
+                    args: vec![ExprNode::Identifier("col".to_owned())],
+                    distinct: false,
+                    wildcard: false,
+                    over: None,
                 }),
                 Projection::NamedExpr {
                     expr: ExprNode::Binary {
@@ -1063,16 +2098,734 @@ mod tests {
                         op: BinaryOperator::Plus,
                         right: Box::new(ExprNode::FunctionCall {
                             name: "bar".to_owned(),
-                            expr: Box::new(ExprNode::Identifier("col2".to_owned())),
+                            args: vec![
+                                ExprNode::Identifier("col2".to_owned()),
+                                ExprNode::Number("3".to_owned()),
+                            ],
+                            distinct: false,
+                            wildcard: false,
+                            over: None,
                         }),
                     },
                     alias: "baz".to_owned(),
                 },
+                Projection::UnnamedExpr(ExprNode::FunctionCall {
+                    name: "gen".to_owned(),
+                    args: vec![],
+                    distinct: false,
+                    wildcard: false,
+                    over: None,
+                }),
+                Projection::UnnamedExpr(ExprNode::FunctionCall {
+                    name: "count".to_owned(),
+                    args: vec![],
+                    distinct: false,
+                    wildcard: true,
+                    over: None,
+                }),
+                Projection::UnnamedExpr(ExprNode::FunctionCall {
+                    name: "count".to_owned(),
+                    args: vec![ExprNode::Identifier("col".to_owned())],
+                    distinct: true,
+                    wildcard: false,
+                    over: None,
+                }),
+            ],
+            from: VecDeque::new(),
+            filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
+        });
+
+        assert_eq!(statement, expected_statement);
+
+        assert!(parse_sql("select count(distinct)").is_err());
+        assert!(parse_sql("select count(distinct *)").is_err());
+    }
+
+    #[test]
+    fn can_parse_temporal_expressions() {
+        let sql = "
+            select date '2024-01-31', extract(year from created_at), current_timestamp
+        ";
+
+        let (_, statement) = parse_sql(sql).unwrap();
+        let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
+            values: None,
+            distinct: None,
+            set_operation: None,
+            projections: vec![
+                Projection::UnnamedExpr(ExprNode::TypedString {
+                    data_type: DataType::Date,
+                    value: "2024-01-31".to_owned(),
+                }),
+                Projection::UnnamedExpr(ExprNode::Extract {
+                    field: DateTimeField::Year,
+                    expr: Box::new(ExprNode::Identifier("created_at".to_owned())),
+                }),
+                Projection::UnnamedExpr(ExprNode::CurrentTimestamp),
+            ],
+            from: VecDeque::new(),
+            filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
+        });
+
+        assert_eq!(statement, expected_statement);
+    }
+
+    #[test]
+    fn can_parse_array_expressions() {
+        let sql = "
+            select tags[1], 2 = any(array[1, 2]) from unnest(array[]) as t
+        ";
+
+        let (_, statement) = parse_sql(sql).unwrap();
+        let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
+            values: None,
+            distinct: None,
+            set_operation: None,
+            projections: vec![
+                Projection::UnnamedExpr(ExprNode::Subscript {
+                    expr: Box::new(ExprNode::Identifier("tags".to_owned())),
+                    index: Box::new(ExprNode::Number("1".to_owned())),
+                }),
+                Projection::UnnamedExpr(ExprNode::AnyComparison {
+                    left: Box::new(ExprNode::Number("2".to_owned())),
+                    op: BinaryOperator::Eq,
+                    array: Box::new(ExprNode::Array(vec![
+                        ExprNode::Number("1".to_owned()),
+                        ExprNode::Number("2".to_owned()),
+                    ])),
+                }),
+            ],
+            from: VecDeque::from([TableNode::TableFunction {
+                name: "unnest".to_owned(),
+                args: vec![ExprNode::Array(vec![])],
+                alias: Some("t".to_owned()),
+            }]),
+            filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
+        });
+        assert_eq!(statement, expected_statement);
+
+        let (_, statement) = parse_sql("create table t (ids integer[], names text [])").unwrap();
+        let columns = match statement {
+            Statement::CreateTable { name: _, columns } => columns,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            columns[0].data_type,
+            DataType::Array(Box::new(DataType::Integer))
+        );
+        assert_eq!(
+            columns[1].data_type,
+            DataType::Array(Box::new(DataType::Text))
+        );
+        assert!(parse_sql("create table t (d date[])").is_err());
+    }
+
+    #[test]
+    fn can_parse_casts() {
+        let sql = "select cast(a as text), '5'::integer + 1, b::text[]";
+
+        let (_, statement) = parse_sql(sql).unwrap();
+        let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
+            values: None,
+            distinct: None,
+            set_operation: None,
+            projections: vec![
+                Projection::UnnamedExpr(ExprNode::Cast {
+                    expr: Box::new(ExprNode::Identifier("a".to_owned())),
+                    data_type: DataType::Text,
+                }),
+                Projection::UnnamedExpr(ExprNode::Binary {
+                    left: Box::new(ExprNode::Cast {
+                        expr: Box::new(ExprNode::String("5".to_owned())),
+                        data_type: DataType::Integer,
+                    }),
+                    op: BinaryOperator::Plus,
+                    right: Box::new(ExprNode::Number("1".to_owned())),
+                }),
+                Projection::UnnamedExpr(ExprNode::Cast {
+                    expr: Box::new(ExprNode::Identifier("b".to_owned())),
+                    data_type: DataType::Array(Box::new(DataType::Text)),
+                }),
+            ],
+            from: VecDeque::new(),
+            filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
+        });
+        assert_eq!(statement, expected_statement);
+
+        assert!(parse_sql("select cast(a text)").is_err());
+        assert!(parse_sql("select a:integer").is_err());
+    }
+
+    #[test]
+    fn can_parse_case_expressions() {
+        let sql = "select case when a > 1 then 'b' else 'c' end, case a when 1 then 2 end";
+
+        let (_, statement) = parse_sql(sql).unwrap();
+        let projections = match statement {
+            Statement::Select(select) => select.projections,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            projections,
+            vec![
+                Projection::UnnamedExpr(ExprNode::Case {
+                    operand: None,
+                    conditions: vec![(
+                        ExprNode::Binary {
+                            left: Box::new(ExprNode::Identifier("a".to_owned())),
+                            op: BinaryOperator::Greater,
+                            right: Box::new(ExprNode::Number("1".to_owned())),
+                        },
+                        ExprNode::String("b".to_owned()),
+                    )],
+                    else_result: Some(Box::new(ExprNode::String("c".to_owned()))),
+                }),
+                Projection::UnnamedExpr(ExprNode::Case {
+                    operand: Some(Box::new(ExprNode::Identifier("a".to_owned()))),
+                    conditions: vec![(
+                        ExprNode::Number("1".to_owned()),
+                        ExprNode::Number("2".to_owned()),
+                    )],
+                    else_result: None,
+                }),
+            ]
+        );
+
+        assert!(parse_sql("select case end").is_err());
+        assert!(parse_sql("select case when a then b").is_err());
+        assert!(parse_sql("select case when a b end").is_err());
+    }
+
+    #[test]
+    fn can_parse_pattern_matching_and_range_predicates() {
+        let sql = "select a not in (1, 2), a between 1 and 2 and b, a ilike 'x!%' escape '!', \
+                   a || b like c";
+
+        let (_, statement) = parse_sql(sql).unwrap();
+        let projections = match statement {
+            Statement::Select(select) => select.projections,
+            _ => unreachable!(),
+        };
+        let identifier = |name: &str| Box::new(ExprNode::Identifier(name.to_owned()));
+        assert_eq!(
+            projections,
+            vec![
+                Projection::UnnamedExpr(ExprNode::InList {
+                    expr: identifier("a"),
+                    list: vec![
+                        ExprNode::Number("1".to_owned()),
+                        ExprNode::Number("2".to_owned()),
+                    ],
+                    negated: true,
+                }),
+                Projection::UnnamedExpr(ExprNode::Binary {
+                    left: Box::new(ExprNode::Between {
+                        expr: identifier("a"),
+                        low: Box::new(ExprNode::Number("1".to_owned())),
+                        high: Box::new(ExprNode::Number("2".to_owned())),
+                        negated: false,
+                    }),
+                    op: BinaryOperator::And,
+                    right: identifier("b"),
+                }),
+                Projection::UnnamedExpr(ExprNode::Like {
+                    expr: identifier("a"),
+                    pattern: Box::new(ExprNode::String("x!%".to_owned())),
+                    escape: Some(Box::new(ExprNode::String("!".to_owned()))),
+                    case_insensitive: true,
+                    negated: false,
+                }),
+                Projection::UnnamedExpr(ExprNode::Like {
+                    expr: Box::new(ExprNode::Binary {
+                        left: identifier("a"),
+                        op: BinaryOperator::Concat,
+                        right: identifier("b"),
+                    }),
+                    pattern: identifier("c"),
+                    escape: None,
+                    case_insensitive: false,
+                    negated: false,
+                }),
+            ]
+        );
+
+        assert!(parse_sql("select a in ()").is_err());
+        assert!(parse_sql("select a between 1").is_err());
+        assert!(parse_sql("select a not 1").is_err());
+        assert!(parse_sql("select a | b").is_err());
+    }
+
+    #[test]
+    fn can_parse_functions_with_special_syntax() {
+        let sql = "select position('b' in a), substring(a from 2 for 3), substring(a, 2), \
+                   trim(leading 'x' from a), trim(a)";
+
+        let (_, statement) = parse_sql(sql).unwrap();
+        let projections = match statement {
+            Statement::Select(select) => select.projections,
+            _ => unreachable!(),
+        };
+        let call = |name: &str, args: Vec<ExprNode>| {
+            Projection::UnnamedExpr(ExprNode::FunctionCall {
+                name: name.to_owned(),
+                args,
+                distinct: false,
+                wildcard: false,
+                over: None,
+            })
+        };
+        let a = || ExprNode::Identifier("a".to_owned());
+        let number = |num: &str| ExprNode::Number(num.to_owned());
+        assert_eq!(
+            projections,
+            vec![
+                call("position", vec![a(), ExprNode::String("b".to_owned())]),
+                call("substring", vec![a(), number("2"), number("3")]),
+                call("substring", vec![a(), number("2")]),
+                call("ltrim", vec![a(), ExprNode::String("x".to_owned())]),
+                call("btrim", vec![a()]),
+            ]
+        );
+
+        assert!(parse_sql("select position('b', a)").is_err());
+        assert!(parse_sql("select substring(a from 1 from 2)").is_err());
+        assert!(parse_sql("select trim(both 'x' a)").is_err());
+    }
+
+    #[test]
+    fn can_parse_boolean_tests() {
+        let sql = "select not a and b is not true, a = 1 is distinct from null, c is unknown";
+
+        let (_, statement) = parse_sql(sql).unwrap();
+        let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
+            values: None,
+            distinct: None,
+            set_operation: None,
+            projections: vec![
+                Projection::UnnamedExpr(ExprNode::Binary {
+                    left: Box::new(ExprNode::Unary {
+                        op: UnaryOperator::Not,
+                        expr: Box::new(ExprNode::Identifier("a".to_owned())),
+                    }),
+                    op: BinaryOperator::And,
+                    right: Box::new(ExprNode::IsTruthValue {
+                        expr: Box::new(ExprNode::Identifier("b".to_owned())),
+                        value: Some(true),
+                        negated: true,
+                    }),
+                }),
+                Projection::UnnamedExpr(ExprNode::Binary {
+                    left: Box::new(ExprNode::Binary {
+                        left: Box::new(ExprNode::Identifier("a".to_owned())),
+                        op: BinaryOperator::Eq,
+                        right: Box::new(ExprNode::Number("1".to_owned())),
+                    }),
+                    op: BinaryOperator::IsDistinctFrom,
+                    right: Box::new(ExprNode::Null),
+                }),
+                Projection::UnnamedExpr(ExprNode::IsTruthValue {
+                    expr: Box::new(ExprNode::Identifier("c".to_owned())),
+                    value: None,
+                    negated: false,
+                }),
             ],
             from: VecDeque::new(),
             filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
+        });
+        assert_eq!(statement, expected_statement);
+
+        assert!(parse_sql("select a is distinct b").is_err());
+        assert!(parse_sql("select a is not 1").is_err());
+    }
+
+    #[test]
+    fn can_parse_window_functions() {
+        let sql = "select rank() over (partition by a order by b desc rows between 1 preceding \
+                   and unbounded following), count(*) over ()";
+
+        let (_, statement) = parse_sql(sql).unwrap();
+        let projections = match statement {
+            Statement::Select(select) => select.projections,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            projections,
+            vec![
+                Projection::UnnamedExpr(ExprNode::FunctionCall {
+                    name: "rank".to_owned(),
+                    args: vec![],
+                    distinct: false,
+                    wildcard: false,
+                    over: Some(WindowSpec {
+                        partition_by: vec![ExprNode::Identifier("a".to_owned())],
+                        order_by: vec![OrderByExpr {
+                            expr: ExprNode::Identifier("b".to_owned()),
+                            asc: Some(false),
+                            nulls_first: None,
+                        }],
+                        frame: Some(WindowFrame {
+                            start: FrameBound::Preceding(Box::new(ExprNode::Number(
+                                "1".to_owned()
+                            ))),
+                            end: FrameBound::UnboundedFollowing,
+                        }),
+                    }),
+                }),
+                Projection::UnnamedExpr(ExprNode::FunctionCall {
+                    name: "count".to_owned(),
+                    args: vec![],
+                    distinct: false,
+                    wildcard: true,
+                    over: Some(WindowSpec {
+                        partition_by: vec![],
+                        order_by: vec![],
+                        frame: None,
+                    }),
+                }),
+            ]
+        );
+
+        assert!(parse_sql("select rank() over (rows unbounded following)").is_err());
+        assert!(
+            parse_sql("select rank() over (rows between current row and 1 preceding)").is_err()
+        );
+        assert!(parse_sql("select rank() over (order by a").is_err());
+    }
+
+    #[test]
+    fn can_parse_group_by_clauses() {
+        let sql = "select a, count(b) group by a, 2 having count(b) > 1";
+
+        let (_, statement) = parse_sql(sql).unwrap();
+        let count = || ExprNode::FunctionCall {
+            name: "count".to_owned(),
+            args: vec![ExprNode::Identifier("b".to_owned())],
+            distinct: false,
+            wildcard: false,
+            over: None,
+        };
+        let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
+            values: None,
+            distinct: None,
+            set_operation: None,
+            projections: vec![
+                Projection::UnnamedExpr(ExprNode::Identifier("a".to_owned())),
+                Projection::UnnamedExpr(count()),
+            ],
+            from: VecDeque::new(),
+            filter: None,
+            group_by: vec![
+                ExprNode::Identifier("a".to_owned()),
+                ExprNode::Number("2".to_owned()),
+            ],
+            having: Some(ExprNode::Binary {
+                left: Box::new(count()),
+                op: BinaryOperator::Greater,
+                right: Box::new(ExprNode::Number("1".to_owned())),
+            }),
+            order_by: vec![],
+            limit: None,
+            offset: None,
         });
+        assert_eq!(statement, expected_statement);
+
+        assert!(parse_sql("select a group a").is_err());
+        assert!(parse_sql("select a group by").is_err());
+    }
+
+    #[test]
+    fn can_parse_order_by_clauses() {
+        let sql = "select a order by a, 2 desc, b asc nulls first, c nulls last";
 
+        let (_, statement) = parse_sql(sql).unwrap();
+        let expected_statement = Statement::Select(SelectStatement {
+            with: vec![],
+            values: None,
+            distinct: None,
+            set_operation: None,
+            projections: vec![Projection::UnnamedExpr(ExprNode::Identifier(
+                "a".to_owned(),
+            ))],
+            from: VecDeque::new(),
+            filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![
+                OrderByExpr {
+                    expr: ExprNode::Identifier("a".to_owned()),
+                    asc: None,
+                    nulls_first: None,
+                },
+                OrderByExpr {
+                    expr: ExprNode::Number("2".to_owned()),
+                    asc: Some(false),
+                    nulls_first: None,
+                },
+                OrderByExpr {
+                    expr: ExprNode::Identifier("b".to_owned()),
+                    asc: Some(true),
+                    nulls_first: Some(true),
+                },
+                OrderByExpr {
+                    expr: ExprNode::Identifier("c".to_owned()),
+                    asc: None,
+                    nulls_first: Some(false),
+                },
+            ],
+            limit: None,
+            offset: None,
+        });
         assert_eq!(statement, expected_statement);
+
+        assert!(parse_sql("select a order a").is_err());
+        assert!(parse_sql("select a order by").is_err());
+        assert!(parse_sql("select a order by a nulls").is_err());
+    }
+
+    #[test]
+    fn can_parse_distinct() {
+        let distinct = |sql: &str| match parse_sql(sql).unwrap().1 {
+            Statement::Select(select) => select.distinct,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(distinct("select a, b"), None);
+        assert_eq!(distinct("select all a, b"), None);
+        assert_eq!(distinct("select distinct a, b"), Some(Distinct::AllColumns));
+        assert_eq!(
+            distinct("select distinct on (a, 2) a, b"),
+            Some(Distinct::On(vec![
+                ExprNode::Identifier("a".to_owned()),
+                ExprNode::Number("2".to_owned()),
+            ]))
+        );
+
+        assert!(parse_sql("select distinct on a, b").is_err());
+        assert!(parse_sql("select distinct on () a").is_err());
+        assert!(parse_sql("select distinct on (a a").is_err());
+    }
+
+    #[test]
+    fn can_parse_limit_and_offset() {
+        let limit_and_offset = |sql: &str| match parse_sql(sql).unwrap().1 {
+            Statement::Select(select) => (select.limit, select.offset),
+            _ => unreachable!(),
+        };
+        let number = |number: &str| Some(ExprNode::Number(number.to_owned()));
+
+        assert_eq!(
+            limit_and_offset("select a limit 10 offset 5"),
+            (number("10"), number("5"))
+        );
+        assert_eq!(
+            limit_and_offset("select a offset 5 rows limit 10"),
+            (number("10"), number("5"))
+        );
+        assert_eq!(
+            limit_and_offset("select a limit all"),
+            (Some(ExprNode::Null), None)
+        );
+        assert_eq!(
+            limit_and_offset("select a offset 1 row fetch first 2 rows only"),
+            (number("2"), number("1"))
+        );
+        assert_eq!(
+            limit_and_offset("select a fetch next row only"),
+            (number("1"), None)
+        );
+
+        assert!(parse_sql("select a limit").is_err());
+        assert!(parse_sql("select a limit 1 fetch first 1 rows only").is_err());
+        assert!(parse_sql("select a offset 1 offset 2").is_err());
+        assert!(parse_sql("select a fetch first 1 rows").is_err());
+        assert!(parse_sql("select a fetch 1 rows only").is_err());
+    }
+
+    #[test]
+    fn can_parse_set_operations() {
+        fn describe(select: &SelectStatement) -> String {
+            match (&select.set_operation, &select.values) {
+                (Some(set_operation), _) => format!(
+                    "({} {}{} {})",
+                    describe(&set_operation.left),
+                    set_operation.op,
+                    if set_operation.all { " ALL" } else { "" },
+                    describe(&set_operation.right)
+                ),
+                (None, Some(values)) => match &values[0][0] {
+                    ExprNode::Number(number) => number.clone(),
+                    _ => unreachable!(),
+                },
+                (None, None) => "select".to_owned(),
+            }
+        }
+        let query = |sql: &str| match parse_sql(sql).unwrap().1 {
+            Statement::Select(select) => select,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            describe(&query("values (1) union all values (2) except values (3)")),
+            "((1 UNION ALL 2) EXCEPT 3)"
+        );
+        assert_eq!(
+            describe(&query(
+                "values (1) union distinct values (2) intersect all values (3)"
+            )),
+            "(1 UNION (2 INTERSECT ALL 3))"
+        );
+        assert_eq!(
+            describe(&query("values (1) except (values (2) union values (3))")),
+            "(1 EXCEPT (2 UNION 3))"
+        );
+        assert_eq!(
+            describe(&query("select a from b intersect select c")),
+            "(select INTERSECT select)"
+        );
+
+        // ORDER BY and LIMIT apply to the result of the set operation
+        let select = query("select a union select b order by 1 limit 2");
+        assert_eq!(select.order_by.len(), 1);
+        assert_eq!(select.limit, Some(ExprNode::Number("2".to_owned())));
+        let left = &select.set_operation.as_ref().unwrap().left;
+        assert!(left.order_by.is_empty());
+        let select = query("(select a order by a limit 1) union select b");
+        let left = &select.set_operation.as_ref().unwrap().left;
+        assert_eq!(left.limit, Some(ExprNode::Number("1".to_owned())));
+
+        assert!(parse_sql("(select a limit 1) limit 2").is_err());
+        assert!(parse_sql("select a union").is_err());
+        assert!(parse_sql("select a union all all select b").is_err());
+        assert!(parse_sql("(select a union select b").is_err());
+        assert!(parse_sql("insert into a (select 1) union (select 2)").is_ok());
+    }
+
+    #[test]
+    fn can_parse_subqueries() {
+        let expression = |sql: &str| match parse_sql(sql).unwrap().1 {
+            Statement::Select(mut select) => match select.projections.remove(0) {
+                Projection::UnnamedExpr(expr) => expr,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        let subquery = |sql: &str| match parse_sql(sql).unwrap().1 {
+            Statement::Select(select) => Box::new(select),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            expression("select (select max(a) from b)"),
+            ExprNode::Subquery(subquery("select max(a) from b"))
+        );
+        assert_eq!(
+            expression("select ((values (1)))"),
+            ExprNode::Grouping(Box::new(ExprNode::Subquery(subquery("values (1)"))))
+        );
+        assert_eq!(
+            expression("select not exists (select 1 union select 2)"),
+            ExprNode::Unary {
+                op: UnaryOperator::Not,
+                expr: Box::new(ExprNode::Exists(subquery("select 1 union select 2")))
+            }
+        );
+        // IN binds more tightly than comparisons, but less than arithmetic
+        assert_eq!(
+            expression("select a + 1 not in (select b) = true"),
+            ExprNode::Binary {
+                left: Box::new(ExprNode::InSubquery {
+                    expr: Box::new(ExprNode::Binary {
+                        left: Box::new(ExprNode::Identifier("a".to_owned())),
+                        op: BinaryOperator::Plus,
+                        right: Box::new(ExprNode::Number("1".to_owned())),
+                    }),
+                    subquery: subquery("select b"),
+                    negated: true,
+                }),
+                op: BinaryOperator::Eq,
+                right: Box::new(ExprNode::Boolean(true)),
+            }
+        );
+
+        assert!(parse_sql("select exists select 1").is_err());
+        assert!(parse_sql("select a in select 1").is_err());
+        assert!(parse_sql("select a not (select 1)").is_err());
+        assert!(parse_sql("select (select 1").is_err());
+    }
+
+    #[test]
+    fn can_parse_common_table_expressions() {
+        let select = |sql: &str| match parse_sql(sql).unwrap().1 {
+            Statement::Select(select) => select,
+            _ => unreachable!(),
+        };
+
+        let mut query = select("with a as (select 1), b (x, y) as (values (1, 2)) select * from b");
+        let mut expected_query = select("select * from b");
+        expected_query.with = vec![
+            CommonTableExpression {
+                name: "a".to_owned(),
+                columns: vec![],
+                query: select("select 1"),
+                recursive: false,
+            },
+            CommonTableExpression {
+                name: "b".to_owned(),
+                columns: vec!["x".to_owned(), "y".to_owned()],
+                query: select("values (1, 2)"),
+                recursive: false,
+            },
+        ];
+        assert_eq!(query, expected_query);
+
+        // the WITH clause belongs to the whole set operation
+        query = select("with a as (select 1) select * from a union select 2 order by 1");
+        assert_eq!(query.with.len(), 1);
+        assert!(query.set_operation.is_some());
+        assert!(matches!(
+            parse_sql("insert into t with a as (select 1) select * from a").unwrap().1,
+            Statement::Insert { into: _, select } if select.with.len() == 1
+        ));
+
+        let query = select(
+            "with recursive a (n) as (select 1 union all select n + 1 from a where n < 3), \
+             b as (select 1) select * from a",
+        );
+        assert!(query.with.iter().all(|cte| cte.recursive));
+
+        assert!(parse_sql("with a (select 1) select * from a").is_err());
+        assert!(parse_sql("with a as select 1 select * from a").is_err());
+        assert!(parse_sql("with a as (select 1), select * from a").is_err());
+        assert!(parse_sql("with a () as (select 1) select * from a").is_err());
     }
 }
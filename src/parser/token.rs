@@ -5,90 +5,212 @@ use anyhow::{Error, Result};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Keyword {
+    All,
     And,
+    Any,
+    Array,
     As,
+    Asc,
+    Between,
     Boolean,
+    Both,
+    By,
+    Bytea,
+    Case,
+    Cast,
+    Char,
     Commit,
     Committed,
     Create,
     Cross,
+    Current,
+    CurrentDate,
+    CurrentTimestamp,
+    Date,
     Delete,
+    Desc,
+    Distinct,
+    Else,
+    End,
+    Escape,
+    Except,
+    Exists,
     Explain,
+    Extract,
     False,
+    Fetch,
+    First,
+    Following,
+    For,
     From,
+    Full,
+    Group,
+    Having,
+    Ilike,
+    In,
     Inner,
     Insert,
     Integer,
+    Intersect,
+    Interval,
     Into,
     Is,
     Isolation,
     Join,
+    Last,
+    Leading,
     Left,
     Level,
+    Like,
+    Limit,
+    Natural,
+    Next,
     Not,
     Null,
+    Nulls,
+    Offset,
     On,
+    Only,
     Or,
+    Order,
     Outer,
+    Over,
+    Partition,
+    Preceding,
     Read,
+    Recursive,
     Repeatable,
     Right,
     Rollback,
+    Row,
+    Rows,
     Select,
     Set,
     Start,
     Table,
     Text,
+    Then,
+    Time,
+    Timestamp,
+    Trailing,
     Transaction,
     True,
+    Unbounded,
+    Union,
+    Unknown,
     Update,
+    Using,
     Values,
+    Varchar,
+    When,
     Where,
+    With,
 }
 
 impl Keyword {
     /// Returns None if string is not a keyword, else the keyword
     pub fn is_keyword(s: &str) -> Option<Self> {
         let res = match s {
+            "all" => Self::All,
             "and" => Self::And,
+            "any" => Self::Any,
+            "array" => Self::Array,
             "as" => Self::As,
+            "asc" => Self::Asc,
+            "between" => Self::Between,
+            "by" => Self::By,
             "boolean" => Self::Boolean,
+            "both" => Self::Both,
+            "bytea" => Self::Bytea,
+            "case" => Self::Case,
+            "cast" => Self::Cast,
+            "char" => Self::Char,
             "commit" => Self::Commit,
             "committed" => Self::Committed,
             "create" => Self::Create,
             "cross" => Self::Cross,
+            "current" => Self::Current,
+            "current_date" => Self::CurrentDate,
+            "current_timestamp" => Self::CurrentTimestamp,
+            "date" => Self::Date,
             "delete" => Self::Delete,
+            "desc" => Self::Desc,
+            "distinct" => Self::Distinct,
+            "else" => Self::Else,
+            "end" => Self::End,
+            "escape" => Self::Escape,
+            "except" => Self::Except,
+            "exists" => Self::Exists,
             "explain" => Self::Explain,
+            "extract" => Self::Extract,
             "false" => Self::False,
+            "fetch" => Self::Fetch,
+            "first" => Self::First,
+            "following" => Self::Following,
+            "for" => Self::For,
             "from" => Self::From,
+            "full" => Self::Full,
+            "group" => Self::Group,
+            "having" => Self::Having,
+            "ilike" => Self::Ilike,
+            "in" => Self::In,
             "inner" => Self::Inner,
             "insert" => Self::Insert,
             "integer" => Self::Integer,
+            "intersect" => Self::Intersect,
+            "interval" => Self::Interval,
             "into" => Self::Into,
             "is" => Self::Is,
             "isolation" => Self::Isolation,
             "join" => Self::Join,
+            "last" => Self::Last,
+            "leading" => Self::Leading,
             "left" => Self::Left,
             "level" => Self::Level,
+            "like" => Self::Like,
+            "limit" => Self::Limit,
+            "natural" => Self::Natural,
+            "next" => Self::Next,
             "not" => Self::Not,
             "null" => Self::Null,
+            "nulls" => Self::Nulls,
+            "offset" => Self::Offset,
             "on" => Self::On,
+            "only" => Self::Only,
             "or" => Self::Or,
+            "order" => Self::Order,
             "outer" => Self::Outer,
+            "over" => Self::Over,
+            "partition" => Self::Partition,
+            "preceding" => Self::Preceding,
             "read" => Self::Read,
+            "recursive" => Self::Recursive,
             "repeatable" => Self::Repeatable,
             "right" => Self::Right,
             "rollback" => Self::Rollback,
+            "row" => Self::Row,
+            "rows" => Self::Rows,
             "select" => Self::Select,
             "set" => Self::Set,
             "start" => Self::Start,
             "table" => Self::Table,
             "text" => Self::Text,
+            "then" => Self::Then,
+            "time" => Self::Time,
+            "timestamp" => Self::Timestamp,
+            "trailing" => Self::Trailing,
             "transaction" => Self::Transaction,
             "true" => Self::True,
+            "unbounded" => Self::Unbounded,
+            "union" => Self::Union,
+            "unknown" => Self::Unknown,
             "update" => Self::Update,
+            "using" => Self::Using,
             "values" => Self::Values,
+            "varchar" => Self::Varchar,
+            "when" => Self::When,
             "where" => Self::Where,
+            "with" => Self::With,
             _ => return None,
         };
         Some(res)
@@ -107,12 +229,18 @@ pub enum Token {
     QuotedString(String),
     /// Dot '.'
     Dot,
+    /// Double colon '::'
+    DoubleColon,
     /// Comma ','
     Comma,
     /// Left parenthesis '('
     LeftParen,
     /// Right parenthesis ')'
     RightParen,
+    /// Left bracket '['
+    LeftBracket,
+    /// Right bracket ']'
+    RightBracket,
     /// Semicolon ';'
     Semicolon,
     /// star '*'
@@ -139,6 +267,14 @@ pub enum Token {
     GreaterEq,
     /// Exclamation mark '!'
     Exclamation,
+    /// Arrow '->'
+    Arrow,
+    /// Long arrow '->>'
+    LongArrow,
+    /// Contains '@>'
+    AtGreater,
+    /// String concatenation '||'
+    Concat,
     /// not a token, just end of query
     End,
 }
@@ -159,9 +295,9 @@ impl<'a> Tokenizer<'a> {
     fn word(&mut self, start: usize) -> String {
         let mut end = start + 1;
         while let Some((pos, ch)) = self.chars.peek() {
-            if ('a'..='z').contains(ch)
-                || ('A'..='Z').contains(ch)
-                || ('0'..='9').contains(ch)
+            if ch.is_ascii_lowercase()
+                || ch.is_ascii_uppercase()
+                || ch.is_ascii_digit()
                 || *ch == '_'
             {
                 end = *pos + 1;
@@ -177,7 +313,7 @@ impl<'a> Tokenizer<'a> {
     fn number(&mut self, start: usize) -> String {
         let mut end = start + 1;
         while let Some((pos, ch)) = self.chars.peek() {
-            if ('0'..='9').contains(ch) {
+            if ch.is_ascii_digit() {
                 end = *pos + 1;
                 self.chars.next();
                 continue;
@@ -208,12 +344,36 @@ impl<'a> Tokenizer<'a> {
                 ch if ch.is_whitespace() => return self.next_token(),
                 '(' => Token::LeftParen,
                 ')' => Token::RightParen,
+                '[' => Token::LeftBracket,
+                ']' => Token::RightBracket,
                 ';' => Token::Semicolon,
                 ',' => Token::Comma,
                 '.' => Token::Dot,
                 '*' => Token::Star,
                 '+' => Token::Plus,
-                '-' => Token::Minus,
+                '-' => match self.chars.peek() {
+                    Some((_pos, '>')) => {
+                        self.chars.next();
+                        if self.chars.next_if(|(_pos, ch)| *ch == '>').is_some() {
+                            Token::LongArrow
+                        } else {
+                            Token::Arrow
+                        }
+                    }
+                    _ => Token::Minus,
+                },
+                ':' => match self.chars.next() {
+                    Some((_pos, ':')) => Token::DoubleColon,
+                    _ => return Err(Error::msg("Unexpected character ':'")),
+                },
+                '@' => match self.chars.next() {
+                    Some((_pos, '>')) => Token::AtGreater,
+                    _ => return Err(Error::msg("Unexpected character '@'")),
+                },
+                '|' => match self.chars.next() {
+                    Some((_pos, '|')) => Token::Concat,
+                    _ => return Err(Error::msg("Unexpected character '|'")),
+                },
                 '/' => Token::Division,
                 '%' => Token::Modulo,
                 '!' => match self.chars.peek() {
@@ -1,21 +1,62 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::{Error, Result};
 
-use self::physical_plan::{Aggregation, Expr, PhysicalPlan};
+use self::physical_plan::{Aggregation, Expr, PhysicalPlan, SortKey, SubqueryKind, WindowFunction};
 use crate::analyzer::logical_plan::{
-    LogicalExpr, LogicalPlan, Query, TableReference, EMPTY_SCHEMA,
+    CommonTableExpression, LogicalExpr, LogicalPlan, OrderByExpr, Query, SetOperation,
+    TableReference, Window, EMPTY_SCHEMA,
 };
-use crate::catalog::schema::Schema;
+use crate::catalog::schema::{ColumnDefinition, Schema};
+use crate::parser::ast::{BinaryOperator, UnaryOperator};
+use crate::tuple::datetime;
 use crate::tuple::value::Value;
 
 pub mod physical_plan;
 
-pub struct Planner {}
+pub struct Planner {
+    /// the common table expressions which are referenced only once, by their id. They are
+    /// planned in place of their reference
+    inlined_ctes: RefCell<HashMap<usize, Query>>,
+    /// the ids of the recursive CTEs, whose recursive term is being planned. Their
+    /// references scan the working table
+    working_tables: RefCell<HashSet<usize>>,
+    /// the window functions of the query being planned together with the columns, which
+    /// contain their results
+    window_columns: RefCell<Vec<(LogicalExpr, usize)>>,
+}
+
+/// The input of a SELECT together with the expressions, which are evaluated on its tuples
+struct PlannedSelect {
+    plan: PhysicalPlan,
+    sort_keys: Vec<SortKey>,
+    distinct: Vec<Expr>,
+    projections: Vec<Expr>,
+}
+
+/// The window functions, which share the same window, together with the sort, which orders the
+/// tuples by partition and within the partitions
+struct PlannedWindow {
+    sort_keys: Vec<SortKey>,
+    partition_by: Vec<Expr>,
+    order_by: Vec<Expr>,
+    functions: Vec<WindowFunction>,
+}
+
+impl Default for Planner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Planner {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            inlined_ctes: RefCell::new(HashMap::new()),
+            working_tables: RefCell::new(HashSet::new()),
+            window_columns: RefCell::new(vec![]),
+        }
     }
 
     pub fn prepare_logical_plan(&self, logical_plan: LogicalPlan) -> Result<PhysicalPlan> {
@@ -44,13 +85,13 @@ impl Planner {
         set_expressions: HashMap<Vec<String>, LogicalExpr>,
         filter: Vec<LogicalExpr>,
     ) -> Result<PhysicalPlan> {
-        let table_id = match &table {
+        let (table_id, target_schema) = match &table {
             TableReference::BaseTable {
                 table_id,
                 name: _,
-                schema: _,
+                schema,
                 filter: _,
-            } => *table_id,
+            } => (*table_id, schema.clone()),
             _ => unreachable!(),
         };
 
@@ -76,6 +117,7 @@ impl Planner {
 
         Ok(PhysicalPlan::Update {
             table: table_id,
+            target_schema,
             set: set_expressions,
             child: Box::new(child),
         })
@@ -100,12 +142,64 @@ impl Planner {
         })
     }
 
-    fn plan_query(&self, query: Query) -> Result<PhysicalPlan> {
+    fn plan_query(&self, mut query: Query) -> Result<PhysicalPlan> {
+        let ctes = self.plan_ctes(std::mem::take(&mut query.ctes))?;
+        // the window functions of an enclosing query can't be referenced by a subquery
+        let outer_window_columns = self.window_columns.take();
+        let plan = self.plan_query_without_ctes(query);
+        self.window_columns.replace(outer_window_columns);
+        let plan = plan?;
+        if ctes.is_empty() {
+            Ok(plan)
+        } else {
+            Ok(PhysicalPlan::With {
+                ctes,
+                child: Box::new(plan),
+            })
+        }
+    }
+
+    /// Plans the common table expressions, which are referenced more than once, so that they
+    /// can be materialized. The others are planned at their reference, unreferenced ones not
+    /// at all
+    fn plan_ctes(&self, ctes: Vec<CommonTableExpression>) -> Result<Vec<(usize, PhysicalPlan)>> {
+        let mut materialized = vec![];
+        for cte in ctes {
+            let CommonTableExpression {
+                id,
+                name: _,
+                query,
+                references,
+            } = cte;
+            match references {
+                0 => (),
+                1 => {
+                    self.inlined_ctes.borrow_mut().insert(id, query);
+                }
+                _ => materialized.push((id, query)),
+            }
+        }
+        // the inlined CTEs have to be known first, as they can be referenced by later CTEs
+        materialized
+            .into_iter()
+            .map(|(id, query)| Ok((id, self.plan_query(query)?)))
+            .collect()
+    }
+
+    fn plan_query_without_ctes(&self, query: Query) -> Result<PhysicalPlan> {
         let Query {
+            ctes: _,
             values,
             from,
             projections,
             filter,
+            group_by,
+            having,
+            order_by,
+            distinct,
+            limit,
+            offset,
+            set_operation,
             output_schema,
         } = query;
 
@@ -118,27 +212,471 @@ impl Planner {
                 output_schema,
             })
         } else {
-            let mut plan = self.plan_table_reference(from)?;
-            plan = self.plan_filter(filter, plan)?;
+            let limit = self.plan_row_count(limit, "LIMIT")?;
+            let offset = self.plan_row_count(offset, "OFFSET")?.unwrap_or(0);
+            // with ORDER BY, only the first rows up to the limit need to be sorted
+            let top_n = limit.map(|limit| limit.saturating_add(offset));
 
-            if projections.iter().any(|expr| expr.has_aggregation()) {
-                let (aggregations, projections) = self.plan_aggregations(projections, &plan)?;
-                plan = PhysicalPlan::Aggregate {
-                    aggregations,
-                    child: Box::new(plan),
-                };
-                plan = PhysicalPlan::Projection {
-                    projections,
+            let is_grouped = !group_by.is_empty()
+                || !having.is_empty()
+                || projections.iter().any(|expr| expr.has_aggregation())
+                || order_by.iter().any(|order| order.expr.has_aggregation())
+                || distinct.iter().any(|expr| expr.has_aggregation());
+            let PlannedSelect {
+                plan,
+                sort_keys,
+                distinct,
+                projections,
+            } = if let Some(set_operation) = set_operation {
+                self.plan_set_operation(*set_operation, order_by, &output_schema)?
+            } else {
+                let plan = self.plan_table_reference(from)?;
+                let plan = self.plan_filter(filter, plan)?;
+                if is_grouped {
+                    self.plan_grouping(group_by, having, order_by, distinct, projections, plan)?
+                } else {
+                    let windows = self.plan_windows(
+                        Self::collect_window_functions(&projections, &order_by, &distinct),
+                        plan.schema().columns().len(),
+                        |expr| self.plan_expression(expr, &[&plan]),
+                    )?;
+                    let sort_keys =
+                        self.plan_sort_keys(order_by, |expr| self.plan_expression(expr, &[&plan]))?;
+                    let distinct = self.plan_expressions(distinct, &[&plan])?;
+                    let projections = self.plan_expressions(projections, &[&plan])?;
+                    PlannedSelect {
+                        plan: self.plan_window_evaluation(windows, plan),
+                        sort_keys,
+                        distinct,
+                        projections,
+                    }
+                }
+            };
+
+            // duplicates are removed before the limit is applied, so all tuples must be sorted
+            let top_n = if distinct.is_empty() { top_n } else { None };
+            let mut plan = self.plan_sort(sort_keys, top_n, plan);
+            plan = self.plan_distinct(distinct, plan);
+            plan = PhysicalPlan::Projection {
+                projections,
+                child: Box::new(plan),
+                output_schema,
+            };
+
+            if limit.is_some() || offset > 0 {
+                plan = PhysicalPlan::Limit {
+                    limit,
+                    offset,
                     child: Box::new(plan),
-                    output_schema,
                 };
-            } else {
-                plan = self.plan_projections(projections, output_schema, plan)?;
             }
             Ok(plan)
         }
     }
 
+    /// Plans UNION, INTERSECT or EXCEPT of two queries. The sort keys reference the output
+    /// columns by position, the projections return all output columns
+    fn plan_set_operation(
+        &self,
+        set_operation: SetOperation,
+        order_by: Vec<OrderByExpr>,
+        output_schema: &Schema,
+    ) -> Result<PlannedSelect> {
+        let SetOperation {
+            op,
+            all,
+            left,
+            right,
+            working_table,
+        } = set_operation;
+        let left = self.plan_query(left)?;
+        if let Some(cte_id) = working_table {
+            self.working_tables.borrow_mut().insert(cte_id);
+        }
+        let right = self.plan_query(right);
+        if let Some(cte_id) = working_table {
+            self.working_tables.borrow_mut().remove(&cte_id);
+        }
+        let right = right?;
+
+        let positional_columns = output_schema
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(col, col_def)| {
+                ColumnDefinition::new(
+                    col_def.type_id,
+                    (col + 1).to_string(),
+                    col as u8,
+                    col_def.not_null,
+                )
+            })
+            .collect();
+        let plan = match working_table {
+            Some(cte_id) => PhysicalPlan::RecursiveUnion {
+                cte_id,
+                all,
+                left: Box::new(left),
+                right: Box::new(right),
+                output_schema: Schema::new(positional_columns),
+            },
+            None => PhysicalPlan::SetOperation {
+                op,
+                all,
+                left: Box::new(left),
+                right: Box::new(right),
+                output_schema: Schema::new(positional_columns),
+            },
+        };
+
+        let sort_keys =
+            self.plan_sort_keys(order_by, |expr| self.plan_expression(expr, &[&plan]))?;
+        let projections = (0..output_schema.columns().len())
+            .map(|col_idx| Expr::ColumnReference {
+                tuple_idx: 0,
+                col_idx,
+            })
+            .collect();
+        Ok(PlannedSelect {
+            plan,
+            sort_keys,
+            distinct: vec![],
+            projections,
+        })
+    }
+
+    /// Plans the aggregation of a grouped query, followed by the HAVING filter and the window
+    /// functions. The sort keys, the DISTINCT expressions and the projections reference the
+    /// aggregated tuples. Without any GROUP BY expressions, all tuples form a single group
+    fn plan_grouping(
+        &self,
+        group_by: Vec<LogicalExpr>,
+        having: Vec<LogicalExpr>,
+        order_by: Vec<OrderByExpr>,
+        distinct: Vec<LogicalExpr>,
+        projections: Vec<LogicalExpr>,
+        child: PhysicalPlan,
+    ) -> Result<PlannedSelect> {
+        let mut aggregations = vec![];
+        // the results of the window functions follow the aggregation results, so all
+        // aggregations have to be known before the window functions are planned
+        for aggregation in projections
+            .iter()
+            .chain(having.iter())
+            .chain(order_by.iter().map(|order| &order.expr))
+            .chain(distinct.iter())
+            .flat_map(|expr| expr.aggregations())
+        {
+            self.plan_aggregation(aggregation.clone(), &group_by, &child, &mut aggregations)?;
+        }
+        let windows = self.plan_windows(
+            Self::collect_window_functions(&projections, &order_by, &distinct),
+            group_by.len() + aggregations.len(),
+            |expr| self.plan_aggregation(expr, &group_by, &child, &mut aggregations),
+        )?;
+
+        let projections = projections
+            .into_iter()
+            .map(|expr| self.plan_aggregation(expr, &group_by, &child, &mut aggregations))
+            .collect::<Result<Vec<_>>>()?;
+        let having = having
+            .into_iter()
+            .map(|expr| self.plan_aggregation(expr, &group_by, &child, &mut aggregations))
+            .collect::<Result<Vec<_>>>()?;
+        let sort_keys = self.plan_sort_keys(order_by, |expr| {
+            self.plan_aggregation(expr, &group_by, &child, &mut aggregations)
+        })?;
+        let distinct = distinct
+            .into_iter()
+            .map(|expr| self.plan_aggregation(expr, &group_by, &child, &mut aggregations))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut plan = if group_by.is_empty() {
+            let aggregate_schema = PhysicalPlan::aggregate_schema(&[], &aggregations, &child);
+            PhysicalPlan::Aggregate {
+                aggregations,
+                child: Box::new(child),
+                output_schema: aggregate_schema,
+            }
+        } else {
+            let group_by = self.plan_expressions(group_by, &[&child])?;
+            let aggregate_schema = PhysicalPlan::aggregate_schema(&group_by, &aggregations, &child);
+            PhysicalPlan::HashAggregate {
+                group_by,
+                aggregations,
+                child: Box::new(child),
+                output_schema: aggregate_schema,
+            }
+        };
+
+        if !having.is_empty() {
+            plan = PhysicalPlan::Filter {
+                filter: having,
+                child: Box::new(plan),
+            };
+        }
+        plan = self.plan_window_evaluation(windows, plan);
+
+        Ok(PlannedSelect {
+            plan,
+            sort_keys,
+            distinct,
+            projections,
+        })
+    }
+
+    /// Returns the distinct window functions of the expressions, which are evaluated after the
+    /// grouping
+    fn collect_window_functions(
+        projections: &[LogicalExpr],
+        order_by: &[OrderByExpr],
+        distinct: &[LogicalExpr],
+    ) -> Vec<LogicalExpr> {
+        let mut window_functions: Vec<LogicalExpr> = vec![];
+        for expr in projections
+            .iter()
+            .chain(order_by.iter().map(|order| &order.expr))
+            .chain(distinct.iter())
+            .flat_map(|expr| expr.window_functions())
+        {
+            if !window_functions.contains(expr) {
+                window_functions.push(expr.clone());
+            }
+        }
+        window_functions
+    }
+
+    /// Plans the window functions with the given planning function, which transforms their
+    /// arguments and windows. The functions with the same window are evaluated together. Their
+    /// results are appended to the tuples, starting at the given column, and are registered, so
+    /// that the expressions of the query can reference them
+    fn plan_windows(
+        &self,
+        window_functions: Vec<LogicalExpr>,
+        first_column: usize,
+        mut plan_expr: impl FnMut(LogicalExpr) -> Result<Expr>,
+    ) -> Result<Vec<PlannedWindow>> {
+        let mut grouped_functions: Vec<(Window, Vec<LogicalExpr>)> = vec![];
+        for expr in window_functions {
+            let window = match &expr {
+                LogicalExpr::WindowFunction {
+                    func: _,
+                    args: _,
+                    window,
+                } => window,
+                _ => unreachable!(),
+            };
+            let same_window = grouped_functions.iter_mut().find(|(other, _)| {
+                other.partition_by == window.partition_by && other.order_by == window.order_by
+            });
+            match same_window {
+                Some((_, functions)) => functions.push(expr),
+                None => grouped_functions.push((window.as_ref().clone(), vec![expr])),
+            }
+        }
+
+        let mut windows = vec![];
+        let mut column = first_column;
+        for (window, window_functions) in grouped_functions {
+            let Window {
+                partition_by,
+                order_by,
+                frame: _,
+            } = window;
+            // the keys are needed by the sort as well as by the window, so they are planned twice
+            let partition_keys = partition_by.iter().map(|expr| OrderByExpr {
+                expr: expr.clone(),
+                ascending: true,
+                nulls_first: false,
+            });
+            let sort_keys = self.plan_sort_keys(
+                partition_keys.chain(order_by.iter().cloned()).collect(),
+                &mut plan_expr,
+            )?;
+            let partition_by = partition_by
+                .into_iter()
+                .map(&mut plan_expr)
+                .collect::<Result<Vec<_>>>()?;
+            let order_by = order_by
+                .into_iter()
+                .map(|order| plan_expr(order.expr))
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut functions = vec![];
+            for expr in window_functions {
+                let (func, args, frame) = match expr.clone() {
+                    LogicalExpr::WindowFunction { func, args, window } => {
+                        (func, args, window.frame)
+                    }
+                    _ => unreachable!(),
+                };
+                let args = args
+                    .into_iter()
+                    .map(&mut plan_expr)
+                    .collect::<Result<Vec<_>>>()?;
+                let frame = frame
+                    .map(|frame| {
+                        frame.try_map(|offset| {
+                            self.plan_row_count(Some(offset), "ROWS")?
+                                .ok_or_else(|| Error::msg("Frame offsets must not be NULL"))
+                        })
+                    })
+                    .transpose()?;
+                functions.push(WindowFunction { func, args, frame });
+                self.window_columns.borrow_mut().push((expr, column));
+                column += 1;
+            }
+            windows.push(PlannedWindow {
+                sort_keys,
+                partition_by,
+                order_by,
+                functions,
+            });
+        }
+        Ok(windows)
+    }
+
+    /// Evaluates the planned windows one after another, each on tuples sorted by its keys
+    fn plan_window_evaluation(
+        &self,
+        windows: Vec<PlannedWindow>,
+        mut plan: PhysicalPlan,
+    ) -> PhysicalPlan {
+        for window in windows {
+            let PlannedWindow {
+                sort_keys,
+                partition_by,
+                order_by,
+                functions,
+            } = window;
+            plan = self.plan_sort(sort_keys, None, plan);
+            let output_schema = PhysicalPlan::window_schema(&functions, &plan);
+            plan = PhysicalPlan::Window {
+                partition_by,
+                order_by,
+                functions,
+                child: Box::new(plan),
+                output_schema,
+            };
+        }
+        plan
+    }
+
+    /// Returns the column with the result of a window function of the query being planned
+    fn plan_window_function_reference(&self, expr: &LogicalExpr) -> Result<Expr> {
+        let window_columns = self.window_columns.borrow();
+        match window_columns
+            .iter()
+            .find(|(window_function, _)| window_function == expr)
+        {
+            Some((_, col_idx)) => Ok(Expr::ColumnReference {
+                tuple_idx: 0,
+                col_idx: *col_idx,
+            }),
+            None => Err(Error::msg(
+                "Window functions are only allowed in the SELECT list and in ORDER BY",
+            )),
+        }
+    }
+
+    /// Transforms the ORDER BY expressions with the given planning function
+    fn plan_sort_keys(
+        &self,
+        order_by: Vec<OrderByExpr>,
+        mut plan_expr: impl FnMut(LogicalExpr) -> Result<Expr>,
+    ) -> Result<Vec<SortKey>> {
+        order_by
+            .into_iter()
+            .map(|order| {
+                let OrderByExpr {
+                    expr,
+                    ascending,
+                    nulls_first,
+                } = order;
+                Ok(SortKey {
+                    expr: plan_expr(expr)?,
+                    ascending,
+                    nulls_first,
+                })
+            })
+            .collect()
+    }
+
+    /// Plans a sort, which only has to keep the first top_n tuples if given
+    fn plan_sort(
+        &self,
+        sort_keys: Vec<SortKey>,
+        top_n: Option<usize>,
+        child: PhysicalPlan,
+    ) -> PhysicalPlan {
+        if sort_keys.is_empty() {
+            return child;
+        }
+        match top_n {
+            Some(limit) => PhysicalPlan::TopNSort {
+                sort_keys,
+                limit,
+                child: Box::new(child),
+            },
+            None => PhysicalPlan::Sort {
+                sort_keys,
+                child: Box::new(child),
+            },
+        }
+    }
+
+    /// Plans the removal of tuples with duplicate keys. If the child is sorted by the keys,
+    /// duplicates are adjacent and only need to be compared to the previous tuple
+    fn plan_distinct(&self, keys: Vec<Expr>, child: PhysicalPlan) -> PhysicalPlan {
+        if keys.is_empty() {
+            return child;
+        }
+        let sort_keys = match &child {
+            PhysicalPlan::Sort {
+                sort_keys,
+                child: _,
+            } => sort_keys.as_slice(),
+            _ => &[],
+        };
+        let leading_sort_keys = sort_keys.iter().take(keys.len()).collect::<Vec<_>>();
+        let is_sorted = leading_sort_keys.len() == keys.len()
+            && leading_sort_keys.iter().all(|key| keys.contains(&key.expr))
+            && keys
+                .iter()
+                .all(|expr| leading_sort_keys.iter().any(|key| &key.expr == expr));
+
+        if is_sorted {
+            PhysicalPlan::SortDistinct {
+                keys,
+                child: Box::new(child),
+            }
+        } else {
+            PhysicalPlan::HashDistinct {
+                keys,
+                child: Box::new(child),
+            }
+        }
+    }
+
+    /// Evaluates the argument of LIMIT or OFFSET, returns None if it is absent or NULL
+    fn plan_row_count(&self, expr: Option<LogicalExpr>, clause: &str) -> Result<Option<usize>> {
+        let expr = match expr {
+            Some(expr) => self.plan_expression(expr, &[])?,
+            None => return Ok(None),
+        };
+        match expr.evaluate(&[])? {
+            Value::Integer(count) if count < 0 => {
+                Err(Error::msg(format!("{} must not be negative", clause)))
+            }
+            Value::Integer(count) => Ok(Some(count as usize)),
+            Value::Null => Ok(None),
+            value => Err(Error::msg(format!(
+                "Argument of {} must be of type integer, but found {}",
+                clause, value
+            ))),
+        }
+    }
+
     fn plan_filter(&self, filter: Vec<LogicalExpr>, child: PhysicalPlan) -> Result<PhysicalPlan> {
         if !filter.is_empty() {
             Ok(PhysicalPlan::Filter {
@@ -170,6 +708,7 @@ impl Planner {
                 right,
                 join_type,
                 on,
+                using: _,
             } => {
                 let left_child = self.plan_table_reference(*left)?;
                 let right_child = self.plan_table_reference(*right)?;
@@ -177,8 +716,10 @@ impl Planner {
                 let on = self.plan_expressions(on, &[&left_child, &right_child])?;
 
                 let mut left_columns = left_child.schema().columns().to_vec();
-                let mut right_columns = right_child.schema().columns().to_vec();
-                left_columns.append(&mut right_columns);
+                if !join_type.returns_left_only() {
+                    let mut right_columns = right_child.schema().columns().to_vec();
+                    left_columns.append(&mut right_columns);
+                }
                 let output_schema = Schema::new(left_columns);
 
                 PhysicalPlan::NestedLoopJoin {
@@ -189,6 +730,58 @@ impl Planner {
                     output_schema,
                 }
             }
+            TableReference::TableFunction {
+                func,
+                args,
+                name,
+                mut schema,
+                filter,
+            } => {
+                schema.prepend_column_name(&name);
+                let function_scan = PhysicalPlan::TableFunctionScan {
+                    func,
+                    args: self.plan_expressions(args, &[])?,
+                    output_schema: schema,
+                };
+                self.plan_filter(filter, function_scan)?
+            }
+            TableReference::Cte {
+                cte_id,
+                name,
+                mut schema,
+                filter,
+            } => {
+                schema.prepend_column_name(&name);
+                if self.working_tables.borrow().contains(&cte_id) {
+                    let working_table_scan = PhysicalPlan::WorkingTableScan {
+                        cte_id,
+                        output_schema: schema,
+                    };
+                    return self.plan_filter(filter, working_table_scan);
+                }
+                let inlined_query = self.inlined_ctes.borrow_mut().remove(&cte_id);
+                let plan = match inlined_query {
+                    Some(query) => {
+                        let child = self.plan_query(query)?;
+                        // only renames the columns, so that they can be referenced by name
+                        PhysicalPlan::Projection {
+                            projections: (0..schema.columns().len())
+                                .map(|col_idx| Expr::ColumnReference {
+                                    tuple_idx: 0,
+                                    col_idx,
+                                })
+                                .collect(),
+                            child: Box::new(child),
+                            output_schema: schema,
+                        }
+                    }
+                    None => PhysicalPlan::CteScan {
+                        cte_id,
+                        output_schema: schema,
+                    },
+                };
+                self.plan_filter(filter, plan)?
+            }
             TableReference::EmptyTable => PhysicalPlan::Values {
                 values: vec![vec![]],
                 output_schema: EMPTY_SCHEMA.clone(),
@@ -198,23 +791,6 @@ impl Planner {
         Ok(plan)
     }
 
-    fn plan_projections(
-        &self,
-        projections: Vec<LogicalExpr>,
-        schema: Schema,
-        child: PhysicalPlan,
-    ) -> Result<PhysicalPlan> {
-        if projections.is_empty() {
-            Ok(child)
-        } else {
-            Ok(PhysicalPlan::Projection {
-                projections: self.plan_expressions(projections, &[&child])?,
-                child: Box::new(child),
-                output_schema: schema,
-            })
-        }
-    }
-
     fn plan_expressions(
         &self,
         logical_expressions: Vec<LogicalExpr>,
@@ -226,66 +802,152 @@ impl Planner {
             .collect()
     }
 
-    /// Transforms the expressions (of which at least one contains an aggregation) and returns
-    /// the aggregations and their projections
-    fn plan_aggregations(
-        &self,
-        exprs: Vec<LogicalExpr>,
-        child: &PhysicalPlan,
-    ) -> Result<(Vec<Aggregation>, Vec<Expr>)> {
-        let mut aggregations = vec![];
-
-        let planned_expressions = exprs
-            .into_iter()
-            .map(|expr| self.plan_aggregation(expr, child, &mut aggregations))
-            .collect::<Result<Vec<_>>>()?;
-
-        Ok((aggregations, planned_expressions))
-    }
-
     /// Transforms a logical to a physical expression. Any encountered aggregations is pushed to the aggregations vec
     /// and replaced by a column reference, so that the aggregation result can be referenced by parent physical plans
     fn plan_aggregation(
         &self,
         expr: LogicalExpr,
+        group_by: &[LogicalExpr],
         child: &PhysicalPlan,
         aggregations: &mut Vec<Aggregation>,
     ) -> Result<Expr> {
+        // the aggregation's output tuple starts with the values of the group
+        if let Some(col_idx) = group_by.iter().position(|group_expr| group_expr == &expr) {
+            return Ok(Expr::ColumnReference {
+                tuple_idx: 0,
+                col_idx,
+            });
+        }
+
         let res = match expr {
             LogicalExpr::Column(_) => {
-                unreachable!("Mixing column references and aggregations is not possible")
+                unreachable!("Column references outside of aggregations must be grouped")
             }
+            LogicalExpr::OuterColumn(path) => return Err(Self::correlated_column_error(&path)),
             LogicalExpr::Integer(num) => Expr::Value(Value::Integer(num)),
             LogicalExpr::String(s) => Expr::Value(Value::String(s)),
             LogicalExpr::Boolean(val) => Expr::Value(Value::Boolean(val)),
+            LogicalExpr::Date(date) => Expr::Value(Value::Date(date)),
+            LogicalExpr::Time(time) => Expr::Value(Value::Time(time)),
+            LogicalExpr::Timestamp(ts) => Expr::Value(Value::Timestamp(ts)),
+            LogicalExpr::Interval(interval) => Expr::Value(Value::Interval(interval)),
+            LogicalExpr::Bytea(bytes) => Expr::Value(Value::Bytea(bytes)),
+            LogicalExpr::Uuid(uuid) => Expr::Value(Value::Uuid(uuid)),
+            LogicalExpr::Json(json) => Expr::Value(Value::Json(json)),
+            LogicalExpr::CurrentDate => Expr::Value(Value::Date(datetime::current_date())),
+            LogicalExpr::CurrentTimestamp => {
+                Expr::Value(Value::Timestamp(datetime::current_timestamp()))
+            }
             LogicalExpr::Null => Expr::Value(Value::Null),
+            LogicalExpr::Extract { field, expr } => Expr::Extract {
+                field,
+                expr: Box::new(self.plan_aggregation(*expr, group_by, child, aggregations)?),
+            },
+            LogicalExpr::ScalarFunction(func, args) => Expr::ScalarFunction(
+                func,
+                args.into_iter()
+                    .map(|arg| self.plan_aggregation(arg, group_by, child, aggregations))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            LogicalExpr::Case {
+                operand,
+                conditions,
+                else_result,
+            } => {
+                let mut plan = |expr| self.plan_aggregation(expr, group_by, child, aggregations);
+                Expr::Case {
+                    operand: operand
+                        .map(|operand| plan(*operand))
+                        .transpose()?
+                        .map(Box::new),
+                    conditions: conditions
+                        .into_iter()
+                        .map(|(condition, result)| Ok((plan(condition)?, plan(result)?)))
+                        .collect::<Result<Vec<_>>>()?,
+                    else_result: else_result
+                        .map(|else_result| plan(*else_result))
+                        .transpose()?
+                        .map(Box::new),
+                }
+            }
+            LogicalExpr::WindowFunction {
+                func: _,
+                args: _,
+                window: _,
+            } => self.plan_window_function_reference(&expr)?,
             LogicalExpr::Unary { op, expr } => Expr::Unary {
                 op,
-                expr: Box::new(self.plan_aggregation(*expr, child, aggregations)?),
+                expr: Box::new(self.plan_aggregation(*expr, group_by, child, aggregations)?),
             },
             LogicalExpr::Binary { left, op, right } => Expr::Binary {
-                left: Box::new(self.plan_aggregation(*left, child, aggregations)?),
+                left: Box::new(self.plan_aggregation(*left, group_by, child, aggregations)?),
                 op,
-                right: Box::new(self.plan_aggregation(*right, child, aggregations)?),
+                right: Box::new(self.plan_aggregation(*right, group_by, child, aggregations)?),
             },
             LogicalExpr::IsNull(expr) => Expr::IsNull(Box::new(self.plan_aggregation(
                 *expr,
+                group_by,
                 child,
                 aggregations,
             )?)),
             LogicalExpr::IsNotNull(expr) => Expr::IsNotNull(Box::new(self.plan_aggregation(
                 *expr,
+                group_by,
                 child,
                 aggregations,
             )?)),
-            LogicalExpr::Aggregation(agg_func, expr) => {
-                aggregations.push(Aggregation::new(
-                    agg_func,
-                    self.plan_expression(*expr, &[child])?,
-                ));
+            LogicalExpr::Array(elements) => Expr::Array(
+                elements
+                    .into_iter()
+                    .map(|element| self.plan_aggregation(element, group_by, child, aggregations))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            LogicalExpr::Cast {
+                expr,
+                type_id,
+                text_length,
+            } => Expr::Cast {
+                expr: Box::new(self.plan_aggregation(*expr, group_by, child, aggregations)?),
+                type_id,
+                text_length,
+            },
+            LogicalExpr::Subscript { expr, index } => Expr::Subscript {
+                expr: Box::new(self.plan_aggregation(*expr, group_by, child, aggregations)?),
+                index: Box::new(self.plan_aggregation(*index, group_by, child, aggregations)?),
+            },
+            LogicalExpr::AnyComparison { left, op, array } => Expr::AnyComparison {
+                left: Box::new(self.plan_aggregation(*left, group_by, child, aggregations)?),
+                op,
+                array: Box::new(self.plan_aggregation(*array, group_by, child, aggregations)?),
+            },
+            LogicalExpr::Subquery(query) => self.plan_subquery(SubqueryKind::Scalar, *query)?,
+            LogicalExpr::Exists(query) => self.plan_subquery(SubqueryKind::Exists, *query)?,
+            LogicalExpr::InSubquery {
+                expr,
+                query,
+                negated,
+            } => {
+                let expr = self.plan_aggregation(*expr, group_by, child, aggregations)?;
+                self.plan_in_subquery(expr, *query, negated)?
+            }
+            LogicalExpr::Aggregation {
+                func,
+                args,
+                distinct,
+            } => {
+                let args = self.plan_expressions(args, &[child])?;
+                let aggregation = Aggregation::new(func, args, distinct);
+                // identical aggregations are only computed once
+                let agg_idx = match aggregations.iter().position(|agg| agg == &aggregation) {
+                    Some(agg_idx) => agg_idx,
+                    None => {
+                        aggregations.push(aggregation);
+                        aggregations.len() - 1
+                    }
+                };
                 Expr::ColumnReference {
                     tuple_idx: 0,
-                    col_idx: aggregations.len() - 1,
+                    col_idx: group_by.len() + agg_idx,
                 }
             }
         };
@@ -299,10 +961,55 @@ impl Planner {
     ) -> Result<Expr> {
         let res = match logical_expr {
             LogicalExpr::Column(path) => self.resolve_column(path, children)?,
+            LogicalExpr::OuterColumn(path) => return Err(Self::correlated_column_error(&path)),
             LogicalExpr::Integer(num) => Expr::Value(Value::Integer(num)),
             LogicalExpr::String(s) => Expr::Value(Value::String(s)),
             LogicalExpr::Boolean(val) => Expr::Value(Value::Boolean(val)),
+            LogicalExpr::Date(date) => Expr::Value(Value::Date(date)),
+            LogicalExpr::Time(time) => Expr::Value(Value::Time(time)),
+            LogicalExpr::Timestamp(ts) => Expr::Value(Value::Timestamp(ts)),
+            LogicalExpr::Interval(interval) => Expr::Value(Value::Interval(interval)),
+            LogicalExpr::Bytea(bytes) => Expr::Value(Value::Bytea(bytes)),
+            LogicalExpr::Uuid(uuid) => Expr::Value(Value::Uuid(uuid)),
+            LogicalExpr::Json(json) => Expr::Value(Value::Json(json)),
+            LogicalExpr::CurrentDate => Expr::Value(Value::Date(datetime::current_date())),
+            LogicalExpr::CurrentTimestamp => {
+                Expr::Value(Value::Timestamp(datetime::current_timestamp()))
+            }
             LogicalExpr::Null => Expr::Value(Value::Null),
+            LogicalExpr::Extract { field, expr } => Expr::Extract {
+                field,
+                expr: Box::new(self.plan_expression(*expr, children)?),
+            },
+            LogicalExpr::ScalarFunction(func, args) => {
+                Expr::ScalarFunction(func, self.plan_expressions(args, children)?)
+            }
+            LogicalExpr::Case {
+                operand,
+                conditions,
+                else_result,
+            } => {
+                let plan = |expr| self.plan_expression(expr, children);
+                Expr::Case {
+                    operand: operand
+                        .map(|operand| plan(*operand))
+                        .transpose()?
+                        .map(Box::new),
+                    conditions: conditions
+                        .into_iter()
+                        .map(|(condition, result)| Ok((plan(condition)?, plan(result)?)))
+                        .collect::<Result<Vec<_>>>()?,
+                    else_result: else_result
+                        .map(|else_result| plan(*else_result))
+                        .transpose()?
+                        .map(Box::new),
+                }
+            }
+            LogicalExpr::WindowFunction {
+                func: _,
+                args: _,
+                window: _,
+            } => self.plan_window_function_reference(&logical_expr)?,
             LogicalExpr::Unary { op, expr } => Expr::Unary {
                 op,
                 expr: Box::new(self.plan_expression(*expr, children)?),
@@ -318,11 +1025,79 @@ impl Planner {
             LogicalExpr::IsNotNull(expr) => {
                 Expr::IsNotNull(Box::new(self.plan_expression(*expr, children)?))
             }
-            LogicalExpr::Aggregation(_, _) => unreachable!(),
+            LogicalExpr::Array(elements) => Expr::Array(self.plan_expressions(elements, children)?),
+            LogicalExpr::Cast {
+                expr,
+                type_id,
+                text_length,
+            } => Expr::Cast {
+                expr: Box::new(self.plan_expression(*expr, children)?),
+                type_id,
+                text_length,
+            },
+            LogicalExpr::Subscript { expr, index } => Expr::Subscript {
+                expr: Box::new(self.plan_expression(*expr, children)?),
+                index: Box::new(self.plan_expression(*index, children)?),
+            },
+            LogicalExpr::AnyComparison { left, op, array } => Expr::AnyComparison {
+                left: Box::new(self.plan_expression(*left, children)?),
+                op,
+                array: Box::new(self.plan_expression(*array, children)?),
+            },
+            LogicalExpr::Aggregation {
+                func: _,
+                args: _,
+                distinct: _,
+            } => unreachable!(),
+            LogicalExpr::Subquery(query) => self.plan_subquery(SubqueryKind::Scalar, *query)?,
+            LogicalExpr::Exists(query) => self.plan_subquery(SubqueryKind::Exists, *query)?,
+            LogicalExpr::InSubquery {
+                expr,
+                query,
+                negated,
+            } => {
+                let expr = self.plan_expression(*expr, children)?;
+                self.plan_in_subquery(expr, *query, negated)?
+            }
         };
         Ok(res)
     }
 
+    /// Correlated subqueries are only executed as semi or anti joins, into which the
+    /// optimizer rewrites EXISTS and IN conditions. Every other outer reference is left over
+    fn correlated_column_error(path: &[String]) -> Error {
+        Error::msg(format!(
+            "Column {} of an outer query can only be referenced by EXISTS and IN subqueries \
+             in WHERE",
+            path.join(".")
+        ))
+    }
+
+    fn plan_subquery(&self, kind: SubqueryKind, query: Query) -> Result<Expr> {
+        Ok(Expr::Subquery {
+            kind,
+            plan: Box::new(self.plan_query(query)?),
+        })
+    }
+
+    /// expr IN (SELECT ...) is evaluated like expr = ANY(ARRAY(SELECT ...)), which has the
+    /// same semantics for NULL values
+    fn plan_in_subquery(&self, expr: Expr, query: Query, negated: bool) -> Result<Expr> {
+        let expr = Expr::AnyComparison {
+            left: Box::new(expr),
+            op: BinaryOperator::Eq,
+            array: Box::new(self.plan_subquery(SubqueryKind::Array, query)?),
+        };
+        if negated {
+            Ok(Expr::Unary {
+                op: UnaryOperator::Not,
+                expr: Box::new(expr),
+            })
+        } else {
+            Ok(expr)
+        }
+    }
+
     fn resolve_column(&self, path: Vec<String>, children: &[&PhysicalPlan]) -> Result<Expr> {
         let column_name = path.join(".");
         if children.is_empty() {
@@ -1,51 +1,190 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Debug};
 
-use crate::analyzer::logical_plan::AggregationFunc;
-use crate::catalog::schema::Schema;
+use anyhow::{Error, Result};
+use rand::Rng;
+
+use crate::analyzer::logical_plan::{AggregationFunc, ScalarFunc, TableFunc, WindowFunc};
+use crate::catalog::schema::{ColumnDefinition, Schema, TextLength, TypeId};
 use crate::common::TableId;
-use crate::parser::ast::{BinaryOperator, JoinType, UnaryOperator};
+use crate::parser::ast::{BinaryOperator, JoinType, SetOperator, UnaryOperator, WindowFrame};
+use crate::tuple::datetime::DateTimeField;
 use crate::tuple::value::Value;
-use crate::tuple::Tuple;
+use crate::tuple::{cast, uuid, Tuple};
 
 #[derive(Debug, PartialEq)]
-pub enum Aggregation {
-    Count(Expr),
-    Max(Expr),
+pub struct Aggregation {
+    func: AggregationFunc,
+    args: Vec<Expr>,
+    /// only aggregate distinct argument values, e.g. count(DISTINCT col_a)
+    distinct: bool,
+}
+
+/// The intermediate state of an aggregation for a single group.
+/// Turned into the aggregation result by `Aggregation::finalize` once all tuples are aggregated
+#[derive(Clone, Debug)]
+pub struct Accumulator {
+    /// the result so far, used by all aggregations except count, sum and avg
+    value: Value,
+    /// number of aggregated values
+    count: i64,
+    /// running sum of all aggregated values, used by sum and avg
+    sum: i64,
+    /// argument values which were already aggregated, only used by DISTINCT aggregations
+    seen: HashSet<Vec<Value>>,
 }
 
 impl Aggregation {
-    pub fn new(func: AggregationFunc, expr: Expr) -> Self {
-        match func {
-            AggregationFunc::Count => Self::Count(expr),
-            AggregationFunc::Max => Self::Max(expr),
+    pub fn new(func: AggregationFunc, args: Vec<Expr>, distinct: bool) -> Self {
+        Self {
+            func,
+            args,
+            distinct,
         }
     }
 
-    /// Returns an initial value which will be used to accumulate the result
-    pub fn initial_accumulator_value(&self) -> Value {
-        match self {
-            Self::Count(_) => Value::Integer(0),
-            Self::Max(_) => Value::Null,
+    /// Returns an initial state which will be used to accumulate the result
+    pub fn initial_accumulator(&self) -> Accumulator {
+        Accumulator {
+            value: Value::Null,
+            count: 0,
+            sum: 0,
+            seen: HashSet::new(),
         }
     }
 
-    pub fn aggregate(&self, acc: &mut Value, tuple: &Tuple) {
-        match self {
-            Self::Count(expr) => {
-                let val = expr.evaluate(&[tuple]);
-                if !val.is_null() {
-                    *acc = Value::Integer(acc.as_i32() + 1)
+    /// Adds the tuple to the accumulated state.
+    /// Except for count(*) and array_agg, NULL values are ignored
+    pub fn aggregate(&self, acc: &mut Accumulator, tuple: &Tuple) -> Result<()> {
+        let mut args = self
+            .args
+            .iter()
+            .map(|arg| arg.evaluate(&[tuple]))
+            .collect::<Result<Vec<_>>>()?;
+        if self.distinct {
+            if acc.seen.contains(&args) {
+                return Ok(());
+            }
+            acc.seen.insert(args.clone());
+        }
+
+        if self.func == AggregationFunc::CountAll {
+            acc.count += 1;
+            return Ok(());
+        }
+        let val = args.remove(0);
+        if val.is_null() && self.func != AggregationFunc::ArrayAgg {
+            return Ok(());
+        }
+
+        match self.func {
+            AggregationFunc::CountAll => unreachable!(),
+            AggregationFunc::Count => acc.count += 1,
+            AggregationFunc::Sum | AggregationFunc::Avg => {
+                acc.sum = acc
+                    .sum
+                    .checked_add(val.as_i32() as i64)
+                    .ok_or_else(|| Error::msg("Integer out of range"))?;
+                acc.count += 1;
+            }
+            AggregationFunc::Min => acc.value.cmp_and_set_min(val),
+            AggregationFunc::Max => acc.value.cmp_and_set_max(val),
+            AggregationFunc::BoolAnd | AggregationFunc::BoolOr => {
+                acc.value = match &acc.value {
+                    Value::Null => val,
+                    Value::Boolean(_) if self.func == AggregationFunc::BoolAnd => {
+                        Value::Boolean(acc.value.as_bool() && val.as_bool())
+                    }
+                    Value::Boolean(_) => Value::Boolean(acc.value.as_bool() || val.as_bool()),
+                    _ => unreachable!(),
                 }
             }
-            Self::Max(expr) => {
-                let val = expr.evaluate(&[tuple]);
-                if !val.is_null() {
-                    acc.cmp_and_set_max(val);
+            AggregationFunc::StringAgg => match &mut acc.value {
+                Value::String(result) => {
+                    // a NULL delimiter is treated like an empty one
+                    if let Value::String(delimiter) = &args[0] {
+                        result.push_str(delimiter);
+                    }
+                    result.push_str(val.as_str());
                 }
+                _ => acc.value = val,
+            },
+            AggregationFunc::ArrayAgg => match &mut acc.value {
+                Value::Array(elements) => elements.push(val),
+                _ => acc.value = Value::Array(vec![val]),
+            },
+        }
+        Ok(())
+    }
+
+    /// Returns the result of the aggregation. Aggregations over no (non-NULL) values result in
+    /// NULL, except for count which results in 0
+    pub fn finalize(&self, acc: Accumulator) -> Result<Value> {
+        let val = match self.func {
+            AggregationFunc::CountAll | AggregationFunc::Count => Value::Integer(
+                i32::try_from(acc.count).map_err(|_| Error::msg("Integer out of range"))?,
+            ),
+            AggregationFunc::Sum | AggregationFunc::Avg if acc.count == 0 => Value::Null,
+            AggregationFunc::Sum => Value::Integer(
+                i32::try_from(acc.sum).map_err(|_| Error::msg("Integer out of range"))?,
+            ),
+            AggregationFunc::Avg => {
+                // the average of integers always fits into an integer, round half away from zero
+                let (quotient, remainder) = (acc.sum / acc.count, acc.sum % acc.count);
+                let rounding = if 2 * remainder.abs() >= acc.count {
+                    acc.sum.signum()
+                } else {
+                    0
+                };
+                Value::Integer((quotient + rounding) as i32)
             }
+            _ => acc.value,
+        };
+        Ok(val)
+    }
+}
+
+/// A window function, which is evaluated for every row of a partition
+#[derive(Debug, PartialEq)]
+pub struct WindowFunction {
+    pub func: WindowFunc,
+    pub args: Vec<Expr>,
+    /// the frame with the offsets as row counts, None for the default frame. The default frame
+    /// ranges from the first row of the partition to the last peer of the current row
+    pub frame: Option<WindowFrame<usize>>,
+}
+
+/// A key by which tuples are sorted, see ORDER BY
+#[derive(Debug, PartialEq)]
+pub struct SortKey {
+    pub expr: Expr,
+    pub ascending: bool,
+    pub nulls_first: bool,
+}
+
+impl SortKey {
+    /// Compares two values of this key according to the requested order
+    pub fn compare(&self, left: &Value, right: &Value) -> Ordering {
+        match (left.is_null(), right.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) if self.nulls_first => Ordering::Less,
+            (true, false) => Ordering::Greater,
+            (false, true) if self.nulls_first => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) if self.ascending => left.sort_cmp(right),
+            (false, false) => left.sort_cmp(right).reverse(),
         }
     }
+
+    /// Lexicographically compares the values of multiple keys
+    pub fn compare_all(keys: &[SortKey], left: &[Value], right: &[Value]) -> Ordering {
+        keys.iter()
+            .zip(left.iter().zip(right.iter()))
+            .map(|(key, (left, right))| key.compare(left, right))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -68,36 +207,436 @@ pub enum Expr {
     },
     IsNull(Box<Expr>),
     IsNotNull(Box<Expr>),
+    Extract {
+        field: DateTimeField,
+        expr: Box<Expr>,
+    },
+    ScalarFunction(ScalarFunc, Vec<Expr>),
+    Cast {
+        expr: Box<Expr>,
+        type_id: TypeId,
+        /// only set for explicit casts to VARCHAR(n) or CHAR(n)
+        text_length: Option<TextLength>,
+    },
+    /// CASE, with an operand the conditions are values compared to the operand
+    Case {
+        operand: Option<Box<Expr>>,
+        conditions: Vec<(Expr, Expr)>,
+        else_result: Option<Box<Expr>>,
+    },
+    Array(Vec<Expr>),
+    Subscript {
+        expr: Box<Expr>,
+        index: Box<Expr>,
+    },
+    AnyComparison {
+        left: Box<Expr>,
+        op: BinaryOperator,
+        array: Box<Expr>,
+    },
+    /// an uncorrelated subquery. It is executed only once before the statement and replaced
+    /// by its result, see `ExecutorFactory::create_executor`
+    Subquery {
+        kind: SubqueryKind,
+        plan: Box<PhysicalPlan>,
+    },
+}
+
+/// Determines how the rows of a subquery are turned into a value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SubqueryKind {
+    /// the value of the only row, NULL without rows. More than one row is an error
+    Scalar,
+    /// whether there are any rows, see EXISTS
+    Exists,
+    /// an array of the values of all rows, see IN
+    Array,
 }
 
 impl Expr {
-    pub fn evaluate(&self, tuple: &[&Tuple]) -> Value {
+    /// Collects the subqueries within the expression
+    fn collect_subqueries_mut<'a>(&'a mut self, subqueries: &mut Vec<&'a mut Expr>) {
         match self {
+            Expr::Subquery { kind: _, plan: _ } => subqueries.push(self),
+            Expr::ColumnReference {
+                tuple_idx: _,
+                col_idx: _,
+            } => (),
+            Expr::Value(_) => (),
+            Expr::Unary { op: _, expr } => expr.collect_subqueries_mut(subqueries),
+            Expr::Binary { left, op: _, right } => {
+                left.collect_subqueries_mut(subqueries);
+                right.collect_subqueries_mut(subqueries);
+            }
+            Expr::IsNull(expr) | Expr::IsNotNull(expr) => expr.collect_subqueries_mut(subqueries),
+            Expr::Extract { field: _, expr } => expr.collect_subqueries_mut(subqueries),
+            Expr::ScalarFunction(_, args) => {
+                for arg in args {
+                    arg.collect_subqueries_mut(subqueries);
+                }
+            }
+            Expr::Cast {
+                expr,
+                type_id: _,
+                text_length: _,
+            } => expr.collect_subqueries_mut(subqueries),
+            Expr::Case {
+                operand,
+                conditions,
+                else_result,
+            } => {
+                if let Some(operand) = operand {
+                    operand.collect_subqueries_mut(subqueries);
+                }
+                for (condition, result) in conditions {
+                    condition.collect_subqueries_mut(subqueries);
+                    result.collect_subqueries_mut(subqueries);
+                }
+                if let Some(else_result) = else_result {
+                    else_result.collect_subqueries_mut(subqueries);
+                }
+            }
+            Expr::Array(elements) => {
+                for element in elements {
+                    element.collect_subqueries_mut(subqueries);
+                }
+            }
+            Expr::Subscript { expr, index } => {
+                expr.collect_subqueries_mut(subqueries);
+                index.collect_subqueries_mut(subqueries);
+            }
+            Expr::AnyComparison { left, op: _, array } => {
+                left.collect_subqueries_mut(subqueries);
+                array.collect_subqueries_mut(subqueries);
+            }
+        }
+    }
+
+    /// Evaluates the expression for the given tuples.
+    /// Fails if the evaluation is not possible for these values, e.g. an invalid cast
+    pub fn evaluate(&self, tuple: &[&Tuple]) -> Result<Value> {
+        let val = match self {
             Expr::ColumnReference { tuple_idx, col_idx } => {
                 tuple[*tuple_idx].values().get(*col_idx).unwrap().clone()
             }
             Expr::Value(val) => val.clone(),
             Expr::Unary { op, expr } => match op {
-                UnaryOperator::Plus => expr.evaluate(tuple),
-                UnaryOperator::Minus => Value::Integer(-expr.evaluate(tuple).as_i32()),
+                UnaryOperator::Plus => expr.evaluate(tuple)?,
+                UnaryOperator::Minus => match expr.evaluate(tuple)? {
+                    Value::Integer(val) => Value::Integer(
+                        val.checked_neg()
+                            .ok_or_else(|| Error::msg("Integer out of range"))?,
+                    ),
+                    Value::Interval(interval) => Value::Interval(
+                        interval
+                            .checked_negate()
+                            .ok_or_else(|| Error::msg("Interval out of range"))?,
+                    ),
+                    Value::Null => Value::Null,
+                    _ => unreachable!(),
+                },
+                UnaryOperator::Not => match expr.evaluate(tuple)? {
+                    Value::Boolean(val) => Value::Boolean(!val),
+                    Value::Null => Value::Null,
+                    _ => unreachable!(),
+                },
             },
             Expr::Binary { left, op, right } => {
-                let left = left.evaluate(tuple);
-                let right = right.evaluate(tuple);
-                left.evaluate_binary_expression(&right, *op)
+                let left = left.evaluate(tuple)?;
+                let right = right.evaluate(tuple)?;
+                left.evaluate_binary_expression(&right, *op)?
             }
             Expr::IsNull(expr) => {
-                let val = expr.evaluate(tuple);
+                let val = expr.evaluate(tuple)?;
                 Value::Boolean(val.is_null())
             }
             Expr::IsNotNull(expr) => {
-                let val = expr.evaluate(tuple);
+                let val = expr.evaluate(tuple)?;
                 Value::Boolean(!val.is_null())
             }
-        }
+            Expr::Extract { field, expr } => expr.evaluate(tuple)?.extract(*field)?,
+            // the arguments after the first non NULL value are not evaluated
+            Expr::ScalarFunction(ScalarFunc::Coalesce, args) => {
+                for arg in args {
+                    let val = arg.evaluate(tuple)?;
+                    if !val.is_null() {
+                        return Ok(val);
+                    }
+                }
+                Value::Null
+            }
+            Expr::ScalarFunction(func, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.evaluate(tuple))
+                    .collect::<Result<Vec<_>>>()?;
+                evaluate_scalar_function(func, &args)?
+            }
+            Expr::Cast {
+                expr,
+                type_id,
+                text_length,
+            } => match (
+                cast::cast_value(expr.evaluate(tuple)?, *type_id)?,
+                text_length,
+            ) {
+                (Value::String(val), Some(text_length)) => Value::String(text_length.cast(&val)),
+                (val, _) => val,
+            },
+            Expr::Case {
+                operand,
+                conditions,
+                else_result,
+            } => {
+                let operand = match operand {
+                    Some(operand) => Some(operand.evaluate(tuple)?),
+                    None => None,
+                };
+                for (condition, result) in conditions {
+                    let condition = condition.evaluate(tuple)?;
+                    let is_true = match &operand {
+                        Some(operand) => {
+                            operand.evaluate_binary_expression(&condition, BinaryOperator::Eq)?
+                        }
+                        None => condition,
+                    };
+                    if is_true == Value::Boolean(true) {
+                        return result.evaluate(tuple);
+                    }
+                }
+                match else_result {
+                    Some(else_result) => else_result.evaluate(tuple)?,
+                    None => Value::Null,
+                }
+            }
+            Expr::Array(elements) => Value::Array(
+                elements
+                    .iter()
+                    .map(|element| element.evaluate(tuple))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            Expr::Subscript { expr, index } => {
+                match (expr.evaluate(tuple)?, index.evaluate(tuple)?) {
+                    // arrays are 1-based, out of bounds subscripts evaluate to NULL
+                    (Value::Array(mut elements), Value::Integer(idx))
+                        if idx >= 1 && idx as usize <= elements.len() =>
+                    {
+                        elements.swap_remove(idx as usize - 1)
+                    }
+                    _ => Value::Null,
+                }
+            }
+            Expr::AnyComparison { left, op, array } => {
+                let left = left.evaluate(tuple)?;
+                let elements = match array.evaluate(tuple)? {
+                    Value::Array(elements) => elements,
+                    _ => return Ok(Value::Null),
+                };
+                // true if any comparison is true, else NULL if any comparison is NULL
+                let mut result = Value::Boolean(false);
+                for element in elements.iter() {
+                    match left.evaluate_binary_expression(element, *op)? {
+                        Value::Boolean(true) => return Ok(Value::Boolean(true)),
+                        Value::Null => result = Value::Null,
+                        _ => (),
+                    }
+                }
+                result
+            }
+            Expr::Subquery { kind: _, plan: _ } => {
+                unreachable!("subqueries are executed before the statement")
+            }
+        };
+        Ok(val)
     }
 }
 
+/// Evaluates a scalar function.
+/// The analyzer makes sure that the arguments are of the expected types, it only fails for
+/// invalid argument values
+fn evaluate_scalar_function(func: &ScalarFunc, args: &[Value]) -> Result<Value> {
+    if func.is_strict() && args.iter().any(|arg| arg.is_null()) {
+        return Ok(Value::Null);
+    }
+    let val = match (func, args) {
+        (ScalarFunc::GenRandomUuid, []) => Value::Uuid(uuid::generate_v4()),
+        (ScalarFunc::JsonExtractPath | ScalarFunc::JsonExtractPathText, [json, path @ ..]) => {
+            let json = match json {
+                Value::Json(json) => json,
+                _ => return Ok(Value::Null),
+            };
+            let path = match path
+                .iter()
+                .map(|element| match element {
+                    Value::String(element) => Some(element.as_str()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(path) => path,
+                None => return Ok(Value::Null),
+            };
+            match (json.get_path(&path), func) {
+                (None, _) => Value::Null,
+                (Some(element), ScalarFunc::JsonExtractPath) => Value::Json(element.clone()),
+                (Some(element), _) => element.to_text().map(Value::String).unwrap_or(Value::Null),
+            }
+        }
+        (ScalarFunc::Coalesce, _) => unreachable!("coalesce is evaluated lazily"),
+        (ScalarFunc::UserDefined(func), args) => func.call(args)?,
+        (ScalarFunc::NullIf, [left, right]) => {
+            match left.evaluate_binary_expression(right, BinaryOperator::Eq) {
+                Ok(Value::Boolean(true)) => Value::Null,
+                _ => left.clone(),
+            }
+        }
+        (ScalarFunc::Greatest | ScalarFunc::Least, args) => {
+            let op = match func {
+                ScalarFunc::Greatest => BinaryOperator::Greater,
+                _ => BinaryOperator::Less,
+            };
+            let mut result = Value::Null;
+            for arg in args.iter().filter(|arg| !arg.is_null()) {
+                let replaces = result.is_null()
+                    || arg.evaluate_binary_expression(&result, op).ok()
+                        == Some(Value::Boolean(true));
+                if replaces {
+                    result = arg.clone();
+                }
+            }
+            result
+        }
+        (ScalarFunc::LikeEscape, [Value::String(pattern), Value::String(escape)]) => {
+            let mut escape_chars = escape.chars();
+            let escape = match (escape_chars.next(), escape_chars.next()) {
+                (escape, None) => escape,
+                _ => {
+                    return Err(Error::msg(
+                        "Invalid escape string, it must be empty or one character",
+                    ))
+                }
+            };
+            // the escape character is replaced with a backslash, other backslashes are escaped
+            let mut result = String::new();
+            let mut chars = pattern.chars();
+            while let Some(ch) = chars.next() {
+                if Some(ch) == escape {
+                    result.push('\\');
+                    result.extend(chars.next());
+                } else if ch == '\\' {
+                    result.push_str("\\\\");
+                } else {
+                    result.push(ch);
+                }
+            }
+            Value::String(result)
+        }
+        (ScalarFunc::Length, [Value::String(val)]) => Value::Integer(val.chars().count() as i32),
+        (ScalarFunc::Length | ScalarFunc::OctetLength, [Value::Bytea(val)]) => {
+            Value::Integer(val.len() as i32)
+        }
+        (ScalarFunc::OctetLength, [Value::String(val)]) => Value::Integer(val.len() as i32),
+        (ScalarFunc::Lower, [Value::String(val)]) => Value::String(val.to_lowercase()),
+        (ScalarFunc::Upper, [Value::String(val)]) => Value::String(val.to_uppercase()),
+        (ScalarFunc::Substring, [Value::String(val), Value::Integer(start), count @ ..]) => {
+            // the positions before the first character count towards the length as well
+            let start = *start as i64;
+            let end = match count {
+                [Value::Integer(count)] if *count < 0 => {
+                    return Err(Error::msg("Negative substring length not allowed"))
+                }
+                [Value::Integer(count)] => start + *count as i64,
+                _ => i64::MAX,
+            };
+            let skip = start.max(1) - 1;
+            let take = (end - start.max(1)).max(0);
+            Value::String(
+                val.chars()
+                    .skip(skip as usize)
+                    .take(take as usize)
+                    .collect(),
+            )
+        }
+        (
+            ScalarFunc::BTrim | ScalarFunc::LTrim | ScalarFunc::RTrim,
+            [Value::String(val), characters @ ..],
+        ) => {
+            let characters = match characters {
+                [Value::String(characters)] => characters.chars().collect(),
+                _ => vec![' '],
+            };
+            let characters = characters.as_slice();
+            let trimmed = match func {
+                ScalarFunc::LTrim => val.trim_start_matches(characters),
+                ScalarFunc::RTrim => val.trim_end_matches(characters),
+                _ => val.trim_matches(characters),
+            };
+            Value::String(trimmed.to_owned())
+        }
+        (ScalarFunc::Replace, [Value::String(val), Value::String(from), Value::String(to)]) => {
+            if from.is_empty() {
+                Value::String(val.clone())
+            } else {
+                Value::String(val.replace(from.as_str(), to))
+            }
+        }
+        (ScalarFunc::Position, [Value::String(val), Value::String(substring)]) => {
+            match val.find(substring.as_str()) {
+                Some(index) => Value::Integer(val[..index].chars().count() as i32 + 1),
+                None => Value::Integer(0),
+            }
+        }
+        (ScalarFunc::Abs, [Value::Integer(val)]) => Value::Integer(
+            val.checked_abs()
+                .ok_or_else(|| Error::msg("Integer out of range"))?,
+        ),
+        (ScalarFunc::Round, [Value::Integer(val), places @ ..]) => {
+            let places = match places {
+                [Value::Integer(places)] => *places,
+                _ => 0,
+            };
+            if places >= 0 {
+                return Ok(Value::Integer(*val));
+            }
+            // rounds half away from zero, a factor larger than any integer rounds to zero
+            let rounded = match 10i64.checked_pow(places.unsigned_abs()) {
+                Some(factor) => {
+                    let val = *val as i64;
+                    (val.abs() + factor / 2) / factor * factor * val.signum()
+                }
+                None => 0,
+            };
+            Value::Integer(i32::try_from(rounded).map_err(|_| Error::msg("Integer out of range"))?)
+        }
+        (ScalarFunc::Mod, [Value::Integer(_), Value::Integer(0)]) => {
+            return Err(Error::msg("Division by zero"))
+        }
+        (ScalarFunc::Mod, [Value::Integer(left), Value::Integer(right)]) => {
+            Value::Integer(left.wrapping_rem(*right))
+        }
+        (ScalarFunc::Power, [Value::Integer(_), Value::Integer(exponent)]) if *exponent < 0 => {
+            return Err(Error::msg(
+                "Negative exponents are not supported, the result would not be an integer",
+            ))
+        }
+        (ScalarFunc::Power, [Value::Integer(base), Value::Integer(exponent)]) => Value::Integer(
+            base.checked_pow(*exponent as u32)
+                .ok_or_else(|| Error::msg("Integer out of range"))?,
+        ),
+        (ScalarFunc::Random, []) => Value::Integer((rand::random::<u32>() >> 1) as i32),
+        (ScalarFunc::Random, [Value::Integer(low), Value::Integer(high)]) => {
+            if low > high {
+                return Err(Error::msg(
+                    "The lower bound of random must not be greater than the upper bound",
+                ));
+            }
+            Value::Integer(rand::thread_rng().gen_range(*low..=*high))
+        }
+        _ => unreachable!(),
+    };
+    Ok(val)
+}
+
 struct ExprWriter<'a> {
     expr: &'a Expr,
     plans: &'a [&'a PhysicalPlan],
@@ -149,6 +688,103 @@ impl fmt::Display for ExprWriter<'_> {
                 write!(f, "{}", expr_writer)?;
                 f.write_str(" IS NOT NULL")
             }
+            Expr::Extract { field, expr } => {
+                let expr_writer = ExprWriter {
+                    expr,
+                    plans: self.plans,
+                };
+                write!(f, "EXTRACT({} FROM {})", field, expr_writer)
+            }
+            Expr::ScalarFunction(func, args) => {
+                write!(f, "{}(", func)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    let expr_writer = ExprWriter {
+                        expr: arg,
+                        plans: self.plans,
+                    };
+                    write!(f, "{}", expr_writer)?;
+                }
+                f.write_str(")")
+            }
+            Expr::Cast {
+                expr,
+                type_id,
+                text_length,
+            } => {
+                let expr_writer = ExprWriter {
+                    expr,
+                    plans: self.plans,
+                };
+                match text_length {
+                    Some(text_length) => write!(f, "CAST({} AS {})", expr_writer, text_length),
+                    None => write!(f, "CAST({} AS {})", expr_writer, type_id),
+                }
+            }
+            Expr::Case {
+                operand,
+                conditions,
+                else_result,
+            } => {
+                let writer = |expr| ExprWriter {
+                    expr,
+                    plans: self.plans,
+                };
+                f.write_str("CASE")?;
+                if let Some(operand) = operand {
+                    write!(f, " {}", writer(operand))?;
+                }
+                for (condition, result) in conditions {
+                    write!(f, " WHEN {} THEN {}", writer(condition), writer(result))?;
+                }
+                if let Some(else_result) = else_result {
+                    write!(f, " ELSE {}", writer(else_result))?;
+                }
+                f.write_str(" END")
+            }
+            Expr::Array(elements) => {
+                f.write_str("ARRAY[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    let expr_writer = ExprWriter {
+                        expr: element,
+                        plans: self.plans,
+                    };
+                    write!(f, "{}", expr_writer)?;
+                }
+                f.write_str("]")
+            }
+            Expr::Subscript { expr, index } => {
+                let expr_writer = ExprWriter {
+                    expr,
+                    plans: self.plans,
+                };
+                let index_writer = ExprWriter {
+                    expr: index,
+                    plans: self.plans,
+                };
+                write!(f, "{}[{}]", expr_writer, index_writer)
+            }
+            Expr::AnyComparison { left, op, array } => {
+                let left_writer = ExprWriter {
+                    expr: left,
+                    plans: self.plans,
+                };
+                let array_writer = ExprWriter {
+                    expr: array,
+                    plans: self.plans,
+                };
+                write!(f, "{}{}ANY({})", left_writer, op, array_writer)
+            }
+            Expr::Subquery { kind, plan: _ } => match kind {
+                SubqueryKind::Scalar => f.write_str("(subquery)"),
+                SubqueryKind::Exists => f.write_str("EXISTS(subquery)"),
+                SubqueryKind::Array => f.write_str("ARRAY(subquery)"),
+            },
         }
     }
 }
@@ -168,9 +804,47 @@ pub enum PhysicalPlan {
         values: Vec<Vec<Expr>>,
         output_schema: Schema,
     },
+    TableFunctionScan {
+        func: TableFunc,
+        args: Vec<Expr>,
+        output_schema: Schema,
+    },
     Aggregate {
         aggregations: Vec<Aggregation>,
         child: Box<PhysicalPlan>,
+        output_schema: Schema,
+    },
+    HashAggregate {
+        group_by: Vec<Expr>,
+        aggregations: Vec<Aggregation>,
+        child: Box<PhysicalPlan>,
+        output_schema: Schema,
+    },
+    Sort {
+        sort_keys: Vec<SortKey>,
+        child: Box<PhysicalPlan>,
+    },
+    /// sorts only the first `limit` tuples, see ORDER BY combined with LIMIT
+    TopNSort {
+        sort_keys: Vec<SortKey>,
+        limit: usize,
+        child: Box<PhysicalPlan>,
+    },
+    /// removes tuples with duplicate keys, keeping the first one, see DISTINCT
+    HashDistinct {
+        keys: Vec<Expr>,
+        child: Box<PhysicalPlan>,
+    },
+    /// like HashDistinct, but requires the child to be sorted by the keys
+    SortDistinct {
+        keys: Vec<Expr>,
+        child: Box<PhysicalPlan>,
+    },
+    Limit {
+        /// None if all remaining tuples are returned
+        limit: Option<usize>,
+        offset: usize,
+        child: Box<PhysicalPlan>,
     },
     Insert {
         target: TableId,
@@ -179,6 +853,8 @@ pub enum PhysicalPlan {
     },
     Update {
         table: TableId,
+        /// the schema of the table with the column names of the catalog
+        target_schema: Schema,
         set: HashMap<usize, Expr>,
         child: Box<PhysicalPlan>,
     },
@@ -197,9 +873,268 @@ pub enum PhysicalPlan {
         on: Vec<Expr>,
         output_schema: Schema,
     },
+    /// UNION, INTERSECT or EXCEPT of two inputs with the same column types. The output columns
+    /// are named by their position, as they can only be referenced by ORDER BY
+    SetOperation {
+        op: SetOperator,
+        /// whether duplicates are kept, e.g. UNION ALL
+        all: bool,
+        left: Box<PhysicalPlan>,
+        right: Box<PhysicalPlan>,
+        output_schema: Schema,
+    },
+    /// a query with common table expressions, which are referenced more than once. Each of
+    /// them is executed once on its first scan, the materialized result is scanned by all
+    /// references
+    With {
+        /// the plans of the CTEs by their id
+        ctes: Vec<(usize, PhysicalPlan)>,
+        child: Box<PhysicalPlan>,
+    },
+    /// scans the materialized result of a common table expression
+    CteScan {
+        cte_id: usize,
+        output_schema: Schema,
+    },
+    /// the UNION of a recursive CTE. The rows of the left input form the first working table,
+    /// then the right input is evaluated repeatedly on the rows added by the previous iteration
+    /// until no more rows are added
+    RecursiveUnion {
+        cte_id: usize,
+        /// whether duplicates are kept, i.e. UNION ALL
+        all: bool,
+        left: Box<PhysicalPlan>,
+        right: Box<PhysicalPlan>,
+        output_schema: Schema,
+    },
+    /// scans the rows added by the previous iteration of a recursive CTE
+    WorkingTableScan {
+        cte_id: usize,
+        output_schema: Schema,
+    },
+    /// evaluates window functions over the partitions of its child, which is sorted by the
+    /// partition keys followed by the order keys. The results are appended to the tuples
+    Window {
+        partition_by: Vec<Expr>,
+        /// the keys, by which the rows of a partition are ordered. Rows with equal keys are
+        /// peers, which share their rank
+        order_by: Vec<Expr>,
+        functions: Vec<WindowFunction>,
+        child: Box<PhysicalPlan>,
+        output_schema: Schema,
+    },
 }
 
 impl PhysicalPlan {
+    /// Returns the subqueries within the expressions of the plan and its children. Subqueries
+    /// nested within these subqueries are not included
+    pub fn subqueries_mut(&mut self) -> Vec<&mut Expr> {
+        let mut subqueries = vec![];
+        self.collect_subqueries_mut(&mut subqueries);
+        subqueries
+    }
+
+    fn collect_subqueries_mut<'a>(&'a mut self, subqueries: &mut Vec<&'a mut Expr>) {
+        let (expressions, children): (Vec<&mut Expr>, Vec<&mut PhysicalPlan>) = match self {
+            Self::SequentialScan {
+                table_id: _,
+                output_schema: _,
+            } => (vec![], vec![]),
+            Self::CteScan {
+                cte_id: _,
+                output_schema: _,
+            }
+            | Self::WorkingTableScan {
+                cte_id: _,
+                output_schema: _,
+            } => (vec![], vec![]),
+            Self::RecursiveUnion {
+                cte_id: _,
+                all: _,
+                left,
+                right,
+                output_schema: _,
+            } => (vec![], vec![left, right]),
+            Self::With { ctes, child } => {
+                let mut children = vec![child.as_mut()];
+                children.extend(ctes.iter_mut().map(|(_, plan)| plan));
+                (vec![], children)
+            }
+            Self::Projection {
+                projections,
+                child,
+                output_schema: _,
+            } => (projections.iter_mut().collect(), vec![child]),
+            Self::Values {
+                values,
+                output_schema: _,
+            } => (values.iter_mut().flatten().collect(), vec![]),
+            Self::TableFunctionScan {
+                func: _,
+                args,
+                output_schema: _,
+            } => (args.iter_mut().collect(), vec![]),
+            Self::Aggregate {
+                aggregations,
+                child,
+                output_schema: _,
+            } => (
+                aggregations
+                    .iter_mut()
+                    .flat_map(|aggregation| aggregation.args.iter_mut())
+                    .collect(),
+                vec![child],
+            ),
+            Self::HashAggregate {
+                group_by,
+                aggregations,
+                child,
+                output_schema: _,
+            } => (
+                group_by
+                    .iter_mut()
+                    .chain(
+                        aggregations
+                            .iter_mut()
+                            .flat_map(|aggregation| aggregation.args.iter_mut()),
+                    )
+                    .collect(),
+                vec![child],
+            ),
+            Self::Sort { sort_keys, child }
+            | Self::TopNSort {
+                sort_keys,
+                limit: _,
+                child,
+            } => (
+                sort_keys.iter_mut().map(|key| &mut key.expr).collect(),
+                vec![child],
+            ),
+            Self::HashDistinct { keys, child } | Self::SortDistinct { keys, child } => {
+                (keys.iter_mut().collect(), vec![child])
+            }
+            Self::Limit {
+                limit: _,
+                offset: _,
+                child,
+            } => (vec![], vec![child]),
+            Self::Insert {
+                target: _,
+                target_schema: _,
+                child,
+            } => (vec![], vec![child]),
+            Self::Update {
+                table: _,
+                target_schema: _,
+                set,
+                child,
+            } => (set.values_mut().collect(), vec![child]),
+            Self::Delete { from: _, child } => (vec![], vec![child]),
+            Self::Filter { filter, child } => (filter.iter_mut().collect(), vec![child]),
+            Self::NestedLoopJoin {
+                left,
+                right,
+                join_type: _,
+                on,
+                output_schema: _,
+            } => (on.iter_mut().collect(), vec![left, right]),
+            Self::SetOperation {
+                op: _,
+                all: _,
+                left,
+                right,
+                output_schema: _,
+            } => (vec![], vec![left, right]),
+            Self::Window {
+                partition_by,
+                order_by,
+                functions,
+                child,
+                output_schema: _,
+            } => (
+                partition_by
+                    .iter_mut()
+                    .chain(order_by.iter_mut())
+                    .chain(
+                        functions
+                            .iter_mut()
+                            .flat_map(|function| function.args.iter_mut()),
+                    )
+                    .collect(),
+                vec![child],
+            ),
+        };
+        for expr in expressions {
+            expr.collect_subqueries_mut(subqueries);
+        }
+        for child in children {
+            child.collect_subqueries_mut(subqueries);
+        }
+    }
+
+    /// Returns the output schema of an aggregation: the values of the grouping expressions
+    /// followed by the aggregation results. The columns are only named to describe the plan
+    pub fn aggregate_schema(
+        group_by: &[Expr],
+        aggregations: &[Aggregation],
+        child: &PhysicalPlan,
+    ) -> Schema {
+        let describe = |expr| {
+            let expr_writer = ExprWriter {
+                expr,
+                plans: &[child],
+            };
+            format!("{}", expr_writer)
+        };
+        let group_columns = group_by.iter().map(describe);
+        let aggregation_columns = aggregations.iter().map(|aggregation| {
+            let args = if aggregation.func == AggregationFunc::CountAll {
+                "*".to_owned()
+            } else {
+                aggregation
+                    .args
+                    .iter()
+                    .map(describe)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let distinct = if aggregation.distinct {
+                "DISTINCT "
+            } else {
+                ""
+            };
+            format!("{}({}{})", aggregation.func, distinct, args)
+        });
+        let columns = group_columns
+            .chain(aggregation_columns)
+            .enumerate()
+            .map(|(offset, name)| ColumnDefinition::new(TypeId::Unknown, name, offset as u8, false))
+            .collect();
+        Schema::new(columns)
+    }
+
+    /// Returns the output schema of a window: the columns of the child followed by the results
+    /// of the window functions. Like for aggregations, the results are only named to describe
+    /// the plan
+    pub fn window_schema(functions: &[WindowFunction], child: &PhysicalPlan) -> Schema {
+        let mut columns = child.schema().columns().to_vec();
+        for function in functions {
+            let args = if function.func == WindowFunc::Aggregation(AggregationFunc::CountAll) {
+                "*".to_owned()
+            } else {
+                describe_expressions(&function.args, child)
+            };
+            let name = format!("{}({})", function.func, args);
+            columns.push(ColumnDefinition::new(
+                TypeId::Unknown,
+                name,
+                columns.len() as u8,
+                false,
+            ));
+        }
+        Schema::new(columns)
+    }
+
     pub fn schema(&self) -> &Schema {
         match self {
             Self::SequentialScan {
@@ -215,10 +1150,22 @@ impl PhysicalPlan {
                 values: _,
                 output_schema,
             } => output_schema,
+            Self::TableFunctionScan {
+                func: _,
+                args: _,
+                output_schema,
+            } => output_schema,
             Self::Aggregate {
                 aggregations: _,
                 child: _,
-            } => unimplemented!(),
+                output_schema,
+            } => output_schema,
+            Self::HashAggregate {
+                group_by: _,
+                aggregations: _,
+                child: _,
+                output_schema,
+            } => output_schema,
             Self::Insert {
                 target: _,
                 target_schema: _,
@@ -227,10 +1174,27 @@ impl PhysicalPlan {
             Self::Delete { from: _, child: _ } => unreachable!(),
             Self::Update {
                 table: _,
+                target_schema: _,
                 set: _,
                 child: _,
             } => unreachable!(),
             Self::Filter { filter: _, child } => child.schema(),
+            Self::Sort {
+                sort_keys: _,
+                child,
+            } => child.schema(),
+            Self::TopNSort {
+                sort_keys: _,
+                limit: _,
+                child,
+            } => child.schema(),
+            Self::HashDistinct { keys: _, child } => child.schema(),
+            Self::SortDistinct { keys: _, child } => child.schema(),
+            Self::Limit {
+                limit: _,
+                offset: _,
+                child,
+            } => child.schema(),
             Self::NestedLoopJoin {
                 left: _,
                 right: _,
@@ -238,10 +1202,77 @@ impl PhysicalPlan {
                 on: _,
                 output_schema,
             } => output_schema,
+            Self::SetOperation {
+                op: _,
+                all: _,
+                left: _,
+                right: _,
+                output_schema,
+            } => output_schema,
+            Self::With { ctes: _, child } => child.schema(),
+            Self::CteScan {
+                cte_id: _,
+                output_schema,
+            }
+            | Self::WorkingTableScan {
+                cte_id: _,
+                output_schema,
+            } => output_schema,
+            Self::RecursiveUnion {
+                cte_id: _,
+                all: _,
+                left: _,
+                right: _,
+                output_schema,
+            } => output_schema,
+            Self::Window {
+                partition_by: _,
+                order_by: _,
+                functions: _,
+                child: _,
+                output_schema,
+            } => output_schema,
         }
     }
 }
 
+/// Describes the expressions like in `col_a, col_b + 1`
+fn describe_expressions(expressions: &[Expr], child: &PhysicalPlan) -> String {
+    expressions
+        .iter()
+        .map(|expr| {
+            let expr_writer = ExprWriter {
+                expr,
+                plans: &[child],
+            };
+            format!("{}", expr_writer)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Describes the sort keys like in `col_a ASC, col_b DESC NULLS LAST`
+fn describe_sort_keys(sort_keys: &[SortKey], child: &PhysicalPlan) -> String {
+    sort_keys
+        .iter()
+        .map(|key| {
+            let expr_writer = ExprWriter {
+                expr: &key.expr,
+                plans: &[child],
+            };
+            let direction = if key.ascending { "ASC" } else { "DESC" };
+            // NULLS FIRST is the default for descending order
+            let nulls = match (key.ascending, key.nulls_first) {
+                (true, true) => " NULLS FIRST",
+                (false, false) => " NULLS LAST",
+                _ => "",
+            };
+            format!("{} {}{}", expr_writer, direction, nulls)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 struct PaddedWriter<'a> {
     buffer: &'a mut (dyn fmt::Write + 'a),
     use_padding: bool,
@@ -279,9 +1310,15 @@ impl fmt::Display for PhysicalPlan {
                 values: _,
                 output_schema: _,
             } => write!(f, "Values Scan"),
+            Self::TableFunctionScan {
+                func,
+                args: _,
+                output_schema: _,
+            } => write!(f, "Function Scan on {}", func),
             Self::Aggregate {
                 aggregations: _,
                 child,
+                output_schema: _,
             } => {
                 writeln!(f, "Aggregate")?;
                 let mut writer = PaddedWriter {
@@ -290,6 +1327,79 @@ impl fmt::Display for PhysicalPlan {
                 };
                 write!(&mut writer, "{}", child)
             }
+            Self::HashAggregate {
+                group_by,
+                aggregations: _,
+                child,
+                output_schema: _,
+            } => {
+                writeln!(
+                    f,
+                    "Hash Aggregate ({})",
+                    describe_expressions(group_by, child)
+                )?;
+                let mut writer = PaddedWriter {
+                    buffer: f,
+                    use_padding: true,
+                };
+                write!(&mut writer, "{}", child)
+            }
+            Self::Sort { sort_keys, child } => {
+                writeln!(f, "Sort ({})", describe_sort_keys(sort_keys, child))?;
+                let mut writer = PaddedWriter {
+                    buffer: f,
+                    use_padding: true,
+                };
+                write!(&mut writer, "{}", child)
+            }
+            Self::TopNSort {
+                sort_keys,
+                limit,
+                child,
+            } => {
+                writeln!(
+                    f,
+                    "Top-N Sort ({}) (rows: {})",
+                    describe_sort_keys(sort_keys, child),
+                    limit
+                )?;
+                let mut writer = PaddedWriter {
+                    buffer: f,
+                    use_padding: true,
+                };
+                write!(&mut writer, "{}", child)
+            }
+            Self::HashDistinct { keys, child } => {
+                writeln!(f, "Hash Distinct ({})", describe_expressions(keys, child))?;
+                let mut writer = PaddedWriter {
+                    buffer: f,
+                    use_padding: true,
+                };
+                write!(&mut writer, "{}", child)
+            }
+            Self::SortDistinct { keys, child } => {
+                writeln!(f, "Sort Distinct ({})", describe_expressions(keys, child))?;
+                let mut writer = PaddedWriter {
+                    buffer: f,
+                    use_padding: true,
+                };
+                write!(&mut writer, "{}", child)
+            }
+            Self::Limit {
+                limit,
+                offset,
+                child,
+            } => {
+                match limit {
+                    Some(limit) => writeln!(f, "Limit (rows: {}, offset: {})", limit, offset)?,
+                    None => writeln!(f, "Limit (rows: all, offset: {})", offset)?,
+                }
+                let mut writer = PaddedWriter {
+                    buffer: f,
+                    use_padding: true,
+                };
+                write!(&mut writer, "{}", child)
+            }
             Self::Insert {
                 target,
                 target_schema: _,
@@ -304,6 +1414,7 @@ impl fmt::Display for PhysicalPlan {
             }
             Self::Update {
                 table,
+                target_schema: _,
                 set: _,
                 child,
             } => {
@@ -345,7 +1456,7 @@ impl fmt::Display for PhysicalPlan {
             Self::NestedLoopJoin {
                 left,
                 right,
-                join_type: _,
+                join_type,
                 on,
                 output_schema: _,
             } => {
@@ -361,7 +1472,12 @@ impl fmt::Display for PhysicalPlan {
                     .collect::<Vec<_>>()
                     .join("AND");
 
-                writeln!(f, "Nested Loop Join ({})", on_expr)?;
+                let join_name = match join_type {
+                    JoinType::Semi => "Semi Join",
+                    JoinType::Anti => "Anti Join",
+                    JoinType::Inner | JoinType::Left | JoinType::Right | JoinType::Full => "Join",
+                };
+                writeln!(f, "Nested Loop {} ({})", join_name, on_expr)?;
 
                 let mut writer = PaddedWriter {
                     buffer: f,
@@ -370,6 +1486,98 @@ impl fmt::Display for PhysicalPlan {
                 writeln!(&mut writer, "{}", left)?;
                 write!(&mut writer, "{}", right)
             }
+            Self::SetOperation {
+                op,
+                all,
+                left,
+                right,
+                output_schema: _,
+            } => {
+                let name = match op {
+                    SetOperator::Union => "Union",
+                    SetOperator::Intersect => "Intersect",
+                    SetOperator::Except => "Except",
+                };
+                if *all {
+                    writeln!(f, "{} All", name)?;
+                } else {
+                    writeln!(f, "{}", name)?;
+                }
+                let mut writer = PaddedWriter {
+                    buffer: f,
+                    use_padding: true,
+                };
+                writeln!(&mut writer, "{}", left)?;
+                write!(&mut writer, "{}", right)
+            }
+            Self::With { ctes, child } => {
+                writeln!(f, "With")?;
+                let mut writer = PaddedWriter {
+                    buffer: f,
+                    use_padding: true,
+                };
+                for (cte_id, plan) in ctes {
+                    writeln!(&mut writer, "CTE {}", cte_id)?;
+                    let mut cte_writer = PaddedWriter {
+                        buffer: &mut writer,
+                        use_padding: true,
+                    };
+                    writeln!(&mut cte_writer, "{}", plan)?;
+                }
+                write!(&mut writer, "{}", child)
+            }
+            Self::CteScan {
+                cte_id,
+                output_schema: _,
+            } => write!(f, "CTE Scan on CTE {}", cte_id),
+            Self::RecursiveUnion {
+                cte_id,
+                all,
+                left,
+                right,
+                output_schema: _,
+            } => {
+                if *all {
+                    writeln!(f, "Recursive Union All on CTE {}", cte_id)?;
+                } else {
+                    writeln!(f, "Recursive Union on CTE {}", cte_id)?;
+                }
+                let mut writer = PaddedWriter {
+                    buffer: f,
+                    use_padding: true,
+                };
+                writeln!(&mut writer, "{}", left)?;
+                write!(&mut writer, "{}", right)
+            }
+            Self::WorkingTableScan {
+                cte_id,
+                output_schema: _,
+            } => write!(f, "Working Table Scan on CTE {}", cte_id),
+            Self::Window {
+                partition_by,
+                order_by,
+                functions: _,
+                child,
+                output_schema: _,
+            } => {
+                let mut clauses = vec![];
+                if !partition_by.is_empty() {
+                    let partition_by = describe_expressions(partition_by, child);
+                    clauses.push(format!("PARTITION BY {}", partition_by));
+                }
+                if !order_by.is_empty() {
+                    clauses.push(format!(
+                        "ORDER BY {}",
+                        describe_expressions(order_by, child)
+                    ));
+                }
+                writeln!(f, "Window ({})", clauses.join(" "))?;
+                let mut writer = PaddedWriter {
+                    buffer: f,
+                    use_padding: true,
+                };
+                write!(&mut writer, "{}", child)
+            }
         }
     }
 }
@@ -404,26 +1612,26 @@ mod tests {
             }),
         };
 
-        let value = expr.evaluate(&[]);
+        let value = expr.evaluate(&[]).unwrap();
         assert_eq!(value, Value::Integer(14));
     }
 
     #[test]
     fn can_evaluate_is_null() {
         let expr = Expr::IsNull(Box::new(Expr::Value(Value::Null)));
-        assert_eq!(expr.evaluate(&[]), Value::Boolean(true));
+        assert_eq!(expr.evaluate(&[]).unwrap(), Value::Boolean(true));
 
         let expr = Expr::IsNull(Box::new(Expr::Value(Value::Integer(42))));
-        assert_eq!(expr.evaluate(&[]), Value::Boolean(false));
+        assert_eq!(expr.evaluate(&[]).unwrap(), Value::Boolean(false));
     }
 
     #[test]
     fn can_evaluate_is_not_null() {
         let expr = Expr::IsNotNull(Box::new(Expr::Value(Value::Null)));
-        assert_eq!(expr.evaluate(&[]), Value::Boolean(false));
+        assert_eq!(expr.evaluate(&[]).unwrap(), Value::Boolean(false));
 
         let expr = Expr::IsNotNull(Box::new(Expr::Value(Value::Integer(42))));
-        assert_eq!(expr.evaluate(&[]), Value::Boolean(true));
+        assert_eq!(expr.evaluate(&[]).unwrap(), Value::Boolean(true));
     }
 
     #[test]
@@ -458,74 +1666,131 @@ mod tests {
     fn test_count_aggregation() {
         let agg = Aggregation::new(
             AggregationFunc::Count,
-            Expr::ColumnReference {
+            vec![Expr::ColumnReference {
                 tuple_idx: 0,
                 col_idx: 0,
-            },
+            }],
+            false,
         );
 
-        let mut acc = agg.initial_accumulator_value();
-        assert_eq!(acc.as_i32(), 0);
+        let mut acc = agg.initial_accumulator();
+        assert_eq!(acc.count, 0);
+
+        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Boolean(true)]))
+            .unwrap();
+        assert_eq!(acc.count, 1);
+
+        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Boolean(false)]))
+            .unwrap();
+        assert_eq!(acc.count, 2);
+
+        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Null]))
+            .unwrap();
+        assert_eq!(acc.count, 2);
+        assert_eq!(agg.finalize(acc).unwrap(), Value::Integer(2));
+    }
+
+    #[test]
+    fn test_sum_and_avg_aggregations() {
+        let column = || {
+            vec![Expr::ColumnReference {
+                tuple_idx: 0,
+                col_idx: 0,
+            }]
+        };
+        let sum = Aggregation::new(AggregationFunc::Sum, column(), false);
+        let avg = Aggregation::new(AggregationFunc::Avg, column(), false);
 
-        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Boolean(true)]));
-        assert_eq!(acc.as_i32(), 1);
+        assert_eq!(
+            sum.finalize(sum.initial_accumulator()).unwrap(),
+            Value::Null
+        );
+        assert_eq!(
+            avg.finalize(avg.initial_accumulator()).unwrap(),
+            Value::Null
+        );
 
-        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Boolean(false)]));
-        assert_eq!(acc.as_i32(), 2);
+        let aggregate = |agg: &Aggregation, values: &[i32]| {
+            let mut acc = agg.initial_accumulator();
+            for val in values {
+                agg.aggregate(&mut acc, &Tuple::new(vec![Value::Integer(*val)]))
+                    .unwrap();
+            }
+            agg.aggregate(&mut acc, &Tuple::new(vec![Value::Null]))
+                .unwrap();
+            agg.finalize(acc)
+        };
+        assert_eq!(aggregate(&sum, &[1, 2, 4]).unwrap(), Value::Integer(7));
+        assert_eq!(aggregate(&avg, &[1, 2, 4]).unwrap(), Value::Integer(2));
+        assert_eq!(aggregate(&avg, &[1, 2]).unwrap(), Value::Integer(2));
+        assert_eq!(aggregate(&avg, &[-1, -2]).unwrap(), Value::Integer(-2));
 
-        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Null]));
-        assert_eq!(acc.as_i32(), 2);
+        // intermediate sums may exceed the integer range, the result may not
+        let values = [i32::MAX, i32::MAX, i32::MIN, i32::MIN];
+        assert_eq!(aggregate(&sum, &values).unwrap(), Value::Integer(-2));
+        assert_eq!(aggregate(&avg, &values).unwrap(), Value::Integer(-1));
+        assert!(aggregate(&sum, &[i32::MAX, 1]).is_err());
     }
 
     #[test]
     fn test_max_aggregation_of_integers() {
         let agg = Aggregation::new(
             AggregationFunc::Max,
-            Expr::ColumnReference {
+            vec![Expr::ColumnReference {
                 tuple_idx: 0,
                 col_idx: 0,
-            },
+            }],
+            false,
         );
 
-        let mut acc = agg.initial_accumulator_value();
-        assert!(acc.is_null());
+        let mut acc = agg.initial_accumulator();
+        assert!(acc.value.is_null());
 
-        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Integer(3)]));
-        assert_eq!(acc.as_i32(), 3);
+        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Integer(3)]))
+            .unwrap();
+        assert_eq!(acc.value.as_i32(), 3);
 
-        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Integer(2)]));
-        assert_eq!(acc.as_i32(), 3);
+        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Integer(2)]))
+            .unwrap();
+        assert_eq!(acc.value.as_i32(), 3);
 
-        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Null]));
-        assert_eq!(acc.as_i32(), 3);
+        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Null]))
+            .unwrap();
+        assert_eq!(acc.value.as_i32(), 3);
 
-        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Integer(42)]));
-        assert_eq!(acc.as_i32(), 42);
+        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Integer(42)]))
+            .unwrap();
+        assert_eq!(acc.value.as_i32(), 42);
     }
 
     #[test]
     fn test_max_aggregation_of_text() {
         let agg = Aggregation::new(
             AggregationFunc::Max,
-            Expr::ColumnReference {
+            vec![Expr::ColumnReference {
                 tuple_idx: 0,
                 col_idx: 0,
-            },
+            }],
+            false,
         );
 
-        let mut acc = agg.initial_accumulator_value();
-        assert!(acc.is_null());
+        let mut acc = agg.initial_accumulator();
+        assert!(acc.value.is_null());
 
-        agg.aggregate(&mut acc, &Tuple::new(vec![Value::String("cd".to_owned())]));
-        assert_eq!(acc.as_str(), "cd");
+        agg.aggregate(&mut acc, &Tuple::new(vec![Value::String("cd".to_owned())]))
+            .unwrap();
+        assert_eq!(acc.value.as_str(), "cd");
 
-        agg.aggregate(&mut acc, &Tuple::new(vec![Value::String("cat".to_owned())]));
-        assert_eq!(acc.as_str(), "cd");
+        agg.aggregate(&mut acc, &Tuple::new(vec![Value::String("cat".to_owned())]))
+            .unwrap();
+        assert_eq!(acc.value.as_str(), "cd");
 
-        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Null]));
-        assert_eq!(acc.as_str(), "cd");
+        agg.aggregate(&mut acc, &Tuple::new(vec![Value::Null]))
+            .unwrap();
+        assert_eq!(acc.value.as_str(), "cd");
 
-        agg.aggregate(&mut acc, &Tuple::new(vec![Value::String("rm".to_owned())]));
-        assert_eq!(acc.as_str(), "rm");
+        agg.aggregate(&mut acc, &Tuple::new(vec![Value::String("rm".to_owned())]))
+            .unwrap();
+        assert_eq!(acc.value.as_str(), "rm");
     }
 }
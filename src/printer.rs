@@ -1,5 +1,4 @@
-use std::io::{BufWriter, Write};
-use std::net::TcpStream;
+use std::io::Write;
 
 use anyhow::Result;
 
@@ -20,7 +19,20 @@ impl<'a> Printer<'a> {
             let col_width = match col.type_id() {
                 TypeId::Boolean => col_name_size.max("false".chars().count()),
                 TypeId::Integer => col_name_size.max(10),
-                TypeId::Text => col_name_size.max(25),
+                TypeId::Text => match col.text_length() {
+                    Some(text_length) => col_name_size.max(text_length.max_chars()),
+                    None => col_name_size.max(25),
+                },
+                TypeId::Date => col_name_size.max("YYYY-MM-DD".chars().count()),
+                TypeId::Time => col_name_size.max("HH:MM:SS.ffffff".chars().count()),
+                TypeId::Timestamp => {
+                    col_name_size.max("YYYY-MM-DD HH:MM:SS.ffffff".chars().count())
+                }
+                TypeId::Interval => col_name_size.max(25),
+                TypeId::Bytea => col_name_size.max(25),
+                TypeId::Uuid => col_name_size.max(36),
+                TypeId::Json => col_name_size.max(25),
+                TypeId::IntegerArray | TypeId::TextArray => col_name_size.max(25),
                 TypeId::Unknown => col_name_size.max("NULL".chars().count()),
             };
             column_widths.push(col_width);
@@ -32,7 +44,7 @@ impl<'a> Printer<'a> {
         }
     }
 
-    fn print_header(&self, writer: &mut BufWriter<&TcpStream>) -> Result<()> {
+    fn print_header(&self, writer: &mut impl Write) -> Result<()> {
         let col_names = self
             .executor
             .schema()
@@ -60,7 +72,7 @@ impl<'a> Printer<'a> {
         Ok(())
     }
 
-    pub fn print_all_tuples(&mut self, writer: &mut BufWriter<&TcpStream>) -> Result<()> {
+    pub fn print_all_tuples(&mut self, writer: &mut impl Write) -> Result<()> {
         self.print_header(writer)?;
 
         while let Some(tuple) = self.executor.next() {
@@ -80,3 +92,53 @@ impl<'a> Printer<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Printer;
+    use crate::catalog::schema::{ColumnDefinition, TextLength, TypeId};
+    use crate::executors::tests::TestDb;
+
+    #[test]
+    fn sizes_columns_by_declared_text_length() {
+        let test_db = TestDb::new();
+        test_db
+            .create_table(
+                "countries",
+                vec![
+                    ColumnDefinition::new(TypeId::Text, "code".to_owned(), 0, true)
+                        .with_text_length(TextLength::Fixed(2)),
+                    ColumnDefinition::new(TypeId::Text, "name".to_owned(), 1, true)
+                        .with_text_length(TextLength::Varying(12)),
+                ],
+            )
+            .unwrap();
+        test_db
+            .execute_query("insert into countries values ('de', 'Germany')")
+            .unwrap();
+
+        let print = |sql: &str| {
+            test_db
+                .with_executor(sql, |executor| {
+                    let mut output = vec![];
+                    Printer::new(executor).print_all_tuples(&mut output)?;
+                    Ok(String::from_utf8(output)?)
+                })
+                .unwrap()
+        };
+
+        assert_eq!(
+            print("select code, name from countries"),
+            "code|        name\n----+------------\n  de|     Germany\n"
+        );
+        assert_eq!(
+            print("select * from countries c"),
+            "c.code|      c.name\n------+------------\n    de|     Germany\n"
+        );
+        // expressions have no declared length
+        assert_eq!(
+            print("select lower(code) from countries"),
+            format!("{:>25}\n{}\n{:>25}\n", "lower(code)", "-".repeat(25), "de")
+        );
+    }
+}
@@ -10,12 +10,17 @@ use dashmap::DashMap;
 
 use crate::common::{PageNo, TableId, INVALID_PAGE_NO, PAGE_SIZE};
 
+/// Subdirectory of the data directory, which contains temporary files
+const TEMP_DIRECTORY: &str = "tmp";
+
 /// FileManager takes care of reading and writing pages of tables.
 /// It assumes that all tables are stored inside a single directory, the data directory,
 /// where each table is represented as a single file, with the table id used as the filename.
 pub struct FileManager {
     data_directory: PathBuf,
     table_id_to_file: DashMap<TableId, FileHandle>,
+    /// used to give temporary files unique names
+    temp_file_counter: AtomicU64,
 }
 
 impl FileManager {
@@ -61,11 +66,39 @@ impl FileManager {
         Ok(Self {
             data_directory,
             table_id_to_file,
+            temp_file_counter: AtomicU64::new(0),
         })
     }
 
+    /// Creates a temporary file, e.g. for intermediate results which do not fit into memory.
+    /// The file is unlinked right away, so it is removed as soon as the returned handle is
+    /// dropped (or the process exits)
+    pub fn create_temp_file(&self) -> Result<File> {
+        let temp_directory = self.data_directory.join(TEMP_DIRECTORY);
+        std::fs::create_dir_all(&temp_directory).with_context(|| {
+            format!(
+                "Could not create directory for temporary files {}",
+                temp_directory.display()
+            )
+        })?;
+
+        let file_no = self.temp_file_counter.fetch_add(1, Ordering::Relaxed);
+        let path = temp_directory.join(file_no.to_string());
+        let file = OpenOptions::new()
+            .write(true)
+            .read(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .with_context(|| format!("Failed to create temporary file {}", path.display()))?;
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to unlink temporary file {}", path.display()))?;
+
+        Ok(file)
+    }
+
     /// Returns the FileHandle object of a table.
-    fn get_file(&self, table_id: TableId) -> Result<Ref<TableId, FileHandle>> {
+    fn get_file(&self, table_id: TableId) -> Result<Ref<'_, TableId, FileHandle>> {
         self.table_id_to_file
             .get(&table_id)
             .ok_or_else(|| Error::msg(format!("No data file for table with id {}", table_id)))
@@ -245,10 +278,12 @@ fn read_table(entry: DirEntry, table_id: TableId) -> Result<FileHandle> {
 #[cfg(test)]
 mod tests {
 
+    use std::io::{Read, Seek, SeekFrom, Write};
+
     use anyhow::Result;
     use tempfile::tempdir;
 
-    use super::{FileManager, PAGE_SIZE};
+    use super::{FileManager, PAGE_SIZE, TEMP_DIRECTORY};
 
     #[test]
     fn basic_test() -> Result<()> {
@@ -270,4 +305,24 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn can_create_temp_files() -> Result<()> {
+        let data_dir = tempdir()?;
+        let file_manager = FileManager::new(data_dir.path())?;
+
+        let mut file = file_manager.create_temp_file()?;
+        file.write_all(b"erdb")?;
+        file.seek(SeekFrom::Start(0))?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        assert_eq!(content, "erdb");
+
+        // temporary files are neither visible in the data directory nor treated as tables
+        let temp_dir = data_dir.path().join(TEMP_DIRECTORY);
+        assert_eq!(temp_dir.read_dir()?.count(), 0);
+        FileManager::new(data_dir.path())?;
+
+        Ok(())
+    }
 }
@@ -186,7 +186,7 @@ impl Table {
         &self.schema
     }
 
-    fn fetch_page(&self, page_no: PageNo) -> Result<BufferGuard> {
+    fn fetch_page(&self, page_no: PageNo) -> Result<BufferGuard<'_>> {
         let buffer = self.buffer_manager.fetch((self.table_id, page_no))?;
         match buffer {
             None => Err(Error::msg(format!(
@@ -197,7 +197,7 @@ impl Table {
         }
     }
 
-    fn allocate_new_page(&self) -> Result<BufferGuard> {
+    fn allocate_new_page(&self) -> Result<BufferGuard<'_>> {
         let buffer = self
             .buffer_manager
             .allocate_new_page(self.table_id, EMPTY_HEAP_PAGE.as_slice())?;
@@ -210,16 +210,19 @@ impl Table {
         }
     }
 
-    /// Checks whether a tuple fits into a page.
+    /// Checks whether a tuple fits into a page and all of its values can be serialized.
     /// If so, returns the required free space in a page
     fn check_tuple_size(&self, tuple: &Tuple) -> Result<u16> {
+        for value in tuple.values() {
+            value.check_storage_size()?;
+        }
         let required_size = required_free_space(tuple);
-        if required_size >= MAX_TUPLE_SIZE {
+        if required_size >= MAX_TUPLE_SIZE as usize {
             return Err(Error::msg(format!(
                 "Attempted to insert a tuple which would occupy {required_size} bytes."
             )));
         }
-        Ok(required_size)
+        Ok(required_size as u16)
     }
 
     pub fn fetch_tuple(&self, tuple_id: TupleId) -> Result<Tuple> {
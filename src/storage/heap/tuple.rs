@@ -31,11 +31,11 @@ pub fn parse_heap_tuple(bytes: &[u8], header: &HeapTupleHeader, schema: &Schema)
 }
 
 /// Calculates how many bytes a serialized tuple, including its header would occupy
-pub fn required_free_space(tuple: &Tuple) -> u16 {
+pub fn required_free_space(tuple: &Tuple) -> usize {
     let header_size = HeapTupleHeader::required_free_space(tuple);
     let data_size: usize = tuple.values().iter().map(|val| val.size()).sum();
 
-    (header_size + data_size) as u16
+    header_size + data_size
 }
 
 pub fn serialize_heap_tuple(
@@ -47,7 +47,7 @@ pub fn serialize_heap_tuple(
 ) {
     let header = HeapTupleHeader::new_tuple(tuple, insert_tid, command_id, tuple_id);
     let mut user_data_next_value = header.user_data_start();
-    for (_column, value) in tuple.values().iter().enumerate() {
+    for value in tuple.values().iter() {
         if !value.is_null() {
             value.serialize_value(&mut buffer[user_data_next_value..]);
             user_data_next_value += value.size();
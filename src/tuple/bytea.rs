@@ -0,0 +1,54 @@
+use anyhow::{Error, Result};
+
+/// Parses the hex format of a BYTEA value, e.g. `\xdeadbeef`.
+/// Like Postgres, whitespace between two hex digit pairs is ignored
+pub fn parse_hex(s: &str) -> Result<Vec<u8>> {
+    let hex = s
+        .strip_prefix("\\x")
+        .or_else(|| s.strip_prefix("\\X"))
+        .ok_or_else(|| Error::msg(format!("Invalid bytea '{}', expected a '\\x' prefix", s)))?;
+
+    let mut bytes = vec![];
+    let mut digits = hex.chars().filter(|c| !c.is_whitespace());
+    while let Some(high) = digits.next() {
+        let low = digits
+            .next()
+            .ok_or_else(|| Error::msg(format!("Invalid bytea '{}', odd number of digits", s)))?;
+        let byte = match (high.to_digit(16), low.to_digit(16)) {
+            (Some(high), Some(low)) => (high << 4 | low) as u8,
+            _ => return Err(Error::msg(format!("Invalid hex digit in bytea '{}'", s))),
+        };
+        bytes.push(byte);
+    }
+
+    Ok(bytes)
+}
+
+pub fn format_hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(2 + 2 * bytes.len());
+    s.push_str("\\x");
+    for byte in bytes {
+        s.push_str(&format!("{:02x}", byte));
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_hex, parse_hex};
+
+    #[test]
+    fn can_parse_and_format_hex() {
+        assert_eq!(
+            parse_hex("\\xDEADbeef").unwrap(),
+            vec![0xde, 0xad, 0xbe, 0xef]
+        );
+        assert_eq!(parse_hex("\\x00 ff").unwrap(), vec![0x00, 0xff]);
+        assert_eq!(parse_hex("\\x").unwrap(), Vec::<u8>::new());
+        assert!(parse_hex("deadbeef").is_err());
+        assert!(parse_hex("\\xabc").is_err());
+        assert!(parse_hex("\\xzz").is_err());
+
+        assert_eq!(format_hex(&[0x01, 0xab]), "\\x01ab");
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::{Error, Result};
+
+use super::datetime::{self, MICROS_PER_DAY};
+use super::json::Json;
+use super::value::Value;
+use super::{bytea, uuid};
+use crate::catalog::schema::TypeId;
+
+/// Describes in which situations a cast between two types may be applied
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CastContext {
+    /// the cast is applied automatically, e.g. when comparing a date with a timestamp
+    Implicit,
+    /// the cast is only applied if requested with CAST(expr AS type) or expr::type
+    Explicit,
+}
+
+/// The coercion table: returns whether (and when) a value of type `from` can be cast
+/// to type `to`, or None if there is no such cast
+pub fn cast_context(from: TypeId, to: TypeId) -> Option<CastContext> {
+    use TypeId::*;
+
+    let context = match (from, to) {
+        (from, to) if from == to => CastContext::Implicit,
+        (Unknown, _) => CastContext::Implicit,
+        (Date, Timestamp) => CastContext::Implicit,
+        (Timestamp, Date | Time) => CastContext::Explicit,
+        (Integer, Boolean) | (Boolean, Integer) => CastContext::Explicit,
+        (IntegerArray, TextArray) | (TextArray, IntegerArray) => CastContext::Explicit,
+        // every type has a text representation, which can be parsed again
+        (Text, _) | (_, Text) => CastContext::Explicit,
+        _ => return None,
+    };
+    Some(context)
+}
+
+/// Casts a value to the target type. The analyzer makes sure that such a cast exists,
+/// but the conversion itself might fail, e.g. for CAST('abc' AS INTEGER)
+pub fn cast_value(value: Value, target: TypeId) -> Result<Value> {
+    let value = match (value, target) {
+        (Value::Null, _) => Value::Null,
+        (Value::String(s), target) => parse_text(&s, target)?,
+        (value, TypeId::Text) => Value::String(value.to_string()),
+        (Value::Date(days), TypeId::Timestamp) => Value::Timestamp(days as i64 * MICROS_PER_DAY),
+        (Value::Timestamp(micros), TypeId::Date) => {
+            Value::Date(micros.div_euclid(MICROS_PER_DAY) as i32)
+        }
+        (Value::Timestamp(micros), TypeId::Time) => Value::Time(micros.rem_euclid(MICROS_PER_DAY)),
+        (Value::Integer(val), TypeId::Boolean) => Value::Boolean(val != 0),
+        (Value::Boolean(val), TypeId::Integer) => Value::Integer(val as i32),
+        (Value::Array(elements), target @ (TypeId::IntegerArray | TypeId::TextArray)) => {
+            let element_type = target.element_type().unwrap();
+            Value::Array(
+                elements
+                    .into_iter()
+                    .map(|element| cast_value(element, element_type))
+                    .collect::<Result<Vec<_>>>()?,
+            )
+        }
+        (value, _) => value,
+    };
+    Ok(value)
+}
+
+/// Parses the text representation of a value of the target type
+fn parse_text(s: &str, target: TypeId) -> Result<Value> {
+    let value = match target {
+        TypeId::Text | TypeId::Unknown => Value::String(s.to_owned()),
+        TypeId::Integer => Value::Integer(
+            s.trim()
+                .parse::<i32>()
+                .map_err(|_| Error::msg(format!("Invalid input for type {}: '{}'", target, s)))?,
+        ),
+        TypeId::Boolean => match s.trim().to_lowercase().as_str() {
+            "t" | "true" | "y" | "yes" | "on" | "1" => Value::Boolean(true),
+            "f" | "false" | "n" | "no" | "off" | "0" => Value::Boolean(false),
+            _ => {
+                return Err(Error::msg(format!(
+                    "Invalid input for type {}: '{}'",
+                    target, s
+                )))
+            }
+        },
+        TypeId::Date => Value::Date(datetime::parse_date(s)?),
+        TypeId::Time => Value::Time(datetime::parse_time(s)?),
+        TypeId::Timestamp => Value::Timestamp(datetime::parse_timestamp(s)?),
+        TypeId::Interval => Value::Interval(datetime::parse_interval(s)?),
+        TypeId::Bytea => Value::Bytea(bytea::parse_hex(s)?),
+        TypeId::Uuid => Value::Uuid(uuid::parse_uuid(s)?),
+        TypeId::Json => Value::Json(Json::parse(s)?),
+        TypeId::IntegerArray | TypeId::TextArray => {
+            Value::Array(parse_array(s, target.element_type().unwrap())?)
+        }
+    };
+    Ok(value)
+}
+
+/// Parses an array in its text representation, e.g. `{1,2,NULL}` or `{a,"b c"}`
+fn parse_array(s: &str, element_type: TypeId) -> Result<Vec<Value>> {
+    let invalid = || Error::msg(format!("Invalid array literal '{}'", s));
+
+    let inner = s
+        .trim()
+        .strip_prefix('{')
+        .and_then(|s| s.strip_suffix('}'))
+        .ok_or_else(invalid)?;
+    if inner.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut elements = vec![];
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let element = if chars.next_if_eq(&'"').is_some() {
+            let mut element = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => element.push(chars.next().ok_or_else(invalid)?),
+                    Some(c) => element.push(c),
+                    None => return Err(invalid()),
+                }
+            }
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            Value::String(element)
+        } else {
+            let mut element = String::new();
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                if ['{', '}', '"'].contains(&c) {
+                    return Err(invalid());
+                }
+                element.push(c);
+            }
+            let element = element.trim();
+            if element.is_empty() {
+                return Err(invalid());
+            }
+            if element.eq_ignore_ascii_case("null") {
+                Value::Null
+            } else {
+                Value::String(element.to_owned())
+            }
+        };
+        elements.push(cast_value(element, element_type)?);
+
+        match chars.next() {
+            Some(',') => continue,
+            None => break,
+            Some(_) => return Err(invalid()),
+        }
+    }
+
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cast_context, cast_value, CastContext};
+    use crate::catalog::schema::TypeId;
+    use crate::tuple::value::Value;
+
+    #[test]
+    fn can_cast_values() {
+        let text = |s: &str| Value::String(s.to_owned());
+
+        assert_eq!(
+            cast_value(text(" 42 "), TypeId::Integer).unwrap(),
+            Value::Integer(42)
+        );
+        assert!(cast_value(text("4x2"), TypeId::Integer).is_err());
+        assert_eq!(
+            cast_value(Value::Integer(-7), TypeId::Text).unwrap(),
+            text("-7")
+        );
+        assert_eq!(
+            cast_value(text("Yes"), TypeId::Boolean).unwrap(),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            cast_value(Value::Timestamp(-1), TypeId::Date).unwrap(),
+            Value::Date(-1)
+        );
+        assert_eq!(
+            cast_value(Value::Null, TypeId::Integer).unwrap(),
+            Value::Null
+        );
+
+        assert_eq!(
+            cast_value(text(r#"{a, "b,c" ,NULL}"#), TypeId::TextArray).unwrap(),
+            Value::Array(vec![text("a"), text("b,c"), Value::Null])
+        );
+        assert_eq!(
+            cast_value(text("{}"), TypeId::IntegerArray).unwrap(),
+            Value::Array(vec![])
+        );
+        assert!(cast_value(text("{1,,2}"), TypeId::IntegerArray).is_err());
+        assert!(cast_value(text("{1,a}"), TypeId::IntegerArray).is_err());
+    }
+
+    #[test]
+    fn only_date_to_timestamp_is_implicit() {
+        assert_eq!(
+            cast_context(TypeId::Date, TypeId::Timestamp),
+            Some(CastContext::Implicit)
+        );
+        assert_eq!(
+            cast_context(TypeId::Timestamp, TypeId::Date),
+            Some(CastContext::Explicit)
+        );
+        assert_eq!(
+            cast_context(TypeId::Integer, TypeId::Text),
+            Some(CastContext::Explicit)
+        );
+        assert_eq!(cast_context(TypeId::Integer, TypeId::Date), None);
+    }
+}
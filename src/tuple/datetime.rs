@@ -0,0 +1,584 @@
+//! Calendar arithmetic for the temporal types.
+//!
+//! Dates are stored as days since 1970-01-01, times as microseconds since midnight and
+//! timestamps as microseconds since 1970-01-01 00:00:00. The conversions between days and
+//! (year, month, day) are based on the algorithms from
+//! http://howardhinnant.github.io/date_algorithms.html
+
+use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+
+use anyhow::{Error, Result};
+
+pub const MICROS_PER_SECOND: i64 = 1_000_000;
+pub const MICROS_PER_MINUTE: i64 = 60 * MICROS_PER_SECOND;
+pub const MICROS_PER_HOUR: i64 = 60 * MICROS_PER_MINUTE;
+pub const MICROS_PER_DAY: i64 = 24 * MICROS_PER_HOUR;
+/// used to compare intervals, same as postgres does
+const DAYS_PER_MONTH: i64 = 30;
+
+/// An interval is kept in three separate parts, because the length of months and days
+/// depends on the date the interval is added to
+#[derive(Clone, Copy, Debug)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub micros: i64,
+}
+
+impl Interval {
+    pub fn new(months: i32, days: i32, micros: i64) -> Self {
+        Self {
+            months,
+            days,
+            micros,
+        }
+    }
+
+    /// Returns the negated interval, or None if one of its fields overflows
+    pub fn checked_negate(&self) -> Option<Self> {
+        Some(Self::new(
+            self.months.checked_neg()?,
+            self.days.checked_neg()?,
+            self.micros.checked_neg()?,
+        ))
+    }
+
+    /// Returns the sum of two intervals, or None if one of its fields overflows
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(Self::new(
+            self.months.checked_add(other.months)?,
+            self.days.checked_add(other.days)?,
+            self.micros.checked_add(other.micros)?,
+        ))
+    }
+
+    /// Returns the interval multiplied by `factor`, or None if one of its fields overflows
+    pub fn checked_multiply(&self, factor: i32) -> Option<Self> {
+        Some(Self::new(
+            self.months.checked_mul(factor)?,
+            self.days.checked_mul(factor)?,
+            self.micros.checked_mul(factor as i64)?,
+        ))
+    }
+
+    /// Returns the length of an interval in microseconds, where a month has 30 days
+    pub fn total_micros(&self) -> i128 {
+        (self.months as i128 * DAYS_PER_MONTH as i128 + self.days as i128) * MICROS_PER_DAY as i128
+            + self.micros as i128
+    }
+}
+
+// like in Postgres, intervals are equal if they have the same length, e.g. 1 day = 24 hours.
+// This keeps equality consistent with the ordering, e.g. for GROUP BY and DISTINCT
+impl PartialEq for Interval {
+    fn eq(&self, other: &Self) -> bool {
+        self.total_micros() == other.total_micros()
+    }
+}
+
+impl Eq for Interval {}
+
+impl Hash for Interval {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.total_micros().hash(state)
+    }
+}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.total_micros().partial_cmp(&other.total_micros())
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        let years = self.months / 12;
+        let months = self.months % 12;
+        if years != 0 {
+            parts.push(format!("{} year{}", years, plural(years as i64)));
+        }
+        if months != 0 {
+            parts.push(format!("{} mon{}", months, plural(months as i64)));
+        }
+        if self.days != 0 {
+            parts.push(format!("{} day{}", self.days, plural(self.days as i64)));
+        }
+        if self.micros != 0 || parts.is_empty() {
+            let sign = if self.micros < 0 { "-" } else { "" };
+            parts.push(format!(
+                "{}{}",
+                sign,
+                format_time(self.micros.unsigned_abs())
+            ));
+        }
+        f.pad(&parts.join(" "))
+    }
+}
+
+fn plural(n: i64) -> &'static str {
+    if n.abs() == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => unreachable!(),
+    }
+}
+
+/// Returns the number of days since 1970-01-01
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Returns (year, month, day) of a date given as days since 1970-01-01
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = if days >= 0 { days } else { days - 146096 } / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+fn parse_number(s: &str, what: &str, input: &str) -> Result<i64> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+        return Err(Error::msg(format!("Invalid {} '{}'", what, input)));
+    }
+    s.parse::<i64>()
+        .map_err(|_| Error::msg(format!("Invalid {} '{}'", what, input)))
+}
+
+/// Parses a date in the format YYYY-MM-DD
+pub fn parse_date(s: &str) -> Result<i32> {
+    let parts = s.trim().split('-').collect::<Vec<_>>();
+    if parts.len() != 3 {
+        return Err(Error::msg(format!("Invalid date '{}'", s)));
+    }
+    let year = parse_number(parts[0], "date", s)?;
+    let month = parse_number(parts[1], "date", s)?;
+    let day = parse_number(parts[2], "date", s)?;
+    if !(1..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+    {
+        return Err(Error::msg(format!("Date '{}' is out of range", s)));
+    }
+    Ok(days_from_civil(year, month, day) as i32)
+}
+
+/// Parses a time in the format HH:MM[:SS[.ffffff]]
+pub fn parse_time(s: &str) -> Result<i64> {
+    let parts = s.trim().split(':').collect::<Vec<_>>();
+    if parts.len() < 2 || parts.len() > 3 {
+        return Err(Error::msg(format!("Invalid time '{}'", s)));
+    }
+    let hour = parse_number(parts[0], "time", s)?;
+    let minute = parse_number(parts[1], "time", s)?;
+    let (second, fraction) = match parts.get(2) {
+        Some(seconds) => match seconds.split_once('.') {
+            Some((second, fraction)) => {
+                if fraction.len() > 6 {
+                    return Err(Error::msg(format!(
+                        "Time '{}' has more than 6 fractional digits",
+                        s
+                    )));
+                }
+                let micros =
+                    parse_number(fraction, "time", s)? * 10i64.pow(6 - fraction.len() as u32);
+                (parse_number(second, "time", s)?, micros)
+            }
+            None => (parse_number(seconds, "time", s)?, 0),
+        },
+        None => (0, 0),
+    };
+    if hour > 23 || minute > 59 || second > 59 {
+        return Err(Error::msg(format!("Time '{}' is out of range", s)));
+    }
+    Ok(hour * MICROS_PER_HOUR + minute * MICROS_PER_MINUTE + second * MICROS_PER_SECOND + fraction)
+}
+
+/// Parses a timestamp in the format YYYY-MM-DD[( |T)HH:MM[:SS[.ffffff]]]
+pub fn parse_timestamp(s: &str) -> Result<i64> {
+    let s = s.trim();
+    let (date, time) = match s.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+    let days = parse_date(date)? as i64;
+    let micros = match time {
+        Some(time) => parse_time(time)?,
+        None => 0,
+    };
+    Ok(days * MICROS_PER_DAY + micros)
+}
+
+/// Parses an interval, e.g. '1 year 2 months', '3 days 04:05:06' or '-2 hours'
+pub fn parse_interval(s: &str) -> Result<Interval> {
+    let mut interval = Interval::new(0, 0, 0);
+    let mut words = s.split_whitespace().peekable();
+    if words.peek().is_none() {
+        return Err(Error::msg(format!("Invalid interval '{}'", s)));
+    }
+    let overflow = || Error::msg(format!("Interval '{}' is out of range", s));
+    // adds the value of a unit to a field, the value is None if it already overflowed
+    let add_i32 = |field: i32, value: Option<i64>| {
+        value
+            .and_then(|value| i32::try_from(value).ok())
+            .and_then(|value| field.checked_add(value))
+            .ok_or_else(overflow)
+    };
+    let add_i64 = |field: i64, value: Option<i64>| {
+        value
+            .and_then(|value| field.checked_add(value))
+            .ok_or_else(overflow)
+    };
+
+    while let Some(word) = words.next() {
+        let (negative, number) = match word.strip_prefix('-') {
+            Some(number) => (true, number),
+            None => (false, word.strip_prefix('+').unwrap_or(word)),
+        };
+        let sign = if negative { -1 } else { 1 };
+
+        if number.contains(':') {
+            interval.micros = add_i64(interval.micros, Some(sign * parse_time(number)?))?;
+            continue;
+        }
+
+        let number = parse_number(number, "interval", s)? * sign;
+        let unit = words
+            .next()
+            .ok_or_else(|| Error::msg(format!("Missing unit in interval '{}'", s)))?;
+        match unit.to_lowercase().as_str() {
+            "year" | "years" => interval.months = add_i32(interval.months, number.checked_mul(12))?,
+            "month" | "months" | "mon" | "mons" => {
+                interval.months = add_i32(interval.months, Some(number))?
+            }
+            "week" | "weeks" => interval.days = add_i32(interval.days, number.checked_mul(7))?,
+            "day" | "days" => interval.days = add_i32(interval.days, Some(number))?,
+            "hour" | "hours" => {
+                interval.micros = add_i64(interval.micros, number.checked_mul(MICROS_PER_HOUR))?
+            }
+            "minute" | "minutes" | "min" | "mins" => {
+                interval.micros = add_i64(interval.micros, number.checked_mul(MICROS_PER_MINUTE))?
+            }
+            "second" | "seconds" | "sec" | "secs" => {
+                interval.micros = add_i64(interval.micros, number.checked_mul(MICROS_PER_SECOND))?
+            }
+            "millisecond" | "milliseconds" | "ms" => {
+                interval.micros = add_i64(interval.micros, number.checked_mul(1000))?
+            }
+            "microsecond" | "microseconds" | "us" => {
+                interval.micros = add_i64(interval.micros, Some(number))?
+            }
+            unit => {
+                return Err(Error::msg(format!(
+                    "Invalid unit '{}' in interval '{}'",
+                    unit, s
+                )))
+            }
+        }
+    }
+
+    Ok(interval)
+}
+
+pub fn format_date(days: i32) -> String {
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Formats a time of day or the time part of an interval. The magnitude of an interval can
+/// exceed `i64::MAX` microseconds, so it is passed unsigned
+pub fn format_time(micros: u64) -> String {
+    let hours = micros / MICROS_PER_HOUR as u64;
+    let minutes = micros % MICROS_PER_HOUR as u64 / MICROS_PER_MINUTE as u64;
+    let seconds = micros % MICROS_PER_MINUTE as u64 / MICROS_PER_SECOND as u64;
+    let fraction = micros % MICROS_PER_SECOND as u64;
+    if fraction == 0 {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        let fraction = format!("{:06}", fraction);
+        format!(
+            "{:02}:{:02}:{:02}.{}",
+            hours,
+            minutes,
+            seconds,
+            fraction.trim_end_matches('0')
+        )
+    }
+}
+
+pub fn format_timestamp(micros: i64) -> String {
+    let days = micros.div_euclid(MICROS_PER_DAY);
+    let time = micros.rem_euclid(MICROS_PER_DAY);
+    format!("{} {}", format_date(days as i32), format_time(time as u64))
+}
+
+/// Adds a number of months to a date. If the day does not exist in the resulting month,
+/// the last day of that month is used instead (e.g. 2024-01-31 + 1 month = 2024-02-29)
+fn add_months(days: i64, months: i32) -> i64 {
+    let (year, month, day) = civil_from_days(days);
+    let total_months = year * 12 + (month - 1) + months as i64;
+    let year = total_months.div_euclid(12);
+    let month = total_months.rem_euclid(12) + 1;
+    days_from_civil(year, month, day.min(days_in_month(year, month)))
+}
+
+/// Adds an interval to a timestamp. Returns None if the result is out of range
+pub fn add_interval_to_timestamp(timestamp: i64, interval: &Interval) -> Option<i64> {
+    let days = timestamp.div_euclid(MICROS_PER_DAY);
+    let time = timestamp.rem_euclid(MICROS_PER_DAY);
+    let days = add_months(days, interval.months) + interval.days as i64;
+    days.checked_mul(MICROS_PER_DAY)?
+        .checked_add(time)?
+        .checked_add(interval.micros)
+}
+
+/// Returns the difference between two timestamps as an interval of days and microseconds,
+/// or None if the difference is out of range
+pub fn subtract_timestamps(left: i64, right: i64) -> Option<Interval> {
+    let diff = left.checked_sub(right)?;
+    Some(Interval::new(
+        0,
+        (diff / MICROS_PER_DAY) as i32,
+        diff % MICROS_PER_DAY,
+    ))
+}
+
+pub fn current_timestamp() -> i64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    now.as_micros() as i64
+}
+
+pub fn current_date() -> i32 {
+    current_timestamp().div_euclid(MICROS_PER_DAY) as i32
+}
+
+/// Extracts a field of a timestamp
+pub fn extract_from_timestamp(field: DateTimeField, micros: i64) -> i64 {
+    let days = micros.div_euclid(MICROS_PER_DAY);
+    let time = micros.rem_euclid(MICROS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    match field {
+        DateTimeField::Year => year,
+        DateTimeField::Quarter => (month - 1) / 3 + 1,
+        DateTimeField::Month => month,
+        DateTimeField::Day => day,
+        DateTimeField::Hour => time / MICROS_PER_HOUR,
+        DateTimeField::Minute => time % MICROS_PER_HOUR / MICROS_PER_MINUTE,
+        DateTimeField::Second => time % MICROS_PER_MINUTE / MICROS_PER_SECOND,
+        // 1970-01-01 was a thursday
+        DateTimeField::DayOfWeek => (days + 4).rem_euclid(7),
+        DateTimeField::DayOfYear => days - days_from_civil(year, 1, 1) + 1,
+        DateTimeField::Epoch => micros.div_euclid(MICROS_PER_SECOND),
+    }
+}
+
+/// Extracts a field of a time. Returns None if the time has no such field
+pub fn extract_from_time(field: DateTimeField, micros: i64) -> Option<i64> {
+    match field {
+        DateTimeField::Hour | DateTimeField::Minute | DateTimeField::Second => {
+            Some(extract_from_timestamp(field, micros))
+        }
+        DateTimeField::Epoch => Some(micros / MICROS_PER_SECOND),
+        _ => None,
+    }
+}
+
+/// Extracts a field of an interval. Returns None if the interval has no such field
+pub fn extract_from_interval(field: DateTimeField, interval: &Interval) -> Option<i64> {
+    let res = match field {
+        DateTimeField::Year => interval.months as i64 / 12,
+        DateTimeField::Month => interval.months as i64 % 12,
+        DateTimeField::Day => interval.days as i64,
+        DateTimeField::Hour => interval.micros / MICROS_PER_HOUR,
+        DateTimeField::Minute => interval.micros % MICROS_PER_HOUR / MICROS_PER_MINUTE,
+        DateTimeField::Second => interval.micros % MICROS_PER_MINUTE / MICROS_PER_SECOND,
+        DateTimeField::Epoch => (interval.total_micros() / MICROS_PER_SECOND as i128) as i64,
+        _ => return None,
+    };
+    Some(res)
+}
+
+/// A field which can be extracted from a temporal value, e.g. EXTRACT(year FROM ts)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateTimeField {
+    Year,
+    Quarter,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    DayOfWeek,
+    DayOfYear,
+    Epoch,
+}
+
+impl DateTimeField {
+    /// Returns None if s is not a valid field, else the field
+    pub fn is_field(s: &str) -> Option<Self> {
+        let res = match s {
+            "year" => Self::Year,
+            "quarter" => Self::Quarter,
+            "month" => Self::Month,
+            "day" => Self::Day,
+            "hour" => Self::Hour,
+            "minute" => Self::Minute,
+            "second" => Self::Second,
+            "dow" => Self::DayOfWeek,
+            "doy" => Self::DayOfYear,
+            "epoch" => Self::Epoch,
+            _ => return None,
+        };
+        Some(res)
+    }
+}
+
+impl Display for DateTimeField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Year => "year",
+            Self::Quarter => "quarter",
+            Self::Month => "month",
+            Self::Day => "day",
+            Self::Hour => "hour",
+            Self::Minute => "minute",
+            Self::Second => "second",
+            Self::DayOfWeek => "dow",
+            Self::DayOfYear => "doy",
+            Self::Epoch => "epoch",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        add_interval_to_timestamp, civil_from_days, days_from_civil, format_date, format_timestamp,
+        parse_date, parse_interval, parse_time, parse_timestamp, Interval, MICROS_PER_DAY,
+        MICROS_PER_HOUR,
+    };
+
+    #[test]
+    fn days_and_civil_dates_round_trip() {
+        for days in -800_000..800_000 {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+    }
+
+    #[test]
+    fn can_parse_and_format_temporal_values() {
+        let date = parse_date("2024-02-29").unwrap();
+        assert_eq!(format_date(date), "2024-02-29");
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("2023-13-01").is_err());
+        assert!(parse_date("yesterday").is_err());
+
+        assert_eq!(
+            parse_time("01:02").unwrap(),
+            MICROS_PER_HOUR + 2 * 60 * 1_000_000
+        );
+        assert!(parse_time("24:00:00").is_err());
+
+        let timestamp = parse_timestamp("1969-12-31 23:59:59.5").unwrap();
+        assert_eq!(timestamp, -500_000);
+        assert_eq!(format_timestamp(timestamp), "1969-12-31 23:59:59.5");
+        assert_eq!(
+            parse_timestamp("2024-01-31T10:00:00").unwrap(),
+            parse_timestamp("2024-01-31 10:00").unwrap()
+        );
+    }
+
+    #[test]
+    fn can_parse_and_format_intervals() {
+        let interval = parse_interval("1 year 2 months 3 days 04:05:06").unwrap();
+        assert_eq!(
+            interval,
+            Interval::new(14, 3, 4 * MICROS_PER_HOUR + 5 * 60 * 1_000_000 + 6_000_000)
+        );
+        assert_eq!((interval.months, interval.days), (14, 3));
+        assert_eq!(interval.to_string(), "1 year 2 mons 3 days 04:05:06");
+        // intervals of the same length are equal, even if their parts differ
+        assert_eq!(
+            parse_interval("1 day").unwrap(),
+            parse_interval("24 hours").unwrap()
+        );
+        assert_ne!(
+            parse_interval("1 month").unwrap(),
+            parse_interval("29 days").unwrap()
+        );
+        assert_eq!(parse_interval("-2 hours").unwrap().to_string(), "-02:00:00");
+        assert!(parse_interval("2 fortnights").is_err());
+        assert!(parse_interval("").is_err());
+        assert!(parse_interval("1000000000000000 hours").is_err());
+        assert!(parse_interval("9223372036854775807 ms").is_err());
+        assert!(parse_interval("300000000 years").is_err());
+        assert!(parse_interval("2147483647 days 1 day").is_err());
+        assert!(parse_interval("9223372036854775807 us 1 us").is_err());
+        assert_eq!(
+            parse_interval("2147483647 days").unwrap(),
+            Interval::new(0, i32::MAX, 0)
+        );
+        assert_eq!(
+            Interval::new(0, 0, i64::MIN).to_string(),
+            "-2562047788:00:54.775808"
+        );
+        assert_eq!(
+            format!(
+                "[{:>12}|{:<9}]",
+                Interval::new(0, 1, 0),
+                Interval::new(0, 0, 0)
+            ),
+            "[       1 day|00:00:00 ]"
+        );
+    }
+
+    #[test]
+    fn adding_months_clamps_to_end_of_month() {
+        let timestamp = parse_timestamp("2024-01-31 12:00:00").unwrap();
+        let result = add_interval_to_timestamp(timestamp, &Interval::new(1, 0, 0)).unwrap();
+        assert_eq!(format_timestamp(result), "2024-02-29 12:00:00");
+
+        let result =
+            add_interval_to_timestamp(timestamp, &Interval::new(0, -31, MICROS_PER_DAY)).unwrap();
+        assert_eq!(format_timestamp(result), "2024-01-01 12:00:00");
+    }
+}
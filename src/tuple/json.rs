@@ -0,0 +1,596 @@
+use std::fmt::{self, Display, Write};
+use std::hash::{Hash, Hasher};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use anyhow::{Error, Result};
+
+/// A parsed JSON document.
+/// Like Postgres' JSONB, objects don't preserve the order of their keys and keep only the
+/// last value of duplicate keys. Keys are kept sorted, so that lookups can use binary search
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Boolean(bool),
+    /// an integral number, kept exactly instead of rounding it to a float
+    Integer(i128),
+    /// a number with a fractional part, or an integral one too large for `Integer`.
+    /// Use `Json::number` to keep integral numbers in `Integer`, so that equal numbers are
+    /// always represented the same way
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_NUMBER: u8 = 3;
+const TAG_STRING: u8 = 4;
+const TAG_ARRAY: u8 = 5;
+const TAG_OBJECT: u8 = 6;
+const TAG_INTEGER: u8 = 7;
+
+/// The maximum number of nested arrays and objects. Parsing, formatting and comparing documents
+/// recurse into nested values, so a limit keeps deeply nested input from overflowing the stack
+const MAX_NESTING_DEPTH: usize = 256;
+
+impl Json {
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut parser = JsonParser {
+            input: s,
+            chars: s.chars().peekable(),
+        };
+        let json = parser.parse_value(0)?;
+        parser.skip_whitespace();
+        if parser.chars.peek().is_some() {
+            return Err(parser.error());
+        }
+        Ok(json)
+    }
+
+    /// A JSON number, which is an `Integer` if it is integral
+    pub fn number(num: f64) -> Self {
+        // i128::MAX as f64 rounds up to 2^127, which is out of range
+        if num.fract() == 0.0 && num >= i128::MIN as f64 && num < i128::MAX as f64 {
+            Json::Integer(num as i128)
+        } else {
+            Json::Number(num)
+        }
+    }
+
+    /// Returns the value of an object's key. None if this is not an object or it has no such key
+    pub fn get_key(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries
+                .binary_search_by(|(k, _)| k.as_str().cmp(key))
+                .ok()
+                .map(|idx| &entries[idx].1),
+            _ => None,
+        }
+    }
+
+    /// Returns the element of an array at the index, negative indexes count from the end.
+    /// None if this is not an array or the index is out of bounds
+    pub fn get_index(&self, idx: i32) -> Option<&Json> {
+        match self {
+            Json::Array(elements) => {
+                let idx = if idx < 0 {
+                    elements.len().checked_sub(idx.unsigned_abs() as usize)?
+                } else {
+                    idx as usize
+                };
+                elements.get(idx)
+            }
+            _ => None,
+        }
+    }
+
+    /// Follows a path of object keys (or array indexes, if the path element is a number)
+    pub fn get_path<S: AsRef<str>>(&self, path: &[S]) -> Option<&Json> {
+        let mut current = self;
+        for element in path {
+            let element = element.as_ref();
+            current = match current {
+                Json::Array(_) => current.get_index(element.parse().ok()?)?,
+                _ => current.get_key(element)?,
+            };
+        }
+        Some(current)
+    }
+
+    /// The `@>` operator: whether this document contains the other one.
+    /// Objects contain another object if all its keys are contained, arrays contain another
+    /// array if each element of it is contained in any element. As in Postgres, an array also
+    /// contains a primitive value if it is one of its elements
+    pub fn contains(&self, other: &Json) -> bool {
+        match (self, other) {
+            (Json::Object(_), Json::Object(other_entries)) => {
+                other_entries.iter().all(|(key, other_value)| {
+                    self.get_key(key)
+                        .is_some_and(|value| value.contains(other_value))
+                })
+            }
+            (Json::Array(elements), Json::Array(other_elements)) => {
+                other_elements.iter().all(|other_element| {
+                    elements
+                        .iter()
+                        .any(|element| element.contains_element(other_element))
+                })
+            }
+            (Json::Array(elements), other) if other.is_scalar() => {
+                elements.iter().any(|element| element == other)
+            }
+            (this, other) => this.is_scalar() && this == other,
+        }
+    }
+
+    /// Like `contains`, but nested arrays don't match primitive values
+    fn contains_element(&self, other: &Json) -> bool {
+        match (self, other) {
+            (Json::Array(_), other) if other.is_scalar() => false,
+            _ => self.contains(other),
+        }
+    }
+
+    fn is_scalar(&self) -> bool {
+        !matches!(self, Json::Array(_) | Json::Object(_))
+    }
+
+    /// The text representation which the `->>` operator returns: strings are unquoted,
+    /// everything else is formatted as JSON. Returns None for a JSON null
+    pub fn to_text(&self) -> Option<String> {
+        match self {
+            Json::Null => None,
+            Json::String(s) => Some(s.clone()),
+            json => Some(json.to_string()),
+        }
+    }
+
+    /// Returns how many bytes the serialized document occupies
+    pub fn serialized_size(&self) -> usize {
+        match self {
+            Json::Null | Json::Boolean(_) => 1,
+            Json::Integer(_) => 1 + std::mem::size_of::<i128>(),
+            Json::Number(_) => 1 + std::mem::size_of::<f64>(),
+            Json::String(s) => 1 + 4 + s.len(),
+            Json::Array(elements) => {
+                1 + 4 + elements.iter().map(Json::serialized_size).sum::<usize>()
+            }
+            Json::Object(entries) => {
+                1 + 4
+                    + entries
+                        .iter()
+                        .map(|(key, value)| 4 + key.len() + value.serialized_size())
+                        .sum::<usize>()
+            }
+        }
+    }
+
+    pub fn serialize(&self, buffer: &mut Vec<u8>) {
+        match self {
+            Json::Null => buffer.push(TAG_NULL),
+            Json::Boolean(false) => buffer.push(TAG_FALSE),
+            Json::Boolean(true) => buffer.push(TAG_TRUE),
+            Json::Integer(num) => {
+                buffer.push(TAG_INTEGER);
+                buffer.extend_from_slice(&num.to_be_bytes());
+            }
+            Json::Number(num) => {
+                buffer.push(TAG_NUMBER);
+                buffer.extend_from_slice(&num.to_be_bytes());
+            }
+            Json::String(s) => {
+                buffer.push(TAG_STRING);
+                serialize_str(s, buffer);
+            }
+            Json::Array(elements) => {
+                buffer.push(TAG_ARRAY);
+                buffer.extend_from_slice(&(elements.len() as u32).to_be_bytes());
+                for element in elements {
+                    element.serialize(buffer);
+                }
+            }
+            Json::Object(entries) => {
+                buffer.push(TAG_OBJECT);
+                buffer.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+                for (key, value) in entries {
+                    serialize_str(key, buffer);
+                    value.serialize(buffer);
+                }
+            }
+        }
+    }
+
+    /// Parses a serialized document, returns it together with the number of consumed bytes
+    pub fn deserialize(bytes: &[u8]) -> (Self, usize) {
+        match bytes[0] {
+            TAG_NULL => (Json::Null, 1),
+            TAG_FALSE => (Json::Boolean(false), 1),
+            TAG_TRUE => (Json::Boolean(true), 1),
+            TAG_NUMBER => {
+                let num = f64::from_be_bytes(bytes[1..9].try_into().unwrap());
+                (Json::number(num), 9)
+            }
+            TAG_INTEGER => {
+                let num = i128::from_be_bytes(bytes[1..17].try_into().unwrap());
+                (Json::Integer(num), 17)
+            }
+            TAG_STRING => {
+                let (s, len) = deserialize_str(&bytes[1..]);
+                (Json::String(s), 1 + len)
+            }
+            TAG_ARRAY => {
+                let count = u32::from_be_bytes(bytes[1..5].try_into().unwrap());
+                let mut offset = 5;
+                let mut elements = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let (element, len) = Self::deserialize(&bytes[offset..]);
+                    elements.push(element);
+                    offset += len;
+                }
+                (Json::Array(elements), offset)
+            }
+            TAG_OBJECT => {
+                let count = u32::from_be_bytes(bytes[1..5].try_into().unwrap());
+                let mut offset = 5;
+                let mut entries = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let (key, len) = deserialize_str(&bytes[offset..]);
+                    offset += len;
+                    let (value, len) = Self::deserialize(&bytes[offset..]);
+                    offset += len;
+                    entries.push((key, value));
+                }
+                (Json::Object(entries), offset)
+            }
+            tag => unreachable!("Invalid json tag {}", tag),
+        }
+    }
+}
+
+fn serialize_str(s: &str, buffer: &mut Vec<u8>) {
+    buffer.extend_from_slice(&(s.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(s.as_bytes());
+}
+
+fn deserialize_str(bytes: &[u8]) -> (String, usize) {
+    let len = u32::from_be_bytes(bytes[..4].try_into().unwrap()) as usize;
+    let s = std::str::from_utf8(&bytes[4..4 + len]).unwrap().to_owned();
+    (s, 4 + len)
+}
+
+fn write_json_string(s: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_char('"')?;
+    for ch in s.chars() {
+        match ch {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            ch if ch.is_control() => write!(f, "\\u{:04x}", ch as u32)?,
+            ch => f.write_char(ch)?,
+        }
+    }
+    f.write_char('"')
+}
+
+// JSON numbers are never NaN, so the equality is total
+impl Eq for Json {}
+
+impl Hash for Json {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Json::Null => (),
+            Json::Boolean(val) => val.hash(state),
+            Json::Integer(val) => val.hash(state),
+            Json::Number(val) => val.to_bits().hash(state),
+            Json::String(s) => s.hash(state),
+            Json::Array(elements) => elements.hash(state),
+            Json::Object(entries) => entries.hash(state),
+        }
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Boolean(val) => write!(f, "{}", val),
+            Json::Integer(num) => write!(f, "{}", num),
+            Json::Number(num) => write!(f, "{}", num),
+            Json::String(s) => write_json_string(s, f),
+            Json::Array(elements) => {
+                f.write_char('[')?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(entries) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_json_string(key, f)?;
+                    write!(f, ": {}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    input: &'a str,
+    chars: Peekable<Chars<'a>>,
+}
+
+impl JsonParser<'_> {
+    fn error(&self) -> Error {
+        Error::msg(format!("Invalid json '{}'", self.input))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(ch) if ch == expected => Ok(()),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<()> {
+        for expected in word.chars() {
+            if self.chars.next() != Some(expected) {
+                return Err(self.error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a value, which is nested in `depth` arrays or objects
+    fn parse_value(&mut self, depth: usize) -> Result<Json> {
+        self.skip_whitespace();
+        let json = match self.chars.peek() {
+            Some('n') => {
+                self.expect_word("null")?;
+                Json::Null
+            }
+            Some('t') => {
+                self.expect_word("true")?;
+                Json::Boolean(true)
+            }
+            Some('f') => {
+                self.expect_word("false")?;
+                Json::Boolean(false)
+            }
+            Some('"') => Json::String(self.parse_string()?),
+            Some('[' | '{') if depth >= MAX_NESTING_DEPTH => {
+                return Err(Error::msg("Invalid json: nesting too deep"))
+            }
+            Some('[') => self.parse_array(depth + 1)?,
+            Some('{') => self.parse_object(depth + 1)?,
+            Some(ch) if *ch == '-' || ch.is_ascii_digit() => self.parse_number()?,
+            _ => return Err(self.error()),
+        };
+        Ok(json)
+    }
+
+    fn parse_number(&mut self) -> Result<Json> {
+        let mut number = String::new();
+        while let Some(ch) = self
+            .chars
+            .next_if(|ch| ch.is_ascii_digit() || ['-', '+', '.', 'e', 'E'].contains(ch))
+        {
+            number.push(ch);
+        }
+        let valid_start = match number.strip_prefix('-').unwrap_or(&number).as_bytes() {
+            [b'0', b'0'..=b'9', ..] => false,
+            [b'0'..=b'9', ..] => true,
+            _ => false,
+        };
+        if !valid_start {
+            return Err(self.error());
+        }
+        // integers are parsed exactly, floats lose precision above 2^53
+        if let Ok(num) = number.parse::<i128>() {
+            return Ok(Json::Integer(num));
+        }
+        match number.parse::<f64>() {
+            Ok(num) if num.is_finite() => Ok(Json::number(num)),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next().ok_or_else(|| self.error())? {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escaped = match self.chars.next().ok_or_else(|| self.error())? {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => self.parse_unicode_escape()?,
+                        _ => return Err(self.error()),
+                    };
+                    s.push(escaped);
+                }
+                ch if ch.is_control() => return Err(self.error()),
+                ch => s.push(ch),
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .next()
+                .and_then(|ch| ch.to_digit(16))
+                .ok_or_else(|| self.error())?;
+            code = code << 4 | digit;
+        }
+        Ok(code)
+    }
+
+    /// Parses the XXXX of a \uXXXX escape, including UTF-16 surrogate pairs
+    fn parse_unicode_escape(&mut self) -> Result<char> {
+        let high = self.parse_hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            self.expect_word("\\u")?;
+            let low = self.parse_hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error());
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error())
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<Json> {
+        self.expect('[')?;
+        let mut elements = vec![];
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&']').is_some() {
+            return Ok(Json::Array(elements));
+        }
+        loop {
+            elements.push(self.parse_value(depth)?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(elements)),
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<Json> {
+        self.expect('{')?;
+        let mut entries: Vec<(String, Json)> = vec![];
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'}').is_none() {
+            loop {
+                self.skip_whitespace();
+                let key = self.parse_string()?;
+                self.expect(':')?;
+                let value = self.parse_value(depth)?;
+                entries.push((key, value));
+                self.skip_whitespace();
+                match self.chars.next() {
+                    Some(',') => continue,
+                    Some('}') => break,
+                    _ => return Err(self.error()),
+                }
+            }
+        }
+
+        // the last value of a duplicate key wins, so keep the order of insertion when sorting
+        entries.reverse();
+        entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+        entries.dedup_by(|(right, _), (left, _)| left == right);
+        Ok(Json::Object(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Json, MAX_NESTING_DEPTH};
+
+    #[test]
+    fn can_parse_and_format_json() {
+        let json =
+            Json::parse(r#" {"b": [1, 2.5, -3e2], "a": {"c": null}, "b": "ä\n", "d": true} "#)
+                .unwrap();
+        assert_eq!(
+            json.to_string(),
+            r#"{"a": {"c": null}, "b": "ä\n", "d": true}"#
+        );
+
+        assert!(Json::parse("[1, 2").is_err());
+        assert!(Json::parse("{'a': 1}").is_err());
+        assert!(Json::parse("01").is_err());
+        assert!(Json::parse("[1] 2").is_err());
+
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Json::parse(&nested(MAX_NESTING_DEPTH)).is_ok());
+        assert!(Json::parse(&nested(MAX_NESTING_DEPTH + 1)).is_err());
+        assert!(Json::parse(&"[{\"a\": ".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn keeps_integers_exact() {
+        for number in ["12345678901234567890", "-9223372036854775809", "0", "2.5"] {
+            assert_eq!(Json::parse(number).unwrap().to_string(), number);
+        }
+        let json = Json::parse("[12345678901234567890, 1.0, -0.0, -3e2, 1e40]").unwrap();
+        assert_eq!(
+            json.to_string(),
+            "[12345678901234567890, 1, 0, -300, 10000000000000000000000000000000000000000]"
+        );
+        assert_eq!(Json::parse("100").unwrap(), Json::parse("1e2").unwrap());
+        assert_ne!(
+            Json::parse("12345678901234567890").unwrap(),
+            Json::parse("12345678901234567891").unwrap()
+        );
+
+        let mut buffer = vec![];
+        json.serialize(&mut buffer);
+        assert_eq!(Json::deserialize(&buffer), (json.clone(), buffer.len()));
+    }
+
+    #[test]
+    fn can_serialize_and_deserialize_json() {
+        let json = Json::parse(r#"{"id": 42, "tags": ["a", "b"], "meta": {"ok": false}}"#).unwrap();
+        let mut buffer = vec![];
+        json.serialize(&mut buffer);
+        let (deserialized, len) = Json::deserialize(&buffer);
+        assert_eq!(len, buffer.len());
+        assert_eq!(json.serialized_size(), buffer.len());
+        assert_eq!(deserialized, json);
+    }
+
+    #[test]
+    fn can_navigate_and_test_containment() {
+        let json =
+            Json::parse(r#"{"user": {"tags": ["x", "y"], "age": 3}, "ids": [1, [2, 3]]}"#).unwrap();
+        assert_eq!(
+            json.get_path(&["user", "tags", "-1"]),
+            Some(&Json::String("y".to_owned()))
+        );
+        assert_eq!(json.get_path(&["user", "missing"]), None);
+        assert_eq!(
+            json.get_key("ids").unwrap().get_index(0),
+            Some(&Json::Integer(1))
+        );
+
+        let contains = |other: &str| json.contains(&Json::parse(other).unwrap());
+        assert!(contains(r#"{"user": {"tags": ["y"]}}"#));
+        assert!(contains(r#"{"ids": [[3]]}"#));
+        assert!(!contains(r#"{"ids": [3]}"#));
+        assert!(!contains(r#"{"user": {"age": "3"}}"#));
+        assert!(Json::parse("[1, 2]").unwrap().contains(&Json::Integer(2)));
+    }
+}
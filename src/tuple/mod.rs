@@ -2,6 +2,11 @@ use self::value::Value;
 use crate::common::INVALID_PAGE_NO;
 use crate::storage::TupleId;
 
+pub mod bytea;
+pub mod cast;
+pub mod datetime;
+pub mod json;
+pub mod uuid;
 pub mod value;
 
 const INVALID_TUPLE_ID: TupleId = (INVALID_PAGE_NO, 0);
@@ -0,0 +1,65 @@
+use anyhow::{Error, Result};
+
+/// Parses a UUID in its canonical form (`a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11`).
+/// The hyphens are optional, upper case hex digits are accepted as well
+pub fn parse_uuid(s: &str) -> Result<u128> {
+    let invalid = || Error::msg(format!("Invalid uuid '{}'", s));
+
+    let hex = s.replace('-', "");
+    let canonical = s.len() == 36 && [8, 13, 18, 23].iter().all(|&i| &s[i..i + 1] == "-");
+    if hex.len() != 32 || (hex.len() != s.len() && !canonical) {
+        return Err(invalid());
+    }
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    u128::from_str_radix(&hex, 16).map_err(|_| invalid())
+}
+
+pub fn format_uuid(uuid: u128) -> String {
+    let hex = format!("{:032x}", uuid);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Generates a random (version 4) UUID
+pub fn generate_v4() -> u128 {
+    let random: u128 = rand::random();
+    // version 4 in bits 48-51, variant 0b10 in bits 64-65 (counted from the most significant bit)
+    (random & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_uuid, generate_v4, parse_uuid};
+
+    #[test]
+    fn can_parse_and_format_uuids() {
+        let uuid = parse_uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11").unwrap();
+        assert_eq!(uuid, 0xa0eebc999c0b4ef8bb6d6bb9bd380a11);
+        assert_eq!(format_uuid(uuid), "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11");
+        assert_eq!(
+            parse_uuid("A0EEBC999C0B4EF8BB6D6BB9BD380A11").unwrap(),
+            uuid
+        );
+
+        assert!(parse_uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a1").is_err());
+        assert!(parse_uuid("a0eebc999-c0b-4ef8-bb6d-6bb9bd380a11").is_err());
+        assert!(parse_uuid("g0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11").is_err());
+    }
+
+    #[test]
+    fn generates_version_4_uuids() {
+        let uuid = format_uuid(generate_v4());
+        assert_eq!(&uuid[14..15], "4");
+        assert!(["8", "9", "a", "b"].contains(&&uuid[19..20]));
+        assert_ne!(generate_v4(), generate_v4());
+    }
+}
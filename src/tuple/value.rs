@@ -1,16 +1,47 @@
+use std::cmp::Ordering;
 use std::fmt::Display;
 
+use anyhow::{Error, Result};
+
+use super::datetime::{self, DateTimeField, Interval, MICROS_PER_DAY};
+use super::json::Json;
+use super::{bytea, uuid};
 use crate::catalog::schema::{ColumnDefinition, TypeId};
 use crate::parser::ast::BinaryOperator;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Value {
     Boolean(bool),
     Integer(i32),
     String(String),
+    /// days since 1970-01-01
+    Date(i32),
+    /// microseconds since midnight
+    Time(i64),
+    /// microseconds since 1970-01-01 00:00:00
+    Timestamp(i64),
+    Interval(Interval),
+    Bytea(Vec<u8>),
+    Uuid(u128),
+    Json(Json),
+    /// a one-dimensional array, elements may be NULL
+    Array(Vec<Value>),
     Null,
 }
 
+const TAG_NULL: u8 = 0;
+const TAG_BOOLEAN: u8 = 1;
+const TAG_INTEGER: u8 = 2;
+const TAG_STRING: u8 = 3;
+const TAG_DATE: u8 = 4;
+const TAG_TIME: u8 = 5;
+const TAG_TIMESTAMP: u8 = 6;
+const TAG_INTERVAL: u8 = 7;
+const TAG_BYTEA: u8 = 8;
+const TAG_UUID: u8 = 9;
+const TAG_JSON: u8 = 10;
+const TAG_ARRAY: u8 = 11;
+
 fn compare<T: PartialEq + PartialOrd + ?Sized>(left: &T, right: &T, op: BinaryOperator) -> bool {
     match op {
         BinaryOperator::Eq => left == right,
@@ -44,10 +75,92 @@ impl Value {
                 let val = std::str::from_utf8(slice).unwrap().to_owned();
                 Value::String(val)
             }
+            TypeId::Date => {
+                let val = i32::from_be_bytes(bytes[..4].try_into().unwrap());
+                Value::Date(val)
+            }
+            TypeId::Time => {
+                let val = i64::from_be_bytes(bytes[..8].try_into().unwrap());
+                Value::Time(val)
+            }
+            TypeId::Timestamp => {
+                let val = i64::from_be_bytes(bytes[..8].try_into().unwrap());
+                Value::Timestamp(val)
+            }
+            TypeId::Interval => {
+                let months = i32::from_be_bytes(bytes[..4].try_into().unwrap());
+                let days = i32::from_be_bytes(bytes[4..8].try_into().unwrap());
+                let micros = i64::from_be_bytes(bytes[8..16].try_into().unwrap());
+                Value::Interval(Interval::new(months, days, micros))
+            }
+            TypeId::Bytea => {
+                let len = u16::from_be_bytes(bytes[..2].try_into().unwrap()) as usize;
+                Value::Bytea(bytes[2..len + 2].to_vec())
+            }
+            TypeId::Uuid => {
+                let val = u128::from_be_bytes(bytes[..16].try_into().unwrap());
+                Value::Uuid(val)
+            }
+            TypeId::Json => {
+                let (val, _) = Json::deserialize(&bytes[2..]);
+                Value::Json(val)
+            }
+            TypeId::IntegerArray | TypeId::TextArray => {
+                let element_column =
+                    ColumnDefinition::with_type_id(column.type_id().element_type().unwrap());
+                let len = u16::from_be_bytes(bytes[..2].try_into().unwrap()) as usize;
+                let mut offset = 2;
+                let mut elements = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let is_null = bytes[offset] == 1;
+                    let element =
+                        Value::parse_value(&bytes[offset + 1..], &element_column, is_null);
+                    offset += 1 + element.size();
+                    elements.push(element);
+                }
+                Value::Array(elements)
+            }
             TypeId::Unknown => unreachable!(),
         }
     }
 
+    /// Makes sure that the value can be serialized with `serialize_value`, whose length
+    /// prefixes limit the size of some types
+    pub fn check_storage_size(&self) -> Result<()> {
+        match self {
+            Value::String(val) if val.len() > u8::MAX as usize => Err(Error::msg(format!(
+                "Text value of {} bytes exceeds the maximum of {} bytes",
+                val.len(),
+                u8::MAX
+            ))),
+            Value::Bytea(val) if val.len() > u16::MAX as usize => Err(Error::msg(format!(
+                "Bytea value of {} bytes exceeds the maximum of {} bytes",
+                val.len(),
+                u16::MAX
+            ))),
+            Value::Json(val) if val.serialized_size() > u16::MAX as usize => {
+                Err(Error::msg(format!(
+                    "Json document of {} bytes exceeds the maximum of {} bytes",
+                    val.serialized_size(),
+                    u16::MAX
+                )))
+            }
+            Value::Array(elements) if elements.len() > u16::MAX as usize => {
+                Err(Error::msg(format!(
+                    "Array of {} elements exceeds the maximum of {} elements",
+                    elements.len(),
+                    u16::MAX
+                )))
+            }
+            Value::Array(elements) => elements
+                .iter()
+                .try_for_each(|element| element.check_storage_size()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Serializes the value into the buffer. The size of the value must have been checked with
+    /// `check_storage_size`
     pub fn serialize_value(&self, buffer: &mut [u8]) {
         match self {
             Value::Boolean(b) => buffer[0] = *b as u8,
@@ -55,55 +168,259 @@ impl Value {
                 buffer[..std::mem::size_of::<i32>()].copy_from_slice(val.to_be_bytes().as_slice())
             }
             Value::String(val) => {
-                let len = val.as_bytes().len() as u8;
+                let len = val.len() as u8;
                 buffer[0] = len;
                 buffer[1..len as usize + 1].copy_from_slice(val.as_bytes())
             }
+            Value::Date(val) => buffer[..4].copy_from_slice(val.to_be_bytes().as_slice()),
+            Value::Time(val) | Value::Timestamp(val) => {
+                buffer[..8].copy_from_slice(val.to_be_bytes().as_slice())
+            }
+            Value::Interval(val) => {
+                buffer[..4].copy_from_slice(val.months.to_be_bytes().as_slice());
+                buffer[4..8].copy_from_slice(val.days.to_be_bytes().as_slice());
+                buffer[8..16].copy_from_slice(val.micros.to_be_bytes().as_slice());
+            }
+            Value::Bytea(val) => {
+                let len = u16::try_from(val.len()).expect("bytea value exceeds the maximum size");
+                buffer[..2].copy_from_slice(len.to_be_bytes().as_slice());
+                buffer[2..val.len() + 2].copy_from_slice(val)
+            }
+            Value::Uuid(val) => buffer[..16].copy_from_slice(val.to_be_bytes().as_slice()),
+            Value::Json(val) => {
+                let mut serialized = Vec::with_capacity(val.serialized_size());
+                val.serialize(&mut serialized);
+                let len = u16::try_from(serialized.len())
+                    .expect("json document exceeds the maximum size");
+                buffer[..2].copy_from_slice(len.to_be_bytes().as_slice());
+                buffer[2..serialized.len() + 2].copy_from_slice(&serialized)
+            }
+            Value::Array(elements) => {
+                let len = u16::try_from(elements.len()).expect("array exceeds the maximum size");
+                buffer[..2].copy_from_slice(len.to_be_bytes().as_slice());
+                // every element is prefixed with a flag whether it is NULL
+                let mut offset = 2;
+                for element in elements {
+                    buffer[offset] = element.is_null() as u8;
+                    element.serialize_value(&mut buffer[offset + 1..]);
+                    offset += 1 + element.size();
+                }
+            }
             Value::Null => (),
         }
     }
 
+    /// Serializes the value together with a tag of its type, so that it can be deserialized
+    /// without a schema. Unlike `serialize_value`, the size of values is not limited.
+    /// Used for intermediate results, which are written to temporary files
+    pub fn serialize_tagged(&self, buffer: &mut Vec<u8>) {
+        match self {
+            Value::Boolean(b) => buffer.extend_from_slice(&[TAG_BOOLEAN, *b as u8]),
+            Value::Integer(val) => {
+                buffer.push(TAG_INTEGER);
+                buffer.extend_from_slice(&val.to_be_bytes());
+            }
+            Value::String(val) => {
+                buffer.push(TAG_STRING);
+                buffer.extend_from_slice(&(val.len() as u32).to_be_bytes());
+                buffer.extend_from_slice(val.as_bytes());
+            }
+            Value::Date(val) => {
+                buffer.push(TAG_DATE);
+                buffer.extend_from_slice(&val.to_be_bytes());
+            }
+            Value::Time(val) => {
+                buffer.push(TAG_TIME);
+                buffer.extend_from_slice(&val.to_be_bytes());
+            }
+            Value::Timestamp(val) => {
+                buffer.push(TAG_TIMESTAMP);
+                buffer.extend_from_slice(&val.to_be_bytes());
+            }
+            Value::Interval(val) => {
+                buffer.push(TAG_INTERVAL);
+                buffer.extend_from_slice(&val.months.to_be_bytes());
+                buffer.extend_from_slice(&val.days.to_be_bytes());
+                buffer.extend_from_slice(&val.micros.to_be_bytes());
+            }
+            Value::Bytea(val) => {
+                buffer.push(TAG_BYTEA);
+                buffer.extend_from_slice(&(val.len() as u32).to_be_bytes());
+                buffer.extend_from_slice(val);
+            }
+            Value::Uuid(val) => {
+                buffer.push(TAG_UUID);
+                buffer.extend_from_slice(&val.to_be_bytes());
+            }
+            Value::Json(val) => {
+                buffer.push(TAG_JSON);
+                val.serialize(buffer);
+            }
+            Value::Array(elements) => {
+                buffer.push(TAG_ARRAY);
+                buffer.extend_from_slice(&(elements.len() as u32).to_be_bytes());
+                for element in elements {
+                    element.serialize_tagged(buffer);
+                }
+            }
+            Value::Null => buffer.push(TAG_NULL),
+        }
+    }
+
+    /// Deserializes a value written by `serialize_tagged`.
+    /// Returns the value and the number of bytes it occupied
+    pub fn deserialize_tagged(bytes: &[u8]) -> (Self, usize) {
+        let payload = &bytes[1..];
+        let (val, len) = match bytes[0] {
+            TAG_BOOLEAN => (Value::Boolean(payload[0] == 1), 1),
+            TAG_INTEGER => (
+                Value::Integer(i32::from_be_bytes(payload[..4].try_into().unwrap())),
+                4,
+            ),
+            TAG_STRING => {
+                let len = u32::from_be_bytes(payload[..4].try_into().unwrap()) as usize;
+                let val = std::str::from_utf8(&payload[4..4 + len])
+                    .unwrap()
+                    .to_owned();
+                (Value::String(val), 4 + len)
+            }
+            TAG_DATE => (
+                Value::Date(i32::from_be_bytes(payload[..4].try_into().unwrap())),
+                4,
+            ),
+            TAG_TIME => (
+                Value::Time(i64::from_be_bytes(payload[..8].try_into().unwrap())),
+                8,
+            ),
+            TAG_TIMESTAMP => (
+                Value::Timestamp(i64::from_be_bytes(payload[..8].try_into().unwrap())),
+                8,
+            ),
+            TAG_INTERVAL => {
+                let months = i32::from_be_bytes(payload[..4].try_into().unwrap());
+                let days = i32::from_be_bytes(payload[4..8].try_into().unwrap());
+                let micros = i64::from_be_bytes(payload[8..16].try_into().unwrap());
+                (Value::Interval(Interval::new(months, days, micros)), 16)
+            }
+            TAG_BYTEA => {
+                let len = u32::from_be_bytes(payload[..4].try_into().unwrap()) as usize;
+                (Value::Bytea(payload[4..4 + len].to_vec()), 4 + len)
+            }
+            TAG_UUID => (
+                Value::Uuid(u128::from_be_bytes(payload[..16].try_into().unwrap())),
+                16,
+            ),
+            TAG_JSON => {
+                let (val, len) = Json::deserialize(payload);
+                (Value::Json(val), len)
+            }
+            TAG_ARRAY => {
+                let count = u32::from_be_bytes(payload[..4].try_into().unwrap());
+                let mut offset = 4;
+                let mut elements = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let (element, len) = Self::deserialize_tagged(&payload[offset..]);
+                    elements.push(element);
+                    offset += len;
+                }
+                (Value::Array(elements), offset)
+            }
+            TAG_NULL => (Value::Null, 0),
+            tag => unreachable!("Unknown value tag {}", tag),
+        };
+        (val, 1 + len)
+    }
+
     pub fn is_null(&self) -> bool {
         *self == Value::Null
     }
 
+    /// Returns whether the value is of this type, NULL is of every type
+    pub fn is_of_type(&self, type_id: TypeId) -> bool {
+        match (self, type_id) {
+            (Value::Null, _) => true,
+            (Value::Array(elements), _) => type_id.element_type().is_some_and(|element_type| {
+                elements
+                    .iter()
+                    .all(|element| element.is_of_type(element_type))
+            }),
+            (Value::Boolean(_), TypeId::Boolean)
+            | (Value::Integer(_), TypeId::Integer)
+            | (Value::String(_), TypeId::Text)
+            | (Value::Date(_), TypeId::Date)
+            | (Value::Time(_), TypeId::Time)
+            | (Value::Timestamp(_), TypeId::Timestamp)
+            | (Value::Interval(_), TypeId::Interval)
+            | (Value::Bytea(_), TypeId::Bytea)
+            | (Value::Uuid(_), TypeId::Uuid)
+            | (Value::Json(_), TypeId::Json) => true,
+            _ => false,
+        }
+    }
+
     /// Returns how many bytes a serialized value occupies
     pub fn size(&self) -> usize {
         match self {
             Value::Boolean(_) => std::mem::size_of::<bool>(),
             Value::Integer(_) => std::mem::size_of::<i32>(),
-            Value::String(val) => std::mem::size_of::<u8>() + val.as_bytes().len(),
+            Value::String(val) => std::mem::size_of::<u8>() + val.len(),
+            Value::Date(_) => std::mem::size_of::<i32>(),
+            Value::Time(_) | Value::Timestamp(_) => std::mem::size_of::<i64>(),
+            Value::Interval(_) => 2 * std::mem::size_of::<i32>() + std::mem::size_of::<i64>(),
+            Value::Bytea(val) => std::mem::size_of::<u16>() + val.len(),
+            Value::Uuid(_) => std::mem::size_of::<u128>(),
+            Value::Json(val) => std::mem::size_of::<u16>() + val.serialized_size(),
+            Value::Array(elements) => {
+                std::mem::size_of::<u16>()
+                    + elements
+                        .iter()
+                        .map(|element| std::mem::size_of::<u8>() + element.size())
+                        .sum::<usize>()
+            }
             Value::Null => 0,
         }
     }
 
     /// Compares itself with another value and assigns the greater of these to itself.
-    /// Assumes that the other value is of same type.
-    /// Currently only implemented for integer and text
+    /// Assumes that the other value is of same type. NULL values are ignored
     pub fn cmp_and_set_max(&mut self, other: Value) {
-        if other.is_null() {
-            return;
-        }
+        self.cmp_and_set(other, BinaryOperator::Greater)
+    }
 
+    /// Compares itself with another value and assigns the lesser of these to itself.
+    /// Assumes that the other value is of same type. NULL values are ignored
+    pub fn cmp_and_set_min(&mut self, other: Value) {
+        self.cmp_and_set(other, BinaryOperator::Less)
+    }
+
+    /// Compares two non-NULL values of the same type, e.g. for sorting.
+    /// Json and arrays have no ordering
+    pub fn sort_cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
-            (Value::Integer(val), Value::Integer(other)) => {
-                if *val < other {
-                    *val = other;
-                }
-            }
-            (Value::String(val), Value::String(other)) => {
-                if *val < other {
-                    *val = other;
-                }
-            }
-            (_, Value::Null) => (),
-            (this @ Value::Null, other) => {
-                *this = other;
+            (Value::Boolean(left), Value::Boolean(right)) => left.cmp(right),
+            (Value::Integer(left), Value::Integer(right)) => left.cmp(right),
+            (Value::String(left), Value::String(right)) => left.cmp(right),
+            (Value::Date(left), Value::Date(right)) => left.cmp(right),
+            (Value::Time(left), Value::Time(right))
+            | (Value::Timestamp(left), Value::Timestamp(right)) => left.cmp(right),
+            (Value::Interval(left), Value::Interval(right)) => {
+                left.total_micros().cmp(&right.total_micros())
             }
+            (Value::Bytea(left), Value::Bytea(right)) => left.cmp(right),
+            (Value::Uuid(left), Value::Uuid(right)) => left.cmp(right),
             _ => unreachable!(),
         }
     }
 
+    fn cmp_and_set(&mut self, other: Value, op: BinaryOperator) {
+        if other.is_null() {
+            return;
+        }
+        if self.is_null() || other.evaluate_comparison(self, op) == Value::Boolean(true) {
+            *self = other;
+        }
+    }
+
     pub fn as_str(&self) -> &str {
         match &self {
             Value::String(val) => val,
@@ -126,49 +443,312 @@ impl Value {
     }
 
     /// Evaluates the binary expression.
-    pub fn evaluate_binary_expression(&self, right: &Self, op: BinaryOperator) -> Value {
-        if self == &Value::Null || right == &Value::Null {
+    /// Fails if the result cannot be computed, e.g. on division by zero or an overflow
+    pub fn evaluate_binary_expression(&self, right: &Self, op: BinaryOperator) -> Result<Value> {
+        let val = match op {
+            BinaryOperator::And | BinaryOperator::Or => self.evaluate_logical_expression(right, op),
+            BinaryOperator::IsDistinctFrom | BinaryOperator::IsNotDistinctFrom => {
+                self.evaluate_distinctness(right, op)
+            }
+            _ if self.is_null() || right.is_null() => Value::Null,
+            BinaryOperator::Plus
+            | BinaryOperator::Minus
+            | BinaryOperator::Multiply
+            | BinaryOperator::Divide
+            | BinaryOperator::Modulo => self.evaluate_arithmetic_expression(right, op)?,
+            BinaryOperator::Eq
+            | BinaryOperator::NotEq
+            | BinaryOperator::Less
+            | BinaryOperator::LessEq
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEq => self.evaluate_comparison(right, op),
+            BinaryOperator::JsonGet | BinaryOperator::JsonGetText => {
+                self.evaluate_json_access(right, op)
+            }
+            BinaryOperator::JsonContains => match (self, right) {
+                (Value::Json(left), Value::Json(right)) => Value::Boolean(left.contains(right)),
+                _ => unreachable!(),
+            },
+            BinaryOperator::Concat => match (self, right) {
+                (Value::String(left), Value::String(right)) => Value::String(left.clone() + right),
+                _ => unreachable!(),
+            },
+            BinaryOperator::Like | BinaryOperator::ILike => self.evaluate_like(right, op)?,
+        };
+        Ok(val)
+    }
+
+    /// Evaluates AND and OR with SQL's three-valued logic, where NULL means unknown.
+    /// E.g. `NULL OR TRUE` is true, but `NULL OR FALSE` is NULL
+    fn evaluate_logical_expression(&self, right: &Self, op: BinaryOperator) -> Value {
+        // the value which decides the result regardless of the other side
+        let dominant = Value::Boolean(op == BinaryOperator::Or);
+        if self == &dominant || right == &dominant {
+            dominant
+        } else if self.is_null() || right.is_null() {
             Value::Null
         } else {
-            match op {
-                BinaryOperator::Plus
-                | BinaryOperator::Minus
-                | BinaryOperator::Multiply
-                | BinaryOperator::Divide
-                | BinaryOperator::Modulo => self.evaluate_arithmetic_expression(right, op),
-                BinaryOperator::Eq
-                | BinaryOperator::NotEq
-                | BinaryOperator::Less
-                | BinaryOperator::LessEq
-                | BinaryOperator::Greater
-                | BinaryOperator::GreaterEq => self.evaluate_comparison(right, op),
-                BinaryOperator::And => Value::Boolean(self.as_bool() && right.as_bool()),
-                BinaryOperator::Or => Value::Boolean(self.as_bool() || right.as_bool()),
-            }
-        }
-    }
-
-    fn evaluate_arithmetic_expression(&self, right: &Self, op: BinaryOperator) -> Value {
-        match op {
-            BinaryOperator::Plus => Value::Integer(self.as_i32() + right.as_i32()),
-            BinaryOperator::Minus => Value::Integer(self.as_i32() - right.as_i32()),
-            BinaryOperator::Multiply => Value::Integer(self.as_i32() * right.as_i32()),
-            BinaryOperator::Divide => Value::Integer(self.as_i32() / right.as_i32()),
-            BinaryOperator::Modulo => Value::Integer(self.as_i32() % right.as_i32()),
-            _ => unreachable!(),
+            Value::Boolean(op == BinaryOperator::And)
         }
     }
 
+    /// Evaluates IS [NOT] DISTINCT FROM, which compares NULL like any other value
+    fn evaluate_distinctness(&self, right: &Self, op: BinaryOperator) -> Value {
+        let distinct = match (self.is_null(), right.is_null()) {
+            (true, true) => false,
+            (true, false) | (false, true) => true,
+            (false, false) => {
+                self.evaluate_comparison(right, BinaryOperator::NotEq) == Value::Boolean(true)
+            }
+        };
+        Value::Boolean(distinct == (op == BinaryOperator::IsDistinctFrom))
+    }
+
+    /// Evaluates an arithmetic expression.
+    /// The analyzer makes sure that only supported combinations of types (see
+    /// `arithmetic_result_type`) reach this point
+    fn evaluate_arithmetic_expression(&self, right: &Self, op: BinaryOperator) -> Result<Value> {
+        let val = match (self, right) {
+            (Value::Integer(left), Value::Integer(right)) => {
+                let result = match op {
+                    BinaryOperator::Plus => left.checked_add(*right),
+                    BinaryOperator::Minus => left.checked_sub(*right),
+                    BinaryOperator::Multiply => left.checked_mul(*right),
+                    BinaryOperator::Divide | BinaryOperator::Modulo if *right == 0 => {
+                        return Err(Error::msg("Division by zero"))
+                    }
+                    BinaryOperator::Divide => left.checked_div(*right),
+                    BinaryOperator::Modulo => left.checked_rem(*right),
+                    _ => unreachable!(),
+                };
+                Value::Integer(result.ok_or_else(|| Error::msg("Integer out of range"))?)
+            }
+            (Value::Date(date), Value::Integer(days)) => match op {
+                BinaryOperator::Plus => checked_date(*date as i64 + *days as i64)?,
+                BinaryOperator::Minus => checked_date(*date as i64 - *days as i64)?,
+                _ => unreachable!(),
+            },
+            (Value::Integer(days), Value::Date(date)) => checked_date(*date as i64 + *days as i64)?,
+            (Value::Date(left), Value::Date(right)) => Value::Integer(left - right),
+            (Value::Date(date), Value::Time(time)) | (Value::Time(time), Value::Date(date)) => {
+                Value::Timestamp(*date as i64 * MICROS_PER_DAY + time)
+            }
+            (Value::Date(date), Value::Interval(_)) => {
+                Value::Timestamp(*date as i64 * MICROS_PER_DAY)
+                    .evaluate_arithmetic_expression(right, op)?
+            }
+            (Value::Timestamp(timestamp), Value::Interval(interval)) => {
+                let interval = match op {
+                    BinaryOperator::Plus => *interval,
+                    BinaryOperator::Minus => checked_interval(interval.checked_negate())?,
+                    _ => unreachable!(),
+                };
+                Value::Timestamp(
+                    datetime::add_interval_to_timestamp(*timestamp, &interval)
+                        .ok_or_else(|| Error::msg("Timestamp out of range"))?,
+                )
+            }
+            (Value::Interval(_), Value::Date(_) | Value::Timestamp(_) | Value::Time(_)) => {
+                right.evaluate_arithmetic_expression(self, op)?
+            }
+            (Value::Timestamp(left), Value::Timestamp(right)) => Value::Interval(checked_interval(
+                datetime::subtract_timestamps(*left, *right),
+            )?),
+            (Value::Time(time), Value::Interval(interval)) => {
+                let micros = match op {
+                    BinaryOperator::Plus => time + interval.micros % MICROS_PER_DAY,
+                    BinaryOperator::Minus => time - interval.micros % MICROS_PER_DAY,
+                    _ => unreachable!(),
+                };
+                Value::Time(micros.rem_euclid(MICROS_PER_DAY))
+            }
+            (Value::Time(left), Value::Time(right)) => {
+                Value::Interval(Interval::new(0, 0, left - right))
+            }
+            (Value::Interval(left), Value::Interval(right)) => {
+                let result = match op {
+                    BinaryOperator::Plus => left.checked_add(right),
+                    BinaryOperator::Minus => right
+                        .checked_negate()
+                        .and_then(|right| left.checked_add(&right)),
+                    _ => unreachable!(),
+                };
+                Value::Interval(checked_interval(result)?)
+            }
+            (Value::Interval(interval), Value::Integer(factor))
+            | (Value::Integer(factor), Value::Interval(interval)) => {
+                Value::Interval(checked_interval(interval.checked_multiply(*factor))?)
+            }
+            _ => unreachable!(),
+        };
+        Ok(val)
+    }
+
     fn evaluate_comparison(&self, right: &Self, op: BinaryOperator) -> Value {
-        let val = match self {
-            Value::Integer(left) => compare(left, &right.as_i32(), op),
-            Value::String(left) => compare(left.as_str(), right.as_str(), op),
-            Value::Boolean(left) => compare(left, &right.as_bool(), op),
-            Value::Null => unreachable!(),
+        let val = match (self, right) {
+            (Value::Integer(left), Value::Integer(right)) => compare(left, right, op),
+            (Value::String(left), Value::String(right)) => {
+                compare(left.as_str(), right.as_str(), op)
+            }
+            (Value::Boolean(left), Value::Boolean(right)) => compare(left, right, op),
+            (Value::Date(left), Value::Date(right)) => compare(left, right, op),
+            (Value::Time(left), Value::Time(right))
+            | (Value::Timestamp(left), Value::Timestamp(right)) => compare(left, right, op),
+            (Value::Interval(left), Value::Interval(right)) => compare(left, right, op),
+            (Value::Bytea(left), Value::Bytea(right)) => {
+                compare(left.as_slice(), right.as_slice(), op)
+            }
+            (Value::Uuid(left), Value::Uuid(right)) => compare(left, right, op),
+            // the analyzer only allows (in)equality for json and arrays
+            (Value::Json(left), Value::Json(right)) => match op {
+                BinaryOperator::Eq => left == right,
+                BinaryOperator::NotEq => left != right,
+                _ => unreachable!(),
+            },
+            (Value::Array(left), Value::Array(right)) => match op {
+                BinaryOperator::Eq => left == right,
+                BinaryOperator::NotEq => left != right,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
         };
 
         Value::Boolean(val)
     }
+
+    /// Evaluates `json -> key` (or index) and `json ->> key`
+    fn evaluate_json_access(&self, right: &Self, op: BinaryOperator) -> Value {
+        let json = match self {
+            Value::Json(json) => json,
+            _ => unreachable!(),
+        };
+        let element = match right {
+            Value::String(key) => json.get_key(key),
+            Value::Integer(idx) => json.get_index(*idx),
+            _ => unreachable!(),
+        };
+        match (element, op) {
+            (None, _) => Value::Null,
+            (Some(element), BinaryOperator::JsonGet) => Value::Json(element.clone()),
+            (Some(element), _) => element.to_text().map(Value::String).unwrap_or(Value::Null),
+        }
+    }
+
+    /// Matches the text against a LIKE pattern. ILIKE ignores the case of both
+    fn evaluate_like(&self, pattern: &Self, op: BinaryOperator) -> Result<Value> {
+        let (text, pattern) = match (self, pattern) {
+            (Value::String(text), Value::String(pattern)) => (text, pattern),
+            _ => unreachable!(),
+        };
+        let (text, pattern) = if op == BinaryOperator::ILike {
+            (text.to_lowercase(), pattern.to_lowercase())
+        } else {
+            (text.clone(), pattern.clone())
+        };
+        let pattern = parse_like_pattern(&pattern)?;
+        let text = text.chars().collect::<Vec<_>>();
+        Ok(Value::Boolean(like_matches(&text, &pattern)))
+    }
+
+    /// Extracts a field (like the year) of a temporal value.
+    /// The analyzer makes sure that the field exists for the type of this value, it only fails
+    /// if the field does not fit into an integer, e.g. the epoch of a timestamp after 2038
+    pub fn extract(&self, field: DateTimeField) -> Result<Value> {
+        let val = match self {
+            Value::Date(days) => {
+                datetime::extract_from_timestamp(field, *days as i64 * MICROS_PER_DAY)
+            }
+            Value::Timestamp(micros) => datetime::extract_from_timestamp(field, *micros),
+            Value::Time(micros) => datetime::extract_from_time(field, *micros).unwrap(),
+            Value::Interval(interval) => datetime::extract_from_interval(field, interval).unwrap(),
+            Value::Null => return Ok(Value::Null),
+            _ => unreachable!(),
+        };
+        let val = i32::try_from(val)
+            .map_err(|_| Error::msg(format!("Extracted {} is out of range", field)))?;
+        Ok(Value::Integer(val))
+    }
+}
+
+/// Returns the date for the given number of days since 1970-01-01, if it can also be
+/// represented as a timestamp
+fn checked_date(days: i64) -> Result<Value> {
+    match days.checked_mul(MICROS_PER_DAY) {
+        Some(_) if i32::try_from(days).is_ok() => Ok(Value::Date(days as i32)),
+        _ => Err(Error::msg("Date out of range")),
+    }
+}
+
+fn checked_interval(interval: Option<Interval>) -> Result<Interval> {
+    interval.ok_or_else(|| Error::msg("Interval out of range"))
+}
+
+/// An element of a LIKE pattern
+enum LikeToken {
+    /// `%`, any sequence of characters
+    AnySequence,
+    /// `_`, any single character
+    AnyCharacter,
+    Character(char),
+}
+
+/// Splits a LIKE pattern into its elements. A backslash escapes the next character, so that
+/// e.g. `\%` matches a percent sign
+fn parse_like_pattern(pattern: &str) -> Result<Vec<LikeToken>> {
+    let mut tokens = vec![];
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        let token = match ch {
+            '%' => LikeToken::AnySequence,
+            '_' => LikeToken::AnyCharacter,
+            '\\' => match chars.next() {
+                Some(escaped) => LikeToken::Character(escaped),
+                None => {
+                    return Err(Error::msg(
+                        "LIKE pattern must not end with escape character",
+                    ))
+                }
+            },
+            ch => LikeToken::Character(ch),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Returns whether the text matches the pattern. When a mismatch occurs after a `%`, the `%`
+/// is retried with one more character
+fn like_matches(text: &[char], pattern: &[LikeToken]) -> bool {
+    let (mut text_pos, mut pattern_pos) = (0, 0);
+    // the pattern position after the last `%` and the text position it has been tried with
+    let mut retry: Option<(usize, usize)> = None;
+    while text_pos < text.len() {
+        match pattern.get(pattern_pos) {
+            Some(LikeToken::AnySequence) => {
+                pattern_pos += 1;
+                retry = Some((pattern_pos, text_pos));
+            }
+            Some(LikeToken::AnyCharacter) => {
+                pattern_pos += 1;
+                text_pos += 1;
+            }
+            Some(LikeToken::Character(ch)) if *ch == text[text_pos] => {
+                pattern_pos += 1;
+                text_pos += 1;
+            }
+            _ => match retry {
+                Some((retry_pattern_pos, retry_text_pos)) => {
+                    pattern_pos = retry_pattern_pos;
+                    text_pos = retry_text_pos + 1;
+                    retry = Some((retry_pattern_pos, text_pos));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[pattern_pos..]
+        .iter()
+        .all(|token| matches!(token, LikeToken::AnySequence))
 }
 
 impl Display for Value {
@@ -177,15 +757,46 @@ impl Display for Value {
             Value::Boolean(val) => Display::fmt(val, f),
             Value::Integer(val) => Display::fmt(val, f),
             Value::String(val) => Display::fmt(val, f),
+            Value::Date(val) => Display::fmt(&datetime::format_date(*val), f),
+            Value::Time(val) => Display::fmt(&datetime::format_time(*val as u64), f),
+            Value::Timestamp(val) => Display::fmt(&datetime::format_timestamp(*val), f),
+            Value::Interval(val) => Display::fmt(val, f),
+            Value::Bytea(val) => Display::fmt(&bytea::format_hex(val), f),
+            Value::Uuid(val) => Display::fmt(&uuid::format_uuid(*val), f),
+            Value::Json(val) => Display::fmt(&val.to_string(), f),
+            Value::Array(elements) => Display::fmt(&format_array(elements), f),
             Value::Null => Display::fmt("NULL", f),
         }
     }
 }
 
+/// Formats an array like Postgres does, e.g. `{1,2,NULL}`.
+/// Text elements are quoted if they would be ambiguous otherwise
+fn format_array(elements: &[Value]) -> String {
+    let elements = elements
+        .iter()
+        .map(|element| match element {
+            Value::String(s)
+                if s.is_empty()
+                    || s.eq_ignore_ascii_case("null")
+                    || s.chars()
+                        .any(|c| c.is_whitespace() || ['{', '}', ',', '"', '\\'].contains(&c)) =>
+            {
+                format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+            element => element.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{{{}}}", elements)
+}
+
 #[cfg(test)]
 mod tests {
     use super::Value;
     use crate::catalog::schema::{ColumnDefinition, TypeId};
+    use crate::tuple::datetime::Interval;
+    use crate::tuple::json::Json;
 
     fn serialize_parse_test_helper(buffer: &mut [u8], col: ColumnDefinition, value: Value) {
         value.serialize_value(buffer);
@@ -210,5 +821,118 @@ mod tests {
             integer_column,
             Value::String("erdb".to_owned()),
         );
+
+        let mut buffer = [0u8; 16];
+        let date_column = ColumnDefinition::new(TypeId::Date, "".to_owned(), 0, true);
+        serialize_parse_test_helper(&mut buffer, date_column, Value::Date(-42));
+
+        let mut buffer = [0u8; 16];
+        let timestamp_column = ColumnDefinition::new(TypeId::Timestamp, "".to_owned(), 0, true);
+        serialize_parse_test_helper(
+            &mut buffer,
+            timestamp_column,
+            Value::Timestamp(1_700_000_000_000_000),
+        );
+
+        let mut buffer = [0u8; 16];
+        let interval_column = ColumnDefinition::new(TypeId::Interval, "".to_owned(), 0, true);
+        serialize_parse_test_helper(
+            &mut buffer,
+            interval_column,
+            Value::Interval(Interval::new(-1, 2, -3)),
+        );
+
+        let mut buffer = [0u8; 16];
+        let bytea_column = ColumnDefinition::new(TypeId::Bytea, "".to_owned(), 0, true);
+        serialize_parse_test_helper(
+            &mut buffer,
+            bytea_column,
+            Value::Bytea(vec![0xde, 0xad, 0xbe, 0xef]),
+        );
+
+        let mut buffer = [0u8; 16];
+        let uuid_column = ColumnDefinition::new(TypeId::Uuid, "".to_owned(), 0, true);
+        serialize_parse_test_helper(
+            &mut buffer,
+            uuid_column,
+            Value::Uuid(0xa0eebc999c0b4ef8bb6d6bb9bd380a11),
+        );
+
+        let mut buffer = [0u8; 64];
+        let json_column = ColumnDefinition::new(TypeId::Json, "".to_owned(), 0, true);
+        serialize_parse_test_helper(
+            &mut buffer,
+            json_column,
+            Value::Json(Json::parse(r#"{"a": [1, "b"]}"#).unwrap()),
+        );
+
+        let mut buffer = [0u8; 32];
+        let array_column = ColumnDefinition::new(TypeId::TextArray, "".to_owned(), 0, true);
+        serialize_parse_test_helper(
+            &mut buffer,
+            array_column,
+            Value::Array(vec![
+                Value::String("erdb".to_owned()),
+                Value::Null,
+                Value::String("".to_owned()),
+            ]),
+        );
+    }
+
+    #[test]
+    fn can_format_arrays() {
+        let array = Value::Array(vec![Value::Integer(1), Value::Null, Value::Integer(-3)]);
+        assert_eq!(array.to_string(), "{1,NULL,-3}");
+
+        let array = Value::Array(vec![
+            Value::String("a".to_owned()),
+            Value::String("b c".to_owned()),
+            Value::String("NULL".to_owned()),
+            Value::String("x\"y".to_owned()),
+        ]);
+        assert_eq!(array.to_string(), r#"{a,"b c","NULL","x\"y"}"#);
+    }
+
+    #[test]
+    fn serialize_tagged_test() {
+        let values = vec![
+            Value::Boolean(false),
+            Value::Integer(-42),
+            Value::String("a".repeat(300)),
+            Value::Date(42),
+            Value::Time(1),
+            Value::Timestamp(-1),
+            Value::Interval(Interval::new(-1, 2, -3)),
+            Value::Bytea(vec![0xde, 0xad]),
+            Value::Uuid(u128::MAX),
+            Value::Json(Json::parse(r#"{"a": [1, "b"]}"#).unwrap()),
+            Value::Array(vec![Value::Integer(1), Value::Null]),
+            Value::Null,
+        ];
+
+        let mut buffer = vec![];
+        for value in &values {
+            value.serialize_tagged(&mut buffer);
+        }
+
+        let mut offset = 0;
+        for value in values {
+            let (deserialized, len) = Value::deserialize_tagged(&buffer[offset..]);
+            assert_eq!(deserialized, value);
+            offset += len;
+        }
+        assert_eq!(offset, buffer.len());
+    }
+
+    #[test]
+    fn rejects_arrays_exceeding_length_prefix() {
+        let integers = |len: usize| Value::Array(vec![Value::Integer(1); len]);
+        assert!(integers(u16::MAX as usize).check_storage_size().is_ok());
+        assert!(integers(u16::MAX as usize + 1)
+            .check_storage_size()
+            .is_err());
+        assert!(Value::Array(vec![Value::Bytea(vec![0; 70_000])])
+            .check_storage_size()
+            .is_err());
     }
 }